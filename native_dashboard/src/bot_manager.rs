@@ -7,12 +7,27 @@ use serde::{Deserialize, Serialize};
 use std::fs;
 use std::io::{Read, Seek, SeekFrom};
 use std::os::windows::process::CommandExt;
-use std::path::PathBuf;
-use std::process::{Child, Command};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc};
 use sysinfo::System;
 
 const CREATE_NO_WINDOW: u32 = 0x08000000;
 
+/// How long a `get_status()` snapshot is reused before the next call does a
+/// fresh process refresh. The dashboard polls `get_status` on a tight
+/// interval; on a busy host (hundreds of processes) redoing the full
+/// scan+cmdline-verification dance every single tick is pure idle-CPU
+/// overhead when nothing has changed since the last poll a few hundred
+/// milliseconds ago.
+const STATUS_CACHE_TTL: std::time::Duration = std::time::Duration::from_millis(500);
+
+/// Hard ceiling on `run_script`'s caller-supplied `timeout_secs` — a
+/// fat-fingered UI input shouldn't be able to pin the dashboard on a
+/// runaway one-off script indefinitely.
+const RUN_SCRIPT_MAX_TIMEOUT_SECS: u64 = 600;
+
 /// Absolute path to taskkill.exe so we don't fall through to a poisoned
 /// PATH entry. On every supported Windows build this lives in System32.
 ///
@@ -105,7 +120,7 @@ fn resolve_python_on_path() -> Option<String> {
     None
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BotStatus {
     pub is_running: bool,
     pub pid: Option<u32>,
@@ -167,10 +182,22 @@ pub struct BotManager {
     /// 0.38, which silently breaks our PID-reuse defence (see helper doc).
     sys: System,
     python_cmd: String,
+    /// Path to the bot's log file, relative to `base_path` — resolved once at
+    /// construction the same way `python_cmd` is (env var first, a sane
+    /// default otherwise). `logs_dir()` is derived from this rather than
+    /// stored separately, so `BOT_LOG_FILE=app.log` (no directory component)
+    /// and `BOT_LOG_FILE=logs/app.log` both do the sensible thing.
+    log_relative_path: PathBuf,
     /// Held Child handles so we can `wait()` on stop and avoid leaking
     /// Windows process handles / zombie process descriptors.
     child: Option<Child>,
     dev_watcher_child: Option<Child>,
+    /// Last `get_status()` result plus when it was computed, reused for
+    /// `STATUS_CACHE_TTL` before the next call redoes the process refresh.
+    /// Invalidated eagerly on `start`/`start_dev`/`stop_begin` so a
+    /// dashboard action's tight-poll still sees the transition promptly
+    /// instead of waiting out a stale cache entry.
+    status_cache: Option<(std::time::Instant, BotStatus)>,
 }
 
 #[allow(dead_code)]
@@ -218,13 +245,44 @@ impl BotManager {
         let base_path_canonical = std::fs::canonicalize(&base_path)
             .ok()
             .map(|p| p.to_string_lossy().to_lowercase().to_string());
+        let log_relative_path = Self::resolve_log_relative_path();
         Self {
             base_path,
             base_path_canonical,
             sys,
             python_cmd,
+            log_relative_path,
             child: None,
             dev_watcher_child: None,
+            status_cache: None,
+        }
+    }
+
+    /// Default `log_relative_path` for a bot that follows this repo's own
+    /// logging convention.
+    const DEFAULT_LOG_RELATIVE_PATH: &'static str = "logs/bot.log";
+
+    /// Resolve `log_relative_path` from `BOT_LOG_FILE`, for a bot whose log
+    /// file isn't named `logs/bot.log` (see `Self::DEFAULT_LOG_RELATIVE_PATH`).
+    /// An absolute path or one that escapes `base_path` via `..` is rejected
+    /// in favor of the default — `log_file()`'s result is truncated by
+    /// `clear_logs()` and read wholesale by `read_logs()`, so unlike
+    /// `PYTHON_CMD` (which may legitimately point anywhere) this one must
+    /// stay inside the bot directory.
+    fn resolve_log_relative_path() -> PathBuf {
+        match std::env::var("BOT_LOG_FILE") {
+            Ok(value) if !value.is_empty() => match parse_log_relative_path(&value) {
+                Some(candidate) => candidate,
+                None => {
+                    eprintln!(
+                        "WARNING: BOT_LOG_FILE '{}' must be a relative path inside the bot \
+                         directory, ignoring",
+                        value
+                    );
+                    PathBuf::from(Self::DEFAULT_LOG_RELATIVE_PATH)
+                }
+            },
+            _ => PathBuf::from(Self::DEFAULT_LOG_RELATIVE_PATH),
         }
     }
 
@@ -323,6 +381,30 @@ impl BotManager {
         );
     }
 
+    /// Same field-population contract as `refresh_processes_with_cmd` (memory,
+    /// cpu, cmd, cwd), but scoped to only `pids` via `ProcessesToUpdate::Some`
+    /// instead of enumerating every process on the machine.
+    ///
+    /// `get_status` and `is_running` are the dashboard's hot polling paths
+    /// and only ever inspect a PID they already read from the pid file — on a
+    /// host with hundreds of processes, doing a full `All` refresh there just
+    /// to read one or two entries is wasted work. Every OTHER caller in this
+    /// file (orphan scans, `process_is_gone`) genuinely needs the full table
+    /// to find PIDs it doesn't already know and MUST keep using
+    /// `refresh_processes_with_cmd` — do not replace those with this.
+    fn refresh_processes_targeted(sys: &mut System, pids: &[sysinfo::Pid]) {
+        sys.refresh_processes_specifics(
+            sysinfo::ProcessesToUpdate::Some(pids),
+            true,
+            sysinfo::ProcessRefreshKind::nothing()
+                .with_memory()
+                .with_cpu()
+                .with_exe(sysinfo::UpdateKind::OnlyIfNotSet)
+                .with_cmd(sysinfo::UpdateKind::Always)
+                .with_cwd(sysinfo::UpdateKind::Always),
+        );
+    }
+
     /// Whether `process` belongs to THIS dashboard's project tree.
     ///
     /// Two ways a `bot.py` process can be ours:
@@ -677,11 +759,17 @@ impl BotManager {
     }
 
     pub fn log_file(&self) -> PathBuf {
-        self.base_path.join("logs").join("bot.log")
+        self.base_path.join(&self.log_relative_path)
     }
 
+    /// Directory containing `log_file()` — the parent of `log_relative_path`,
+    /// or `base_path` itself when `BOT_LOG_FILE` names a bare filename with no
+    /// directory component.
     pub fn logs_dir(&self) -> PathBuf {
-        self.base_path.join("logs")
+        match self.log_relative_path.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => self.base_path.join(parent),
+            _ => self.base_path.clone(),
+        }
     }
 
     pub fn data_dir(&self) -> PathBuf {
@@ -692,67 +780,101 @@ impl BotManager {
         &self.base_path
     }
 
-    /// Return up to the last `count` lines of the bot log.
-    ///
-    /// NOTE (return contract): the tail read is capped at 1 MiB. The line
-    /// budget is estimated at ~1 KiB/line, so on a log whose average line
-    /// exceeds ~100 bytes a large request can return FEWER than `count` lines
-    /// even when more exist — only the trailing ~1 MiB is scanned. This is an
-    /// intentional bound to avoid loading a multi-MB log into memory.
+    /// Return up to the last `count` lines of the bot log. Thin wrapper
+    /// around [`tail_file`] — see its doc comment for the return contract.
     pub fn read_logs(&self, count: usize) -> Vec<String> {
+        tail_file(&self.log_file(), count)
+    }
+
+    /// Known log filenames [`Self::tail_named_log`] may read from
+    /// `logs_dir()` — the same "diagnostic log" set `export_logs_impl`
+    /// packages, minus the rotated `.old`/numbered siblings (a live-tail
+    /// view only makes sense on the current file), plus this instance's
+    /// actual configured bot log basename (which may differ from `bot.log`
+    /// via `BOT_LOG_FILE`).
+    fn known_log_names(&self) -> [String; 2] {
+        let bot_log_name = self
+            .log_file()
+            .file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or("bot.log")
+            .to_string();
+        [bot_log_name, "dashboard_errors.log".to_string()]
+    }
+
+    /// Tail an arbitrary known log file by basename (not a path — `name`
+    /// must exactly match one of [`Self::known_log_names`]), reusing
+    /// [`tail_file`]'s seek/lossy-UTF8/partial-line handling instead of
+    /// duplicating it for each log. The whitelist is what keeps this from
+    /// becoming an arbitrary-file-read primitive.
+    pub fn tail_named_log(&self, name: &str, count: usize) -> Result<Vec<String>, String> {
+        let known = self.known_log_names();
+        if !known.iter().any(|n| n == name) {
+            return Err(format!("Unknown log file: {}", name));
+        }
+        Ok(tail_file(&self.logs_dir().join(name), count))
+    }
+
+    /// Tail the internal control-plane operations log (`logs/dashboard.log`,
+    /// see [`log_dashboard_op`]) — one structured JSON line per
+    /// start/stop/restart/watchdog event. Not in [`Self::known_log_names`]'s
+    /// whitelist (that set is for `tail_named_log`'s free-text diagnostic
+    /// logs); this has its own dedicated reader since it's always the same
+    /// file and always JSON, not an arbitrary caller-chosen name.
+    pub fn read_dashboard_log(&self, count: usize) -> Vec<String> {
+        tail_file(&self.logs_dir().join("dashboard.log"), count)
+    }
+
+    /// Read only the log bytes written after `offset`, returning the new lines
+    /// plus the file's current length (the offset to pass on the next poll).
+    ///
+    /// Unlike [`Self::read_logs`], which always reads a window relative to EOF
+    /// (so a polling client re-reads overlapping content every call), this
+    /// seeks straight to `offset` and reads forward — the efficient primitive
+    /// for a live-tailing log viewer. If the file shrank below `offset` (log
+    /// rotation/truncation), resets to reading from the start instead of
+    /// erroring or seeking past EOF.
+    pub fn read_logs_since(&self, offset: u64) -> (Vec<String>, u64) {
         let log_path = self.log_file();
 
         if !log_path.exists() {
-            return vec![];
+            return (vec![], 0);
         }
 
-        let file = match fs::File::open(&log_path) {
+        let mut file = match fs::File::open(&log_path) {
             Ok(f) => f,
-            Err(_) => return vec![],
+            Err(_) => return (vec![], offset),
         };
 
-        // Read from end of file to avoid loading entire file into memory
-        let metadata = match file.metadata() {
-            Ok(m) => m,
-            Err(_) => return vec![],
+        let file_size = match file.metadata() {
+            Ok(m) => m.len(),
+            Err(_) => return (vec![], offset),
         };
 
-        let file_size = metadata.len();
-        if file_size == 0 {
-            return vec![];
-        }
+        // Rotation/truncation: the file is now smaller than where we left off.
+        // Start over from the beginning rather than treating it as "no new data".
+        let start_pos = if offset > file_size { 0 } else { offset };
 
-        // Read at most 1MB from end of file (enough for ~count lines)
-        let max_read: u64 = std::cmp::min(file_size, (count as u64).saturating_mul(1024)); // ~1KB per line estimate
-        let max_read = std::cmp::min(max_read, 1024 * 1024); // Cap at 1MB
-        let start_pos = file_size.saturating_sub(max_read);
+        if start_pos == file_size {
+            return (vec![], file_size);
+        }
 
-        let mut file = file;
         if file.seek(SeekFrom::Start(start_pos)).is_err() {
-            return vec![];
+            return (vec![], file_size);
         }
 
-        // Read as raw bytes and convert with lossy UTF-8 to avoid
-        // corruption when seek lands on a multi-byte character boundary
         let mut raw_bytes = Vec::new();
         if file.read_to_end(&mut raw_bytes).is_err() {
-            return vec![];
+            return (vec![], file_size);
         }
-        let buffer = String::from_utf8_lossy(&raw_bytes);
 
-        let lines: Vec<String> = buffer.lines().map(|l| l.to_string()).collect();
+        // Lossy UTF-8 in case the seek landed mid-character (matches read_logs).
+        let lines: Vec<String> = String::from_utf8_lossy(&raw_bytes)
+            .lines()
+            .map(|l| l.to_string())
+            .collect();
 
-        // If we started mid-file, skip potentially partial first line
-        let skip = if start_pos > 0 { 1 } else { 0 };
-        lines
-            .into_iter()
-            .skip(skip)
-            .rev()
-            .take(count)
-            .collect::<Vec<_>>()
-            .into_iter()
-            .rev()
-            .collect()
+        (lines, file_size)
     }
 
     pub fn clear_logs(&self) -> Result<String, String> {
@@ -811,7 +933,11 @@ impl BotManager {
 
     pub fn is_running(&mut self) -> bool {
         if let Some(pid) = self.get_pid() {
-            Self::refresh_processes_with_cmd(&mut self.sys);
+            // PID is already known here, so a full-table refresh is wasted work —
+            // scope to just this one (see `refresh_processes_targeted`'s doc
+            // comment on when that's safe vs. when `refresh_processes_with_cmd`'s
+            // full `All` scan is still required).
+            Self::refresh_processes_targeted(&mut self.sys, &[sysinfo::Pid::from_u32(pid)]);
             // PID alone is not enough — Windows recycles PIDs aggressively.
             // Verify the cmdline references both bot.py AND our base_path so we
             // don't report "running" for an unrelated PID-reuse.
@@ -839,6 +965,69 @@ impl BotManager {
         self.sys.process(sysinfo::Pid::from_u32(pid)).is_none()
     }
 
+    /// Remove `bot.pid` and/or `dev_watcher.pid` when the PID inside no
+    /// longer names a live process of ours — the bot (or dev watcher)
+    /// crashed, or was killed outside `stop`/`stop_dev_watcher`, leaving the
+    /// file behind. A stale `bot.pid` otherwise confuses `get_uptime` (keyed
+    /// off the file's mtime, so a long-dead file reports an ever-growing
+    /// uptime) and forces `start`'s cold-start handling to special-case it.
+    ///
+    /// Called opportunistically from `compute_status` so every status poll
+    /// self-heals instead of needing a dedicated cleanup pass. Safe to call
+    /// when nothing is stale — a missing PID file is simply a no-op.
+    pub fn cleanup_stale_pid(&mut self) {
+        if let Some(pid) = self.get_pid() {
+            Self::refresh_processes_targeted(&mut self.sys, &[sysinfo::Pid::from_u32(pid)]);
+            let base_path_str = self.base_path.to_string_lossy().to_lowercase().to_string();
+            let is_ours = self
+                .sys
+                .process(sysinfo::Pid::from_u32(pid))
+                .map(|p| {
+                    Self::is_our_bot_process(p, &base_path_str, self.base_path_canonical.as_deref())
+                })
+                .unwrap_or(false);
+            if !is_ours {
+                let _ = fs::remove_file(self.pid_file());
+            }
+        }
+
+        if let Some(pid) = self.get_dev_watcher_pid() {
+            Self::refresh_processes_targeted(&mut self.sys, &[sysinfo::Pid::from_u32(pid)]);
+            let base_path_str = self.base_path.to_string_lossy().to_lowercase().to_string();
+            // Same python-name + entry-script + belongs-to-us gate
+            // `stop_dev_watcher`/`reap_orphan_dev_watcher` use — a bare PID
+            // match is never trusted because Windows reuses PIDs.
+            let is_ours = self
+                .sys
+                .process(sysinfo::Pid::from_u32(pid))
+                .map(|p| {
+                    let name = p.name().to_string_lossy().to_lowercase();
+                    if !name.contains("python") {
+                        return false;
+                    }
+                    let cmd: Vec<String> = p
+                        .cmd()
+                        .iter()
+                        .map(|s| s.to_string_lossy().to_lowercase().to_string())
+                        .collect();
+                    if !Self::entry_script_is(&cmd, "dev_watcher.py") {
+                        return false;
+                    }
+                    let cmdline = cmd.join(" ");
+                    Self::process_belongs_to_us(
+                        p,
+                        &cmdline,
+                        &base_path_str,
+                        self.base_path_canonical.as_deref(),
+                    )
+                })
+                .unwrap_or(false);
+            if !is_ours {
+                let _ = fs::remove_file(self.dev_watcher_pid_file());
+            }
+        }
+    }
+
     /// Format uptime from PID file modification time.
     fn format_uptime_from_pid_file(&self) -> String {
         let pid_file = self.pid_file();
@@ -871,10 +1060,45 @@ impl BotManager {
     }
 
     pub fn get_status(&mut self) -> BotStatus {
-        // Single process refresh for all status fields (instead of 3-5 separate refreshes)
-        Self::refresh_processes_with_cmd(&mut self.sys);
+        if let Some((computed_at, cached)) = &self.status_cache {
+            if computed_at.elapsed() < STATUS_CACHE_TTL {
+                return cached.clone();
+            }
+        }
+
+        let status = self.compute_status();
+        self.status_cache = Some((std::time::Instant::now(), status.clone()));
+        status
+    }
 
+    /// Uncached core of `get_status` — always does a fresh (but PID-scoped,
+    /// not whole-machine) process refresh. Split out so `get_status` can
+    /// short-circuit on the TTL cache above without duplicating this body.
+    fn compute_status(&mut self) -> BotStatus {
+        self.cleanup_stale_pid();
         let pid = self.get_pid();
+        let dev_pid = self.get_dev_watcher_pid();
+        // Only the two PIDs we might report on are ever inspected below —
+        // refresh just those instead of the whole process table (see
+        // `refresh_processes_targeted`'s doc comment). Neither PID on disk
+        // means neither process is tracked as running, so skip the refresh
+        // entirely rather than scanning for nothing.
+        let targeted_pids: Vec<sysinfo::Pid> = [pid, dev_pid]
+            .into_iter()
+            .flatten()
+            .map(sysinfo::Pid::from_u32)
+            .collect();
+        if targeted_pids.is_empty() {
+            return BotStatus {
+                is_running: false,
+                pid: None,
+                uptime: "-".to_string(),
+                memory_mb: 0.0,
+                mode: "-".to_string(),
+            };
+        }
+        Self::refresh_processes_targeted(&mut self.sys, &targeted_pids);
+
         // Mirror is_running()'s cmdline verification — pure PID existence is
         // unreliable on Windows due to aggressive PID reuse.
         let base_path_str = self.base_path.to_string_lossy().to_lowercase().to_string();
@@ -999,6 +1223,10 @@ impl BotManager {
     }
 
     pub fn start(&mut self) -> Result<String, String> {
+        // Invalidate the get_status cache so the frontend's tight-poll after
+        // this call sees the transition promptly rather than a snapshot from
+        // up to STATUS_CACHE_TTL ago.
+        self.status_cache = None;
         // Refuse to spawn without a pinned, validated interpreter. An empty
         // python_cmd means new() found no trusted python (PYTHON_CMD/.venv/PATH
         // all failed); spawning a bare "python" here would be the PATH-hijack
@@ -1045,8 +1273,14 @@ impl BotManager {
         // still-live tracked child's tree first (mirroring stop()'s teardown) so
         // overwriting self.child below can't orphan a still-booting bot.
         self.kill_tracked_bot_child();
+        let pid = child.id();
         self.child = Some(child);
 
+        // The freshly-spawned OS pid, not get_pid() — bot.py hasn't written
+        // bot.pid yet at this point (see the comment below on why we don't
+        // wait for it).
+        log_dashboard_op(&self.logs_dir(), "start", "ok", Some(pid));
+
         // Return as soon as spawn() succeeds. The previous design held the
         // BotManager lock for up to 10s waiting for bot.py to write bot.pid,
         // which made the UI freeze for ~1s on every Start click. The frontend
@@ -1058,6 +1292,8 @@ impl BotManager {
     }
 
     pub fn start_dev(&mut self) -> Result<String, String> {
+        // See start()'s identical invalidation for why.
+        self.status_cache = None;
         // Same pinned-interpreter requirement as start() — never spawn the
         // dev watcher via an unpinned bare "python".
         if self.python_cmd.is_empty() {
@@ -1143,6 +1379,140 @@ impl BotManager {
         Ok("Dev Watcher launched - bot starting...".to_string())
     }
 
+    /// Resolve `relative_path` against `base_path`, rejecting anything that
+    /// escapes it — the same canonicalize + symlink-reject + `starts_with`
+    /// containment check `open_folder` in main.rs applies to an already-
+    /// absolute path, adapted for one that starts relative. Rejecting an
+    /// absolute `relative_path` up front matters here: `Path::join` with an
+    /// absolute operand discards the base entirely (a drive-rooted path on
+    /// Windows), which would otherwise let a caller step around the
+    /// containment check by passing an absolute path in as "relative".
+    fn resolve_script_path(base_path: &Path, relative_path: &str) -> Result<PathBuf, String> {
+        if relative_path.is_empty() || relative_path.len() > 4096 {
+            return Err("relative_path is empty or too long".to_string());
+        }
+        let candidate_rel = Path::new(relative_path);
+        if candidate_rel.is_absolute() {
+            return Err("relative_path must be relative to the bot directory".to_string());
+        }
+        let candidate = base_path.join(candidate_rel);
+        if !candidate.exists() {
+            return Err(format!("Script not found: {}", relative_path));
+        }
+        let symlink_meta = std::fs::symlink_metadata(&candidate)
+            .map_err(|e| format!("Failed to stat script: {}", e))?;
+        if symlink_meta.file_type().is_symlink() {
+            return Err("Access denied: symlinked scripts are not allowed".to_string());
+        }
+        let canonical = candidate
+            .canonicalize()
+            .map_err(|e| format!("Failed to resolve script path: {}", e))?;
+        let base_canonical = base_path
+            .canonicalize()
+            .map_err(|e| format!("Failed to resolve base path: {}", e))?;
+        if !canonical.starts_with(&base_canonical) {
+            return Err("Access denied: script is outside the bot directory".to_string());
+        }
+        if !canonical.is_file() {
+            return Err("Path is not a file".to_string());
+        }
+        Ok(canonical)
+    }
+
+    /// Run a one-off script inside the bot's own environment: same
+    /// interpreter (`self.python_cmd`), same working directory
+    /// (`self.base_path`) — for ad-hoc migrations or diagnostics launched
+    /// from the dashboard UI without leaving it.
+    ///
+    /// `relative_path` is resolved and containment-checked via
+    /// [`Self::resolve_script_path`]. `timeout_secs` is clamped to
+    /// [`RUN_SCRIPT_MAX_TIMEOUT_SECS`]; if the script is still running when
+    /// it elapses, its process tree is killed via `taskkill_path()` the same
+    /// way `stop_begin` tears down the bot, and the timeout is reported as
+    /// an error rather than a truncated `(stdout, stderr, exit_code)`.
+    ///
+    /// The kill decision is made by a watchdog thread racing a
+    /// `recv_timeout` against the main thread's `wait_with_output()`, not a
+    /// simple "sleep, then check `try_wait()`" poll — using `wait_with_output`
+    /// to collect output (instead of separate `read_to_end` calls on the
+    /// piped stdout/stderr) avoids the deadlock a chatty script can cause by
+    /// filling one pipe's OS buffer while we're blocked reading the other.
+    /// The main thread signals the watchdog once `wait_with_output` returns
+    /// so a script that exits just under the wire can't have its exited PID
+    /// (which Windows is free to reuse for an unrelated process) killed out
+    /// from under it.
+    ///
+    /// Blocks the calling thread until the script exits or is killed —
+    /// callers on the Tauri IPC path should run this via `spawn_blocking`
+    /// the same way `start_dev_bot` does, not on the async executor directly.
+    pub fn run_script(
+        &self,
+        relative_path: &str,
+        args: &[String],
+        timeout_secs: u64,
+    ) -> Result<(String, String, i32), String> {
+        if self.python_cmd.is_empty() {
+            return Err(
+                "No trusted Python interpreter found; set PYTHON_CMD to an absolute interpreter \
+                 path"
+                    .to_string(),
+            );
+        }
+        let script_path = Self::resolve_script_path(&self.base_path, relative_path)?;
+        let clamped_timeout_secs = timeout_secs.clamp(1, RUN_SCRIPT_MAX_TIMEOUT_SECS);
+        let timeout = std::time::Duration::from_secs(clamped_timeout_secs);
+
+        let mut child = Command::new(&self.python_cmd)
+            .arg(&script_path)
+            .args(args)
+            .current_dir(&self.base_path)
+            .creation_flags(CREATE_NO_WINDOW)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| format!("Failed to start script: {}", e))?;
+
+        let pid = child.id();
+        let (done_tx, done_rx) = mpsc::channel::<()>();
+        let timed_out = Arc::new(AtomicBool::new(false));
+        let timed_out_watchdog = timed_out.clone();
+        let watchdog_logs_dir = self.logs_dir();
+        let watchdog = std::thread::spawn(move || {
+            if done_rx.recv_timeout(timeout).is_err() {
+                // Timed out (or the sender was dropped without signaling,
+                // e.g. an early return before wait_with_output) — either
+                // way the script may still be running and should be killed.
+                timed_out_watchdog.store(true, Ordering::Relaxed);
+                log_dashboard_op(&watchdog_logs_dir, "watchdog_kill", "timed_out", Some(pid));
+                let _ = Command::new(taskkill_path())
+                    .args(["/PID", &pid.to_string(), "/F", "/T"])
+                    .creation_flags(CREATE_NO_WINDOW)
+                    .output();
+            }
+        });
+
+        let output = child
+            .wait_with_output()
+            .map_err(|e| format!("Failed to wait for script: {}", e))?;
+        // Tell the watchdog we're done so it doesn't fire a taskkill against
+        // a PID Windows may since have recycled for an unrelated process.
+        let _ = done_tx.send(());
+        let _ = watchdog.join();
+
+        if timed_out.load(Ordering::Relaxed) {
+            return Err(format!(
+                "Script timed out after {}s and was killed",
+                clamped_timeout_secs
+            ));
+        }
+
+        let stdout = String::from_utf8_lossy(&output.stdout).into_owned();
+        let stderr = String::from_utf8_lossy(&output.stderr).into_owned();
+        let exit_code = output.status.code().unwrap_or(-1);
+        Ok((stdout, stderr, exit_code))
+    }
+
     /// First, lock-held phase of a stop. Performs all the quick work — PID
     /// validation, dev-watcher teardown, and firing the `taskkill` — then hands
     /// control back so the caller can wait for the process to die WITHOUT
@@ -1155,6 +1525,8 @@ impl BotManager {
     /// status/log poll for ~5s. The inter-poll sleeps now live in the lock-free
     /// orchestration in main.rs.
     pub fn stop_begin(&mut self) -> Result<StopOutcome, String> {
+        // See start()'s identical invalidation for why.
+        self.status_cache = None;
         let pid = match self.get_pid() {
             Some(pid) => pid,
             None => {
@@ -1276,7 +1648,12 @@ impl BotManager {
     /// bot PID to disappear (or the wait timed out). Sweeps remaining orphans,
     /// reaps the tracked Child, and deletes the PID file. Kept separate from
     /// `stop_begin` so the inter-poll sleeps happen with the lock released.
-    pub fn stop_finish(&mut self) -> Result<String, String> {
+    ///
+    /// `pid` is the PID `stop_begin` was tearing down (for the dashboard
+    /// operations log only — this method no longer needs it to do the actual
+    /// teardown); `None` when `stop_begin` reached this point without ever
+    /// finding one.
+    pub fn stop_finish(&mut self, pid: Option<u32>) -> Result<String, String> {
         // Kill any remaining orphan bot.py processes (no sleep — see method doc)
         self.kill_orphan_bot_processes();
 
@@ -1290,6 +1667,7 @@ impl BotManager {
         // Delete PID file
         let _ = fs::remove_file(self.pid_file());
 
+        log_dashboard_op(&self.logs_dir(), "stop", "ok", pid);
         Ok("Bot stopped".to_string())
     }
 
@@ -1310,7 +1688,7 @@ impl BotManager {
             // Process already torn down in the begin phase (no-PID-file /
             // stale-PID branches) — finish the teardown now; nothing to poll.
             Ok(StopOutcome::Done(_)) => {
-                let _ = self.stop_finish();
+                let _ = self.stop_finish(old_pid);
                 RestartBegin::StartNow
             }
             // Normal path: a kill was fired. Caller must poll the PID gone (lock
@@ -1354,8 +1732,269 @@ pub enum RestartBegin {
     PollThenStart(u32),
 }
 
+/// Validate a `BOT_LOG_FILE` value: `None` if `value` is absolute or escapes
+/// its base directory via `..`, `Some(candidate)` otherwise. A free function
+/// (rather than inlined in `resolve_log_relative_path`) so the validation is
+/// unit-testable without touching process-wide env state.
+fn parse_log_relative_path(value: &str) -> Option<PathBuf> {
+    let candidate = PathBuf::from(value);
+    let escapes = candidate.is_absolute()
+        || candidate
+            .components()
+            .any(|c| matches!(c, std::path::Component::ParentDir));
+    if escapes {
+        None
+    } else {
+        Some(candidate)
+    }
+}
+
 // Legacy function removed - use BotManager::read_logs() instead
 
+/// Return up to the last `count` lines of the file at `path`.
+///
+/// NOTE (return contract): the tail read is capped at 1 MiB. The line
+/// budget is estimated at ~1 KiB/line, so on a log whose average line
+/// exceeds ~100 bytes a large request can return FEWER than `count` lines
+/// even when more exist — only the trailing ~1 MiB is scanned. This is an
+/// intentional bound to avoid loading a multi-MB log into memory.
+///
+/// A free function (not a `BotManager` method) so [`BotManager::read_logs`]
+/// and [`BotManager::tail_named_log`] can share it without either owning the
+/// other's log path.
+fn tail_file(path: &Path, count: usize) -> Vec<String> {
+    if !path.exists() {
+        return vec![];
+    }
+
+    let file = match fs::File::open(path) {
+        Ok(f) => f,
+        Err(_) => return vec![],
+    };
+
+    // Read from end of file to avoid loading entire file into memory
+    let metadata = match file.metadata() {
+        Ok(m) => m,
+        Err(_) => return vec![],
+    };
+
+    let file_size = metadata.len();
+    if file_size == 0 {
+        return vec![];
+    }
+
+    // Read at most 1MB from end of file (enough for ~count lines)
+    let max_read: u64 = std::cmp::min(file_size, (count as u64).saturating_mul(1024)); // ~1KB per line estimate
+    let max_read = std::cmp::min(max_read, 1024 * 1024); // Cap at 1MB
+    let start_pos = file_size.saturating_sub(max_read);
+
+    let mut file = file;
+    if file.seek(SeekFrom::Start(start_pos)).is_err() {
+        return vec![];
+    }
+
+    // Read as raw bytes and convert with lossy UTF-8 to avoid
+    // corruption when seek lands on a multi-byte character boundary
+    let mut raw_bytes = Vec::new();
+    if file.read_to_end(&mut raw_bytes).is_err() {
+        return vec![];
+    }
+    let buffer = String::from_utf8_lossy(&raw_bytes);
+
+    let lines: Vec<String> = buffer.lines().map(|l| l.to_string()).collect();
+
+    // If we started mid-file, skip potentially partial first line
+    let skip = if start_pos > 0 { 1 } else { 0 };
+    lines
+        .into_iter()
+        .skip(skip)
+        .rev()
+        .take(count)
+        .collect::<Vec<_>>()
+        .into_iter()
+        .rev()
+        .collect()
+}
+
+/// One structured line in the internal dashboard operations log
+/// (`logs/dashboard.log`) — distinct from `dashboard_errors.log` (frontend JS
+/// errors) and the bot's own `bot.log`. This is the control-plane audit
+/// trail: start/stop/restart/watchdog events used to only exist as the
+/// `Result<String, String>` handed back to the Tauri caller, which vanished
+/// the moment the frontend finished handling it.
+#[derive(Debug, Serialize)]
+struct DashboardOpLogEntry<'a> {
+    timestamp: String,
+    action: &'a str,
+    result: &'a str,
+    pid: Option<u32>,
+}
+
+/// Append one JSON line recording a control-plane action to
+/// `logs/dashboard.log`, creating `logs_dir` if needed. Best-effort like
+/// every other log write in this file (`fs::remove_file`/`fs::write` above
+/// don't propagate their errors either) — a failed audit-log write must
+/// never fail the start/stop/restart it's recording.
+pub fn log_dashboard_op(logs_dir: &Path, action: &str, result: &str, pid: Option<u32>) {
+    let entry = DashboardOpLogEntry {
+        timestamp: Local::now().to_rfc3339(),
+        action,
+        result,
+        pid,
+    };
+    let Ok(line) = serde_json::to_string(&entry) else {
+        return;
+    };
+    let _ = fs::create_dir_all(logs_dir);
+    if let Ok(mut file) = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(logs_dir.join("dashboard.log"))
+    {
+        use std::io::Write;
+        let _ = writeln!(file, "{line}");
+    }
+}
+
+/// Total uncompressed byte budget for [`export_logs_impl`] — a "package all
+/// diagnostics" export must not turn a multi-gigabyte log directory into a
+/// runaway zip that stalls the UI or fills the user's disk.
+const LOG_EXPORT_SIZE_CAP: u64 = 50 * 1024 * 1024; // 50 MiB
+
+/// Gather `bot.log` (and any rotated `bot.log*` siblings) plus
+/// `dashboard_errors.log` (and its `.old` rotation) from `logs_dir` into a
+/// single zip written at `dest_path`. Returns the destination path and the
+/// written archive's size in bytes.
+///
+/// Files are packaged smallest-first and skipped once the running
+/// uncompressed total would exceed [`LOG_EXPORT_SIZE_CAP`], so a single huge
+/// log can't crowd out everything else — a "best effort, capped" bundle beats
+/// either failing outright or writing an unbounded archive. A free function
+/// (not a `BotManager` method) since it only needs a directory path, which
+/// keeps it testable without spinning up a full manager.
+pub fn export_logs_impl(logs_dir: &Path, dest_path: &Path) -> Result<u64, String> {
+    let mut candidates: Vec<PathBuf> = Vec::new();
+    if let Ok(entries) = fs::read_dir(logs_dir) {
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if !path.is_file() {
+                continue;
+            }
+            let is_diagnostic_log = path
+                .file_name()
+                .and_then(|n| n.to_str())
+                .is_some_and(|name| name.starts_with("bot.log") || name.starts_with("dashboard_errors.log"));
+            if is_diagnostic_log {
+                candidates.push(path);
+            }
+        }
+    }
+    candidates.sort_by_key(|p| fs::metadata(p).map(|m| m.len()).unwrap_or(0));
+
+    let file =
+        fs::File::create(dest_path).map_err(|e| format!("Failed to create export archive: {}", e))?;
+    let mut zip = zip::ZipWriter::new(file);
+    let options = zip::write::SimpleFileOptions::default()
+        .compression_method(zip::CompressionMethod::Deflated);
+
+    let mut total_written: u64 = 0;
+    for path in candidates {
+        let size = fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+        if total_written.saturating_add(size) > LOG_EXPORT_SIZE_CAP {
+            continue; // skip files that would blow the cap; smaller ones already fit
+        }
+        let name = path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or("log")
+            .to_string();
+        zip.start_file(name, options)
+            .map_err(|e| format!("Failed to add {} to archive: {}", path.display(), e))?;
+        let mut f = fs::File::open(&path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        std::io::copy(&mut f, &mut zip)
+            .map_err(|e| format!("Failed to write {} to archive: {}", path.display(), e))?;
+        total_written += size;
+    }
+
+    zip.finish()
+        .map_err(|e| format!("Failed to finalize archive: {}", e))?;
+
+    fs::metadata(dest_path)
+        .map(|m| m.len())
+        .map_err(|e| format!("Failed to stat export archive: {}", e))
+}
+
+/// Validate `dest` as a destination for a file-writing command (currently
+/// [`export_logs_impl`]; the proposed `export_stats`/`backup` commands
+/// should route through this too once they exist): checks the destination's
+/// PARENT directory rather than the destination itself, since an export
+/// target typically doesn't exist yet — there's nothing to canonicalize or
+/// symlink-check at a leaf that hasn't been created. The parent must exist,
+/// must not itself be a symlink, must be writable, and (mirroring
+/// `resolve_script_path`'s containment check) must canonicalize to
+/// somewhere inside `base_path`. A free function, not a `BotManager`
+/// method, for the same reason as `export_logs_impl`: it only needs a
+/// directory path, which keeps it testable without spinning up a full
+/// manager.
+///
+/// Returns the destination rebuilt from the canonicalized parent plus the
+/// original file name, ready to hand to `fs::File::create`.
+pub fn validate_write_path(base_path: &Path, dest: &str) -> Result<PathBuf, String> {
+    if dest.is_empty() || dest.len() > 4096 {
+        return Err("dest is empty or too long".to_string());
+    }
+    let dest_path = Path::new(dest);
+    let file_name = dest_path
+        .file_name()
+        .ok_or_else(|| "dest has no file name".to_string())?;
+    let parent = match dest_path.parent() {
+        Some(p) if !p.as_os_str().is_empty() => p,
+        _ => return Err("dest has no parent directory".to_string()),
+    };
+    if !parent.is_dir() {
+        return Err(format!(
+            "Destination directory does not exist: {}",
+            parent.display()
+        ));
+    }
+    let parent_symlink_meta = fs::symlink_metadata(parent)
+        .map_err(|e| format!("Failed to stat destination directory: {}", e))?;
+    if parent_symlink_meta.file_type().is_symlink() {
+        return Err("Access denied: destination directory must not be a symlink".to_string());
+    }
+    let parent_canonical = parent
+        .canonicalize()
+        .map_err(|e| format!("Failed to resolve destination directory: {}", e))?;
+    let base_canonical = base_path
+        .canonicalize()
+        .map_err(|e| format!("Failed to resolve base path: {}", e))?;
+    if !parent_canonical.starts_with(&base_canonical) {
+        return Err("Access denied: destination is outside the bot directory".to_string());
+    }
+
+    // The destination file itself may already exist (an overwrite) — refuse
+    // a symlinked or directory target rather than following/clobbering it.
+    if let Ok(meta) = fs::symlink_metadata(dest_path) {
+        if meta.file_type().is_symlink() {
+            return Err("Access denied: dest must not be a symlink".to_string());
+        }
+        if meta.is_dir() {
+            return Err("dest is a directory, not a file path".to_string());
+        }
+    }
+
+    // Writability probe: create-then-remove a throwaway file in the same
+    // directory, since the destination itself usually doesn't exist yet to
+    // permission-check directly.
+    let probe = parent_canonical.join(format!(".write_check_{}", std::process::id()));
+    fs::File::create(&probe)
+        .map_err(|e| format!("Destination directory is not writable: {}", e))?;
+    let _ = fs::remove_file(&probe);
+
+    Ok(parent_canonical.join(file_name))
+}
+
 // ============================================================================
 // Unit tests for the start-progress state machine. This module is Windows-only
 // (enforced by the `compile_error!` at the top of the file), so the tests use
@@ -1475,6 +2114,82 @@ mod tests {
         );
     }
 
+    // ------- cleanup_stale_pid (#1681) -------
+
+    #[test]
+    fn cleanup_stale_pid_noop_when_no_pid_files_exist() {
+        let (_dir, mut bm) = manager_in_temp();
+        bm.cleanup_stale_pid();
+        assert!(!bm.pid_file().exists());
+        assert!(!bm.dev_watcher_pid_file().exists());
+    }
+
+    #[test]
+    fn cleanup_stale_pid_removes_bot_pid_once_the_process_has_exited() {
+        let (_dir, mut bm) = manager_in_temp();
+        let mut child = Command::new("cmd")
+            .args(["/C", "exit", "0"])
+            .creation_flags(CREATE_NO_WINDOW)
+            .spawn()
+            .expect("spawn cmd /C exit 0");
+        let pid = child.id();
+        let _ = child.wait();
+        fs::write(bm.pid_file(), pid.to_string()).expect("seed stale bot.pid");
+
+        bm.cleanup_stale_pid();
+
+        assert!(
+            !bm.pid_file().exists(),
+            "bot.pid naming an exited process must be removed"
+        );
+    }
+
+    #[test]
+    fn cleanup_stale_pid_removes_dev_watcher_pid_once_the_process_has_exited() {
+        let (_dir, mut bm) = manager_in_temp();
+        let mut child = Command::new("cmd")
+            .args(["/C", "exit", "0"])
+            .creation_flags(CREATE_NO_WINDOW)
+            .spawn()
+            .expect("spawn cmd /C exit 0");
+        let pid = child.id();
+        let _ = child.wait();
+        fs::write(bm.dev_watcher_pid_file(), pid.to_string()).expect("seed stale dev_watcher.pid");
+
+        bm.cleanup_stale_pid();
+
+        assert!(
+            !bm.dev_watcher_pid_file().exists(),
+            "dev_watcher.pid naming an exited process must be removed"
+        );
+    }
+
+    #[test]
+    fn cleanup_stale_pid_removes_bot_pid_pointing_at_a_live_but_foreign_process() {
+        // A live PID that isn't OUR bot.py (Windows PID reuse) is just as stale
+        // for our purposes as an exited one — liveness alone must not save it.
+        let (dir, mut bm) = manager_in_temp();
+        let mut decoy = Command::new("cmd")
+            .args(["/C", "ping", "-n", "30", "127.0.0.1"])
+            .current_dir(dir.path())
+            .creation_flags(CREATE_NO_WINDOW)
+            .spawn()
+            .expect("spawn cmd decoy");
+        let decoy_pid = decoy.id();
+        wait_until_in_snapshot(&mut bm, decoy_pid);
+        fs::write(bm.pid_file(), decoy_pid.to_string()).expect("seed foreign bot.pid");
+
+        bm.cleanup_stale_pid();
+
+        let _ = decoy.kill();
+        let _ = decoy.wait();
+
+        assert!(
+            !bm.pid_file().exists(),
+            "bot.pid pointing at a live non-bot.py process must still be removed"
+        );
+    }
+
     // ------- process_belongs_to_us short-circuit / fail-closed (#27) -------
 
     #[test]
@@ -1784,4 +2499,360 @@ mod tests {
         // Interpreter-only argv -> false.
         assert!(!dev(&["python.exe"]));
     }
+
+    // ------- tail_file / tail_named_log (#1679) -------
+
+    #[test]
+    fn tail_file_missing_file_returns_empty() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        assert!(tail_file(&dir.path().join("missing.log"), 5).is_empty());
+    }
+
+    #[test]
+    fn tail_file_returns_only_the_last_count_lines() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let path = dir.path().join("app.log");
+        fs::write(&path, "one\ntwo\nthree\nfour\n").expect("seed log");
+        assert_eq!(tail_file(&path, 2), vec!["three", "four"]);
+    }
+
+    #[test]
+    fn read_logs_delegates_to_tail_file() {
+        let (_dir, bm) = manager_in_temp();
+        fs::create_dir_all(bm.logs_dir()).expect("create logs dir");
+        fs::write(bm.log_file(), "one\ntwo\nthree\n").expect("seed log");
+        assert_eq!(bm.read_logs(2), vec!["two", "three"]);
+    }
+
+    #[test]
+    fn tail_named_log_reads_the_configured_bot_log() {
+        let (_dir, bm) = manager_in_temp();
+        fs::create_dir_all(bm.logs_dir()).expect("create logs dir");
+        fs::write(bm.log_file(), "one\ntwo\n").expect("seed log");
+        let name = bm
+            .log_file()
+            .file_name()
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .to_string();
+        assert_eq!(
+            bm.tail_named_log(&name, 5).expect("tail"),
+            vec!["one", "two"]
+        );
+    }
+
+    #[test]
+    fn tail_named_log_reads_dashboard_errors_log() {
+        let (_dir, bm) = manager_in_temp();
+        fs::create_dir_all(bm.logs_dir()).expect("create logs dir");
+        fs::write(bm.logs_dir().join("dashboard_errors.log"), "oops\n").expect("seed error log");
+        assert_eq!(
+            bm.tail_named_log("dashboard_errors.log", 5).expect("tail"),
+            vec!["oops"]
+        );
+    }
+
+    #[test]
+    fn tail_named_log_rejects_a_name_outside_the_whitelist() {
+        let (_dir, bm) = manager_in_temp();
+        assert!(bm.tail_named_log("../secrets.txt", 5).is_err());
+        assert!(bm.tail_named_log("dev_watcher.log", 5).is_err());
+    }
+
+    // ------- read_logs_since (#33) -------
+
+    #[test]
+    fn read_logs_since_missing_file_returns_empty_and_zero_offset() {
+        let (_dir, bm) = manager_in_temp();
+        let (lines, offset) = bm.read_logs_since(0);
+        assert!(lines.is_empty());
+        assert_eq!(offset, 0);
+    }
+
+    #[test]
+    fn read_logs_since_returns_only_new_lines_and_advances_offset() {
+        let (_dir, bm) = manager_in_temp();
+        fs::create_dir_all(bm.logs_dir()).expect("create logs dir");
+        fs::write(bm.log_file(), "line1\nline2\n").expect("seed log");
+
+        let (lines, offset) = bm.read_logs_since(0);
+        assert_eq!(lines, vec!["line1", "line2"]);
+
+        // Append more; a re-poll from the returned offset should see only the
+        // new lines, not line1/line2 again.
+        let mut f = fs::OpenOptions::new()
+            .append(true)
+            .open(bm.log_file())
+            .expect("open for append");
+        use std::io::Write;
+        writeln!(f, "line3").expect("append");
+
+        let (lines2, offset2) = bm.read_logs_since(offset);
+        assert_eq!(lines2, vec!["line3"]);
+        assert!(offset2 > offset);
+    }
+
+    #[test]
+    fn read_logs_since_no_new_data_returns_empty() {
+        let (_dir, bm) = manager_in_temp();
+        fs::create_dir_all(bm.logs_dir()).expect("create logs dir");
+        fs::write(bm.log_file(), "line1\n").expect("seed log");
+
+        let (_lines, offset) = bm.read_logs_since(0);
+        let (lines2, offset2) = bm.read_logs_since(offset);
+        assert!(lines2.is_empty());
+        assert_eq!(offset2, offset);
+    }
+
+    #[test]
+    fn read_logs_since_resets_to_start_when_file_shrank() {
+        let (_dir, bm) = manager_in_temp();
+        fs::create_dir_all(bm.logs_dir()).expect("create logs dir");
+        fs::write(bm.log_file(), "aaaaaaaaaa\n").expect("seed log");
+
+        // Simulate rotation/truncation: file is now shorter than the offset we
+        // hold from a previous poll.
+        let stale_offset = 1_000_000u64;
+        fs::write(bm.log_file(), "fresh\n").expect("truncate + rewrite");
+
+        let (lines, offset) = bm.read_logs_since(stale_offset);
+        assert_eq!(lines, vec!["fresh"]);
+        assert_eq!(offset, "fresh\n".len() as u64);
+    }
+
+    // ------- export_logs_impl (#34) -------
+
+    #[test]
+    fn export_logs_impl_bundles_bot_and_error_logs() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        fs::write(dir.path().join("bot.log"), "bot log contents").expect("seed bot.log");
+        fs::write(
+            dir.path().join("dashboard_errors.log"),
+            "error log contents",
+        )
+        .expect("seed dashboard_errors.log");
+        fs::write(dir.path().join("unrelated.txt"), "should not be packaged")
+            .expect("seed unrelated file");
+
+        let dest = dir.path().join("export.zip");
+        let size = export_logs_impl(dir.path(), &dest).expect("export logs");
+        assert!(size > 0);
+
+        let zip_file = fs::File::open(&dest).expect("open exported zip");
+        let mut archive = zip::ZipArchive::new(zip_file).expect("read zip");
+        let mut names: Vec<String> = (0..archive.len())
+            .map(|i| archive.by_index(i).expect("entry").name().to_string())
+            .collect();
+        names.sort();
+        assert_eq!(names, vec!["bot.log", "dashboard_errors.log"]);
+    }
+
+    #[test]
+    fn export_logs_impl_empty_dir_produces_empty_archive() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let dest = dir.path().join("export.zip");
+        let size = export_logs_impl(dir.path(), &dest).expect("export logs");
+        assert!(size > 0, "an empty zip still has central-directory bytes");
+
+        let zip_file = fs::File::open(&dest).expect("open exported zip");
+        let archive = zip::ZipArchive::new(zip_file).expect("read zip");
+        assert_eq!(archive.len(), 0);
+    }
+
+    #[test]
+    fn export_logs_impl_includes_every_file_under_the_cap() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        // The skip branch needs more than the real 50 MiB cap to trigger, so
+        // it is not exercised here. This proves the running-total accounting
+        // packages ALL of several small files that together stay under the
+        // cap — the path that matters for real-world log directories.
+        fs::write(dir.path().join("bot.log"), vec![b'a'; 1024]).expect("seed bot.log");
+        fs::write(dir.path().join("dashboard_errors.log.old"), vec![b'b'; 1024])
+            .expect("seed rotated error log");
+
+        let dest = dir.path().join("export.zip");
+        export_logs_impl(dir.path(), &dest).expect("export logs");
+        let zip_file = fs::File::open(&dest).expect("open exported zip");
+        let mut archive = zip::ZipArchive::new(zip_file).expect("read zip");
+        let mut names: Vec<String> = (0..archive.len())
+            .map(|i| archive.by_index(i).expect("entry").name().to_string())
+            .collect();
+        names.sort();
+        assert_eq!(names, vec!["bot.log", "dashboard_errors.log.old"]);
+    }
+
+    // ------- validate_write_path (#1678) -------
+
+    #[test]
+    fn validate_write_path_accepts_a_destination_inside_base() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let dest = dir.path().join("export.zip");
+        let resolved = validate_write_path(dir.path(), dest.to_str().unwrap()).expect("validate");
+        assert_eq!(
+            resolved,
+            dir.path()
+                .canonicalize()
+                .expect("canonicalize base")
+                .join("export.zip")
+        );
+    }
+
+    #[test]
+    fn validate_write_path_accepts_an_existing_destination_as_an_overwrite() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let dest = dir.path().join("export.zip");
+        fs::write(&dest, "stale export").expect("seed stale export");
+        assert!(validate_write_path(dir.path(), dest.to_str().unwrap()).is_ok());
+    }
+
+    #[test]
+    fn validate_write_path_rejects_escaping_via_dotdot() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let base = dir.path().join("base");
+        fs::create_dir(&base).expect("mkdir base");
+        // Sits next to base/, not under it — a real escape target.
+        let dest = dir.path().join("sibling.zip");
+        let dest_str = format!("{}/../sibling.zip", base.display());
+        assert!(validate_write_path(&base, &dest_str).is_err());
+        assert!(!dest.exists(), "escaping write must not have happened");
+    }
+
+    #[test]
+    fn validate_write_path_rejects_missing_parent_directory() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let dest = dir.path().join("missing_subdir").join("export.zip");
+        assert!(validate_write_path(dir.path(), dest.to_str().unwrap()).is_err());
+    }
+
+    #[test]
+    fn validate_write_path_rejects_a_directory_destination() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let subdir = dir.path().join("subdir");
+        fs::create_dir(&subdir).expect("mkdir subdir");
+        assert!(validate_write_path(dir.path(), subdir.to_str().unwrap()).is_err());
+    }
+
+    // ------- resolve_script_path / run_script -------
+
+    #[test]
+    fn resolve_script_path_accepts_a_script_inside_base() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        fs::write(dir.path().join("diag.py"), "print('hi')").expect("seed script");
+        let resolved = BotManager::resolve_script_path(dir.path(), "diag.py").expect("resolve");
+        assert_eq!(
+            resolved,
+            dir.path()
+                .canonicalize()
+                .expect("canonicalize base")
+                .join("diag.py")
+        );
+    }
+
+    #[test]
+    fn resolve_script_path_accepts_a_nested_relative_script() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        fs::create_dir(dir.path().join("scripts")).expect("mkdir scripts");
+        fs::write(dir.path().join("scripts").join("diag.py"), "print('hi')").expect("seed script");
+        assert!(BotManager::resolve_script_path(dir.path(), "scripts/diag.py").is_ok());
+    }
+
+    #[test]
+    fn resolve_script_path_rejects_escaping_via_dotdot() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let base = dir.path().join("base");
+        fs::create_dir(&base).expect("mkdir base");
+        // Sits next to base/, not under it — a real escape target.
+        fs::write(dir.path().join("sibling.py"), "print('pwned')").expect("seed sibling script");
+        assert!(BotManager::resolve_script_path(&base, "../sibling.py").is_err());
+    }
+
+    #[test]
+    fn resolve_script_path_rejects_absolute_relative_path() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let outside = tempfile::tempdir().expect("outside tempdir");
+        let absolute = outside.path().join("evil.py");
+        fs::write(&absolute, "print('pwned')").expect("seed outside script");
+        let result = BotManager::resolve_script_path(dir.path(), absolute.to_str().unwrap());
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn resolve_script_path_rejects_missing_script() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        assert!(BotManager::resolve_script_path(dir.path(), "missing.py").is_err());
+    }
+
+    #[test]
+    fn resolve_script_path_rejects_a_directory() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        fs::create_dir(dir.path().join("subdir")).expect("mkdir subdir");
+        assert!(BotManager::resolve_script_path(dir.path(), "subdir").is_err());
+    }
+
+    #[test]
+    fn run_script_errors_without_a_trusted_interpreter() {
+        let (_dir, bm) = manager_in_temp();
+        // manager_in_temp() roots BotManager at an empty tempdir with no
+        // .venv, so python_cmd resolution falls through to PATH/empty exactly
+        // like the real "no interpreter configured" case start()/start_dev()
+        // already guard against.
+        if !bm.python_cmd.is_empty() {
+            // A real python happens to be on this host's PATH — the guard
+            // this test targets doesn't apply; nothing to assert.
+            return;
+        }
+        let script = bm.base_path.join("diag.py");
+        fs::write(&script, "print('hi')").expect("seed script");
+        let result = bm.run_script("diag.py", &[], 5);
+        assert!(result.is_err());
+    }
+
+    // ------- parse_log_relative_path / log_file / logs_dir -------
+
+    #[test]
+    fn parse_log_relative_path_accepts_a_nested_relative_path() {
+        assert_eq!(
+            parse_log_relative_path("logs/app.log"),
+            Some(PathBuf::from("logs/app.log"))
+        );
+    }
+
+    #[test]
+    fn parse_log_relative_path_accepts_a_bare_filename() {
+        assert_eq!(
+            parse_log_relative_path("app.log"),
+            Some(PathBuf::from("app.log"))
+        );
+    }
+
+    #[test]
+    fn parse_log_relative_path_rejects_absolute_paths() {
+        assert_eq!(parse_log_relative_path("C:\\Windows\\win.ini"), None);
+    }
+
+    #[test]
+    fn parse_log_relative_path_rejects_dotdot_escapes() {
+        assert_eq!(parse_log_relative_path("../outside.log"), None);
+        assert_eq!(parse_log_relative_path("logs/../../outside.log"), None);
+    }
+
+    #[test]
+    fn log_file_defaults_to_logs_bot_log() {
+        let (_dir, bm) = manager_in_temp();
+        assert_eq!(
+            bm.log_relative_path,
+            PathBuf::from(BotManager::DEFAULT_LOG_RELATIVE_PATH)
+        );
+        assert_eq!(bm.log_file(), bm.base_path.join("logs").join("bot.log"));
+        assert_eq!(bm.logs_dir(), bm.base_path.join("logs"));
+    }
+
+    #[test]
+    fn logs_dir_falls_back_to_base_path_for_a_bare_log_filename() {
+        let (_dir, mut bm) = manager_in_temp();
+        bm.log_relative_path = PathBuf::from("app.log");
+        assert_eq!(bm.log_file(), bm.base_path.join("app.log"));
+        assert_eq!(bm.logs_dir(), bm.base_path);
+    }
 }
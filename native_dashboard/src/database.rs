@@ -17,6 +17,39 @@ pub struct DbStats {
     pub rag_memories: i64,
 }
 
+/// Presence + row count of one optional table, as reported by [`DatabaseService::schema_info`].
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TableInfo {
+    pub name: String,
+    pub exists: bool,
+    pub row_count: Option<i64>,
+}
+
+/// Result of [`DatabaseService::schema_info`] — makes the best-effort table
+/// probing that `get_stats` already does (entity_memories, ai_long_term_memory
+/// vs knowledge_entries) explicit and inspectable, instead of silently folding
+/// a missing table into a zero count.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SchemaInfo {
+    pub tables: Vec<TableInfo>,
+    /// Coarse schema generation derived from which optional tables exist:
+    /// 2 if `ai_long_term_memory` is present (current RAG schema), 1 if only
+    /// the older `knowledge_entries` fallback exists, 0 if neither does.
+    pub version: u32,
+}
+
+/// Result of [`DatabaseService::db_status`] — distinguishes "no DB file at
+/// the expected path" (`exists: false`) from "DB present but empty", which
+/// `get_stats` returning all zeroes can't do on its own.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DbStatus {
+    pub exists: bool,
+    pub path: String,
+    pub writable: bool,
+    pub size_bytes: u64,
+    pub tables: Vec<String>,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ChannelInfo {
     #[serde(serialize_with = "serialize_i64_as_string")]
@@ -233,6 +266,130 @@ impl DatabaseService {
         Ok(stats)
     }
 
+    /// Report which of the known optional tables exist and their row counts,
+    /// plus a derived schema "version". Makes the try/ignore table probing
+    /// `get_stats` already does explicit and debuggable, instead of a missing
+    /// table silently collapsing into a zero count with no way to tell "empty"
+    /// from "older schema, table doesn't exist yet".
+    pub fn schema_info(&self) -> Result<SchemaInfo, String> {
+        const KNOWN_TABLES: &[&str] = &[
+            "ai_history",
+            "entity_memories",
+            "ai_long_term_memory",
+            "knowledge_entries",
+        ];
+
+        // A missing DB means none of the known tables exist yet — not an error
+        // (see get_stats' policy).
+        let guard = match self.get_connection() {
+            Ok(guard) => guard,
+            Err(ConnectError::Missing) => {
+                let tables = KNOWN_TABLES
+                    .iter()
+                    .map(|&name| TableInfo {
+                        name: name.to_string(),
+                        exists: false,
+                        row_count: None,
+                    })
+                    .collect();
+                return Ok(SchemaInfo { tables, version: 0 });
+            }
+            Err(e @ ConnectError::Open(_)) => return Err(e.to_string()),
+        };
+        let conn = guard.conn();
+
+        let mut tables = Vec::with_capacity(KNOWN_TABLES.len());
+        for &name in KNOWN_TABLES {
+            let row_count = conn
+                .query_row(&format!("SELECT COUNT(*) FROM {}", name), [], |row| {
+                    row.get::<_, i64>(0)
+                })
+                .ok();
+            tables.push(TableInfo {
+                name: name.to_string(),
+                exists: row_count.is_some(),
+                row_count,
+            });
+        }
+
+        let has = |name: &str| tables.iter().any(|t| t.name == name && t.exists);
+        let version = if has("ai_long_term_memory") {
+            2
+        } else if has("knowledge_entries") {
+            1
+        } else {
+            0
+        };
+
+        Ok(SchemaInfo { tables, version })
+    }
+
+    /// Report whether the database file exists at the expected path, its
+    /// size, whether it's writable, and its table names — so the dashboard
+    /// can tell "no data yet" (exists, empty tables) apart from "DB missing /
+    /// wrong path" (`exists: false`), which `get_stats` returning all zeroes
+    /// can't distinguish on its own.
+    pub fn db_status(&self) -> Result<DbStatus, String> {
+        let path = self.db_path.display().to_string();
+
+        if !self.db_path.exists() {
+            return Ok(DbStatus {
+                exists: false,
+                path,
+                writable: false,
+                size_bytes: 0,
+                tables: Vec::new(),
+            });
+        }
+
+        let size_bytes = std::fs::metadata(&self.db_path)
+            .map(|m| m.len())
+            .unwrap_or(0);
+
+        // Non-destructive writability probe: open for write WITHOUT
+        // truncating, so existing content is untouched either way — just
+        // confirms the OS/permissions would allow a write.
+        let writable = std::fs::OpenOptions::new()
+            .write(true)
+            .open(&self.db_path)
+            .is_ok();
+
+        let guard = match self.get_connection() {
+            Ok(guard) => guard,
+            Err(e @ ConnectError::Open(_)) => return Err(e.to_string()),
+            // Already checked `exists` above; a TOCTOU (deleted between the
+            // check and the connect) still reports `exists: true` with an
+            // empty table list rather than erroring.
+            Err(ConnectError::Missing) => {
+                return Ok(DbStatus {
+                    exists: true,
+                    path,
+                    writable,
+                    size_bytes,
+                    tables: Vec::new(),
+                })
+            }
+        };
+        let conn = guard.conn();
+
+        let mut stmt = conn
+            .prepare("SELECT name FROM sqlite_master WHERE type = 'table' ORDER BY name")
+            .map_err(|e| format!("Failed to query sqlite_master: {}", e))?;
+        let tables = stmt
+            .query_map([], |row| row.get::<_, String>(0))
+            .map_err(|e| format!("Failed to query sqlite_master: {}", e))?
+            .filter_map(Result::ok)
+            .collect();
+
+        Ok(DbStatus {
+            exists: true,
+            path,
+            writable,
+            size_bytes,
+            tables,
+        })
+    }
+
     pub fn get_recent_channels(&self, limit: i32) -> Result<Vec<ChannelInfo>, String> {
         // A missing DB is an empty state, not an error (see get_stats): return an
         // empty list so a fresh install shows "no data", not a red error toast.
@@ -723,6 +880,100 @@ mod tests {
         assert_eq!(svc.get_stats().expect("stats").total_messages, 1);
     }
 
+    // ------- schema_info: explicit table presence + derived version ------------
+
+    #[test]
+    fn schema_info_missing_db_reports_no_tables_version_zero() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let svc = DatabaseService::new(dir.path().join("does_not_exist.db"));
+        let info = svc.schema_info().expect("missing DB must not error");
+        assert_eq!(info.version, 0);
+        assert!(info.tables.iter().all(|t| !t.exists && t.row_count.is_none()));
+    }
+
+    #[test]
+    fn schema_info_detects_current_rag_table_as_version_two() {
+        let (_dir, svc) = service_with_db(|conn| {
+            create_ai_history(conn);
+            conn.execute_batch(
+                "CREATE TABLE ai_long_term_memory (id INTEGER PRIMARY KEY);
+                 INSERT INTO ai_long_term_memory DEFAULT VALUES;",
+            )
+            .expect("seed");
+        });
+        let info = svc.schema_info().expect("schema info");
+        assert_eq!(info.version, 2);
+        let rag = info
+            .tables
+            .iter()
+            .find(|t| t.name == "ai_long_term_memory")
+            .expect("table listed");
+        assert!(rag.exists);
+        assert_eq!(rag.row_count, Some(1));
+        let missing = info
+            .tables
+            .iter()
+            .find(|t| t.name == "knowledge_entries")
+            .expect("table listed");
+        assert!(!missing.exists);
+        assert_eq!(missing.row_count, None);
+    }
+
+    #[test]
+    fn schema_info_falls_back_to_knowledge_entries_as_version_one() {
+        let (_dir, svc) = service_with_db(|conn| {
+            create_ai_history(conn);
+            conn.execute_batch("CREATE TABLE knowledge_entries (id INTEGER PRIMARY KEY);")
+                .expect("seed");
+        });
+        let info = svc.schema_info().expect("schema info");
+        assert_eq!(info.version, 1);
+    }
+
+    // ------- db_status: "no data yet" vs "DB missing / wrong path" -------------
+
+    #[test]
+    fn db_status_missing_db_reports_not_exists() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let path = dir.path().join("does_not_exist.db");
+        let svc = DatabaseService::new(path.clone());
+        let status = svc.db_status().expect("missing DB must not error");
+        assert!(!status.exists);
+        assert!(!status.writable);
+        assert_eq!(status.size_bytes, 0);
+        assert!(status.tables.is_empty());
+        assert_eq!(status.path, path.display().to_string());
+    }
+
+    #[test]
+    fn db_status_existing_db_lists_tables_and_reports_writable() {
+        let (_dir, svc) = service_with_db(|conn| {
+            create_ai_history(conn);
+            conn.execute_batch("CREATE TABLE entity_memories (id INTEGER PRIMARY KEY);")
+                .expect("seed");
+        });
+        let status = svc.db_status().expect("db status");
+        assert!(status.exists);
+        assert!(status.writable);
+        assert!(status.size_bytes > 0);
+        assert!(status.tables.contains(&"ai_history".to_string()));
+        assert!(status.tables.contains(&"entity_memories".to_string()));
+    }
+
+    // ------- Send-safety across `tauri::async_runtime::spawn_blocking` ---------
+
+    // Regression guard for the async DB layer (main.rs wraps every
+    // `DatabaseService` call in `spawn_blocking`, which requires the closure's
+    // captures to be `Send`): if `ConnectionGuard`/`conn_cache` ever grow a
+    // non-`Send` field (e.g. swapping `rusqlite::Connection` for something
+    // `Rc`-backed), this fails to compile instead of deadlocking or panicking
+    // at runtime deep inside tokio's blocking pool.
+    #[test]
+    fn database_service_is_send_and_sync() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<DatabaseService>();
+    }
+
     #[test]
     fn delete_channels_history_binds_ids_as_params_not_sql() {
         // The signature takes `&[i64]`, so a string like "100 OR 1=1" can never
@@ -6,7 +6,7 @@ mod database;
 use bot_manager::{BotManager, BotStatus, RestartBegin, StartProgress, StopOutcome};
 use database::{
     ChannelInfo, DashboardConversation, DashboardConversationDetail, DatabaseService, DbStats,
-    UserInfo,
+    DbStatus, SchemaInfo, UserInfo,
 };
 use std::process::Stdio;
 use std::sync::{Arc, LazyLock, Mutex};
@@ -192,7 +192,7 @@ async fn stop_bot(state: State<'_, AppState>) -> Result<String, String> {
         let mut mgr = manager
             .lock()
             .unwrap_or_else(|e| e.into_inner()); // recover a poisoned lock — same policy as lock_bot_manager! (an Err here would brick Start/Stop/Restart until app restart while status/logs recover)
-        mgr.stop_finish()
+        mgr.stop_finish(Some(pid))
     })
     .await
     .map_err(|e| format!("Task failed: {}", e))?
@@ -244,9 +244,19 @@ async fn restart_bot(state: State<'_, AppState>) -> Result<String, String> {
             .lock()
             .unwrap_or_else(|e| e.into_inner()); // recover a poisoned lock — same policy as lock_bot_manager! (an Err here would brick Start/Stop/Restart until app restart while status/logs recover)
         if needs_finish {
-            let _ = mgr.stop_finish();
+            let _ = mgr.stop_finish(poll_pid);
         }
-        mgr.start()
+        let result = mgr.start();
+        // Distinct from the "start" entry mgr.start() itself just logged —
+        // this is the restart operation's own audit trail, keyed on the OLD
+        // pid it was replacing (the new one is whatever "start" recorded).
+        bot_manager::log_dashboard_op(
+            &mgr.logs_dir(),
+            "restart",
+            if result.is_ok() { "ok" } else { "error" },
+            poll_pid,
+        );
+        result
     })
     .await
     .map_err(|e| format!("Task failed: {}", e))?
@@ -288,12 +298,82 @@ fn get_logs(state: State<AppState>, count: usize) -> Result<Vec<String>, String>
     }
 }
 
+/// Tail the internal control-plane operations log (`logs/dashboard.log`) —
+/// structured JSON lines recording start/stop/restart/watchdog events, the
+/// audit trail for actions that otherwise only ever surfaced as a return
+/// value the frontend discarded once it finished handling it.
+#[tauri::command]
+fn get_dashboard_log(state: State<AppState>, count: usize) -> Result<Vec<String>, String> {
+    let count = count.min(10_000); // same abuse cap as get_logs
+    let manager = lock_bot_manager!(state)?;
+    Ok(manager.read_dashboard_log(count))
+}
+
+/// Result of [`read_logs_since`] — the frontend polls this repeatedly, passing
+/// `next_offset` back in as `offset` next call so only newly-appended log
+/// bytes are re-fetched instead of the same window every tick.
+#[derive(serde::Serialize)]
+struct LogTail {
+    lines: Vec<String>,
+    next_offset: u64,
+}
+
+#[tauri::command]
+fn read_logs_since(state: State<AppState>, offset: u64) -> Result<LogTail, String> {
+    let manager = lock_bot_manager!(state)?;
+    let (lines, next_offset) = manager.read_logs_since(offset);
+    Ok(LogTail { lines, next_offset })
+}
+
 #[tauri::command]
 fn clear_logs(state: State<AppState>) -> Result<String, String> {
     let manager = lock_bot_manager!(state)?;
     manager.clear_logs()
 }
 
+/// Tail a known log file inside `logs/` by basename — `name` must match one
+/// of `BotManager::tail_named_log`'s whitelist (currently the configured bot
+/// log and `dashboard_errors.log`); anything else is a clear error rather
+/// than an arbitrary-file-read.
+#[tauri::command]
+fn tail_named_log(
+    state: State<AppState>,
+    name: String,
+    count: usize,
+) -> Result<Vec<String>, String> {
+    let count = count.min(10_000); // same abuse cap as get_logs
+    let manager = lock_bot_manager!(state)?;
+    manager.tail_named_log(&name, count)
+}
+
+/// Result of `export_logs` — the "export diagnostics" one-click bundle.
+#[derive(serde::Serialize)]
+struct LogExportResult {
+    path: String,
+    size_bytes: u64,
+}
+
+/// Package `bot.log` (+ rotated siblings) and `dashboard_errors.log` into a
+/// single zip at `dest`, capped at 50 MiB uncompressed (see
+/// `bot_manager::export_logs_impl`) so a support request doesn't hand back a
+/// multi-gigabyte attachment. `dest` is resolved and containment-checked via
+/// `bot_manager::validate_write_path` before anything is written, the same
+/// guard any future file-writing command (export stats, backup) should route
+/// through.
+#[tauri::command]
+fn export_logs(state: State<AppState>, dest: String) -> Result<LogExportResult, String> {
+    let (logs_dir, base_path) = {
+        let manager = lock_bot_manager!(state)?;
+        (manager.logs_dir(), manager.base_path().clone())
+    };
+    let dest_path = bot_manager::validate_write_path(&base_path, &dest)?;
+    let size_bytes = bot_manager::export_logs_impl(&logs_dir, &dest_path)?;
+    Ok(LogExportResult {
+        path: dest_path.to_string_lossy().to_string(),
+        size_bytes,
+    })
+}
+
 #[tauri::command]
 fn get_base_path(state: State<AppState>) -> Result<String, String> {
     let manager = lock_bot_manager!(state)?;
@@ -312,6 +392,42 @@ fn get_data_path(state: State<AppState>) -> Result<String, String> {
     Ok(manager.data_dir().to_string_lossy().to_string())
 }
 
+/// Result of `run_script` — the one-off "run a diagnostic/migration with the
+/// bot's own interpreter" command.
+#[derive(serde::Serialize)]
+struct RunScriptResult {
+    stdout: String,
+    stderr: String,
+    exit_code: i32,
+}
+
+/// Run `relative_path` (resolved and containment-checked against the bot's
+/// base path by `BotManager::run_script`, the same way `open_folder`
+/// validates its path) with `self.python_cmd`, killing it if `timeout_secs`
+/// elapses. Runs via `spawn_blocking` (mirroring `start_bot`) since it
+/// blocks the calling thread until the script exits or is killed.
+#[tauri::command]
+async fn run_script(
+    state: State<'_, AppState>,
+    relative_path: String,
+    args: Vec<String>,
+    timeout_secs: u64,
+) -> Result<RunScriptResult, String> {
+    let manager = state.bot_manager.clone();
+
+    tauri::async_runtime::spawn_blocking(move || {
+        let mgr = manager.lock().unwrap_or_else(|e| e.into_inner()); // recover a poisoned lock — same policy as lock_bot_manager!
+        let (stdout, stderr, exit_code) = mgr.run_script(&relative_path, &args, timeout_secs)?;
+        Ok(RunScriptResult {
+            stdout,
+            stderr,
+            exit_code,
+        })
+    })
+    .await
+    .map_err(|e| format!("Task failed: {}", e))?
+}
+
 // DB commands run their lock-holding rusqlite work inside
 // `tauri::async_runtime::spawn_blocking` (mirroring `start_bot`) so a slow or
 // SQLITE_BUSY-blocked query can't pin an IPC worker and freeze unrelated
@@ -332,6 +448,37 @@ async fn get_db_stats(state: State<'_, AppState>) -> Result<DbStats, String> {
     .map_err(|e| format!("Task failed: {}", e))?
 }
 
+/// Explicit table-presence/row-count/version report backing the "older schema
+/// detected, some stats unavailable" banner — see `DatabaseService::schema_info`.
+#[tauri::command]
+async fn get_schema_info(state: State<'_, AppState>) -> Result<SchemaInfo, String> {
+    let db = state.db_service.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        let db = db
+            .lock()
+            .map_err(|e| format!("Failed to acquire database lock: {}", e))?;
+        db.schema_info()
+    })
+    .await
+    .map_err(|e| format!("Task failed: {}", e))?
+}
+
+/// Backs the "no data yet" vs "DB missing / wrong path" distinction that
+/// `get_db_stats` returning all zeroes can't make on its own — see
+/// `DatabaseService::db_status`.
+#[tauri::command]
+async fn db_status(state: State<'_, AppState>) -> Result<DbStatus, String> {
+    let db = state.db_service.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        let db = db
+            .lock()
+            .map_err(|e| format!("Failed to acquire database lock: {}", e))?;
+        db.db_status()
+    })
+    .await
+    .map_err(|e| format!("Task failed: {}", e))?
+}
+
 #[tauri::command]
 async fn get_recent_channels(
     state: State<'_, AppState>,
@@ -627,6 +774,18 @@ fn log_frontend_error(
     let manager = lock_bot_manager!(state)?;
     let log_dir = manager.logs_dir();
     let error_log_path = log_dir.join("dashboard_errors.log");
+    let env_path = manager.base_path().join(".env");
+    // DASHBOARD_ERROR_LOG_MAX_BYTES lets an operator raise/lower the 5MB
+    // default without a rebuild — same read_dotenv_value-then-env-var
+    // fallback `get_ws_endpoint` uses for WS_DASHBOARD_PORT. A missing,
+    // unparsable, or zero value falls back to the 5MB default rather than
+    // disabling rotation, since an unbounded dashboard_errors.log is exactly
+    // the disk-exhaustion failure mode this check exists to prevent.
+    let max_error_log_bytes = read_dotenv_value(&env_path, "DASHBOARD_ERROR_LOG_MAX_BYTES")
+        .or_else(|| std::env::var("DASHBOARD_ERROR_LOG_MAX_BYTES").ok())
+        .and_then(|v| v.trim().parse::<u64>().ok())
+        .filter(|bytes| *bytes > 0)
+        .unwrap_or(5 * 1024 * 1024);
 
     // Ensure logs directory exists
     if !log_dir.exists() {
@@ -671,14 +830,15 @@ fn log_frontend_error(
         ERROR_LOG_SEPARATOR.as_str()
     );
 
-    // Rotate error log if it exceeds 5 MB.
+    // Rotate error log if it exceeds max_error_log_bytes (default 5 MB,
+    // configurable via DASHBOARD_ERROR_LOG_MAX_BYTES).
     // Held under ``error_log_rotation`` so two concurrent log writers
-    // can't both see ``len > 5MB`` and both attempt the rename — on
-    // Windows the second rename would fail because the destination
-    // exists, and on POSIX the first archive would be lost.
+    // can't both see ``len > max_error_log_bytes`` and both attempt the
+    // rename — on Windows the second rename would fail because the
+    // destination exists, and on POSIX the first archive would be lost.
     if error_log_path.exists() {
         if let Ok(meta) = std::fs::metadata(&error_log_path) {
-            if meta.len() > 5 * 1024 * 1024 {
+            if meta.len() > max_error_log_bytes {
                 let _rot_guard = state
                     .error_log_rotation
                     .lock()
@@ -686,7 +846,7 @@ fn log_frontend_error(
                 // Re-check size under the lock — another writer may have
                 // already rotated while we were waiting on the mutex.
                 if let Ok(meta2) = std::fs::metadata(&error_log_path) {
-                    if meta2.len() > 5 * 1024 * 1024 {
+                    if meta2.len() > max_error_log_bytes {
                         let old_path = error_log_path.with_extension("log.old");
                         let _ = std::fs::remove_file(&old_path);
                         if let Err(e) = std::fs::rename(&error_log_path, &old_path) {
@@ -730,6 +890,60 @@ fn log_frontend_error(
     Ok(format!("Error logged to: {}", error_log_path.display()))
 }
 
+// Split on the NEWLINE-PREFIXED separator. Every record is written as
+// "...\n{SEPARATOR}", and sanitized message/stack can never contain
+// "\n" immediately followed by '=' (message strips all newlines; the
+// stack rewrites "\n" -> "\n  "), so this delimiter cannot be faked by
+// user content — unlike the bare 80-'=' run, which a crafted
+// message/stack could embed to fragment one entry into two. Backward
+// compatible: existing logs were already written with the leading \n.
+// Returns entries most-recent-first, capped at `count`. Shared by
+// `get_dashboard_errors` (raw blobs) and `get_dashboard_errors_structured`
+// (parsed into fields) so the two can't drift on what counts as one entry.
+fn split_error_log_entries(content: &str, count: usize) -> Vec<String> {
+    let separator = format!("\n{}", ERROR_LOG_SEPARATOR.as_str());
+    content
+        .split(separator.as_str())
+        .rev()
+        .filter(|s| !s.trim().is_empty())
+        .take(count)
+        .map(|s| s.trim().to_string())
+        .collect()
+}
+
+/// Parses one `split_error_log_entries` entry — written by `log_frontend_error`
+/// as `"[{timestamp}] {error_type}\nMessage: {message}\nStack: {stack_trace}"`
+/// — into its fields. `stack_trace` may itself span multiple lines (indented
+/// continuations), so this only splits the first two `\n`s and keeps
+/// everything after as the stack. Returns `None` for a blob that doesn't
+/// match the expected shape (e.g. a pre-existing placeholder line from an
+/// older log format) rather than fabricating empty fields.
+fn parse_dashboard_error(raw: &str) -> Option<DashboardError> {
+    let mut lines = raw.splitn(3, '\n');
+    let header = lines.next()?.strip_prefix('[')?;
+    let (timestamp, error_type) = header.split_once("] ")?;
+    let message = lines.next()?.strip_prefix("Message: ")?;
+    let stack = lines.next()?.strip_prefix("Stack: ")?;
+
+    Some(DashboardError {
+        timestamp: timestamp.to_string(),
+        error_type: error_type.to_string(),
+        message: message.to_string(),
+        stack: stack.to_string(),
+    })
+}
+
+/// One parsed record from `dashboard_errors.log`, returned by
+/// `get_dashboard_errors_structured` for a sortable/filterable error table —
+/// see `get_dashboard_errors` for the raw-blob variant this is parsed from.
+#[derive(serde::Serialize)]
+struct DashboardError {
+    timestamp: String,
+    error_type: String,
+    message: String,
+    stack: String,
+}
+
 #[tauri::command]
 fn get_dashboard_errors(state: State<AppState>, count: usize) -> Result<Vec<String>, String> {
     let count = count.min(500); // Cap to prevent abuse
@@ -748,28 +962,45 @@ fn get_dashboard_errors(state: State<AppState>, count: usize) -> Result<Vec<Stri
     }
 
     match std::fs::read_to_string(&error_log_path) {
-        Ok(content) => {
-            // Split on the NEWLINE-PREFIXED separator. Every record is written as
-            // "...\n{SEPARATOR}", and sanitized message/stack can never contain
-            // "\n" immediately followed by '=' (message strips all newlines; the
-            // stack rewrites "\n" -> "\n  "), so this delimiter cannot be faked by
-            // user content — unlike the bare 80-'=' run, which a crafted
-            // message/stack could embed to fragment one entry into two. Backward
-            // compatible: existing logs were already written with the leading \n.
-            let separator = format!("\n{}", ERROR_LOG_SEPARATOR.as_str());
-            let entries: Vec<&str> = content.split(separator.as_str()).collect();
-            Ok(entries
-                .iter()
-                .rev()
-                .filter(|s| !s.trim().is_empty())
-                .take(count)
-                .map(|s| s.trim().to_string())
-                .collect())
-        }
+        Ok(content) => Ok(split_error_log_entries(&content, count)),
         Err(_) => Ok(vec!["Failed to read error log.".to_string()]),
     }
 }
 
+/// Structured counterpart to `get_dashboard_errors` — same file, same 10MB
+/// read cap and 500-entry abuse cap, parsed into `DashboardError` fields
+/// instead of opaque blobs so the UI can render a sortable/filterable table.
+/// A blob that fails to parse (see `parse_dashboard_error`) is dropped
+/// rather than surfaced as a malformed row; `get_dashboard_errors` remains
+/// the raw fallback for anything this can't make sense of.
+#[tauri::command]
+fn get_dashboard_errors_structured(
+    state: State<AppState>,
+    count: usize,
+) -> Result<Vec<DashboardError>, String> {
+    let count = count.min(500);
+    let manager = lock_bot_manager!(state)?;
+    let error_log_path = manager.logs_dir().join("dashboard_errors.log");
+
+    if !error_log_path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let metadata = std::fs::metadata(&error_log_path)
+        .map_err(|e| format!("Failed to read error log metadata: {}", e))?;
+    if metadata.len() > 10 * 1024 * 1024 {
+        return Err("Error log too large (>10 MB). Please clear it first.".to_string());
+    }
+
+    let content = std::fs::read_to_string(&error_log_path)
+        .map_err(|e| format!("Failed to read error log: {}", e))?;
+
+    Ok(split_error_log_entries(&content, count)
+        .iter()
+        .filter_map(|raw| parse_dashboard_error(raw))
+        .collect())
+}
+
 #[tauri::command]
 fn clear_dashboard_errors(state: State<AppState>) -> Result<String, String> {
     let manager = lock_bot_manager!(state)?;
@@ -952,37 +1183,7 @@ fn read_dotenv_value(env_path: &std::path::Path, key: &str) -> Option<String> {
             line = rest.trim_start();
         }
         if let Some(val) = line.strip_prefix(&prefix) {
-            // Mirror python-dotenv (the bot parses the SAME file):
-            //   * A quoted value returns the content between the quotes, and any
-            //     trailing text after the closing quote (an inline comment) is
-            //     discarded. `#` INSIDE the quotes is preserved.
-            //   * An unquoted value has its ` # comment` stripped.
-            // The old code required the WHOLE trimmed value to be quote-wrapped,
-            // so `KEY="tok"  # rotate` fell through to the unquoted branch and
-            // returned `"tok"` WITH quotes → the dashboard sent a quoted token,
-            // the bot expected `tok`, and the WS handshake 401'd with no hint.
-            let t = val.trim();
-            let first = t.chars().next();
-            let val: &str = if first == Some('"') || first == Some('\'') {
-                let q = first.unwrap();
-                // Closing quote = first matching quote after the opener. Index 1
-                // is a char boundary (the opener is a 1-byte ASCII quote).
-                match t[1..].find(q) {
-                    // Content between the quotes; trailing comment (if any) dropped.
-                    Some(rel) => &t[1..rel + 1],
-                    // Unterminated quote (malformed) — treat as unquoted, matching
-                    // the prior fall-through for a mismatched pair like `"value'`.
-                    None => match t.find(" #") {
-                        Some(i) => t[..i].trim_end(),
-                        None => t,
-                    },
-                }
-            } else {
-                match t.find(" #") {
-                    Some(i) => t[..i].trim_end(),
-                    None => t,
-                }
-            };
+            let val = parse_dotenv_raw_value(val);
             if !val.is_empty() {
                 return Some(val.to_string());
             }
@@ -991,6 +1192,188 @@ fn read_dotenv_value(env_path: &std::path::Path, key: &str) -> Option<String> {
     None
 }
 
+/// Decode a `.env` line's raw right-hand side, shared by `read_dotenv_value`
+/// (looks up one key) and `parse_dotenv_all` (reads every key).
+///
+/// Mirrors python-dotenv (the bot parses the SAME file):
+///   * A quoted value returns the content between the quotes, and any
+///     trailing text after the closing quote (an inline comment) is
+///     discarded. `#` INSIDE the quotes is preserved.
+///   * An unquoted value has its ` # comment` stripped.
+/// The old code required the WHOLE trimmed value to be quote-wrapped, so
+/// `KEY="tok"  # rotate` fell through to the unquoted branch and returned
+/// `"tok"` WITH quotes — the dashboard sent a quoted token, the bot expected
+/// `tok`, and the WS handshake 401'd with no hint.
+fn parse_dotenv_raw_value(raw: &str) -> &str {
+    let t = raw.trim();
+    let first = t.chars().next();
+    if first == Some('"') || first == Some('\'') {
+        let q = first.unwrap();
+        // Closing quote = first matching quote after the opener. Index 1 is
+        // a char boundary (the opener is a 1-byte ASCII quote).
+        match t[1..].find(q) {
+            // Content between the quotes; trailing comment (if any) dropped.
+            Some(rel) => &t[1..rel + 1],
+            // Unterminated quote (malformed) — treat as unquoted, matching
+            // the prior fall-through for a mismatched pair like `"value'`.
+            None => match t.find(" #") {
+                Some(i) => t[..i].trim_end(),
+                None => t,
+            },
+        }
+    } else {
+        match t.find(" #") {
+            Some(i) => t[..i].trim_end(),
+            None => t,
+        }
+    }
+}
+
+/// Parse every `KEY=VALUE` line out of a `.env` file, applying the same
+/// BOM / `export ` / quoting rules as `read_dotenv_value`. Returns an empty
+/// `Vec` (not an error) when the file is missing — same "absence is fine"
+/// contract `get_ws_token` falls back on for the environment instead.
+fn parse_dotenv_all(env_path: &std::path::Path) -> Vec<(String, String)> {
+    let Ok(content) = std::fs::read_to_string(env_path) else {
+        return Vec::new();
+    };
+    let mut pairs = Vec::new();
+    for (idx, raw_line) in content.lines().enumerate() {
+        let mut line = raw_line.trim();
+        if idx == 0 {
+            line = line.trim_start_matches('\u{feff}');
+        }
+        if let Some(rest) = line.strip_prefix("export ") {
+            line = rest.trim_start();
+        }
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let Some((key, raw_value)) = line.split_once('=') else {
+            continue;
+        };
+        let key = key.trim();
+        if key.is_empty() {
+            continue;
+        }
+        let value = parse_dotenv_raw_value(raw_value);
+        if !value.is_empty() {
+            pairs.push((key.to_string(), value.to_string()));
+        }
+    }
+    pairs
+}
+
+/// Key-name substrings (case-insensitive) that mark a `.env` entry as
+/// secret-like. `get_bot_config` never returns the raw value for a matching
+/// key — it reports `"<redacted>"` instead, so a secret added to `.env`
+/// down the line stays hidden by naming convention rather than requiring
+/// this command to be kept in sync with `env.example` by hand.
+const SECRET_KEY_MARKERS: &[&str] =
+    &["token", "key", "secret", "password", "credential", "dsn", "webhook"];
+
+fn is_secret_like_key(key: &str) -> bool {
+    let lower = key.to_ascii_lowercase();
+    SECRET_KEY_MARKERS.iter().any(|marker| lower.contains(marker))
+}
+
+/// Result of [`get_bot_config`] — a read-only, sanitized snapshot of the
+/// bot's static configuration for the dashboard's config panel.
+#[derive(serde::Serialize)]
+struct BotConfigOverview {
+    command_prefix: String,
+    claude_model: String,
+    claude_backend: String,
+    enabled_modules: Vec<String>,
+    /// Every other `.env` setting, in sorted key order. Values for keys
+    /// matching [`SECRET_KEY_MARKERS`] are replaced with `"<redacted>"` so
+    /// an operator can see a secret is SET without ever seeing it.
+    settings: std::collections::BTreeMap<String, String>,
+}
+
+/// Enumerate the cogs `bot.py`'s `setup_hook` will auto-load at startup:
+/// every `*.py` file directly under `cogs/` except the ones it explicitly
+/// skips (`skip_modules` in `bot.py`), plus `cogs.music` and
+/// `cogs.ai_core.ai_cog`, which load from their own subdirectories rather
+/// than a top-level `cogs/*.py` file.
+fn list_enabled_cog_modules(base_path: &std::path::Path) -> Vec<String> {
+    const SKIP_MODULES: &[&str] = &["__init__.py", "music_utils.py", "spotify_handler.py"];
+
+    let mut modules = Vec::new();
+    if let Ok(entries) = std::fs::read_dir(base_path.join("cogs")) {
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.extension().and_then(|e| e.to_str()) != Some("py") {
+                continue;
+            }
+            let Some(name) = path.file_name().and_then(|n| n.to_str()) else {
+                continue;
+            };
+            if SKIP_MODULES.contains(&name) {
+                continue;
+            }
+            if let Some(stem) = path.file_stem().and_then(|s| s.to_str()) {
+                modules.push(format!("cogs.{stem}"));
+            }
+        }
+    }
+    if base_path.join("cogs").join("music").is_dir() {
+        modules.push("cogs.music".to_string());
+    }
+    if base_path.join("cogs").join("ai_core").join("ai_cog.py").exists() {
+        modules.push("cogs.ai_core.ai_cog".to_string());
+    }
+    modules.sort();
+    modules
+}
+
+/// Read-only, sanitized overview of the bot's static configuration for the
+/// dashboard's config panel — complements `get_ws_token`'s single-secret
+/// read with a broader view: command prefix, active Claude model/backend,
+/// the auto-discovered cog list, and every other `.env` setting (with
+/// anything secret-like redacted). The Discord token is never included:
+/// `DISCORD_TOKEN` matches `SECRET_KEY_MARKERS`' `"token"` marker like any
+/// other token-shaped key.
+#[tauri::command]
+fn get_bot_config(state: State<AppState>) -> Result<BotConfigOverview, String> {
+    let manager = lock_bot_manager!(state)?;
+    let base_path = manager.base_path().clone();
+    drop(manager);
+
+    let pairs = parse_dotenv_all(&base_path.join(".env"));
+    let mut settings = std::collections::BTreeMap::new();
+    for (key, value) in pairs {
+        let display = if is_secret_like_key(&key) {
+            "<redacted>".to_string()
+        } else {
+            value
+        };
+        settings.insert(key, display);
+    }
+
+    let claude_model = settings
+        .get("CLAUDE_MODEL")
+        .cloned()
+        .unwrap_or_else(|| "claude-opus-4-8".to_string());
+    let claude_backend = settings
+        .get("CLAUDE_BACKEND")
+        .cloned()
+        .unwrap_or_else(|| "cli".to_string());
+    // The prefix isn't a .env setting — it's hardcoded in bot.py's
+    // `commands.Bot(command_prefix="!", ...)` call — report the literal
+    // rather than implying it's configurable.
+    let command_prefix = "!".to_string();
+    let enabled_modules = list_enabled_cog_modules(&base_path);
+
+    Ok(BotConfigOverview {
+        command_prefix,
+        claude_model,
+        claude_backend,
+        enabled_modules,
+        settings,
+    })
+}
+
 /// Get the path to the dashboard config file that stores the bot base path.
 fn get_config_path() -> std::path::PathBuf {
     dirs::config_dir()
@@ -1445,11 +1828,18 @@ fn main() {
             stop_bot,
             restart_bot,
             get_logs,
+            get_dashboard_log,
+            read_logs_since,
             clear_logs,
+            tail_named_log,
+            export_logs,
             get_base_path,
             get_logs_path,
             get_data_path,
+            run_script,
             get_db_stats,
+            get_schema_info,
+            db_status,
             get_recent_channels,
             get_top_users,
             get_dashboard_conversations_native,
@@ -1460,11 +1850,13 @@ fn main() {
             open_folder,
             log_frontend_error,
             get_dashboard_errors,
+            get_dashboard_errors_structured,
             clear_dashboard_errors,
             get_ws_token,
             get_ws_endpoint,
             get_telemetry_enabled,
-            set_telemetry_enabled
+            set_telemetry_enabled,
+            get_bot_config
         ])
         .run(tauri::generate_context!())
         .unwrap_or_else(|e| {
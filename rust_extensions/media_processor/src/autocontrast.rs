@@ -0,0 +1,197 @@
+//! Auto-contrast (auto-levels): per-channel histogram stretching.
+
+use image::{DynamicImage, GenericImageView};
+
+use crate::decode;
+use crate::errors::MediaError;
+use crate::histogram;
+use crate::ImageData;
+
+/// `clip_percent` is clamped to this range — beyond 10% either side starts
+/// clipping so much of the image that the result looks posterized rather
+/// than corrected.
+const MAX_CLIP_PERCENT: f32 = 10.0;
+
+/// Stretch each of R/G/B independently from its `clip_percent`/`100 -
+/// clip_percent` percentile to the full 0-255 range, encoding the result
+/// back to the source format. This is the standard "auto levels" fix for a
+/// washed-out (low-contrast) photo: clipping a small percentile at each end
+/// keeps a few outlier pixels (sensor noise, a stray highlight) from
+/// pinning the stretch and leaving the bulk of the image unchanged.
+pub fn auto_contrast_image(data: &[u8], clip_percent: f32) -> Result<ImageData, MediaError> {
+    let clip_percent = clip_percent.clamp(0.0, MAX_CLIP_PERCENT);
+    let (img, format) = decode::decode_with_guard(data, decode::MAX_PIXEL_COUNT)?;
+    let hist = histogram::histogram_from_image(&img);
+    let (width, height) = img.dimensions();
+
+    let lut_r = build_lut(&hist.r, hist.pixel_count(), clip_percent);
+    let lut_g = build_lut(&hist.g, hist.pixel_count(), clip_percent);
+    let lut_b = build_lut(&hist.b, hist.pixel_count(), clip_percent);
+
+    let mut rgba = img.to_rgba8();
+    for pixel in rgba.pixels_mut() {
+        pixel[0] = lut_r[pixel[0] as usize];
+        pixel[1] = lut_g[pixel[1] as usize];
+        pixel[2] = lut_b[pixel[2] as usize];
+    }
+
+    let stretched = DynamicImage::ImageRgba8(rgba);
+    let output = encode_to_format(&stretched, &format)?;
+
+    Ok(ImageData {
+        data: output,
+        width,
+        height,
+        channels: stretched.color().channel_count(),
+        format,
+        is_raw_pixels: false,
+    })
+}
+
+/// Build a 256-entry lookup table mapping `[low, high]` (the values below/
+/// above which `clip_percent` of the pixel count sits) linearly onto
+/// `[0, 255]`, clamping outside that range. Falls back to the identity
+/// mapping when the channel has no dynamic range to stretch (a solid color,
+/// or an empty image), since `(high - low) == 0` would otherwise divide by
+/// zero.
+fn build_lut(bins: &[u32; 256], pixel_count: u64, clip_percent: f32) -> [u8; 256] {
+    let clip_fraction = (clip_percent / 100.0) as f64;
+    let low = clipped_low(bins, pixel_count, clip_fraction);
+    let high = clipped_high(bins, pixel_count, clip_fraction);
+
+    let mut lut = [0u8; 256];
+    if high <= low {
+        for (v, slot) in lut.iter_mut().enumerate() {
+            *slot = v as u8;
+        }
+        return lut;
+    }
+
+    let range = (high - low) as f32;
+    for (v, slot) in lut.iter_mut().enumerate() {
+        let scaled = (v as i32 - low as i32) as f32 * 255.0 / range;
+        *slot = scaled.round().clamp(0.0, 255.0) as u8;
+    }
+    lut
+}
+
+/// Re-encode `img` to `format` (one of `decode_with_guard`'s detected
+/// `"png"`/`"jpeg"`/`"gif"`/`"webp"` strings), so `auto_contrast`'s output
+/// matches its input's format rather than always converting to PNG the way
+/// `quantize_image` does.
+fn encode_to_format(img: &DynamicImage, format: &str) -> Result<Vec<u8>, MediaError> {
+    let image_format = match format {
+        "png" => image::ImageFormat::Png,
+        "jpeg" => image::ImageFormat::Jpeg,
+        "gif" => image::ImageFormat::Gif,
+        "webp" => image::ImageFormat::WebP,
+        other => return Err(MediaError::UnsupportedFormat(other.to_string())),
+    };
+
+    let mut output = Vec::new();
+    img.write_to(&mut std::io::Cursor::new(&mut output), image_format)?;
+    Ok(output)
+}
+
+/// The darkest bin value after excluding the bottom `clip_fraction` of the
+/// pixel count — i.e. the first bin (scanning from 0) whose cumulative
+/// count exceeds that exclusion, so `clip_fraction=0.0` returns the darkest
+/// populated bin rather than always 0.
+fn clipped_low(bins: &[u32; 256], pixel_count: u64, clip_fraction: f64) -> u8 {
+    if pixel_count == 0 {
+        return 0;
+    }
+    let exclude = (clip_fraction * pixel_count as f64).floor() as u64;
+    let mut cumulative: u64 = 0;
+    for (value, &count) in bins.iter().enumerate() {
+        cumulative += count as u64;
+        if cumulative > exclude {
+            return value as u8;
+        }
+    }
+    255
+}
+
+/// The brightest bin value after excluding the top `clip_fraction` of the
+/// pixel count — the mirror image of [`clipped_low`], scanning from 255
+/// down.
+fn clipped_high(bins: &[u32; 256], pixel_count: u64, clip_fraction: f64) -> u8 {
+    if pixel_count == 0 {
+        return 0;
+    }
+    let exclude = (clip_fraction * pixel_count as f64).floor() as u64;
+    let mut cumulative: u64 = 0;
+    for (value, &count) in bins.iter().enumerate().rev() {
+        cumulative += count as u64;
+        if cumulative > exclude {
+            return value as u8;
+        }
+    }
+    0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn png_of(w: u32, h: u32, f: impl Fn(u32, u32) -> [u8; 3]) -> Vec<u8> {
+        let img = image::RgbImage::from_fn(w, h, |x, y| image::Rgb(f(x, y)));
+        let mut out = Vec::new();
+        DynamicImage::ImageRgb8(img)
+            .write_to(&mut Cursor::new(&mut out), image::ImageFormat::Png)
+            .unwrap();
+        out
+    }
+
+    #[test]
+    fn stretches_a_washed_out_gradient_to_the_full_range() {
+        // A narrow-range gradient (100..=150) should stretch out toward 0..255.
+        let washed_out = png_of(64, 1, |x, _| {
+            let v = 100 + (x * 50 / 63) as u8;
+            [v, v, v]
+        });
+        let result = auto_contrast_image(&washed_out, 0.0).unwrap();
+        assert_eq!(result.format, "png");
+
+        let decoded = image::load_from_memory(&result.data).unwrap().to_rgb8();
+        let values: Vec<u8> = decoded.pixels().map(|p| p[0]).collect();
+        assert_eq!(*values.iter().min().unwrap(), 0);
+        assert_eq!(*values.iter().max().unwrap(), 255);
+    }
+
+    #[test]
+    fn solid_color_image_is_left_unchanged_by_the_identity_fallback() {
+        let solid = png_of(4, 4, |_, _| [42, 42, 42]);
+        let result = auto_contrast_image(&solid, 2.0).unwrap();
+
+        let decoded = image::load_from_memory(&result.data).unwrap().to_rgb8();
+        assert!(decoded.pixels().all(|p| p[0] == 42));
+    }
+
+    #[test]
+    fn clip_percent_is_clamped_above_ten() {
+        // clip_percent=50 would otherwise clip past the median, putting the
+        // low percentile above the high one; clamped to 10 it must still
+        // produce a valid image.
+        let washed_out = png_of(32, 1, |x, _| {
+            let v = 100 + (x * 50 / 31) as u8;
+            [v, v, v]
+        });
+        assert!(auto_contrast_image(&washed_out, 50.0).is_ok());
+    }
+
+    #[test]
+    fn rejects_a_pixel_count_bomb_header() {
+        let mut png = vec![
+            0x89, 0x50, 0x4E, 0x47, 0x0D, 0x0A, 0x1A, 0x0A, // PNG signature
+            0x00, 0x00, 0x00, 0x0D, b'I', b'H', b'D', b'R',
+        ];
+        png.extend_from_slice(&20_000u32.to_be_bytes());
+        png.extend_from_slice(&20_000u32.to_be_bytes());
+        png.extend_from_slice(&[8, 6, 0, 0, 0, 0, 0, 0, 0]); // rest of IHDR + bogus CRC
+
+        assert!(auto_contrast_image(&png, 1.0).is_err());
+    }
+}
+
@@ -0,0 +1,85 @@
+//! Cooperative cancellation for long-running batch operations.
+//!
+//! `batch_resize` can take a long time on a big gallery upload, with no way
+//! for a caller to abort once it's started short of dropping the whole
+//! Python process. [`CancelToken`] is a small shared flag a caller can hold
+//! onto, pass into a batch call, and set from elsewhere (another thread, a
+//! UI cancel button's callback) — the batch loop checks it between items and
+//! bails out early with [`crate::MediaError::Cancelled`] instead of running
+//! to completion.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use pyo3::prelude::*;
+
+/// A shared, settable cancellation flag for a batch operation.
+///
+/// One token can be reused across several sequential batch calls (e.g. a
+/// gallery uploader that resizes a series of pages) — it doesn't reset
+/// itself on read, so once cancelled it stays cancelled until [`Self::reset`]
+/// is called.
+#[pyclass]
+#[derive(Default)]
+pub struct CancelToken {
+    flag: Arc<AtomicBool>,
+}
+
+#[pymethods]
+impl CancelToken {
+    #[new]
+    fn new() -> Self {
+        Self::default()
+    }
+
+    /// Request cancellation. Safe to call from any thread, including while a
+    /// batch call holding this token is running with the GIL released.
+    fn cancel(&self) {
+        self.flag.store(true, Ordering::Relaxed);
+    }
+
+    fn is_cancelled(&self) -> bool {
+        self.flag.load(Ordering::Relaxed)
+    }
+
+    /// Clear a previously-set cancellation so the token can be reused.
+    fn reset(&self) {
+        self.flag.store(false, Ordering::Relaxed);
+    }
+}
+
+impl CancelToken {
+    /// The shared flag itself, for a batch loop to poll after `py.detach`
+    /// (a `&CancelToken` borrowed under the GIL can't cross that boundary,
+    /// but the `Arc` it wraps can be cloned out first and polled freely).
+    pub(crate) fn flag(&self) -> Arc<AtomicBool> {
+        self.flag.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn starts_out_not_cancelled() {
+        assert!(!CancelToken::new().is_cancelled());
+    }
+
+    #[test]
+    fn cancel_sets_the_flag_visible_through_a_clone() {
+        let token = CancelToken::new();
+        let flag = token.flag();
+        token.cancel();
+        assert!(token.is_cancelled());
+        assert!(flag.load(Ordering::Relaxed));
+    }
+
+    #[test]
+    fn reset_clears_a_cancelled_token() {
+        let token = CancelToken::new();
+        token.cancel();
+        token.reset();
+        assert!(!token.is_cancelled());
+    }
+}
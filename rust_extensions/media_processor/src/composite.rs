@@ -0,0 +1,131 @@
+//! Image compositing (alpha-blended overlays)
+
+use image::{DynamicImage, GenericImageView, ImageFormat};
+use std::io::Cursor;
+
+use crate::decode;
+use crate::errors::MediaError;
+use crate::ImageData;
+
+/// Alpha-blend `overlay` onto `base` at `(x, y)`, scaled by `opacity`.
+///
+/// `x`/`y` may be negative (partial off-canvas overlay); overlay pixels that
+/// land outside the base bounds are clipped. `opacity` is clamped to
+/// `[0.0, 1.0]` and multiplies the overlay's own alpha channel before
+/// blending. Always encodes to PNG so any resulting transparency survives.
+///
+/// Both inputs go through the shared bomb guard (`decode::decode_with_guard`)
+/// here rather than relying on a caller to have checked first — this used to
+/// be the one decode entry point in the crate without its own guard.
+pub fn overlay_image(
+    base: &[u8],
+    overlay: &[u8],
+    x: i32,
+    y: i32,
+    opacity: f32,
+) -> Result<ImageData, MediaError> {
+    let (base_img, _format) = decode::decode_with_guard(base, decode::MAX_PIXEL_COUNT)?;
+    let (overlay_img, _format) = decode::decode_with_guard(overlay, decode::MAX_PIXEL_COUNT)?;
+    let opacity = opacity.clamp(0.0, 1.0);
+
+    let mut base_rgba = base_img.to_rgba8();
+    let overlay_rgba = overlay_img.to_rgba8();
+    let (base_w, base_h) = base_rgba.dimensions();
+    let (ov_w, ov_h) = overlay_rgba.dimensions();
+
+    for oy in 0..ov_h {
+        let dst_y = y + oy as i32;
+        if dst_y < 0 || dst_y as u32 >= base_h {
+            continue;
+        }
+        for ox in 0..ov_w {
+            let dst_x = x + ox as i32;
+            if dst_x < 0 || dst_x as u32 >= base_w {
+                continue;
+            }
+
+            let overlay_px = overlay_rgba.get_pixel(ox, oy);
+            let src_a = (overlay_px[3] as f32 / 255.0) * opacity;
+            if src_a <= 0.0 {
+                continue;
+            }
+
+            let dst_px = base_rgba.get_pixel(dst_x as u32, dst_y as u32);
+            let dst_a = dst_px[3] as f32 / 255.0;
+            let out_a = src_a + dst_a * (1.0 - src_a);
+
+            let blend = |src: u8, dst: u8| -> u8 {
+                if out_a <= 0.0 {
+                    return 0;
+                }
+                let src = src as f32 / 255.0;
+                let dst = dst as f32 / 255.0;
+                let out = (src * src_a + dst * dst_a * (1.0 - src_a)) / out_a;
+                (out * 255.0).round().clamp(0.0, 255.0) as u8
+            };
+
+            let out = image::Rgba([
+                blend(overlay_px[0], dst_px[0]),
+                blend(overlay_px[1], dst_px[1]),
+                blend(overlay_px[2], dst_px[2]),
+                (out_a * 255.0).round().clamp(0.0, 255.0) as u8,
+            ]);
+            base_rgba.put_pixel(dst_x as u32, dst_y as u32, out);
+        }
+    }
+
+    let composited = DynamicImage::ImageRgba8(base_rgba);
+    let (width, height) = composited.dimensions();
+    let mut output = Vec::new();
+    composited.write_to(&mut Cursor::new(&mut output), ImageFormat::Png)?;
+
+    Ok(ImageData {
+        data: output,
+        width,
+        height,
+        channels: composited.color().channel_count(),
+        format: "png".to_string(),
+        is_raw_pixels: false,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn solid_png(w: u32, h: u32, color: [u8; 4]) -> Vec<u8> {
+        let img = image::RgbaImage::from_pixel(w, h, image::Rgba(color));
+        let mut out = Vec::new();
+        DynamicImage::ImageRgba8(img)
+            .write_to(&mut Cursor::new(&mut out), ImageFormat::Png)
+            .unwrap();
+        out
+    }
+
+    #[test]
+    fn overlay_opaque_replaces_base_pixels() {
+        let base = solid_png(10, 10, [0, 0, 0, 255]);
+        let logo = solid_png(4, 4, [255, 0, 0, 255]);
+        let out = overlay_image(&base, &logo, 2, 2, 1.0).unwrap();
+        assert_eq!((out.width, out.height), (10, 10));
+        assert_eq!(out.format, "png");
+    }
+
+    #[test]
+    fn overlay_clips_negative_and_out_of_bounds_coordinates() {
+        let base = solid_png(4, 4, [0, 0, 0, 255]);
+        let logo = solid_png(4, 4, [255, 0, 0, 255]);
+        // Overlay is shifted so only its bottom-right corner overlaps the base.
+        let out = overlay_image(&base, &logo, -2, -2, 1.0).unwrap();
+        assert_eq!((out.width, out.height), (4, 4));
+    }
+
+    #[test]
+    fn overlay_zero_opacity_leaves_base_unchanged() {
+        let base = solid_png(4, 4, [10, 20, 30, 255]);
+        let logo = solid_png(4, 4, [255, 0, 0, 255]);
+        let out = overlay_image(&base, &logo, 0, 0, 0.0).unwrap();
+        let decoded = image::load_from_memory(&out.data).unwrap().to_rgba8();
+        assert_eq!(*decoded.get_pixel(0, 0), image::Rgba([10, 20, 30, 255]));
+    }
+}
@@ -0,0 +1,178 @@
+//! Shared bomb-guarded decode + format detection.
+//!
+//! `resize_image`, `load_bytes`, `quantize_image`, `overlay_image`, and
+//! `compare_images` all used to carry their own copy of "read the header
+//! dimensions, reject anything past the pixel cap, then decode" — three near-
+//! identical copies plus one call site (`overlay_image`) that had skipped the
+//! guard entirely and relied on its caller to have checked first. This module
+//! is the one place that logic lives now; [`decode_with_guard`] is the entry
+//! point for callers that want a decoded image and its format together,
+//! [`check_dimensions`] is the standalone guard for callers (like
+//! `resize_image`'s ICC-aware decode, or the batch path's whole-chunk
+//! pre-check) that need the pixel-count check on its own.
+
+use image::DynamicImage;
+
+use crate::errors::MediaError;
+
+/// Maximum allowed pixel count before a decode is rejected as a likely
+/// decompression bomb (100 megapixels) — shared by every decode entry point
+/// in this crate.
+pub const MAX_PIXEL_COUNT: u64 = 100_000_000;
+
+/// TIFF magic bytes: "II*\0" (little-endian/Intel byte order).
+const TIFF_MAGIC_LE: &[u8] = b"II*\0";
+/// TIFF magic bytes: "MM\0*" (big-endian/Motorola byte order).
+const TIFF_MAGIC_BE: &[u8] = &[0x4D, 0x4D, 0x00, 0x2A];
+/// ICO magic bytes: reserved=0, image type=1.
+const ICO_MAGIC: &[u8] = &[0x00, 0x00, 0x01, 0x00];
+
+/// TIFF (an arbitrary number of IFDs) and ICO (an arbitrary number of
+/// same-image sizes) are container formats that can pack many
+/// frames/layers into one file — exactly the kind of memory-amplification
+/// risk [`check_dimensions`]'s single-image pixel cap exists for. This
+/// build doesn't compile in either codec (see the `image` feature list in
+/// Cargo.toml: only jpeg/png/gif/webp), so there's no frame/size list to
+/// actually walk and cap here. Rather than let these two formats fall
+/// through to `image`'s own "unsupported format" decode error — which
+/// doesn't distinguish "this is a bomb-risk container we don't parse" from
+/// "this file is simply corrupt" — sniff their magic bytes up front and
+/// reject with a message that says so plainly. If tiff/ico decode support
+/// is ever added to this crate, replace this with real per-format
+/// frame-count/pixel-budget checks instead of a blanket rejection.
+fn reject_unsupported_container_formats(bytes: &[u8]) -> Result<(), MediaError> {
+    if bytes.starts_with(TIFF_MAGIC_LE) || bytes.starts_with(TIFF_MAGIC_BE) {
+        return Err(MediaError::UnsupportedFormat(
+            "TIFF is not decoded by this build, so its layer/frame count can't be bounded before decode".to_string(),
+        ));
+    }
+    if bytes.starts_with(ICO_MAGIC) {
+        return Err(MediaError::UnsupportedFormat(
+            "ICO is not decoded by this build, so its multi-size pixel budget can't be bounded before decode".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Read `bytes`' header dimensions and reject anything whose pixel count
+/// exceeds `max_pixels`, without decoding any pixel data. Also rejects TIFF
+/// and ICO up front — see [`reject_unsupported_container_formats`].
+pub fn check_dimensions(bytes: &[u8], max_pixels: u64) -> Result<(), MediaError> {
+    reject_unsupported_container_formats(bytes)?;
+    let reader = image::ImageReader::new(std::io::Cursor::new(bytes))
+        .with_guessed_format()
+        .map_err(|e| MediaError::Decode(format!("Failed to detect image format: {}", e)))?;
+    match reader.into_dimensions() {
+        Ok((w, h)) => {
+            let product = (w as u64).checked_mul(h as u64).ok_or_else(|| {
+                MediaError::Decode(format!("Image dimensions overflow: {}x{}", w, h))
+            })?;
+            if product > max_pixels {
+                return Err(MediaError::Decode(format!(
+                    "Image too large: {}x{} exceeds {} MP limit",
+                    w,
+                    h,
+                    max_pixels / 1_000_000
+                )));
+            }
+            Ok(())
+        }
+        Err(e) => Err(MediaError::Decode(format!(
+            "Cannot determine image dimensions (possible decompression bomb): {}",
+            e
+        ))),
+    }
+}
+
+/// Bomb-guarded decode: check dimensions, decode, and detect the format from
+/// magic bytes, in that order, so a caller that wants "a decoded image and
+/// what format it came from" isn't three copy-pasted probes away from the
+/// guard. The format string is `crate::detect_format`'s sniff — one of
+/// `"png"`/`"jpeg"`/`"gif"`/`"webp"`, or `"unknown"` if the magic bytes don't
+/// match (which the decode step would already have rejected, since this
+/// build only compiles in codecs for those four formats).
+pub fn decode_with_guard(
+    bytes: &[u8],
+    max_pixels: u64,
+) -> Result<(DynamicImage, String), MediaError> {
+    check_dimensions(bytes, max_pixels)?;
+    let img = image::load_from_memory(bytes)?;
+    let format = crate::detect_format(bytes).unwrap_or("unknown").to_string();
+    Ok((img, format))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn png_header_claiming(width: u32, height: u32) -> Vec<u8> {
+        let mut out = vec![
+            0x89, 0x50, 0x4E, 0x47, 0x0D, 0x0A, 0x1A, 0x0A, // PNG signature
+            0x00, 0x00, 0x00, 0x0D, b'I', b'H', b'D', b'R',
+        ];
+        out.extend_from_slice(&width.to_be_bytes());
+        out.extend_from_slice(&height.to_be_bytes());
+        out.extend_from_slice(&[8, 6, 0, 0, 0, 0, 0, 0, 0]); // rest of IHDR + bogus CRC
+        out
+    }
+
+    fn tiny_png() -> Vec<u8> {
+        let img = image::RgbImage::from_pixel(2, 2, image::Rgb([10, 20, 30]));
+        let mut out = Vec::new();
+        image::DynamicImage::ImageRgb8(img)
+            .write_to(&mut Cursor::new(&mut out), image::ImageFormat::Png)
+            .unwrap();
+        out
+    }
+
+    #[test]
+    fn check_dimensions_rejects_over_the_cap() {
+        let bomb = png_header_claiming(20_000, 20_000);
+        assert!(check_dimensions(&bomb, MAX_PIXEL_COUNT).is_err());
+    }
+
+    #[test]
+    fn check_dimensions_accepts_within_the_cap() {
+        assert!(check_dimensions(&tiny_png(), MAX_PIXEL_COUNT).is_ok());
+    }
+
+    #[test]
+    fn decode_with_guard_rejects_bomb_header_without_decoding_pixels() {
+        let bomb = png_header_claiming(20_000, 20_000);
+        assert!(decode_with_guard(&bomb, MAX_PIXEL_COUNT).is_err());
+    }
+
+    #[test]
+    fn decode_with_guard_returns_image_and_format() {
+        let (img, format) = decode_with_guard(&tiny_png(), MAX_PIXEL_COUNT).unwrap();
+        assert_eq!((img.width(), img.height()), (2, 2));
+        assert_eq!(format, "png");
+    }
+
+    #[test]
+    fn check_dimensions_rejects_tiff_little_endian() {
+        let bytes = [TIFF_MAGIC_LE, &[0, 0, 0, 0]].concat();
+        let err = check_dimensions(&bytes, MAX_PIXEL_COUNT).unwrap_err();
+        assert!(matches!(err, MediaError::UnsupportedFormat(_)));
+    }
+
+    #[test]
+    fn check_dimensions_rejects_tiff_big_endian() {
+        let bytes = [TIFF_MAGIC_BE, &[0, 0, 0, 0]].concat();
+        let err = check_dimensions(&bytes, MAX_PIXEL_COUNT).unwrap_err();
+        assert!(matches!(err, MediaError::UnsupportedFormat(_)));
+    }
+
+    #[test]
+    fn check_dimensions_rejects_ico() {
+        let bytes = [ICO_MAGIC, &[1, 0, 32, 32]].concat();
+        let err = check_dimensions(&bytes, MAX_PIXEL_COUNT).unwrap_err();
+        assert!(matches!(err, MediaError::UnsupportedFormat(_)));
+    }
+
+    #[test]
+    fn check_dimensions_still_accepts_png_after_the_container_guard() {
+        assert!(check_dimensions(&tiny_png(), MAX_PIXEL_COUNT).is_ok());
+    }
+}
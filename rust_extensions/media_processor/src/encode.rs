@@ -1,6 +1,6 @@
 //! Base64 encoding/decoding
 
-use base64::{engine::general_purpose::STANDARD, Engine as _};
+use base64::{engine::general_purpose::STANDARD, engine::Engine as _};
 
 use crate::errors::MediaError;
 
@@ -11,11 +11,58 @@ pub fn to_base64(data: &[u8]) -> String {
 
 /// Decode base64 string to bytes
 pub fn from_base64(encoded: &str) -> Result<Vec<u8>, MediaError> {
+    from_base64_checked(encoded, None)
+}
+
+/// Decode base64 string to bytes, rejecting inputs whose decoded length
+/// would exceed `max_output_bytes` BEFORE allocating the output buffer —
+/// the base64 counterpart to the image side's pre-decode decompression-bomb
+/// guard (`check_bomb_dimensions`), since an attacker-controlled base64
+/// string is otherwise an equally cheap way to force a huge allocation.
+/// `base64::decoded_len_estimate` gives an upper bound purely from the
+/// encoded length, no decoding required, so the cap is enforced without
+/// ever allocating the oversized buffer.
+pub fn from_base64_checked(encoded: &str, max_output_bytes: Option<usize>) -> Result<Vec<u8>, MediaError> {
+    if let Some(max) = max_output_bytes {
+        let estimate = base64::decoded_len_estimate(encoded.len());
+        if estimate > max {
+            return Err(MediaError::Decode(format!(
+                "base64 input decodes to at most {} bytes, exceeding the {} byte limit",
+                estimate, max
+            )));
+        }
+    }
+
     STANDARD
         .decode(encoded)
         .map_err(|e| MediaError::Decode(e.to_string()))
 }
 
+/// Strip a `data:<mime>;base64,<payload>` prefix (as pasted by browsers and
+/// most chat clients) and decode the payload, returning the bytes alongside
+/// the declared MIME type. Bare base64 with no `data:` prefix is rejected
+/// here — callers that want to accept both should try this first and fall
+/// back to `from_base64`/`from_base64_checked` on error, which is exactly
+/// what `MediaProcessor::decode_base64` does.
+pub fn from_data_uri(
+    data_uri: &str,
+    max_output_bytes: Option<usize>,
+) -> Result<(Vec<u8>, String), MediaError> {
+    let rest = data_uri
+        .strip_prefix("data:")
+        .ok_or_else(|| MediaError::Decode("Not a data URI: missing 'data:' prefix".to_string()))?;
+    let (header, payload) = rest
+        .split_once(',')
+        .ok_or_else(|| MediaError::Decode("Not a data URI: missing ',' separator".to_string()))?;
+    let mime = header
+        .strip_suffix(";base64")
+        .ok_or_else(|| MediaError::Decode("Data URI is not base64-encoded".to_string()))?;
+    let mime = if mime.is_empty() { "text/plain" } else { mime };
+
+    let bytes = from_base64_checked(payload, max_output_bytes)?;
+    Ok((bytes, mime.to_string()))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -27,4 +74,66 @@ mod tests {
         let decoded = from_base64(&encoded).expect("Failed to decode base64");
         assert_eq!(original.as_slice(), decoded.as_slice());
     }
+
+    #[test]
+    fn from_base64_checked_accepts_input_within_cap() {
+        let encoded = to_base64(b"Hello, World!");
+        let decoded = from_base64_checked(&encoded, Some(64)).unwrap();
+        assert_eq!(decoded, b"Hello, World!");
+    }
+
+    #[test]
+    fn from_base64_checked_rejects_before_allocating_when_over_cap() {
+        // ~1.33MB of encoded input decodes to ~1MB, comfortably over a 1KB cap.
+        let huge = to_base64(&vec![0u8; 1_000_000]);
+        let err = from_base64_checked(&huge, Some(1024)).unwrap_err();
+        assert!(matches!(err, MediaError::Decode(_)));
+        assert!(err.to_string().contains("1024"));
+    }
+
+    #[test]
+    fn from_base64_checked_with_no_cap_matches_from_base64() {
+        let encoded = to_base64(b"unbounded");
+        assert_eq!(
+            from_base64_checked(&encoded, None).unwrap(),
+            from_base64(&encoded).unwrap()
+        );
+    }
+
+    #[test]
+    fn from_data_uri_strips_prefix_and_returns_mime() {
+        let uri = format!("data:image/png;base64,{}", to_base64(b"fake-png-bytes"));
+        let (bytes, mime) = from_data_uri(&uri, None).unwrap();
+        assert_eq!(bytes, b"fake-png-bytes");
+        assert_eq!(mime, "image/png");
+    }
+
+    #[test]
+    fn from_data_uri_defaults_mime_when_omitted() {
+        let uri = format!("data:;base64,{}", to_base64(b"x"));
+        let (_, mime) = from_data_uri(&uri, None).unwrap();
+        assert_eq!(mime, "text/plain");
+    }
+
+    #[test]
+    fn from_data_uri_rejects_bare_base64() {
+        let encoded = to_base64(b"no prefix here");
+        assert!(matches!(from_data_uri(&encoded, None), Err(MediaError::Decode(_))));
+    }
+
+    #[test]
+    fn from_data_uri_rejects_non_base64_data_uri() {
+        let uri = "data:text/plain,hello%20world";
+        assert!(matches!(from_data_uri(uri, None), Err(MediaError::Decode(_))));
+    }
+
+    #[test]
+    fn from_data_uri_respects_size_cap() {
+        let uri = format!(
+            "data:image/png;base64,{}",
+            to_base64(&vec![0u8; 1_000_000])
+        );
+        let err = from_data_uri(&uri, Some(1024)).unwrap_err();
+        assert!(matches!(err, MediaError::Decode(_)));
+    }
 }
@@ -18,4 +18,7 @@ pub enum MediaError {
 
     #[error("IO error: {0}")]
     Io(#[from] std::io::Error),
+
+    #[error("Operation cancelled")]
+    Cancelled,
 }
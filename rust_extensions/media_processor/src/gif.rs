@@ -1,5 +1,7 @@
 //! GIF animation detection
 
+use crate::errors::MediaError;
+
 /// Check if data is an animated GIF (has multiple frames)
 pub fn is_animated_gif(data: &[u8]) -> bool {
     // Check GIF magic bytes
@@ -214,6 +216,236 @@ pub fn get_gif_frame_count(data: &[u8]) -> usize {
     frame_count
 }
 
+/// Reject a GIF whose frame count exceeds `max_frames`, without decoding any
+/// frame's pixel data — the animation analogue of `decode::check_dimensions`'s
+/// pixel-count guard. A crafted GIF can declare a huge number of tiny frames
+/// while staying small on disk (each frame only needs to survive LZW
+/// compression, not carry unique pixels), so re-encoding or extracting every
+/// frame can blow up memory far out of proportion to the upload size; this
+/// catches that before any such decode starts.
+///
+/// Shares `get_gif_frame_count`'s hand-rolled walker (see its PARITY HAZARD
+/// note) but exits as soon as the count is known to exceed `max_frames`,
+/// rather than always walking to the trailer — a bomb with millions of
+/// declared frames is rejected in O(max_frames) instead of O(file size).
+/// Non-GIF data and a GIF within the limit both return `Ok(())`; this is a
+/// guard against oversized animations, not a "must be an animated GIF" check.
+pub fn check_frame_count(data: &[u8], max_frames: u32) -> Result<(), MediaError> {
+    if data.len() < 13 || (&data[0..6] != b"GIF89a" && &data[0..6] != b"GIF87a") {
+        return Ok(());
+    }
+
+    let mut frame_count: u32 = 0;
+    let mut i: usize = 13; // Skip header
+
+    // Skip Global Color Table if present
+    let flags = data[10];
+    if flags & 0x80 != 0 {
+        let table_size: usize = 3 * (1 << ((flags & 0x07) + 1));
+        i = i.saturating_add(table_size);
+        if i >= data.len() {
+            return Ok(());
+        }
+    }
+
+    // Safety limit to prevent DoS on malformed GIF data
+    let max_iterations = data.len().min(100_000);
+    let mut iterations: usize = 0;
+
+    while i < data.len() && iterations < max_iterations {
+        iterations += 1;
+        match data[i] {
+            0x21 => {
+                // Extension block - skip it
+                if i + 2 >= data.len() {
+                    break;
+                }
+                i += 2;
+                // Skip sub-blocks
+                while i < data.len() && data[i] != 0 {
+                    let block_size = data[i] as usize;
+                    if i.saturating_add(1).saturating_add(block_size) > data.len() {
+                        return Ok(());
+                    }
+                    i += 1 + block_size;
+                }
+                if i >= data.len() {
+                    break;
+                }
+                i += 1; // Skip block terminator
+            }
+            0x2C => {
+                // Image Descriptor = one frame
+                frame_count += 1;
+                if frame_count > max_frames {
+                    return Err(MediaError::Decode(format!(
+                        "Animated GIF exceeds {} frame limit",
+                        max_frames
+                    )));
+                }
+                if i + 10 > data.len() {
+                    break;
+                }
+
+                let local_flags = data[i + 9];
+                i += 10;
+
+                // Skip Local Color Table if present
+                if local_flags & 0x80 != 0 {
+                    let table_size = 3 * (1 << ((local_flags & 0x07) + 1));
+                    if i.saturating_add(table_size) > data.len() {
+                        break;
+                    }
+                    i += table_size;
+                }
+
+                // Skip LZW minimum code size
+                if i >= data.len() {
+                    break;
+                }
+                i += 1;
+
+                // Skip image data sub-blocks
+                while i < data.len() && data[i] != 0 {
+                    let block_size = data[i] as usize;
+                    if i.saturating_add(1).saturating_add(block_size) > data.len() {
+                        return Ok(());
+                    }
+                    i += 1 + block_size;
+                }
+                if i >= data.len() {
+                    break;
+                }
+                i += 1; // Skip block terminator
+            }
+            0x3B => {
+                // Trailer
+                break;
+            }
+            _ => {
+                i += 1;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Sum every frame's Graphic Control Extension delay into a total playback
+/// duration in milliseconds, applying the common browser rule that a delay
+/// of 0 or 1 centiseconds (encoders that mean "as fast as possible", or
+/// omit the field entirely) is rendered as 100ms rather than being treated
+/// as instant. This is total single-loop duration: the `NETSCAPE2.0`
+/// application extension (loop count) is irrelevant to it and is skipped
+/// like any other extension sub-block, never inspected.
+///
+/// Shares the same hand-rolled walker shape as `get_gif_frame_count` (see
+/// its PARITY HAZARD note) — a GCE is `0x21 0xF9 <block size> <flags> <delay
+/// lo> <delay hi> <transparent index> <terminator>`, so the delay is the two
+/// bytes at `i+4`/`i+5` relative to the `0x21`.
+pub fn gif_duration_ms(data: &[u8]) -> u32 {
+    if data.len() < 13 || (&data[0..6] != b"GIF89a" && &data[0..6] != b"GIF87a") {
+        return 0;
+    }
+
+    let mut total_ms: u32 = 0;
+    let mut i: usize = 13; // Skip header
+
+    // Skip Global Color Table if present
+    let flags = data[10];
+    if flags & 0x80 != 0 {
+        let table_size: usize = 3 * (1 << ((flags & 0x07) + 1));
+        i = i.saturating_add(table_size);
+        if i >= data.len() {
+            return total_ms;
+        }
+    }
+
+    // Safety limit to prevent DoS on malformed GIF data
+    let max_iterations = data.len().min(100_000);
+    let mut iterations: usize = 0;
+
+    while i < data.len() && iterations < max_iterations {
+        iterations += 1;
+        match data[i] {
+            0x21 => {
+                // Extension. A Graphic Control Extension is `0x21 0xF9 0x04
+                // <flags> <delay-lo> <delay-hi> <transparent-idx> 0x00`; any
+                // other extension label (including NETSCAPE2.0's Application
+                // Extension, 0x21 0xFF) is walked the same way but its
+                // sub-block bytes are never interpreted as a delay.
+                if i + 1 >= data.len() {
+                    break;
+                }
+                let label = data[i + 1];
+                if label == 0xF9 && i + 5 < data.len() && data[i + 2] == 0x04 {
+                    let delay_cs = u16::from_le_bytes([data[i + 4], data[i + 5]]);
+                    total_ms += if delay_cs <= 1 { 100 } else { delay_cs as u32 * 10 };
+                }
+
+                i += 2;
+                while i < data.len() && data[i] != 0 {
+                    let block_size = data[i] as usize;
+                    if i.saturating_add(1).saturating_add(block_size) > data.len() {
+                        return total_ms;
+                    }
+                    i += 1 + block_size;
+                }
+                if i >= data.len() {
+                    break;
+                }
+                i += 1; // Skip block terminator
+            }
+            0x2C => {
+                // Image Descriptor
+                if i + 10 > data.len() {
+                    break;
+                }
+
+                let local_flags = data[i + 9];
+                i += 10;
+
+                // Skip Local Color Table if present
+                if local_flags & 0x80 != 0 {
+                    let table_size = 3 * (1 << ((local_flags & 0x07) + 1));
+                    if i.saturating_add(table_size) > data.len() {
+                        break;
+                    }
+                    i += table_size;
+                }
+
+                // Skip LZW minimum code size
+                if i >= data.len() {
+                    break;
+                }
+                i += 1;
+
+                // Skip image data sub-blocks
+                while i < data.len() && data[i] != 0 {
+                    let block_size = data[i] as usize;
+                    if i.saturating_add(1).saturating_add(block_size) > data.len() {
+                        return total_ms;
+                    }
+                    i += 1 + block_size;
+                }
+                if i >= data.len() {
+                    break;
+                }
+                i += 1; // Skip block terminator
+            }
+            0x3B => {
+                // Trailer
+                break;
+            }
+            _ => {
+                i += 1;
+            }
+        }
+    }
+
+    total_ms
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -255,6 +487,58 @@ mod tests {
         assert_eq!(get_gif_frame_count(&gif), 2);
     }
 
+    #[test]
+    fn test_gif_duration_ms_sums_two_gce_delays() {
+        // Same two-frame fixture; each GCE's delay field (bytes 4-5 after
+        // 0x21) is 0x0000 -> the 0-or-1 rule floors it to 100ms, so
+        // total = 200ms.
+        let gif = two_frame_gif();
+        assert_eq!(gif_duration_ms(&gif), 200);
+    }
+
+    #[test]
+    fn test_gif_duration_ms_treats_delay_zero_and_one_as_100ms() {
+        let gif = vec![
+            0x47, 0x49, 0x46, 0x38, 0x39, 0x61, // GIF89a
+            0x01, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, // LSD
+            0x21, 0xF9, 0x04, 0x00, 0x00, 0x00, 0x00, 0x00, // GCE, delay=0
+            0x2C, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x01, 0x00, 0x00, // Image 1
+            0x02, 0x02, 0x44, 0x01, 0x00, 0x21, 0xF9, 0x04, 0x00, 0x01, 0x00, 0x00, 0x00, // GCE, delay=1
+            0x2C, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x01, 0x00, 0x00, // Image 2
+            0x02, 0x02, 0x44, 0x01, 0x00, 0x3B, // Trailer
+        ];
+        assert_eq!(gif_duration_ms(&gif), 200);
+    }
+
+    #[test]
+    fn test_gif_duration_ms_ignores_netscape_loop_extension() {
+        // NETSCAPE2.0 application extension (label 0xFF) carries no delay and
+        // must not be misread as one, nor change the GCE-only total.
+        let netscape_ext: Vec<u8> = vec![
+            0x21, 0xFF, 0x0B, // Application Extension, 11-byte block
+            b'N', b'E', b'T', b'S', b'C', b'A', b'P', b'E', b'2', b'.', b'0', 0x03, 0x01, 0x00,
+            0x00, 0x00,
+        ];
+        let mut gif = vec![
+            0x47, 0x49, 0x46, 0x38, 0x39, 0x61, // GIF89a
+            0x01, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, // LSD
+        ];
+        gif.extend_from_slice(&netscape_ext);
+        gif.extend_from_slice(&[
+            0x21, 0xF9, 0x04, 0x00, 0x04, 0x00, 0x00, 0x00, // GCE, delay=4 -> 40ms
+            0x2C, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x01, 0x00, 0x00, // Image 1
+            0x02, 0x02, 0x44, 0x01, 0x00, 0x3B, // Trailer
+        ]);
+        assert_eq!(gif_duration_ms(&gif), 40);
+    }
+
+    #[test]
+    fn test_gif_duration_ms_zero_for_non_gif() {
+        assert_eq!(gif_duration_ms(&[]), 0);
+        let png_header = [0x89, 0x50, 0x4E, 0x47, 0x0D, 0x0A, 0x1A, 0x0A];
+        assert_eq!(gif_duration_ms(&png_header), 0);
+    }
+
     #[test]
     fn test_single_frame_gif_not_animated() {
         // One Image Descriptor only -> static.
@@ -267,4 +551,42 @@ mod tests {
         assert!(!is_animated_gif(&gif));
         assert_eq!(get_gif_frame_count(&gif), 1);
     }
+
+    #[test]
+    fn test_check_frame_count_accepts_two_frames_within_limit() {
+        let gif = two_frame_gif();
+        assert!(check_frame_count(&gif, 2).is_ok());
+    }
+
+    #[test]
+    fn test_check_frame_count_rejects_two_frames_over_limit() {
+        let gif = two_frame_gif();
+        assert!(check_frame_count(&gif, 1).is_err());
+    }
+
+    #[test]
+    fn test_check_frame_count_ignores_non_gif_data() {
+        let png_header = [0x89, 0x50, 0x4E, 0x47, 0x0D, 0x0A, 0x1A, 0x0A];
+        assert!(check_frame_count(&png_header, 0).is_ok());
+    }
+
+    #[test]
+    fn test_check_frame_count_bails_out_before_scanning_every_declared_frame() {
+        // A single Image Descriptor repeated many times past a small limit —
+        // asserts the guard trips on the first frame past the cap rather than
+        // needing to reach the trailer.
+        let mut gif = vec![
+            0x47, 0x49, 0x46, 0x38, 0x39, 0x61, // GIF89a
+            0x01, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, // LSD
+        ];
+        let frame = [
+            0x2C, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x01, 0x00, 0x00, // Image Descriptor
+            0x02, 0x02, 0x44, 0x01, 0x00, // Image data + terminator
+        ];
+        for _ in 0..10_000 {
+            gif.extend_from_slice(&frame);
+        }
+        gif.push(0x3B); // Trailer
+        assert!(check_frame_count(&gif, 5).is_err());
+    }
 }
@@ -0,0 +1,47 @@
+//! Exact-bytes content hashing (SHA-256), for cache keys.
+//!
+//! Distinct from perceptual/similarity hashing (which tolerates
+//! recompression or minor edits) -- this is a plain digest of the raw
+//! bytes, for callers who need to know "have I already processed exactly
+//! this file" rather than "is this visually the same image".
+
+use sha2::{Digest, Sha256};
+
+/// Hex-encoded SHA-256 digest of `data`.
+pub fn sha256_hex(data: &[u8]) -> String {
+    let digest = Sha256::digest(data);
+    hex_encode(&digest)
+}
+
+fn hex_encode(bytes: &[u8]) -> String {
+    use std::fmt::Write;
+    let mut out = String::with_capacity(bytes.len() * 2);
+    for byte in bytes {
+        write!(out, "{:02x}", byte).expect("writing to a String never fails");
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sha256_hex_matches_known_vector() {
+        // SHA-256("") -- the standard empty-input test vector.
+        assert_eq!(
+            sha256_hex(b""),
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+        );
+    }
+
+    #[test]
+    fn sha256_hex_is_deterministic_and_content_sensitive() {
+        let a = sha256_hex(b"hello world");
+        let b = sha256_hex(b"hello world");
+        let c = sha256_hex(b"hello worle");
+        assert_eq!(a, b);
+        assert_ne!(a, c);
+        assert_eq!(a.len(), 64);
+    }
+}
@@ -0,0 +1,146 @@
+//! Per-channel pixel histograms for exposure analysis.
+
+use image::DynamicImage;
+
+use crate::decode;
+use crate::errors::MediaError;
+
+/// R/G/B histograms (256 bins each) plus per-channel mean and population
+/// standard deviation, computed over every decoded pixel in a single pass.
+pub struct Histogram {
+    pub r: [u32; 256],
+    pub g: [u32; 256],
+    pub b: [u32; 256],
+    pub mean: (f64, f64, f64),
+    pub std_dev: (f64, f64, f64),
+}
+
+impl Histogram {
+    /// Total pixel count backing the bins — the same for every channel,
+    /// since each pixel contributes exactly one count to each of `r`/`g`/`b`.
+    pub fn pixel_count(&self) -> u64 {
+        self.r.iter().map(|&count| count as u64).sum()
+    }
+}
+
+/// Decode `data` (through the shared bomb guard, like every other entry
+/// point in this crate) and compute its histogram.
+pub fn compute_histogram(data: &[u8]) -> Result<Histogram, MediaError> {
+    let (img, _format) = decode::decode_with_guard(data, decode::MAX_PIXEL_COUNT)?;
+    Ok(histogram_from_image(&img))
+}
+
+pub(crate) fn histogram_from_image(img: &DynamicImage) -> Histogram {
+    let rgb = img.to_rgb8();
+    let mut r = [0u32; 256];
+    let mut g = [0u32; 256];
+    let mut b = [0u32; 256];
+    // Sum and sum-of-squares accumulated alongside the bin counts so mean
+    // and standard deviation fall out without a second pass over the pixels.
+    let mut sum = (0.0_f64, 0.0_f64, 0.0_f64);
+    let mut sum_sq = (0.0_f64, 0.0_f64, 0.0_f64);
+    let mut n: u64 = 0;
+
+    for pixel in rgb.pixels() {
+        let [pr, pg, pb] = pixel.0;
+        r[pr as usize] += 1;
+        g[pg as usize] += 1;
+        b[pb as usize] += 1;
+
+        let (fr, fg, fb) = (pr as f64, pg as f64, pb as f64);
+        sum.0 += fr;
+        sum.1 += fg;
+        sum.2 += fb;
+        sum_sq.0 += fr * fr;
+        sum_sq.1 += fg * fg;
+        sum_sq.2 += fb * fb;
+        n += 1;
+    }
+
+    if n == 0 {
+        return Histogram {
+            r,
+            g,
+            b,
+            mean: (0.0, 0.0, 0.0),
+            std_dev: (0.0, 0.0, 0.0),
+        };
+    }
+
+    let n_f = n as f64;
+    let mean = (sum.0 / n_f, sum.1 / n_f, sum.2 / n_f);
+    let variance_of = |sum_sq: f64, mean: f64| (sum_sq / n_f - mean * mean).max(0.0);
+    let std_dev = (
+        variance_of(sum_sq.0, mean.0).sqrt(),
+        variance_of(sum_sq.1, mean.1).sqrt(),
+        variance_of(sum_sq.2, mean.2).sqrt(),
+    );
+
+    Histogram {
+        r,
+        g,
+        b,
+        mean,
+        std_dev,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn png_of(w: u32, h: u32, f: impl Fn(u32, u32) -> [u8; 3]) -> Vec<u8> {
+        let img = image::RgbImage::from_fn(w, h, |x, y| image::Rgb(f(x, y)));
+        let mut out = Vec::new();
+        DynamicImage::ImageRgb8(img)
+            .write_to(&mut Cursor::new(&mut out), image::ImageFormat::Png)
+            .unwrap();
+        out
+    }
+
+    #[test]
+    fn solid_color_image_has_a_single_bin_and_zero_std_dev() {
+        let png = png_of(4, 4, |_, _| [10, 20, 30]);
+        let hist = compute_histogram(&png).unwrap();
+
+        assert_eq!(hist.r[10], 16);
+        assert_eq!(hist.g[20], 16);
+        assert_eq!(hist.b[30], 16);
+        assert_eq!(hist.r.iter().sum::<u32>(), 16);
+
+        assert_eq!(hist.mean, (10.0, 20.0, 30.0));
+        assert_eq!(hist.std_dev, (0.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn half_black_half_white_has_mean_127_5_and_matching_std_dev() {
+        let png = png_of(2, 2, |x, _| if x == 0 { [0, 0, 0] } else { [255, 255, 255] });
+        let hist = compute_histogram(&png).unwrap();
+
+        assert_eq!(hist.r[0], 2);
+        assert_eq!(hist.r[255], 2);
+        assert!((hist.mean.0 - 127.5).abs() < 1e-9);
+        assert!((hist.std_dev.0 - 127.5).abs() < 1e-9);
+    }
+
+    /// PNG header alone claiming an over-cap size — same fixture shape as
+    /// `decode.rs`'s `png_header_claiming`, since `compute_histogram` must
+    /// fail via that same header-only guard before any pixel scan.
+    fn png_header_claiming(width: u32, height: u32) -> Vec<u8> {
+        let mut out = vec![
+            0x89, 0x50, 0x4E, 0x47, 0x0D, 0x0A, 0x1A, 0x0A, // PNG signature
+            0x00, 0x00, 0x00, 0x0D, b'I', b'H', b'D', b'R',
+        ];
+        out.extend_from_slice(&width.to_be_bytes());
+        out.extend_from_slice(&height.to_be_bytes());
+        out.extend_from_slice(&[8, 6, 0, 0, 0, 0, 0, 0, 0]); // rest of IHDR + bogus CRC
+        out
+    }
+
+    #[test]
+    fn rejects_a_pixel_count_bomb_header() {
+        let bomb = png_header_claiming(20_000, 20_000);
+        assert!(compute_histogram(&bomb).is_err());
+    }
+}
@@ -0,0 +1,209 @@
+//! Minimal embedded sRGB ICC profile for `resize`'s `tag_srgb` option.
+//!
+//! Untagged PNG/JPEG output is assumed to be sRGB by convention, but not
+//! every viewer honors that convention the same way, which is exactly the
+//! "colors look different after processing" complaint `tag_srgb` exists to
+//! fix. This hand-assembles the smallest valid ICC v2 matrix/TRC display
+//! profile for the sRGB space (IEC 61966-2-1) instead of pulling in a full
+//! color-management crate for a handful of static bytes — see
+//! [`minimal_srgb_icc_profile`].
+
+use crate::resize::srgb_to_linear;
+
+/// Build a minimal, spec-valid ICC v2 RGB matrix/TRC display profile
+/// describing the sRGB color space, for embedding via the same
+/// `icc_profile: Option<Vec<u8>>` path [`crate::resize`] already uses for
+/// `preserve_icc` — see that option's doc for why the two never both apply
+/// (a real carried-over profile always wins over this synthetic one).
+///
+/// The colorant (`rXYZ`/`gXYZ`/`bXYZ`) and white point (`wtpt`) values are
+/// the standard Bradford-adapted sRGB-to-XYZ(D50) matrix and D50 PCS white,
+/// as published for the sRGB IEC61966-2.1 profile. The tone curves
+/// (`rTRC`/`gTRC`/`bTRC`) are a 256-entry `curv` LUT sampled directly from
+/// [`srgb_to_linear`] rather than a plain gamma-2.2 approximation, so the tag
+/// matches the same EOTF the rest of this crate already uses for
+/// `linear_light`.
+pub(crate) fn minimal_srgb_icc_profile() -> Vec<u8> {
+    let tags: [(&[u8; 4], Vec<u8>); 9] = {
+        let trc = curve_tag();
+        [
+            (b"desc", text_description_tag("sRGB")),
+            (b"cprt", text_tag("Public Domain")),
+            (b"wtpt", xyz_tag(0.9642, 1.0000, 0.8249)),
+            (b"rXYZ", xyz_tag(0.436_074_7, 0.222_504_5, 0.013_932_2)),
+            (b"gXYZ", xyz_tag(0.385_064_9, 0.716_878_6, 0.097_104_5)),
+            (b"bXYZ", xyz_tag(0.143_080_4, 0.060_616_9, 0.714_173_3)),
+            (b"rTRC", trc.clone()),
+            (b"gTRC", trc.clone()),
+            (b"bTRC", trc),
+        ]
+    };
+
+    let header_size = 128u32;
+    let tag_table_size = 4 + tags.len() as u32 * 12;
+    let mut offset = header_size + tag_table_size;
+    let mut tag_table = Vec::with_capacity(tag_table_size as usize);
+    let mut data_section = Vec::new();
+    for (sig, data) in &tags {
+        let padded_len = (data.len() as u32).div_ceil(4) * 4;
+        tag_table.extend_from_slice(*sig);
+        tag_table.extend_from_slice(&offset.to_be_bytes());
+        tag_table.extend_from_slice(&(data.len() as u32).to_be_bytes());
+        data_section.extend_from_slice(data);
+        data_section.resize(
+            data_section.len() + (padded_len - data.len() as u32) as usize,
+            0,
+        );
+        offset += padded_len;
+    }
+    let total_size = offset;
+
+    let mut profile = Vec::with_capacity(total_size as usize);
+    profile.extend_from_slice(&total_size.to_be_bytes()); // 0-3: profile size
+    profile.extend_from_slice(&[0, 0, 0, 0]); // 4-7: CMM type (none)
+    profile.extend_from_slice(&[0x02, 0x10, 0x00, 0x00]); // 8-11: version 2.1.0
+    profile.extend_from_slice(b"mntr"); // 12-15: device class (display)
+    profile.extend_from_slice(b"RGB "); // 16-19: color space
+    profile.extend_from_slice(b"XYZ "); // 20-23: profile connection space
+    for field in [2020u16, 1, 1, 0, 0, 0] {
+        // 24-35: creation date/time — fixed, since a generated profile has no
+        // meaningful "creation date" of its own.
+        profile.extend_from_slice(&field.to_be_bytes());
+    }
+    profile.extend_from_slice(b"acsp"); // 36-39: required file signature
+    profile.extend_from_slice(&[0, 0, 0, 0]); // 40-43: primary platform (none)
+    profile.extend_from_slice(&[0, 0, 0, 0]); // 44-47: flags
+    profile.extend_from_slice(&[0, 0, 0, 0]); // 48-51: device manufacturer
+    profile.extend_from_slice(&[0, 0, 0, 0]); // 52-55: device model
+    profile.extend_from_slice(&[0u8; 8]); // 56-63: device attributes
+    profile.extend_from_slice(&0u32.to_be_bytes()); // 64-67: rendering intent (perceptual)
+    for component in [0.9642_f64, 1.0, 0.8249] {
+        // 68-79: PCS illuminant XYZ (D50, required to be this exact value)
+        profile.extend_from_slice(&s15fixed16(component));
+    }
+    profile.extend_from_slice(&[0, 0, 0, 0]); // 80-83: profile creator
+    profile.extend_from_slice(&[0u8; 44]); // 84-127: reserved
+    debug_assert_eq!(profile.len(), header_size as usize);
+
+    profile.extend_from_slice(&(tags.len() as u32).to_be_bytes());
+    profile.extend_from_slice(&tag_table);
+    profile.extend_from_slice(&data_section);
+    debug_assert_eq!(profile.len(), total_size as usize);
+    profile
+}
+
+/// Encode `v` as an ICC `s15Fixed16Number` (Q16.16 fixed point, big-endian).
+fn s15fixed16(v: f64) -> [u8; 4] {
+    ((v * 65536.0).round() as i32).to_be_bytes()
+}
+
+/// `XYZType` tag data (ICC.1:2001-04 §6.5.26): signature, 4 reserved bytes,
+/// then one `XYZNumber` (three `s15Fixed16Number`s).
+fn xyz_tag(x: f64, y: f64, z: f64) -> Vec<u8> {
+    let mut v = Vec::with_capacity(20);
+    v.extend_from_slice(b"XYZ ");
+    v.extend_from_slice(&[0, 0, 0, 0]);
+    v.extend_from_slice(&s15fixed16(x));
+    v.extend_from_slice(&s15fixed16(y));
+    v.extend_from_slice(&s15fixed16(z));
+    v
+}
+
+/// `textType` tag data (ICC.1:2001-04 §6.5.19) — signature, 4 reserved
+/// bytes, then a NUL-terminated ASCII string. Simpler than `desc`'s
+/// `textDescriptionType`, which is why v2 requires it for `cprt`.
+fn text_tag(s: &str) -> Vec<u8> {
+    let mut v = Vec::with_capacity(9 + s.len());
+    v.extend_from_slice(b"text");
+    v.extend_from_slice(&[0, 0, 0, 0]);
+    v.extend_from_slice(s.as_bytes());
+    v.push(0);
+    v
+}
+
+/// `textDescriptionType` tag data (ICC.1:2001-04 §6.5.17), the required
+/// type for `desc`. Only the ASCII portion is populated (no Unicode /
+/// Macintosh localization) — the Unicode count and Macintosh description
+/// count are both left at 0, but the 67-byte Macintosh description field is
+/// still present at its fixed size, as the format requires regardless.
+fn text_description_tag(s: &str) -> Vec<u8> {
+    let mut v = Vec::new();
+    v.extend_from_slice(b"desc");
+    v.extend_from_slice(&[0, 0, 0, 0]); // reserved
+    let ascii_count = s.len() as u32 + 1; // + NUL terminator
+    v.extend_from_slice(&ascii_count.to_be_bytes());
+    v.extend_from_slice(s.as_bytes());
+    v.push(0);
+    v.extend_from_slice(&0u32.to_be_bytes()); // Unicode language code
+    v.extend_from_slice(&0u32.to_be_bytes()); // Unicode description count (none)
+    v.extend_from_slice(&0u16.to_be_bytes()); // ScriptCode code
+    v.push(0); // Macintosh description count (none)
+    v.extend_from_slice(&[0u8; 67]); // Macintosh description (fixed size)
+    v
+}
+
+/// `curveType` tag data (ICC.1:2001-04 §6.5.3), the required type for
+/// `rTRC`/`gTRC`/`bTRC` in a v2 profile — a 256-entry lookup table sampled
+/// from [`srgb_to_linear`] rather than a `count == 1` single gamma value, so
+/// the embedded curve matches this crate's own EOTF exactly instead of the
+/// `c.powf(2.2)` approximation.
+fn curve_tag() -> Vec<u8> {
+    let mut v = Vec::with_capacity(12 + 256 * 2);
+    v.extend_from_slice(b"curv");
+    v.extend_from_slice(&[0, 0, 0, 0]);
+    v.extend_from_slice(&256u32.to_be_bytes());
+    for i in 0..256u32 {
+        let linear = srgb_to_linear(i as f32 / 255.0).clamp(0.0, 1.0);
+        let entry = (linear * 65535.0).round() as u16;
+        v.extend_from_slice(&entry.to_be_bytes());
+    }
+    v
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn profile_size_field_matches_the_actual_byte_count() {
+        let profile = minimal_srgb_icc_profile();
+        let declared = u32::from_be_bytes(profile[0..4].try_into().unwrap());
+        assert_eq!(declared as usize, profile.len());
+    }
+
+    #[test]
+    fn profile_signature_is_acsp_at_the_required_offset() {
+        let profile = minimal_srgb_icc_profile();
+        assert_eq!(&profile[36..40], b"acsp");
+    }
+
+    #[test]
+    fn every_tag_table_entry_stays_within_the_profile_and_matches_its_signature() {
+        let profile = minimal_srgb_icc_profile();
+        let tag_count = u32::from_be_bytes(profile[128..132].try_into().unwrap()) as usize;
+        assert_eq!(tag_count, 9);
+        for i in 0..tag_count {
+            let entry = &profile[132 + i * 12..132 + (i + 1) * 12];
+            let sig = &entry[0..4];
+            let offset = u32::from_be_bytes(entry[4..8].try_into().unwrap()) as usize;
+            let size = u32::from_be_bytes(entry[8..12].try_into().unwrap()) as usize;
+            assert!(offset + size <= profile.len());
+            // The bytes at `offset` are the tag *type* signature (e.g. "XYZ "
+            // for an XYZType), which only equals the tag identifier itself for
+            // "desc" — every other tag here uses a different type.
+            let expected_type: &[u8; 4] = match sig {
+                b"desc" => b"desc",
+                b"cprt" => b"text",
+                b"wtpt" | b"rXYZ" | b"gXYZ" | b"bXYZ" => b"XYZ ",
+                b"rTRC" | b"gTRC" | b"bTRC" => b"curv",
+                other => panic!("unexpected tag signature {other:?}"),
+            };
+            assert_eq!(&profile[offset..offset + 4], expected_type);
+        }
+    }
+
+    #[test]
+    fn is_deterministic_across_calls() {
+        assert_eq!(minimal_srgb_icc_profile(), minimal_srgb_icc_profile());
+    }
+}
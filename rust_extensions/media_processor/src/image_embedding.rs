@@ -0,0 +1,123 @@
+//! Deterministic, model-free image feature vectors for similarity search.
+
+use image::imageops::FilterType;
+
+use crate::decode;
+use crate::errors::MediaError;
+
+/// Largest `dim` [`image_embedding`] will produce — bounds the downscale
+/// side length (`ceil(sqrt(dim))`) so a hostile `dim` can't force an
+/// oversized intermediate thumbnail.
+const MAX_EMBEDDING_DIM: usize = 4096;
+
+/// Produce a deterministic `dim`-length feature vector for `data`, suitable
+/// for cosine similarity against another image's vector (e.g. via
+/// `rag_engine`'s `RagEngine`) — a "find visually similar images" primitive
+/// with no ML model involved. The image is decoded, converted to grayscale,
+/// downscaled to a small square tile, and its pixels flattened to
+/// `[0.0, 1.0]` floats. Two visually similar images downscale to similar
+/// tiles, so their vectors land close under cosine similarity; this is not a
+/// learned embedding and won't capture semantic similarity the way a real
+/// model would.
+///
+/// The tile side length is `ceil(sqrt(dim))`, so the flattened tile has at
+/// least `dim` pixels; any extra past `dim` is dropped, and a tile that
+/// falls short (e.g. a prime `dim`) is zero-padded. `dim` of 0 is rejected,
+/// and `dim` above [`MAX_EMBEDDING_DIM`] is rejected rather than silently
+/// clamped, mirroring `quantize_image`'s validation style.
+pub fn image_embedding(data: &[u8], dim: usize) -> Result<Vec<f32>, MediaError> {
+    if dim == 0 {
+        return Err(MediaError::Encode("dim must be greater than 0".to_string()));
+    }
+    if dim > MAX_EMBEDDING_DIM {
+        return Err(MediaError::Encode(format!(
+            "dim must be at most {}, got {}",
+            MAX_EMBEDDING_DIM, dim
+        )));
+    }
+
+    let (img, _format) = decode::decode_with_guard(data, decode::MAX_PIXEL_COUNT)?;
+    let side = (dim as f64).sqrt().ceil() as u32;
+    let tile = img.resize_exact(side, side, FilterType::Triangle).to_luma8();
+
+    let mut embedding: Vec<f32> = tile.pixels().map(|p| p.0[0] as f32 / 255.0).collect();
+    embedding.truncate(dim);
+    embedding.resize(dim, 0.0);
+    Ok(embedding)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::DynamicImage;
+    use std::io::Cursor;
+
+    fn png_of(w: u32, h: u32, f: impl Fn(u32, u32) -> [u8; 3]) -> Vec<u8> {
+        let img = image::RgbImage::from_fn(w, h, |x, y| image::Rgb(f(x, y)));
+        let mut out = Vec::new();
+        DynamicImage::ImageRgb8(img)
+            .write_to(&mut Cursor::new(&mut out), image::ImageFormat::Png)
+            .unwrap();
+        out
+    }
+
+    #[test]
+    fn rejects_zero_dim() {
+        let png = png_of(4, 4, |_, _| [0, 0, 0]);
+        assert!(image_embedding(&png, 0).is_err());
+    }
+
+    #[test]
+    fn rejects_oversized_dim() {
+        let png = png_of(4, 4, |_, _| [0, 0, 0]);
+        assert!(image_embedding(&png, MAX_EMBEDDING_DIM + 1).is_err());
+    }
+
+    #[test]
+    fn returns_exactly_dim_elements_even_when_the_tile_overshoots() {
+        let png = png_of(16, 16, |x, y| [(x * 16) as u8, (y * 16) as u8, 0]);
+        // dim=50 -> side=ceil(sqrt(50))=8 -> a 64-pixel tile, truncated to 50.
+        let embedding = image_embedding(&png, 50).unwrap();
+        assert_eq!(embedding.len(), 50);
+    }
+
+    #[test]
+    fn is_deterministic_for_the_same_input() {
+        let png = png_of(16, 16, |x, y| [(x * 16) as u8, (y * 16) as u8, 0]);
+        let a = image_embedding(&png, 64).unwrap();
+        let b = image_embedding(&png, 64).unwrap();
+        assert_eq!(a, b);
+    }
+
+    #[test]
+    fn solid_black_and_solid_white_embeddings_hit_the_range_extremes() {
+        let black = png_of(8, 8, |_, _| [0, 0, 0]);
+        let white = png_of(8, 8, |_, _| [255, 255, 255]);
+        let e_black = image_embedding(&black, 16).unwrap();
+        let e_white = image_embedding(&white, 16).unwrap();
+        assert!(e_black.iter().all(|&v| v == 0.0));
+        assert!(e_white.iter().all(|&v| (v - 1.0).abs() < 1e-6));
+    }
+
+    #[test]
+    fn similar_images_score_higher_by_cosine_similarity_than_a_dissimilar_one() {
+        let a = png_of(16, 16, |x, y| [(x * 16) as u8, (y * 16) as u8, 0]);
+        let a_prime = png_of(16, 16, |x, y| {
+            [(x * 16).saturating_add(2) as u8, (y * 16) as u8, 0]
+        });
+        let b = png_of(16, 16, |_, _| [255, 0, 0]);
+
+        let e_a = image_embedding(&a, 64).unwrap();
+        let e_a_prime = image_embedding(&a_prime, 64).unwrap();
+        let e_b = image_embedding(&b, 64).unwrap();
+
+        let cosine = |x: &[f32], y: &[f32]| {
+            let dot: f32 = x.iter().zip(y).map(|(a, b)| a * b).sum();
+            let norm_x: f32 = x.iter().map(|v| v * v).sum::<f32>().sqrt();
+            let norm_y: f32 = y.iter().map(|v| v * v).sum::<f32>().sqrt();
+            dot / (norm_x * norm_y)
+        };
+
+        assert!(cosine(&e_a, &e_a_prime) > cosine(&e_a, &e_b));
+    }
+}
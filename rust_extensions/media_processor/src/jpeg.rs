@@ -0,0 +1,302 @@
+//! JPEG-specific diagnostics that don't fit `decode.rs`'s generic bomb guard.
+//!
+//! `image::load_from_memory` (via its `zune-jpeg` backend) already converts
+//! CMYK and YCCK JPEGs to RGB correctly, including the inverted-CMYK
+//! convention Adobe products emit — that's what made the "pink thumbnail"
+//! bug reports stop once the crate picked up a `zune-jpeg`-backed `image`.
+//! [`adobe_transform`] exists so a support investigation into a still-wrong
+//! thumbnail can tell, without a full decode, whether the source actually
+//! carries the Adobe APP14 marker at all (a JPEG can be CMYK without one, in
+//! which case there's no reliable way to know it's inverted).
+
+/// Scan `data`'s markers for an Adobe APP14 segment and return its transform
+/// byte: `0` (unknown/CMYK, treated as inverted), `1` (YCbCr), or `2`
+/// (YCCK). `None` if there's no APP14 "Adobe" segment, malformed marker
+/// data, or `data` isn't a JPEG at all.
+pub(crate) fn adobe_transform(data: &[u8]) -> Option<u8> {
+    if data.len() < 4 || data[0..2] != [0xFF, 0xD8] {
+        return None;
+    }
+
+    let mut pos = 2;
+    while pos + 4 <= data.len() {
+        if data[pos] != 0xFF {
+            // Not aligned on a marker boundary (or entropy-coded data
+            // reached without hitting SOS first) — bail out rather than
+            // scanning byte-by-byte through pixel data.
+            return None;
+        }
+        let marker = data[pos + 1];
+        // SOI/EOI/RSTn/TEM have no length field; anything else does.
+        if marker == 0xD8 || marker == 0xD9 || (0xD0..=0xD7).contains(&marker) {
+            pos += 2;
+            continue;
+        }
+        if marker == 0xDA {
+            // Start of Scan: entropy-coded data follows, no more markers to
+            // find before pixel data begins.
+            return None;
+        }
+        let segment_len = u16::from_be_bytes([data[pos + 2], data[pos + 3]]) as usize;
+        if segment_len < 2 || pos + 2 + segment_len > data.len() {
+            return None;
+        }
+        if marker == 0xEE {
+            let payload = &data[pos + 4..pos + 2 + segment_len];
+            if payload.len() >= 12 && &payload[0..5] == b"Adobe" {
+                return Some(payload[11]);
+            }
+        }
+        pos += 2 + segment_len;
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Builds a minimal but genuinely decodable baseline JPEG: one 8x8 MCU,
+    /// four non-subsampled components (C/M/Y/K), each a single flat-color
+    /// block (DC-only, no AC energy) tagged with an Adobe APP14 CMYK marker.
+    /// There's no encoder in this crate's dependency tree that emits CMYK
+    /// JPEGs (the `image`/`zune-jpeg` stack only *reads* them), so this
+    /// hand-assembles one from the JPEG/Huffman spec instead of shipping a
+    /// binary fixture — same spirit as `png_of` elsewhere in this crate,
+    /// just lower-level because JPEG's entropy coding leaves no shortcut.
+    mod cmyk_fixture {
+        use std::collections::HashMap;
+
+        // Standard "luminance" DC/AC Huffman tables from the JPEG spec
+        // (Annex K) — reused here for all four components since a flat
+        // 8x8 block only ever needs a DC symbol and an end-of-block, and
+        // every baseline decoder ships these same tables.
+        const BITS_DC: [u8; 16] = [0, 1, 5, 1, 1, 1, 1, 1, 1, 0, 0, 0, 0, 0, 0, 0];
+        const VALS_DC: [u8; 12] = [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11];
+        const BITS_AC: [u8; 16] = [0, 2, 1, 3, 3, 2, 4, 3, 5, 5, 4, 4, 0, 0, 1, 0x7d];
+        #[rustfmt::skip]
+        const VALS_AC: [u8; 162] = [
+            0x01, 0x02, 0x03, 0x00, 0x04, 0x11, 0x05, 0x12,
+            0x21, 0x31, 0x41, 0x06, 0x13, 0x51, 0x61, 0x07,
+            0x22, 0x71, 0x14, 0x32, 0x81, 0x91, 0xa1, 0x08,
+            0x23, 0x42, 0xb1, 0xc1, 0x15, 0x52, 0xd1, 0xf0,
+            0x24, 0x33, 0x62, 0x72, 0x82, 0x09, 0x0a, 0x16,
+            0x17, 0x18, 0x19, 0x1a, 0x25, 0x26, 0x27, 0x28,
+            0x29, 0x2a, 0x34, 0x35, 0x36, 0x37, 0x38, 0x39,
+            0x3a, 0x43, 0x44, 0x45, 0x46, 0x47, 0x48, 0x49,
+            0x4a, 0x53, 0x54, 0x55, 0x56, 0x57, 0x58, 0x59,
+            0x5a, 0x63, 0x64, 0x65, 0x66, 0x67, 0x68, 0x69,
+            0x6a, 0x73, 0x74, 0x75, 0x76, 0x77, 0x78, 0x79,
+            0x7a, 0x83, 0x84, 0x85, 0x86, 0x87, 0x88, 0x89,
+            0x8a, 0x92, 0x93, 0x94, 0x95, 0x96, 0x97, 0x98,
+            0x99, 0x9a, 0xa2, 0xa3, 0xa4, 0xa5, 0xa6, 0xa7,
+            0xa8, 0xa9, 0xaa, 0xb2, 0xb3, 0xb4, 0xb5, 0xb6,
+            0xb7, 0xb8, 0xb9, 0xba, 0xc2, 0xc3, 0xc4, 0xc5,
+            0xc6, 0xc7, 0xc8, 0xc9, 0xca, 0xd2, 0xd3, 0xd4,
+            0xd5, 0xd6, 0xd7, 0xd8, 0xd9, 0xda, 0xe1, 0xe2,
+            0xe3, 0xe4, 0xe5, 0xe6, 0xe7, 0xe8, 0xe9, 0xea,
+            0xf1, 0xf2, 0xf3, 0xf4, 0xf5, 0xf6, 0xf7, 0xf8,
+            0xf9, 0xfa,
+        ];
+
+        /// Canonical Huffman code assignment (JPEG spec Annex C): symbols
+        /// are consumed off `vals` in length order, incrementing the code
+        /// within a length and left-shifting when moving to the next.
+        fn build_codes(bits: &[u8; 16], vals: &[u8]) -> HashMap<u8, (u32, u8)> {
+            let mut codes = HashMap::new();
+            let mut code: u32 = 0;
+            let mut k = 0usize;
+            for (length_minus_one, &count) in bits.iter().enumerate() {
+                for _ in 0..count {
+                    codes.insert(vals[k], (code, (length_minus_one + 1) as u8));
+                    code += 1;
+                    k += 1;
+                }
+                code <<= 1;
+            }
+            codes
+        }
+
+        struct BitWriter {
+            out: Vec<u8>,
+            acc: u32,
+            nbits: u32,
+        }
+
+        impl BitWriter {
+            fn new() -> Self {
+                Self { out: Vec::new(), acc: 0, nbits: 0 }
+            }
+
+            fn push_bits(&mut self, value: u32, length: u8) {
+                for i in (0..length).rev() {
+                    self.acc = (self.acc << 1) | ((value >> i) & 1);
+                    self.nbits += 1;
+                    if self.nbits == 8 {
+                        let byte = self.acc as u8;
+                        self.out.push(byte);
+                        if byte == 0xFF {
+                            self.out.push(0x00); // byte-stuffing
+                        }
+                        self.acc = 0;
+                        self.nbits = 0;
+                    }
+                }
+            }
+
+            fn finish(mut self) -> Vec<u8> {
+                if self.nbits > 0 {
+                    // Pad the final partial byte with 1 bits, per spec.
+                    self.push_bits((1 << (8 - self.nbits)) - 1, 8 - self.nbits as u8);
+                }
+                self.out
+            }
+        }
+
+        /// `SSSS` category (bit length) of a DC/AC coefficient difference.
+        fn category(value: i32) -> u8 {
+            32 - (value.unsigned_abs()).leading_zeros() as u8
+        }
+
+        /// The `category`-bit magnitude encoding JPEG uses for a signed
+        /// coefficient: the value itself if non-negative, or its
+        /// one's-complement-style negative encoding otherwise.
+        fn magnitude_bits(value: i32, cat: u8) -> u32 {
+            if value >= 0 {
+                value as u32
+            } else {
+                (value + (1 << cat) - 1) as u32
+            }
+        }
+
+        fn write_flat_block(
+            bits: &mut BitWriter,
+            dc_codes: &HashMap<u8, (u32, u8)>,
+            ac_codes: &HashMap<u8, (u32, u8)>,
+            stored_value: u8,
+        ) {
+            let dc = 8 * (i32::from(stored_value) - 128);
+            let cat = category(dc);
+            let (code, len) = dc_codes[&cat];
+            bits.push_bits(code, len);
+            if cat > 0 {
+                bits.push_bits(magnitude_bits(dc, cat), cat);
+            }
+            // All 63 AC coefficients are zero for a flat block: a single
+            // end-of-block (run/size 0x00) closes it out.
+            let (eob_code, eob_len) = ac_codes[&0x00];
+            bits.push_bits(eob_code, eob_len);
+        }
+
+        fn segment(marker: u8, payload: &[u8]) -> Vec<u8> {
+            let mut out = vec![0xFF, marker];
+            out.extend_from_slice(&((payload.len() + 2) as u16).to_be_bytes());
+            out.extend_from_slice(payload);
+            out
+        }
+
+        /// `stored_*` are the *already Adobe-inverted* component values, as
+        /// real CMYK-transform=0 JPEGs store them — matching what
+        /// `zune-jpeg`'s `color_convert_cymk_to_rgb` expects on the wire.
+        pub(super) fn build(stored_c: u8, stored_m: u8, stored_y: u8, stored_k: u8) -> Vec<u8> {
+            let mut out = vec![0xFF, 0xD8]; // SOI
+
+            let mut adobe = b"Adobe".to_vec();
+            adobe.extend_from_slice(&[0x00, 0x64, 0x00, 0x00, 0x00, 0x00, 0x00]); // transform=0 (CMYK)
+            out.extend_from_slice(&segment(0xEE, &adobe));
+
+            let mut dqt = vec![0x00]; // precision 0, table id 0
+            dqt.extend_from_slice(&[1u8; 64]);
+            out.extend_from_slice(&segment(0xDB, &dqt));
+
+            let mut sof = vec![8, 0, 8, 0, 8, 4]; // precision, height=8, width=8, Nf=4
+            for id in 1..=4u8 {
+                sof.extend_from_slice(&[id, 0x11, 0x00]); // 1x1 sampling, quant table 0
+            }
+            out.extend_from_slice(&segment(0xC0, &sof));
+
+            let mut dht_dc = vec![0x00]; // class 0 (DC), table id 0
+            dht_dc.extend_from_slice(&BITS_DC);
+            dht_dc.extend_from_slice(&VALS_DC);
+            out.extend_from_slice(&segment(0xC4, &dht_dc));
+
+            let mut dht_ac = vec![0x10]; // class 1 (AC), table id 0
+            dht_ac.extend_from_slice(&BITS_AC);
+            dht_ac.extend_from_slice(&VALS_AC);
+            out.extend_from_slice(&segment(0xC4, &dht_ac));
+
+            let mut sos = vec![4]; // Ns=4
+            for id in 1..=4u8 {
+                sos.extend_from_slice(&[id, 0x00]); // DC table 0, AC table 0
+            }
+            sos.extend_from_slice(&[0, 63, 0]); // Ss, Se, AhAl
+            out.extend_from_slice(&segment(0xDA, &sos));
+
+            let dc_codes = build_codes(&BITS_DC, &VALS_DC);
+            let ac_codes = build_codes(&BITS_AC, &VALS_AC);
+            let mut bits = BitWriter::new();
+            for stored in [stored_c, stored_m, stored_y, stored_k] {
+                write_flat_block(&mut bits, &dc_codes, &ac_codes, stored);
+            }
+            out.extend_from_slice(&bits.finish());
+
+            out.extend_from_slice(&[0xFF, 0xD9]); // EOI
+            out
+        }
+    }
+
+    #[test]
+    fn a_flat_cmyk_jpeg_decodes_to_the_expected_non_inverted_rgb() {
+        // stored_k=255 (no black ink) reduces zune-jpeg's blinn_8x8(x, k)
+        // blend to ~x, so the expected RGB is ~(stored_c, stored_m, stored_y)
+        // directly -- a naive decode that skipped the Adobe un-inversion
+        // would instead come out inverted (~205, ~55, ~55).
+        let jpeg = cmyk_fixture::build(50, 200, 200, 255);
+        assert_eq!(adobe_transform(&jpeg), Some(0));
+
+        let decoded = image::load_from_memory(&jpeg).unwrap().to_rgb8();
+        let pixel = decoded.get_pixel(0, 0);
+        assert!(pixel[0].abs_diff(50) <= 2, "R={} expected ~50", pixel[0]);
+        assert!(pixel[1].abs_diff(200) <= 2, "G={} expected ~200", pixel[1]);
+        assert!(pixel[2].abs_diff(200) <= 2, "B={} expected ~200", pixel[2]);
+    }
+
+    fn app14(transform: u8) -> Vec<u8> {
+        let mut out = vec![0xFF, 0xD8]; // SOI
+        out.extend_from_slice(&[0xFF, 0xEE]); // APP14
+        out.extend_from_slice(&14u16.to_be_bytes()); // length (incl. itself, excl. marker)
+        out.extend_from_slice(b"Adobe");
+        out.extend_from_slice(&[0x00, 0x64]); // version
+        out.extend_from_slice(&[0x00, 0x00]); // flags0
+        out.extend_from_slice(&[0x00, 0x00]); // flags1
+        out.push(transform);
+        out.extend_from_slice(&[0xFF, 0xD9]); // EOI
+        out
+    }
+
+    #[test]
+    fn finds_the_transform_byte_in_an_adobe_app14_segment() {
+        assert_eq!(adobe_transform(&app14(2)), Some(2));
+        assert_eq!(adobe_transform(&app14(0)), Some(0));
+    }
+
+    #[test]
+    fn returns_none_without_an_app14_segment() {
+        let plain = vec![0xFF, 0xD8, 0xFF, 0xD9];
+        assert_eq!(adobe_transform(&plain), None);
+    }
+
+    #[test]
+    fn returns_none_for_non_jpeg_data() {
+        assert_eq!(adobe_transform(b"not a jpeg"), None);
+    }
+
+    #[test]
+    fn ignores_an_app14_segment_that_is_not_adobes() {
+        let mut data = vec![0xFF, 0xD8, 0xFF, 0xEE];
+        data.extend_from_slice(&8u16.to_be_bytes());
+        data.extend_from_slice(b"Other\x00\x00");
+        data.extend_from_slice(&[0xFF, 0xD9]);
+        assert_eq!(adobe_transform(&data), None);
+    }
+}
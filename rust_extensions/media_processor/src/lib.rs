@@ -6,20 +6,54 @@
 //! - Base64 encoding/decoding
 //! - Parallel batch processing
 
+mod autocontrast;
+mod cancel;
+mod composite;
+mod decode;
 mod encode;
 mod errors;
 mod gif;
+mod hash;
+mod histogram;
+mod icc;
+mod image_embedding;
+mod jpeg;
+mod orient;
+mod quality;
+mod quantize;
 mod resize;
+mod svg;
+mod trim;
+mod webp;
 
-use image::GenericImageView;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use image::{DynamicImage, GenericImageView, ImageFormat};
 use pyo3::exceptions::PyValueError;
 use pyo3::prelude::*;
-use pyo3::types::PyBytes;
+use pyo3::types::{PyBytes, PyDict};
 
-pub use encode::{from_base64, to_base64};
+pub use autocontrast::auto_contrast_image;
+pub use cancel::CancelToken;
+pub use composite::overlay_image;
+pub use encode::{from_base64, from_base64_checked, from_data_uri, to_base64};
 pub use errors::MediaError;
-pub use gif::{get_gif_frame_count, is_animated_gif};
-pub use resize::{resize_image, ResizeMode};
+pub use gif::{get_gif_frame_count, gif_duration_ms, is_animated_gif};
+pub use hash::sha256_hex;
+pub use histogram::compute_histogram;
+pub use image_embedding::image_embedding;
+pub use orient::{apply_orientation, read_orientation};
+pub use quality::compare_images;
+pub use quantize::{quantize_frames, quantize_image};
+pub use resize::{
+    generate_thumbnails, resize_decoded, resize_image, try_passthrough_without_decode,
+    CompressionLevel, ResizeMode,
+};
+pub use svg::parse_svg_dimensions;
+#[cfg(feature = "svg")]
+pub use svg::rasterize_svg;
+pub use trim::trim_image;
+pub use webp::{is_animated_webp, webp_duration_ms, webp_frame_count};
 
 /// Image data container
 #[pyclass(from_py_object)]
@@ -33,19 +67,37 @@ pub struct ImageData {
     pub channels: u8,
     #[pyo3(get)]
     pub format: String,
+    /// Whether `data` holds an undecoded raw pixel buffer (`channels`
+    /// bytes per pixel, row-major — as `to_rgba` returns, or as directly
+    /// constructed) rather than an encoded file (png/jpeg/webp/gif bytes —
+    /// as `load`/`resize`/`from_rgba` return). `encode()` needs this to
+    /// know whether to decode `data` before re-encoding to the requested
+    /// format. Every entry point in this crate other than the raw-pixel
+    /// constructor path sets this `false`.
+    #[pyo3(get)]
+    pub is_raw_pixels: bool,
     pub data: Vec<u8>,
 }
 
 #[pymethods]
 impl ImageData {
     #[new]
-    fn new(data: Vec<u8>, width: u32, height: u32, channels: u8, format: String) -> Self {
+    #[pyo3(signature = (data, width, height, channels, format, is_raw_pixels=false))]
+    fn new(
+        data: Vec<u8>,
+        width: u32,
+        height: u32,
+        channels: u8,
+        format: String,
+        is_raw_pixels: bool,
+    ) -> Self {
         Self {
             data,
             width,
             height,
             channels,
             format,
+            is_raw_pixels,
         }
     }
 
@@ -60,6 +112,154 @@ impl ImageData {
     fn __len__(&self) -> usize {
         self.data.len()
     }
+
+    /// Encode this image's pixel data to PNG/JPEG/WebP bytes.
+    ///
+    /// If `is_raw_pixels` is false (the case for anything returned by
+    /// `load`/`resize`/`from_rgba`), `data` is decoded first and re-encoded
+    /// to `format`, so `encode()` doubles as a format-conversion call.
+    /// `quality` only affects JPEG output (default 85, clamped 1-100);
+    /// other formats ignore it.
+    #[pyo3(signature = (format, quality=None))]
+    fn encode<'py>(
+        &self,
+        py: Python<'py>,
+        format: &str,
+        quality: Option<u8>,
+    ) -> PyResult<Bound<'py, PyBytes>> {
+        let encoded = encode_image_data(self, format, quality)?;
+        Ok(PyBytes::new(py, &encoded))
+    }
+}
+
+/// Core logic behind `ImageData::encode`, split out so it's callable without
+/// a `Python` token from this crate's own tests.
+fn encode_image_data(image: &ImageData, format: &str, quality: Option<u8>) -> PyResult<Vec<u8>> {
+    let img = if image.is_raw_pixels {
+        decode_raw_pixels(&image.data, image.width, image.height, image.channels)?
+    } else {
+        image::load_from_memory(&image.data).map_err(|e| {
+            PyValueError::new_err(format!("Failed to decode existing image data: {}", e))
+        })?
+    };
+    encode_dynamic_image(&img, format, quality)
+}
+
+/// Build a `DynamicImage` from a raw, undecoded pixel buffer — the inverse
+/// of `to_rgba`/`from_rgba`'s pixel-side, shared by `ImageData::encode`.
+fn decode_raw_pixels(
+    pixels: &[u8],
+    width: u32,
+    height: u32,
+    channels: u8,
+) -> PyResult<DynamicImage> {
+    match channels {
+        4 => image::RgbaImage::from_raw(width, height, pixels.to_vec())
+            .map(DynamicImage::ImageRgba8)
+            .ok_or_else(|| {
+                PyValueError::new_err("raw pixel buffer length does not match width*height*4")
+            }),
+        3 => image::RgbImage::from_raw(width, height, pixels.to_vec())
+            .map(DynamicImage::ImageRgb8)
+            .ok_or_else(|| {
+                PyValueError::new_err("raw pixel buffer length does not match width*height*3")
+            }),
+        other => Err(PyValueError::new_err(format!(
+            "unsupported raw pixel channel count: {} (expected 3 or 4)",
+            other
+        ))),
+    }
+}
+
+/// Parse `resize`/`resize_path`'s `compression_level` string arg into the
+/// enum `resize::resize_image` takes. Same "lowercase, match, else
+/// PyValueError" shape as `encode_dynamic_image`'s format parsing below.
+fn parse_compression_level(level: &str) -> PyResult<CompressionLevel> {
+    match level.to_ascii_lowercase().as_str() {
+        "fast" => Ok(CompressionLevel::Fast),
+        "default" => Ok(CompressionLevel::Default),
+        "best" => Ok(CompressionLevel::Best),
+        other => Err(PyValueError::new_err(format!(
+            "unsupported compression_level: {} (expected \"fast\", \"default\", or \"best\")",
+            other
+        ))),
+    }
+}
+
+/// Encode a decoded image to PNG/JPEG/WebP bytes. Shared by `from_rgba` and
+/// `ImageData::encode` so both format-name parsing and the JPEG-quality
+/// handling live in one place.
+fn encode_dynamic_image(
+    img: &DynamicImage,
+    format: &str,
+    quality: Option<u8>,
+) -> PyResult<Vec<u8>> {
+    let image_format = match format.to_ascii_lowercase().as_str() {
+        "png" => ImageFormat::Png,
+        "jpeg" | "jpg" => ImageFormat::Jpeg,
+        "webp" => ImageFormat::WebP,
+        other => {
+            return Err(PyValueError::new_err(format!(
+                "unsupported output format: {}",
+                other
+            )))
+        }
+    };
+
+    let mut output = Vec::new();
+    if image_format == ImageFormat::Jpeg {
+        let jpeg_quality = quality.unwrap_or(85).clamp(1, 100);
+        let encoder = image::codecs::jpeg::JpegEncoder::new_with_quality(&mut output, jpeg_quality);
+        img.to_rgb8()
+            .write_with_encoder(encoder)
+            .map_err(|e| PyValueError::new_err(e.to_string()))?;
+    } else {
+        img.write_to(&mut std::io::Cursor::new(&mut output), image_format)
+            .map_err(|e| PyValueError::new_err(e.to_string()))?;
+    }
+    Ok(output)
+}
+
+/// R/G/B histogram (256 bins each) plus per-channel mean and population
+/// standard deviation, for exposure analysis (over/under-exposed uploads,
+/// auto-levels). `MediaProcessor::histogram` computes it over every decoded
+/// pixel.
+#[pyclass]
+pub struct HistogramResult {
+    #[pyo3(get)]
+    pub r: Vec<u32>,
+    #[pyo3(get)]
+    pub g: Vec<u32>,
+    #[pyo3(get)]
+    pub b: Vec<u32>,
+    #[pyo3(get)]
+    pub mean_r: f64,
+    #[pyo3(get)]
+    pub mean_g: f64,
+    #[pyo3(get)]
+    pub mean_b: f64,
+    #[pyo3(get)]
+    pub std_dev_r: f64,
+    #[pyo3(get)]
+    pub std_dev_g: f64,
+    #[pyo3(get)]
+    pub std_dev_b: f64,
+}
+
+impl From<histogram::Histogram> for HistogramResult {
+    fn from(hist: histogram::Histogram) -> Self {
+        Self {
+            r: hist.r.to_vec(),
+            g: hist.g.to_vec(),
+            b: hist.b.to_vec(),
+            mean_r: hist.mean.0,
+            mean_g: hist.mean.1,
+            mean_b: hist.mean.2,
+            std_dev_r: hist.std_dev.0,
+            std_dev_g: hist.std_dev.1,
+            std_dev_b: hist.std_dev.2,
+        }
+    }
 }
 
 /// Main Media Processor class
@@ -67,67 +267,187 @@ impl ImageData {
 pub struct MediaProcessor {
     max_dimension: u32,
     jpeg_quality: u8,
+    /// Cap for `check_gif_frame_count`'s header-only guard — the animation
+    /// analogue of `max_dimension`/`decode::MAX_PIXEL_COUNT`. Not yet wired
+    /// into any frame-decoding entry point: this crate has none (see
+    /// `check_gif_frame_count`'s doc comment).
+    max_frames: u32,
 }
 
 #[pymethods]
 impl MediaProcessor {
     #[new]
-    #[pyo3(signature = (max_dimension=1024, jpeg_quality=85))]
-    fn new(max_dimension: u32, jpeg_quality: u8) -> Self {
+    #[pyo3(signature = (max_dimension=1024, jpeg_quality=85, max_frames=1000))]
+    fn new(max_dimension: u32, jpeg_quality: u8, max_frames: u32) -> Self {
         Self {
             max_dimension,
             jpeg_quality,
+            max_frames,
         }
     }
 
-    /// Load image from bytes
-    fn load<'py>(&self, _py: Python<'py>, data: &Bound<'py, PyBytes>) -> PyResult<ImageData> {
-        let bytes = data.as_bytes();
-
-        check_bomb_dimensions(bytes)?;
-
-        let img = image::load_from_memory(bytes)
-            .map_err(|e| PyValueError::new_err(format!("Failed to load image: {}", e)))?;
+    /// Retune the default max dimension `resize`/`resize_path`/`thumbnail`
+    /// fall back to when their own `max_width`/`max_height`/`size` isn't
+    /// given, without reconstructing this `MediaProcessor`. Must be greater
+    /// than 0; there's no upper bound here beyond `resize_image`'s own
+    /// `MAX_ALLOWED_DIMENSION` clamp, which still applies to every resize
+    /// regardless of what this is set to.
+    fn set_max_dimension(&mut self, max_dimension: u32) -> PyResult<()> {
+        if max_dimension == 0 {
+            return Err(PyValueError::new_err(
+                "max_dimension must be greater than 0",
+            ));
+        }
+        self.max_dimension = max_dimension;
+        Ok(())
+    }
 
-        let (width, height) = img.dimensions();
-        let channels = img.color().channel_count();
+    /// Retune the default JPEG quality `resize`/`resize_path`/`resize_exact`/
+    /// `thumbnail`/`process` fall back to when their own `quality` argument
+    /// isn't given. Must be in 1..=100, the same range `resize_image` itself
+    /// clamps to.
+    fn set_jpeg_quality(&mut self, jpeg_quality: u8) -> PyResult<()> {
+        if !(1..=100).contains(&jpeg_quality) {
+            return Err(PyValueError::new_err(format!(
+                "jpeg_quality must be between 1 and 100, got {}",
+                jpeg_quality
+            )));
+        }
+        self.jpeg_quality = jpeg_quality;
+        Ok(())
+    }
 
-        // Detect format from magic bytes
-        let format = detect_format(bytes).unwrap_or("unknown").to_string();
+    /// Load image from bytes
+    fn load<'py>(&self, _py: Python<'py>, data: &Bound<'py, PyBytes>) -> PyResult<ImageData> {
+        load_bytes(data.as_bytes())
+    }
 
-        Ok(ImageData {
-            data: bytes.to_vec(),
-            width,
-            height,
-            channels,
-            format,
-        })
+    /// Load an image directly from a filesystem path, skipping the
+    /// Python-bytes round trip `load` requires. Same decompression-bomb
+    /// guard and magic-byte format detection as `load`; useful for batch
+    /// jobs over on-disk images where copying megabytes across the
+    /// Python/Rust boundary per call adds up.
+    fn load_path(&self, path: &str) -> PyResult<ImageData> {
+        let bytes = std::fs::read(path)
+            .map_err(|e| PyValueError::new_err(format!("Failed to read {}: {}", path, e)))?;
+        load_bytes(&bytes)
     }
 
-    /// Resize image to fit within max dimensions
+    /// Resize image to fit within max dimensions. `preserve_icc` carries the
+    /// source's embedded ICC color profile (if any) into the re-encoded
+    /// output instead of the default "strip" behavior — see
+    /// `resize::decode_with_optional_icc` for which source formats expose a
+    /// profile (PNG/JPEG/WebP/TIFF) and which outputs can embed one
+    /// (PNG/JPEG; a GIF/WebP output under `preserve_format` carries none).
+    ///
+    /// `compression_level` is one of "fast", "default" (the `image` crate's
+    /// own balance of speed and size), or "best" — it only affects PNG
+    /// output; JPEG output's size is controlled by `jpeg_quality` instead,
+    /// and a WebP output (reachable only through `preserve_format`) is
+    /// written with the encoder's default settings.
+    /// "best" trades CPU for file size: expect 20-30% smaller PNGs than
+    /// "default" at a noticeably slower encode, worthwhile for archival
+    /// output that's written once and read many times.
+    ///
+    /// `preserve_format` re-encodes in the source's own format (sniffed from
+    /// its magic bytes) instead of the default heuristic that always picks
+    /// PNG for alpha/16-bit sources and JPEG for everything else — without
+    /// it, a resized GIF or WebP silently comes back as a PNG or JPEG. If
+    /// encoding in the source format fails, the heuristic is used instead.
+    ///
+    /// `linear_light` resamples in linear light instead of `image`'s default
+    /// of resampling the raw sRGB-encoded samples, which under-weights
+    /// bright pixels relative to dark ones and darkens fine detail on a
+    /// downscale — a real correctness issue, not a stylistic one. Off by
+    /// default to match pre-existing output and because it costs an extra
+    /// float conversion pass in both directions around the resize.
     // Explicit signature so the trailing Option args are genuinely optional
     // from Python. Without it, pyo3 0.28 makes them REQUIRED positional args
     // (the implicit-None default for trailing Option was removed in 0.23),
     // so `resize(data)` — as the .pyi stub advertises — would TypeError.
-    #[pyo3(signature = (data, max_width=None, max_height=None))]
+    #[pyo3(signature = (data, max_width=None, max_height=None, preserve_icc=false, compression_level="default", preserve_format=false, linear_light=false, tag_srgb=false))]
+    #[allow(clippy::too_many_arguments)]
     fn resize<'py>(
         &self,
         _py: Python<'py>,
         data: &Bound<'py, PyBytes>,
         max_width: Option<u32>,
         max_height: Option<u32>,
+        preserve_icc: bool,
+        compression_level: &str,
+        preserve_format: bool,
+        linear_light: bool,
+        tag_srgb: bool,
     ) -> PyResult<ImageData> {
         let bytes = data.as_bytes();
-        check_bomb_dimensions(bytes)?;
         let max_w = max_width.unwrap_or(self.max_dimension);
         let max_h = max_height.unwrap_or(self.max_dimension);
+        let compression_level = parse_compression_level(compression_level)?;
 
-        let result = resize_image(bytes, max_w, max_h, ResizeMode::Fit, self.jpeg_quality)
-            .map_err(|e| PyValueError::new_err(e.to_string()))?;
+        let result = resize_image(
+            bytes,
+            max_w,
+            max_h,
+            ResizeMode::Fit,
+            self.jpeg_quality,
+            preserve_icc,
+            compression_level,
+            preserve_format,
+            linear_light,
+            tag_srgb,
+        )
+        .map_err(|e| PyValueError::new_err(e.to_string()))?;
 
         Ok(result)
     }
 
+    /// Resize an image read directly from a filesystem path, skipping the
+    /// Python-bytes round trip `resize` requires. Same bomb guard,
+    /// `preserve_icc`, `compression_level`, `preserve_format`, `linear_light`,
+    /// and `tag_srgb` behavior as `resize`.
+    #[pyo3(signature = (path, max_width=None, max_height=None, preserve_icc=false, compression_level="default", preserve_format=false, linear_light=false, tag_srgb=false))]
+    #[allow(clippy::too_many_arguments)]
+    fn resize_path(
+        &self,
+        path: &str,
+        max_width: Option<u32>,
+        max_height: Option<u32>,
+        preserve_icc: bool,
+        compression_level: &str,
+        preserve_format: bool,
+        linear_light: bool,
+        tag_srgb: bool,
+    ) -> PyResult<ImageData> {
+        let bytes = std::fs::read(path)
+            .map_err(|e| PyValueError::new_err(format!("Failed to read {}: {}", path, e)))?;
+        let max_w = max_width.unwrap_or(self.max_dimension);
+        let max_h = max_height.unwrap_or(self.max_dimension);
+        let compression_level = parse_compression_level(compression_level)?;
+
+        resize_image(
+            &bytes,
+            max_w,
+            max_h,
+            ResizeMode::Fit,
+            self.jpeg_quality,
+            preserve_icc,
+            compression_level,
+            preserve_format,
+            linear_light,
+            tag_srgb,
+        )
+        .map_err(|e| PyValueError::new_err(e.to_string()))
+    }
+
+    /// Write an `ImageData`'s encoded bytes directly to a filesystem path,
+    /// the write-side counterpart to `load_path`/`resize_path` for batch
+    /// jobs that never need the encoded bytes back in Python at all.
+    #[staticmethod]
+    fn save(image: &ImageData, path: &str) -> PyResult<()> {
+        std::fs::write(path, &image.data)
+            .map_err(|e| PyValueError::new_err(format!("Failed to write {}: {}", path, e)))
+    }
+
     /// Resize image to exact dimensions (with cropping)
     fn resize_exact<'py>(
         &self,
@@ -137,10 +457,20 @@ impl MediaProcessor {
         height: u32,
     ) -> PyResult<ImageData> {
         let bytes = data.as_bytes();
-        check_bomb_dimensions(bytes)?;
 
-        let result = resize_image(bytes, width, height, ResizeMode::Fill, self.jpeg_quality)
-            .map_err(|e| PyValueError::new_err(e.to_string()))?;
+        let result = resize_image(
+            bytes,
+            width,
+            height,
+            ResizeMode::Fill,
+            self.jpeg_quality,
+            false,
+            CompressionLevel::default(),
+            false,
+            false,
+            false,
+        )
+        .map_err(|e| PyValueError::new_err(e.to_string()))?;
 
         Ok(result)
     }
@@ -153,26 +483,149 @@ impl MediaProcessor {
         size: u32,
     ) -> PyResult<ImageData> {
         let bytes = data.as_bytes();
-        check_bomb_dimensions(bytes)?;
 
-        let result = resize_image(bytes, size, size, ResizeMode::Fit, self.jpeg_quality)
-            .map_err(|e| PyValueError::new_err(e.to_string()))?;
+        let result = resize_image(
+            bytes,
+            size,
+            size,
+            ResizeMode::Fit,
+            self.jpeg_quality,
+            false,
+            CompressionLevel::default(),
+            false,
+            false,
+            false,
+        )
+        .map_err(|e| PyValueError::new_err(e.to_string()))?;
 
         Ok(result)
     }
 
+    /// Create `Fit` thumbnails at every size in `sizes` from a single
+    /// decode, instead of calling [`thumbnail`](Self::thumbnail) once per
+    /// size (and re-decoding the source each time). Sizes are resized
+    /// largest-to-smallest, each one downscaling from the previous result
+    /// rather than the original decode, for speed. Order of the returned
+    /// list matches `sizes`. Releases the GIL for the decode+resize work.
+    fn thumbnails<'py>(
+        &self,
+        py: Python<'py>,
+        data: &Bound<'py, PyBytes>,
+        sizes: Vec<u32>,
+    ) -> PyResult<Vec<ImageData>> {
+        let bytes = data.as_bytes();
+        let quality = self.jpeg_quality;
+
+        py.detach(|| generate_thumbnails(bytes, &sizes, quality))
+            .map_err(|e| PyValueError::new_err(e.to_string()))
+    }
+
+    /// Quantize an image to `colors` (2..=256) distinct colors and return it
+    /// as an indexed PNG. Useful both as a stylized posterize filter and for
+    /// shrinking palette-heavy PNGs.
+    fn quantize<'py>(
+        &self,
+        _py: Python<'py>,
+        data: &Bound<'py, PyBytes>,
+        colors: u16,
+    ) -> PyResult<ImageData> {
+        let bytes = data.as_bytes();
+
+        quantize_image(bytes, colors).map_err(|e| PyValueError::new_err(e.to_string()))
+    }
+
+    /// Quantize a batch of frames (e.g. an animated GIF's extracted frames)
+    /// down to `colors` (2..=256), computing one shared palette across every
+    /// frame via NeuQuant so the frames' colors stay consistent instead of
+    /// each frame's independent palette drifting — see
+    /// `quantize::quantize_frames`. `shared_palette=false` falls back to
+    /// quantizing each frame with its own palette, equivalent to calling
+    /// `quantize` once per frame.
+    ///
+    /// This is a standalone palette-computation primitive, not a full
+    /// animation re-encoder — this build has no animated-GIF or
+    /// animated-WebP writer, so there is no `resize_gif`/`gif_to_webp` for
+    /// it to plug into.
+    #[pyo3(signature = (frames, colors, shared_palette=true))]
+    fn quantize_shared<'py>(
+        &self,
+        _py: Python<'py>,
+        frames: Vec<Bound<'py, PyBytes>>,
+        colors: u16,
+        shared_palette: bool,
+    ) -> PyResult<Vec<ImageData>> {
+        let frame_bytes: Vec<&[u8]> = frames.iter().map(|b| b.as_bytes()).collect();
+        quantize_frames(&frame_bytes, colors, shared_palette)
+            .map_err(|e| PyValueError::new_err(e.to_string()))
+    }
+
     /// Check if image is an animated GIF
     #[staticmethod]
     fn is_animated<'py>(data: &Bound<'py, PyBytes>) -> bool {
         is_animated_gif(data.as_bytes())
     }
 
+    /// Header-only check that an animated GIF's frame count doesn't exceed
+    /// this instance's `max_frames`, without decoding any frame data — the
+    /// animation analogue of `resize`'s pixel-count bomb guard
+    /// (`decode::check_dimensions`). Raises `ValueError` if the limit is
+    /// exceeded; returns normally (does nothing else) otherwise, including
+    /// for non-GIF data.
+    ///
+    /// This crate has no frame-extraction or GIF-to-WebP re-encode entry
+    /// point yet — nothing here decodes individual GIF frames — so there is
+    /// no `resize_gif`/`extract_frames`/`gif_to_webp` to call this before.
+    /// It exists so a caller can reject an oversized animated upload ahead
+    /// of handing it to any frame-decoding path outside this crate; wire it
+    /// into a real animation-decode method here once one exists.
+    fn check_gif_frame_count<'py>(&self, data: &Bound<'py, PyBytes>) -> PyResult<()> {
+        gif::check_frame_count(data.as_bytes(), self.max_frames)
+            .map_err(|e| PyValueError::new_err(e.to_string()))
+    }
+
+    /// Total single-loop playback duration of a GIF in milliseconds, summing
+    /// every frame's Graphic Control Extension delay (see `gif::gif_duration_ms`
+    /// for the 0/1-centisecond-as-100ms browser rule). `NETSCAPE2.0` loop
+    /// count is irrelevant to a single-loop duration and is ignored. Lets a
+    /// caller reject GIFs over a duration limit, which `is_animated` alone
+    /// can't express. Returns 0 for non-GIF data or a GIF with no GCE frames.
+    #[staticmethod]
+    fn gif_duration_ms<'py>(data: &Bound<'py, PyBytes>) -> u32 {
+        gif_duration_ms(data.as_bytes())
+    }
+
+    /// Number of animation frames in a WebP, parsed from its `ANMF` chunks.
+    /// Parallels `gif_duration_ms`/`is_animated`'s GIF coverage so the same
+    /// frame/duration limits can be applied to animated WebP uploads, not
+    /// just GIFs. Returns `1` for a static (non-animated) WebP and `0` for
+    /// non-WebP data.
+    #[staticmethod]
+    fn webp_frame_count<'py>(data: &Bound<'py, PyBytes>) -> usize {
+        webp_frame_count(data.as_bytes())
+    }
+
+    /// Total single-loop playback duration of an animated WebP in
+    /// milliseconds, summing each `ANMF` frame's duration field (see
+    /// `webp::webp_duration_ms` for why no unit conversion is needed, unlike
+    /// GIF's centisecond delays). The `ANIM` chunk's loop count is unrelated
+    /// to a single-loop duration and is skipped like any other chunk.
+    /// Returns `0` for non-WebP data or a static WebP.
+    #[staticmethod]
+    fn webp_duration_ms<'py>(data: &Bound<'py, PyBytes>) -> u32 {
+        webp_duration_ms(data.as_bytes())
+    }
+
     /// Get image dimensions without fully decoding.
     ///
     /// Header-only: this reads the format header and never allocates a pixel
     /// buffer, so it is intentionally NOT routed through
     /// ``check_bomb_dimensions`` (there is nothing to bomb). If a future edit
     /// adds a full decode here, it MUST call ``check_bomb_dimensions`` first.
+    ///
+    /// `image` can't decode SVGs at all, so on decode failure this falls
+    /// back to [`svg::parse_svg_dimensions`]'s attribute scan before giving
+    /// up — that fallback is always compiled in (it's a byte scan, not a
+    /// renderer) regardless of the `svg` cargo feature.
     #[staticmethod]
     fn get_dimensions<'py>(data: &Bound<'py, PyBytes>) -> PyResult<(u32, u32)> {
         let bytes = data.as_bytes();
@@ -181,11 +634,36 @@ impl MediaProcessor {
             .with_guessed_format()
             .map_err(|e| PyValueError::new_err(e.to_string()))?;
 
-        let dims = reader
-            .into_dimensions()
-            .map_err(|e| PyValueError::new_err(e.to_string()))?;
+        match reader.into_dimensions() {
+            Ok(dims) => Ok(dims),
+            Err(e) => {
+                svg::parse_svg_dimensions(bytes).ok_or_else(|| PyValueError::new_err(e.to_string()))
+            }
+        }
+    }
 
-        Ok(dims)
+    /// "portrait", "landscape", or "square", from dimensions alone -- for
+    /// layout routing that doesn't need pixels. Builds directly on
+    /// [`get_dimensions`](Self::get_dimensions), so it inherits the same
+    /// header-only, never-fully-decodes guarantee for every format it
+    /// supports (including the SVG attribute-scan fallback).
+    #[staticmethod]
+    fn aspect_class<'py>(data: &Bound<'py, PyBytes>) -> PyResult<String> {
+        let (width, height) = Self::get_dimensions(data)?;
+        Ok(aspect_class_from_dimensions(width, height).to_string())
+    }
+
+    /// Rasterize SVG bytes to a PNG `ImageData` at the requested size.
+    /// Requires the crate to be built with the `svg` feature.
+    #[staticmethod]
+    #[cfg(feature = "svg")]
+    fn rasterize_svg<'py>(
+        data: &Bound<'py, PyBytes>,
+        width: u32,
+        height: u32,
+    ) -> PyResult<ImageData> {
+        svg::rasterize_svg(data.as_bytes(), width, height)
+            .map_err(|e| PyValueError::new_err(e.to_string()))
     }
 
     /// Convert image to base64
@@ -194,13 +672,102 @@ impl MediaProcessor {
         to_base64(data.as_bytes())
     }
 
-    /// Decode base64 to bytes
+    /// Hex-encoded SHA-256 of the raw bytes, for content-addressed caching.
+    ///
+    /// This is exact-bytes identity, not perceptual similarity -- two visually
+    /// identical images that differ by even one byte (re-encode, metadata,
+    /// recompression) hash differently. Callers who want "is this the same
+    /// picture" tolerant of recompression need a perceptual hash instead,
+    /// which this crate doesn't provide.
     #[staticmethod]
-    fn decode_base64<'py>(py: Python<'py>, encoded: &str) -> PyResult<Bound<'py, PyBytes>> {
-        let bytes = from_base64(encoded).map_err(|e| PyValueError::new_err(e.to_string()))?;
+    fn content_hash<'py>(data: &Bound<'py, PyBytes>) -> String {
+        sha256_hex(data.as_bytes())
+    }
+
+    /// Decode base64 to bytes. Transparently accepts a `data:<mime>;base64,`
+    /// URI prefix (as commonly pasted from browsers/chat clients) alongside
+    /// bare base64 — the data-URI form is tried first and bare base64 is the
+    /// fallback.
+    ///
+    /// `max_output_bytes` rejects inputs whose decoded length would exceed
+    /// the cap before allocating the output buffer, guarding this against
+    /// the same decode-bomb abuse vector as the image side (a caller can
+    /// otherwise pass an arbitrarily huge base64 string and force a huge
+    /// allocation for the cost of a small request).
+    #[staticmethod]
+    #[pyo3(signature = (encoded, max_output_bytes=None))]
+    fn decode_base64<'py>(
+        py: Python<'py>,
+        encoded: &str,
+        max_output_bytes: Option<usize>,
+    ) -> PyResult<Bound<'py, PyBytes>> {
+        let bytes = decode_base64_one(encoded, max_output_bytes)
+            .map_err(|e| PyValueError::new_err(e.to_string()))?;
         Ok(PyBytes::new(py, &bytes))
     }
 
+    /// Encode multiple byte buffers to base64 in parallel (releases the GIL).
+    ///
+    /// Mirrors `batch_resize`'s GIL-releasing rayon pattern for the plain
+    /// base64 case: a gallery upload calling `encode_base64` once per image
+    /// from Python pays the per-call GIL round-trip N times and never uses
+    /// more than one core. Order is preserved.
+    #[staticmethod]
+    fn batch_encode_base64<'py>(py: Python<'py>, images: Vec<Bound<'py, PyBytes>>) -> Vec<String> {
+        use rayon::prelude::*;
+
+        let bytes_list: Vec<Vec<u8>> = images.iter().map(|b| b.as_bytes().to_vec()).collect();
+        py.detach(|| {
+            bytes_list
+                .par_iter()
+                .map(|bytes| to_base64(bytes))
+                .collect()
+        })
+    }
+
+    /// Decode multiple base64 (or `data:<mime>;base64,`) strings to bytes in
+    /// parallel (releases the GIL). The `batch_encode_base64` counterpart.
+    ///
+    /// `max_output_bytes` applies the same pre-decode size guard as
+    /// `decode_base64`, per entry — a batch member decoding past the cap
+    /// fails the whole batch, same as any other decode error, since callers
+    /// can't act on a partial `Vec<Bytes>` with holes in it.
+    #[staticmethod]
+    #[pyo3(signature = (encoded, max_output_bytes=None))]
+    fn batch_decode_base64<'py>(
+        py: Python<'py>,
+        encoded: Vec<String>,
+        max_output_bytes: Option<usize>,
+    ) -> PyResult<Vec<Bound<'py, PyBytes>>> {
+        use rayon::prelude::*;
+
+        let decoded: Vec<Vec<u8>> = py
+            .detach(|| {
+                encoded
+                    .par_iter()
+                    .map(|s| decode_base64_one(s, max_output_bytes))
+                    .collect::<Result<Vec<Vec<u8>>, _>>()
+            })
+            .map_err(|e| PyValueError::new_err(e.to_string()))?;
+        Ok(decoded.iter().map(|b| PyBytes::new(py, b)).collect())
+    }
+
+    /// Parse a `data:<mime>;base64,<payload>` URI, returning `(bytes, mime)`.
+    ///
+    /// `max_output_bytes` applies the same pre-decode size guard as
+    /// `decode_base64`.
+    #[staticmethod]
+    #[pyo3(signature = (data_uri, max_output_bytes=None))]
+    fn from_data_uri<'py>(
+        py: Python<'py>,
+        data_uri: &str,
+        max_output_bytes: Option<usize>,
+    ) -> PyResult<(Bound<'py, PyBytes>, String)> {
+        let (bytes, mime) = from_data_uri(data_uri, max_output_bytes)
+            .map_err(|e| PyValueError::new_err(e.to_string()))?;
+        Ok((PyBytes::new(py, &bytes), mime))
+    }
+
     /// Batch resize multiple images (parallel, releases GIL).
     ///
     /// Processes inputs in chunks to bound peak memory: previously the
@@ -208,30 +775,249 @@ impl MediaProcessor {
     /// at once, so a 100-image batch of 5 MB JPEGs would spike to
     /// ~500 MB before resize. We now decode-and-resize one chunk at a
     /// time, releasing the chunk's allocations before pulling the next.
+    ///
+    /// `cancel_token`, when given, is checked before starting each chunk and
+    /// by every worker within the chunk currently in flight — a caller can
+    /// call `cancel_token.cancel()` from another thread (e.g. a UI's cancel
+    /// button) to abort a long batch early, raising `ValueError` instead of
+    /// returning the partial results.
+    #[pyo3(signature = (images, max_width, max_height, cancel_token=None))]
     fn batch_resize<'py>(
         &self,
         py: Python<'py>,
         images: Vec<Bound<'py, PyBytes>>,
         max_width: u32,
         max_height: u32,
+        cancel_token: Option<Py<CancelToken>>,
     ) -> PyResult<Vec<ImageData>> {
         let quality = self.jpeg_quality;
+        let cancel = cancel_token.as_ref().map(|t| t.borrow(py).flag());
         let mut output = Vec::with_capacity(images.len());
 
         for chunk in images.chunks(BATCH_CHUNK_SIZE) {
+            if cancel.as_deref().is_some_and(|c| c.load(Ordering::Relaxed)) {
+                return Err(PyValueError::new_err(MediaError::Cancelled.to_string()));
+            }
             let bytes_list: Vec<Vec<u8>> = chunk.iter().map(|b| b.as_bytes().to_vec()).collect();
             // Release the GIL while this chunk's bytes are bomb-checked and
             // resized on the rayon worker pool (pure-Rust work). Chunking
             // bounds peak memory: we decode+resize one BATCH_CHUNK_SIZE chunk
             // at a time and drop its allocations before pulling the next,
             // instead of holding every decoded image at once.
-            let chunk_results =
-                py.detach(|| process_batch_chunk(&bytes_list, max_width, max_height, quality));
+            let chunk_results = py.detach(|| {
+                process_batch_chunk(
+                    &bytes_list,
+                    max_width,
+                    max_height,
+                    quality,
+                    cancel.as_deref(),
+                )
+            });
             output.extend(chunk_results?);
         }
         Ok(output)
     }
 
+    /// Decode once, optionally apply EXIF orientation, resize, and encode
+    /// once — the "phone photo -> upright thumbnail" fast path. Chaining
+    /// separate orient/resize calls from Python means two decode/encode
+    /// round-trips; this does the whole pipeline on a single decode.
+    #[pyo3(signature = (data, max_width, max_height, auto_orient=true, quality=None))]
+    fn process<'py>(
+        &self,
+        _py: Python<'py>,
+        data: &Bound<'py, PyBytes>,
+        max_width: u32,
+        max_height: u32,
+        auto_orient: bool,
+        quality: Option<u8>,
+    ) -> PyResult<ImageData> {
+        let bytes = data.as_bytes();
+        let (mut img, _format) = decode::decode_with_guard(bytes, decode::MAX_PIXEL_COUNT)
+            .map_err(|e| PyValueError::new_err(e.to_string()))?;
+        if auto_orient {
+            let orientation = read_orientation(bytes);
+            img = apply_orientation(img, orientation);
+        }
+
+        resize_decoded(
+            img,
+            max_width,
+            max_height,
+            ResizeMode::Fit,
+            quality.unwrap_or(self.jpeg_quality),
+        )
+        .map_err(|e| PyValueError::new_err(e.to_string()))
+    }
+
+    /// Composite `overlay` onto `base` at `(x, y)`, alpha-blended by `opacity`.
+    ///
+    /// Overlay pixels outside the base bounds (including negative `x`/`y`) are
+    /// clipped rather than erroring, so a partially off-canvas logo placement
+    /// just draws the visible part. Always encodes PNG to preserve alpha.
+    #[pyo3(signature = (base, overlay, x, y, opacity=1.0))]
+    fn overlay<'py>(
+        &self,
+        _py: Python<'py>,
+        base: &Bound<'py, PyBytes>,
+        overlay: &Bound<'py, PyBytes>,
+        x: i32,
+        y: i32,
+        opacity: f32,
+    ) -> PyResult<ImageData> {
+        let base_bytes = base.as_bytes();
+        let overlay_bytes = overlay.as_bytes();
+
+        overlay_image(base_bytes, overlay_bytes, x, y, opacity)
+            .map_err(|e| PyValueError::new_err(e.to_string()))
+    }
+
+    /// Decode to RGBA8 and return the flat pixel buffer plus dimensions.
+    ///
+    /// The returned bytes are exactly `width * height * 4` bytes (RGBA8,
+    /// row-major). This is the bridge for handing raw pixels to a
+    /// pixel-level consumer (e.g. an OCR binding) without a second decode —
+    /// `ImageData.data` holds encoded file bytes, not decoded pixels.
+    fn to_rgba<'py>(
+        &self,
+        py: Python<'py>,
+        data: &Bound<'py, PyBytes>,
+    ) -> PyResult<(Bound<'py, PyBytes>, u32, u32)> {
+        let bytes = data.as_bytes();
+        let (img, _format) = decode::decode_with_guard(bytes, decode::MAX_PIXEL_COUNT)
+            .map_err(|e| PyValueError::new_err(e.to_string()))?;
+        let (width, height) = img.dimensions();
+        let rgba = img.to_rgba8().into_raw();
+
+        Ok((PyBytes::new(py, &rgba), width, height))
+    }
+
+    /// Construct an image from a raw RGBA8 pixel buffer, encoding it to the
+    /// requested format. `pixels.len()` must equal `width * height * 4`; the
+    /// inverse of `to_rgba`, closing the decode/encode loop for pixel-level
+    /// interop.
+    #[staticmethod]
+    fn from_rgba<'py>(
+        pixels: &Bound<'py, PyBytes>,
+        width: u32,
+        height: u32,
+        format: &str,
+    ) -> PyResult<ImageData> {
+        let pixels = pixels.as_bytes();
+        let expected_len = (width as u64)
+            .checked_mul(height as u64)
+            .and_then(|p| p.checked_mul(4))
+            .ok_or_else(|| PyValueError::new_err("width*height*4 overflows"))?;
+        if pixels.len() as u64 != expected_len {
+            return Err(PyValueError::new_err(format!(
+                "pixel buffer length {} does not match width*height*4 ({})",
+                pixels.len(),
+                expected_len
+            )));
+        }
+
+        let buffer = image::RgbaImage::from_raw(width, height, pixels.to_vec())
+            .ok_or_else(|| PyValueError::new_err("failed to build image from raw pixels"))?;
+        let img = image::DynamicImage::ImageRgba8(buffer);
+
+        let output = encode_dynamic_image(&img, format, None)?;
+
+        Ok(ImageData {
+            data: output,
+            width,
+            height,
+            channels: img.color().channel_count(),
+            format: format.to_ascii_lowercase(),
+            is_raw_pixels: false,
+        })
+    }
+
+    /// Per-channel R/G/B histogram (256 bins each) plus mean and standard
+    /// deviation, computed over every decoded pixel — for detecting over/
+    /// under-exposed uploads or driving an auto-levels filter. Same
+    /// decompression-bomb guard as `resize`/`compare`. Releases the GIL for
+    /// the pixel scan.
+    #[staticmethod]
+    fn histogram<'py>(py: Python<'py>, data: &Bound<'py, PyBytes>) -> PyResult<HistogramResult> {
+        let bytes = data.as_bytes();
+
+        py.detach(|| compute_histogram(bytes))
+            .map(HistogramResult::from)
+            .map_err(|e| PyValueError::new_err(e.to_string()))
+    }
+
+    /// Deterministic, model-free feature vector for "find visually similar
+    /// images" against `rag_engine`'s `RagEngine` — no learned embedding, a
+    /// grayscale-downscale-and-flatten tile (see
+    /// `image_embedding::image_embedding`'s doc comment). `dim` of 0 or
+    /// above 4096 is rejected. Same decompression-bomb guard as
+    /// `resize`/`histogram`. Releases the GIL for the decode and downscale.
+    #[staticmethod]
+    fn image_embedding<'py>(
+        py: Python<'py>,
+        data: &Bound<'py, PyBytes>,
+        dim: usize,
+    ) -> PyResult<Vec<f32>> {
+        let bytes = data.as_bytes();
+
+        py.detach(|| image_embedding::image_embedding(bytes, dim))
+            .map_err(|e| PyValueError::new_err(e.to_string()))
+    }
+
+    /// Auto-contrast (auto-levels): stretch each of R/G/B independently from
+    /// its `clip_percent`/`100-clip_percent` histogram percentile to the
+    /// full 0-255 range, encoding back to the source format. `clip_percent`
+    /// is clamped to 0..10. Fixes washed-out, low-contrast photos in one
+    /// call. Same decompression-bomb guard as `resize`/`histogram`. Releases
+    /// the GIL for the histogram pass and per-pixel remap.
+    #[staticmethod]
+    fn auto_contrast<'py>(
+        py: Python<'py>,
+        data: &Bound<'py, PyBytes>,
+        clip_percent: f32,
+    ) -> PyResult<ImageData> {
+        let bytes = data.as_bytes();
+
+        py.detach(|| auto_contrast_image(bytes, clip_percent))
+            .map_err(|e| PyValueError::new_err(e.to_string()))
+    }
+
+    /// Auto-crop a uniform border (screenshot chrome, scanner margins). The
+    /// background color is inferred from the image's corner pixels; the
+    /// crop is the tightest bounding box of pixels differing from it by more
+    /// than `tolerance` (0-255, per-channel). Output preserves the source
+    /// format. An entirely uniform image, or one with no border to begin
+    /// with, is returned unchanged rather than as a degenerate crop. Same
+    /// decompression-bomb guard as `resize`/`histogram`. Releases the GIL
+    /// for the bounding-box scan.
+    #[staticmethod]
+    fn trim<'py>(
+        py: Python<'py>,
+        data: &Bound<'py, PyBytes>,
+        tolerance: u8,
+    ) -> PyResult<ImageData> {
+        let bytes = data.as_bytes();
+
+        py.detach(|| trim_image(bytes, tolerance))
+            .map_err(|e| PyValueError::new_err(e.to_string()))
+    }
+
+    /// Compare two images and return `(psnr, ssim)` computed on the luma
+    /// channel. Errors if the decoded dimensions differ. Releases the GIL for
+    /// the SSIM windowed pass, which is the expensive part.
+    #[staticmethod]
+    fn compare<'py>(
+        py: Python<'py>,
+        a: &Bound<'py, PyBytes>,
+        b: &Bound<'py, PyBytes>,
+    ) -> PyResult<(f64, f64)> {
+        let a_bytes = a.as_bytes();
+        let b_bytes = b.as_bytes();
+
+        py.detach(|| compare_images(a_bytes, b_bytes))
+            .map_err(|e| PyValueError::new_err(e.to_string()))
+    }
+
     /// Get format from image bytes.
     ///
     /// Header-only magic-byte sniff: never decodes pixels, so (like
@@ -240,10 +1026,62 @@ impl MediaProcessor {
     fn detect_format<'py>(data: &Bound<'py, PyBytes>) -> Option<String> {
         detect_format(data.as_bytes()).map(|s| s.to_string())
     }
+
+    /// Adobe APP14 transform byte from a JPEG's markers, without decoding
+    /// any pixel data: `0` (CMYK), `1` (YCbCr), `2` (YCCK), or `None` if
+    /// there's no Adobe APP14 segment. `load`/`resize`/`histogram`/etc.
+    /// already decode CMYK/YCCK JPEGs to correct (non-inverted) RGB on
+    /// their own — this is a diagnostic for tracking a "why is this
+    /// thumbnail pink" report back to its source file.
+    #[staticmethod]
+    fn jpeg_adobe_transform<'py>(data: &Bound<'py, PyBytes>) -> Option<u8> {
+        jpeg::adobe_transform(data.as_bytes())
+    }
+
+    /// Startup diagnostic: encode+decode a tiny synthetic image through every
+    /// codec this build supports (png/jpeg/webp/gif), round-trip a base64
+    /// blob, and report which of those succeeded plus the crate version.
+    ///
+    /// Never raises for a missing/broken codec — a codec round-trip failure
+    /// (e.g. a build without WebP support) is reported as `false` under its
+    /// key so a deployment smoke test gets a single dict to inspect instead
+    /// of a crash. `"ok"` is true only if every check passed.
+    #[staticmethod]
+    fn self_test<'py>(py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        let results = self_test_impl();
+        let dict = PyDict::new(py);
+        for (key, ok) in &results {
+            dict.set_item(*key, *ok)?;
+        }
+        dict.set_item("ok", results.iter().all(|(_, ok)| *ok))?;
+        dict.set_item("version", env!("CARGO_PKG_VERSION"))?;
+        Ok(dict)
+    }
+}
+
+/// Plain-Rust core of `MediaProcessor::load`/`load_path` — validates,
+/// decodes, and wraps `bytes` into an `ImageData`. Split out so both the
+/// bytes-based and path-based entry points share one bomb-guard/decode/
+/// format-detect path instead of duplicating it.
+fn load_bytes(bytes: &[u8]) -> PyResult<ImageData> {
+    let (img, format) = decode::decode_with_guard(bytes, decode::MAX_PIXEL_COUNT)
+        .map_err(|e| PyValueError::new_err(e.to_string()))?;
+
+    let (width, height) = img.dimensions();
+    let channels = img.color().channel_count();
+
+    Ok(ImageData {
+        data: bytes.to_vec(),
+        width,
+        height,
+        channels,
+        format,
+        is_raw_pixels: false,
+    })
 }
 
 /// Detect image format from magic bytes
-fn detect_format(data: &[u8]) -> Option<&'static str> {
+pub(crate) fn detect_format(data: &[u8]) -> Option<&'static str> {
     if data.len() < 4 {
         return None;
     }
@@ -258,41 +1096,86 @@ fn detect_format(data: &[u8]) -> Option<&'static str> {
 }
 
 /// Reject decompression-bomb inputs by reading the header dimensions before
-/// the full decode allocates pixel memory. ``checked_mul`` is belt-and-
-/// suspenders: both dims are u32, so ``u32::MAX * u32::MAX`` (~1.8e19) still
-/// fits in u64 and the None branch is effectively unreachable — it just makes
-/// the intent explicit at zero cost. All public entry points that decode
-/// untrusted bytes and DECODE pixels go through this so the 100MP cap is
-/// enforced uniformly across the decoding entry points (load, resize,
-/// resize_exact, thumbnail, batch_resize). The header-only entry points
-/// (get_dimensions, detect_format) never allocate a pixel buffer and so are
-/// intentionally guard-free — see their doc comments. The resize/resize_exact/thumbnail
-/// wrappers do parse the header twice — once here and again inside
-/// ``resize_image`` (resize.rs) — but the duplicate probe is cheap next to the
-/// full decode and is deliberate defense-in-depth: ``resize_image`` is ``pub``
-/// (and also reached via ``batch_resize``), so both layers keep the bomb guard
-/// intact. Do NOT drop these calls to save the redundant header parse.
+/// the full decode allocates pixel memory. Thin `PyResult` wrapper around the
+/// shared [`decode::check_dimensions`] guard, for `process_batch_chunk`'s
+/// whole-chunk pre-check, which needs the dimension check on its own (ahead
+/// of the chunk's parallel resize) rather than paired with a decode the way
+/// [`decode::decode_with_guard`]'s other callers use it.
 fn check_bomb_dimensions(bytes: &[u8]) -> PyResult<()> {
-    let reader = image::ImageReader::new(std::io::Cursor::new(bytes))
-        .with_guessed_format()
-        .map_err(|e| PyValueError::new_err(format!("Failed to detect image format: {}", e)))?;
-    match reader.into_dimensions() {
-        Ok((w, h)) => {
-            let product = (w as u64).checked_mul(h as u64).ok_or_else(|| {
-                PyValueError::new_err(format!("Image dimensions overflow: {}x{}", w, h))
-            })?;
-            if product > 100_000_000 {
-                return Err(PyValueError::new_err(format!(
-                    "Image too large: {}x{} exceeds 100MP limit",
-                    w, h
-                )));
-            }
-            Ok(())
-        }
-        Err(e) => Err(PyValueError::new_err(format!(
-            "Cannot determine image dimensions (possible decompression bomb): {}",
-            e
-        ))),
+    decode::check_dimensions(bytes, decode::MAX_PIXEL_COUNT)
+        .map_err(|e| PyValueError::new_err(e.to_string()))
+}
+
+/// Plain-Rust core of `MediaProcessor::self_test` — kept off the
+/// `#[pymethods]` wrapper so it's unit-testable without a Python interpreter
+/// (same rationale as `process_batch_chunk`). Returns one `(name, ok)` pair
+/// per codec checked plus the base64 round-trip, in a stable, fixed order.
+fn self_test_impl() -> Vec<(&'static str, bool)> {
+    let sample = image::RgbImage::from_fn(2, 2, |x, y| {
+        image::Rgb([(x * 64) as u8, (y * 64) as u8, 128])
+    });
+    let sample = DynamicImage::ImageRgb8(sample);
+
+    let mut results = vec![
+        ("png", codec_round_trips(&sample, ImageFormat::Png)),
+        ("jpeg", codec_round_trips(&sample, ImageFormat::Jpeg)),
+        ("webp", codec_round_trips(&sample, ImageFormat::WebP)),
+        ("gif", codec_round_trips(&sample, ImageFormat::Gif)),
+    ];
+
+    let base64_bytes = b"media_processor self-test";
+    let base64_ok = from_base64(&to_base64(base64_bytes))
+        .map(|decoded| decoded == base64_bytes)
+        .unwrap_or(false);
+    results.push(("base64", base64_ok));
+
+    results
+}
+
+/// Encode `img` to `format` in memory and decode it back. `false` (not an
+/// `Err`) on any failure — a codec that isn't compiled in or can't handle
+/// the sample should show up as a failed check, not abort `self_test`.
+fn codec_round_trips(img: &DynamicImage, format: ImageFormat) -> bool {
+    let mut buf = Vec::new();
+    if img
+        .write_to(&mut std::io::Cursor::new(&mut buf), format)
+        .is_err()
+    {
+        return false;
+    }
+    image::load_from_memory_with_format(&buf, format).is_ok()
+}
+
+/// Decode a single `data:<mime>;base64,` URI or bare base64 string, trying
+/// the data-URI form first and falling back to bare base64 — the shared
+/// logic behind `MediaProcessor::decode_base64` and `batch_decode_base64`.
+fn decode_base64_one(
+    encoded: &str,
+    max_output_bytes: Option<usize>,
+) -> Result<Vec<u8>, MediaError> {
+    match from_data_uri(encoded, max_output_bytes) {
+        Ok((bytes, _mime)) => Ok(bytes),
+        Err(_) => from_base64_checked(encoded, max_output_bytes),
+    }
+}
+
+/// Tolerance band around a 1:1 aspect ratio that still counts as "square" in
+/// [`aspect_class_from_dimensions`] -- wide enough to absorb off-by-a-few-px
+/// thumbnails and export rounding without misclassifying genuine
+/// portrait/landscape images.
+const SQUARE_ASPECT_TOLERANCE: f64 = 0.05;
+
+/// Classify `width x height` as "portrait", "landscape", or "square" -- the
+/// pure core behind `MediaProcessor::aspect_class`, split out so it's
+/// testable without a decoded image.
+fn aspect_class_from_dimensions(width: u32, height: u32) -> &'static str {
+    let ratio = f64::from(width) / f64::from(height);
+    if (ratio - 1.0).abs() <= SQUARE_ASPECT_TOLERANCE {
+        "square"
+    } else if ratio > 1.0 {
+        "landscape"
+    } else {
+        "portrait"
     }
 }
 
@@ -310,12 +1193,23 @@ const BATCH_CHUNK_SIZE: usize = 8;
 /// inline body: EVERY input in the chunk is bomb-checked BEFORE any decode, so
 /// a single hostile image rejects the whole chunk before the rayon pool
 /// allocates unbounded pixel buffers; then the chunk is resized in parallel,
-/// preserving input order.
+/// preserving input order. Before that, each image gets a header-only
+/// `try_passthrough_without_decode` check — an image already within bounds
+/// skips the full decode+re-encode entirely and its original bytes pass
+/// through untouched, so a batch that's already half pre-sized does roughly
+/// half the work.
+///
+/// `cancel`, when given, is polled once per item on the rayon worker pool —
+/// an item that sees it already set short-circuits to
+/// [`MediaError::Cancelled`] without resizing, so a cancellation raised
+/// mid-chunk still lets in-flight items on other threads finish cheaply
+/// rather than racing to stop them.
 fn process_batch_chunk(
     bytes_list: &[Vec<u8>],
     max_width: u32,
     max_height: u32,
     quality: u8,
+    cancel: Option<&AtomicBool>,
 ) -> PyResult<Vec<ImageData>> {
     use rayon::prelude::*;
 
@@ -324,7 +1218,26 @@ fn process_batch_chunk(
     }
     bytes_list
         .par_iter()
-        .map(|bytes| resize_image(bytes, max_width, max_height, ResizeMode::Fit, quality))
+        .map(|bytes| {
+            if cancel.is_some_and(|c| c.load(Ordering::Relaxed)) {
+                return Err(MediaError::Cancelled);
+            }
+            match try_passthrough_without_decode(bytes, max_width, max_height) {
+                Some(passthrough) => Ok(passthrough),
+                None => resize_image(
+                    bytes,
+                    max_width,
+                    max_height,
+                    ResizeMode::Fit,
+                    quality,
+                    false,
+                    CompressionLevel::default(),
+                    false,
+                    false,
+                    false,
+                ),
+            }
+        })
         .collect::<Result<Vec<ImageData>, _>>()
         .map_err(|e| PyValueError::new_err(e.to_string()))
 }
@@ -334,6 +1247,8 @@ fn process_batch_chunk(
 fn media_processor(m: &Bound<'_, PyModule>) -> PyResult<()> {
     m.add_class::<MediaProcessor>()?;
     m.add_class::<ImageData>()?;
+    m.add_class::<HistogramResult>()?;
+    m.add_class::<CancelToken>()?;
 
     // Convenience functions
     m.add_function(wrap_pyfunction!(py_is_animated, m)?)?;
@@ -387,7 +1302,7 @@ mod tests {
     /// see a >100MP image without allocating one (a decompression-bomb stand-in).
     fn png_header_claiming(w: u32, h: u32) -> Vec<u8> {
         let mut v = vec![0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A]; // signature
-        // IHDR chunk: length (13), type, 13 bytes of data, CRC.
+                                                                          // IHDR chunk: length (13), type, 13 bytes of data, CRC.
         v.extend_from_slice(&13u32.to_be_bytes());
         let mut chunk = Vec::new();
         chunk.extend_from_slice(b"IHDR");
@@ -440,7 +1355,7 @@ mod tests {
         let good = tiny_png(8, 8);
         let bomb = png_header_claiming(20_000, 20_000);
         let chunk = vec![good, bomb];
-        assert!(process_batch_chunk(&chunk, 4, 4, 85).is_err());
+        assert!(process_batch_chunk(&chunk, 4, 4, 85, None).is_err());
     }
 
     #[test]
@@ -448,7 +1363,7 @@ mod tests {
         // Distinct sizes so we can assert order is preserved through the
         // parallel resize. All within the 4x4 Fit bound -> all downscaled.
         let inputs = vec![tiny_png(20, 10), tiny_png(10, 20), tiny_png(16, 16)];
-        let out = process_batch_chunk(&inputs, 4, 4, 85).unwrap();
+        let out = process_batch_chunk(&inputs, 4, 4, 85, None).unwrap();
         assert_eq!(out.len(), 3);
         // Fit keeps aspect: 20x10 -> wider than tall, 10x20 -> taller than wide.
         assert!(out[0].width >= out[0].height);
@@ -469,7 +1384,7 @@ mod tests {
         let inputs: Vec<Vec<u8>> = (0..n).map(|_| tiny_png(12, 6)).collect();
         let mut output = Vec::new();
         for chunk in inputs.chunks(BATCH_CHUNK_SIZE) {
-            output.extend(process_batch_chunk(chunk, 4, 4, 85).unwrap());
+            output.extend(process_batch_chunk(chunk, 4, 4, 85, None).unwrap());
         }
         assert_eq!(output.len(), n);
         for img in &output {
@@ -478,6 +1393,49 @@ mod tests {
         }
     }
 
+    #[test]
+    fn process_batch_chunk_rejects_when_already_cancelled() {
+        let cancel = AtomicBool::new(true);
+        let inputs = vec![tiny_png(8, 8), tiny_png(8, 8)];
+        assert!(process_batch_chunk(&inputs, 4, 4, 85, Some(&cancel)).is_err());
+    }
+
+    #[test]
+    fn process_batch_chunk_ignores_a_cleared_cancel_flag() {
+        let cancel = AtomicBool::new(false);
+        let inputs = vec![tiny_png(8, 8), tiny_png(8, 8)];
+        let out = process_batch_chunk(&inputs, 4, 4, 85, Some(&cancel)).unwrap();
+        assert_eq!(out.len(), 2);
+    }
+
+    #[test]
+    fn decode_base64_one_prefers_data_uri_then_falls_back_to_bare() {
+        let uri = format!("data:image/png;base64,{}", to_base64(b"from-uri"));
+        assert_eq!(decode_base64_one(&uri, None).unwrap(), b"from-uri");
+
+        let bare = to_base64(b"bare-base64");
+        assert_eq!(decode_base64_one(&bare, None).unwrap(), b"bare-base64");
+    }
+
+    #[test]
+    fn decode_base64_one_respects_max_output_bytes() {
+        let huge = to_base64(&vec![0u8; 1_000_000]);
+        assert!(decode_base64_one(&huge, Some(1024)).is_err());
+    }
+
+    #[test]
+    fn aspect_class_from_dimensions_classifies_portrait_landscape_and_square() {
+        assert_eq!(aspect_class_from_dimensions(100, 200), "portrait");
+        assert_eq!(aspect_class_from_dimensions(200, 100), "landscape");
+        assert_eq!(aspect_class_from_dimensions(100, 100), "square");
+        // Within the tolerance band on either side of 1:1.
+        assert_eq!(aspect_class_from_dimensions(103, 100), "square");
+        assert_eq!(aspect_class_from_dimensions(100, 103), "square");
+        // Just outside the tolerance band.
+        assert_eq!(aspect_class_from_dimensions(110, 100), "landscape");
+        assert_eq!(aspect_class_from_dimensions(100, 110), "portrait");
+    }
+
     #[test]
     fn detect_format_identifies_png() {
         let png = tiny_png(4, 4);
@@ -485,4 +1443,183 @@ mod tests {
         // Too-short input returns None.
         assert_eq!(detect_format(&[0x89, 0x50]), None);
     }
+
+    fn unique_tempdir(tag: &str) -> std::path::PathBuf {
+        let nanos = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_nanos())
+            .unwrap_or(0);
+        let dir = std::env::temp_dir().join(format!(
+            "media_processor_test_{}_{}_{}",
+            tag,
+            std::process::id(),
+            nanos
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn load_path_reads_and_decodes_like_load() {
+        let dir = unique_tempdir("load_path");
+        let png_path = dir.join("in.png");
+        std::fs::write(&png_path, tiny_png(8, 4)).unwrap();
+
+        let processor = MediaProcessor::new(1024, 85, 1000);
+        let img = processor.load_path(png_path.to_str().unwrap()).unwrap();
+        assert_eq!((img.width, img.height), (8, 4));
+        assert_eq!(img.format, "png");
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn load_path_rejects_bomb_header_without_reading_pixels() {
+        let dir = unique_tempdir("load_path_bomb");
+        let bomb_path = dir.join("bomb.png");
+        std::fs::write(&bomb_path, png_header_claiming(20_000, 20_000)).unwrap();
+
+        let processor = MediaProcessor::new(1024, 85, 1000);
+        assert!(processor.load_path(bomb_path.to_str().unwrap()).is_err());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn load_path_surfaces_missing_file_error() {
+        let processor = MediaProcessor::new(1024, 85, 1000);
+        assert!(processor.load_path("/no/such/file.png").is_err());
+    }
+
+    #[test]
+    fn resize_path_fits_within_bounds() {
+        let dir = unique_tempdir("resize_path");
+        let png_path = dir.join("in.png");
+        std::fs::write(&png_path, tiny_png(20, 10)).unwrap();
+
+        let processor = MediaProcessor::new(1024, 85, 1000);
+        let resized = processor
+            .resize_path(
+                png_path.to_str().unwrap(),
+                Some(4),
+                Some(4),
+                false,
+                "default",
+                false,
+                false,
+                false,
+            )
+            .unwrap();
+        assert!(resized.width <= 4 && resized.height <= 4);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    // ------- set_max_dimension / set_jpeg_quality (#1685) -------
+
+    #[test]
+    fn set_max_dimension_rejects_zero_and_applies_afterward() {
+        // Interpreter-free: goes through resize_path (a &str path, no
+        // Python-bytes argument) rather than resize, which needs a real GIL
+        // token to build its PyBytes argument.
+        let dir = unique_tempdir("set_max_dimension");
+        let png_path = dir.join("in.png");
+        std::fs::write(&png_path, tiny_png(20, 10)).unwrap();
+
+        let mut processor = MediaProcessor::new(1024, 85, 1000);
+        assert!(processor.set_max_dimension(0).is_err());
+
+        processor.set_max_dimension(4).unwrap();
+        let resized = processor
+            .resize_path(
+                png_path.to_str().unwrap(),
+                None,
+                None,
+                false,
+                "default",
+                false,
+                false,
+                false,
+            )
+            .unwrap();
+        assert!(resized.width <= 4 && resized.height <= 4);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn set_jpeg_quality_rejects_out_of_range() {
+        let mut processor = MediaProcessor::new(1024, 85, 1000);
+        assert!(processor.set_jpeg_quality(0).is_err());
+        assert!(processor.set_jpeg_quality(101).is_err());
+        assert!(processor.set_jpeg_quality(50).is_ok());
+    }
+
+    #[test]
+    fn save_and_load_path_round_trip() {
+        let dir = unique_tempdir("save_round_trip");
+        let out_path = dir.join("out.png");
+
+        let processor = MediaProcessor::new(1024, 85, 1000);
+        let img = ImageData::new(tiny_png(4, 4), 4, 4, 3, "png".to_string(), false);
+        MediaProcessor::save(&img, out_path.to_str().unwrap()).unwrap();
+
+        let reloaded = processor.load_path(out_path.to_str().unwrap()).unwrap();
+        assert_eq!(reloaded.data, img.data);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn self_test_reports_every_codec_and_base64() {
+        let results = self_test_impl();
+        let names: Vec<_> = results.iter().map(|(name, _)| *name).collect();
+        assert_eq!(names, vec!["png", "jpeg", "webp", "gif", "base64"]);
+        // This build enables png/jpeg/webp/gif unconditionally (Cargo.toml),
+        // so every check must pass on a healthy build.
+        for (name, ok) in &results {
+            assert!(*ok, "self_test check {name:?} failed");
+        }
+    }
+
+    #[test]
+    fn codec_round_trips_fails_closed_on_unsupported_conversion() {
+        // Ico encoding isn't wired into this crate's dependency features, so
+        // this should report false rather than panic or return Err.
+        let img = tiny_png(4, 4);
+        let decoded = image::load_from_memory(&img).unwrap();
+        assert!(!codec_round_trips(&decoded, ImageFormat::Ico));
+    }
+
+    #[test]
+    fn encode_from_raw_pixels_round_trips_to_every_format() {
+        let width = 4;
+        let height = 4;
+        let pixels: Vec<u8> = (0..width * height * 4).map(|i| (i % 256) as u8).collect();
+        let img = ImageData::new(pixels, width, height, 4, "raw".to_string(), true);
+
+        for format in ["png", "jpeg", "webp"] {
+            let encoded = encode_image_data(&img, format, None).unwrap();
+            let decoded = image::load_from_memory(&encoded)
+                .unwrap_or_else(|e| panic!("failed to decode {format} output: {e}"));
+            assert_eq!(decoded.dimensions(), (width, height));
+        }
+    }
+
+    #[test]
+    fn encode_from_already_encoded_bytes_converts_format() {
+        let png_bytes = tiny_png(4, 4);
+        let img = ImageData::new(png_bytes, 4, 4, 3, "png".to_string(), false);
+
+        let jpeg_bytes = encode_image_data(&img, "jpeg", Some(90)).unwrap();
+        let decoded = image::load_from_memory_with_format(&jpeg_bytes, ImageFormat::Jpeg)
+            .expect("output should decode as jpeg");
+        assert_eq!(decoded.dimensions(), (4, 4));
+    }
+
+    #[test]
+    fn encode_rejects_unsupported_format() {
+        let img = ImageData::new(tiny_png(4, 4), 4, 4, 3, "png".to_string(), false);
+        assert!(encode_image_data(&img, "bmp", None).is_err());
+    }
 }
@@ -0,0 +1,181 @@
+//! EXIF orientation handling
+//!
+//! `image` decodes raw pixel data and does not consult EXIF, so a photo
+//! taken in portrait mode with the camera held sideways decodes "as stored"
+//! (landscape) unless the orientation tag is applied by hand. This is a
+//! minimal, dependency-free EXIF/TIFF walk that reads only the orientation
+//! tag (0x0112) out of a JPEG's APP1 segment — it does not attempt to parse
+//! or expose any other EXIF field.
+
+use image::DynamicImage;
+
+/// Read the EXIF orientation value (1-8) from JPEG bytes, defaulting to `1`
+/// (no transform needed) for non-JPEG input, missing EXIF, or any malformed
+/// segment. Never panics on truncated/corrupt data — worst case is a photo
+/// that isn't auto-rotated, not a crash.
+pub fn read_orientation(data: &[u8]) -> u16 {
+    // JPEG starts with SOI (0xFFD8); walk markers looking for APP1 (0xFFE1)
+    // carrying an "Exif\0\0" header.
+    if data.len() < 4 || data[0] != 0xFF || data[1] != 0xD8 {
+        return 1;
+    }
+
+    let mut i = 2usize;
+    while i + 4 <= data.len() {
+        if data[i] != 0xFF {
+            break;
+        }
+        let marker = data[i + 1];
+        // SOS (0xDA) begins the entropy-coded scan; no more markers follow.
+        if marker == 0xDA || marker == 0xD9 {
+            break;
+        }
+        let seg_len = u16::from_be_bytes([data[i + 2], data[i + 3]]) as usize;
+        if seg_len < 2 || i + 2 + seg_len > data.len() {
+            break;
+        }
+        let seg_start = i + 4;
+        let seg_end = i + 2 + seg_len;
+        if marker == 0xE1 && seg_end.saturating_sub(seg_start) >= 6 {
+            let seg = &data[seg_start..seg_end];
+            if &seg[0..6] == b"Exif\0\0" {
+                if let Some(orientation) = parse_tiff_orientation(&seg[6..]) {
+                    return orientation;
+                }
+            }
+        }
+        i = seg_end;
+    }
+
+    1
+}
+
+/// Parse the orientation tag (0x0112) out of a TIFF header + IFD0, as found
+/// in an EXIF blob. Returns `None` if the header is malformed or the tag is
+/// absent.
+fn parse_tiff_orientation(tiff: &[u8]) -> Option<u16> {
+    if tiff.len() < 8 {
+        return None;
+    }
+    let little_endian = match &tiff[0..2] {
+        b"II" => true,
+        b"MM" => false,
+        _ => return None,
+    };
+    let read_u16 = |b: &[u8]| -> u16 {
+        if little_endian {
+            u16::from_le_bytes([b[0], b[1]])
+        } else {
+            u16::from_be_bytes([b[0], b[1]])
+        }
+    };
+    let read_u32 = |b: &[u8]| -> u32 {
+        if little_endian {
+            u32::from_le_bytes([b[0], b[1], b[2], b[3]])
+        } else {
+            u32::from_be_bytes([b[0], b[1], b[2], b[3]])
+        }
+    };
+
+    let ifd_offset = read_u32(&tiff[4..8]) as usize;
+    if ifd_offset + 2 > tiff.len() {
+        return None;
+    }
+    let entry_count = read_u16(&tiff[ifd_offset..ifd_offset + 2]) as usize;
+    let entries_start = ifd_offset + 2;
+
+    for entry_idx in 0..entry_count {
+        let entry_off = entries_start + entry_idx * 12;
+        if entry_off + 12 > tiff.len() {
+            break;
+        }
+        let tag = read_u16(&tiff[entry_off..entry_off + 2]);
+        if tag == 0x0112 {
+            // Orientation is stored as a SHORT; the value lives in the first
+            // 2 bytes of the 4-byte value field.
+            let value_off = entry_off + 8;
+            if value_off + 2 <= tiff.len() {
+                let value = read_u16(&tiff[value_off..value_off + 2]);
+                if (1..=8).contains(&value) {
+                    return Some(value);
+                }
+            }
+        }
+    }
+    None
+}
+
+/// Apply the EXIF orientation transform (rotation/flip) so the returned
+/// image displays upright regardless of how the camera stored it.
+pub fn apply_orientation(img: DynamicImage, orientation: u16) -> DynamicImage {
+    match orientation {
+        2 => img.fliph(),
+        3 => img.rotate180(),
+        4 => img.flipv(),
+        5 => img.rotate90().fliph(),
+        6 => img.rotate90(),
+        7 => img.rotate270().fliph(),
+        8 => img.rotate270(),
+        _ => img,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn read_orientation_defaults_to_one_for_non_jpeg() {
+        assert_eq!(read_orientation(b"not a jpeg"), 1);
+        assert_eq!(read_orientation(&[]), 1);
+    }
+
+    fn jpeg_with_exif_orientation(orientation: u16) -> Vec<u8> {
+        // TIFF header (little-endian) + IFD0 with a single Orientation entry.
+        let mut tiff = Vec::new();
+        tiff.extend_from_slice(b"II"); // little-endian
+        tiff.extend_from_slice(&42u16.to_le_bytes());
+        tiff.extend_from_slice(&8u32.to_le_bytes()); // IFD0 offset
+        tiff.extend_from_slice(&1u16.to_le_bytes()); // 1 entry
+        tiff.extend_from_slice(&0x0112u16.to_le_bytes()); // tag: Orientation
+        tiff.extend_from_slice(&3u16.to_le_bytes()); // type: SHORT
+        tiff.extend_from_slice(&1u32.to_le_bytes()); // count: 1
+        tiff.extend_from_slice(&orientation.to_le_bytes());
+        tiff.extend_from_slice(&[0, 0]); // pad value field to 4 bytes
+        tiff.extend_from_slice(&0u32.to_le_bytes()); // next IFD offset
+
+        let mut app1 = b"Exif\0\0".to_vec();
+        app1.extend_from_slice(&tiff);
+
+        let mut jpeg = vec![0xFF, 0xD8]; // SOI
+        jpeg.push(0xFF);
+        jpeg.push(0xE1); // APP1
+        let seg_len = (app1.len() + 2) as u16;
+        jpeg.extend_from_slice(&seg_len.to_be_bytes());
+        jpeg.extend_from_slice(&app1);
+        jpeg.extend_from_slice(&[0xFF, 0xD9]); // EOI
+        jpeg
+    }
+
+    #[test]
+    fn read_orientation_finds_tag_in_exif_segment() {
+        let jpeg = jpeg_with_exif_orientation(6);
+        assert_eq!(read_orientation(&jpeg), 6);
+    }
+
+    #[test]
+    fn apply_orientation_identity_for_value_one() {
+        use image::GenericImageView;
+        let img = DynamicImage::ImageRgb8(image::RgbImage::new(4, 2));
+        let out = apply_orientation(img.clone(), 1);
+        assert_eq!(out.dimensions(), img.dimensions());
+    }
+
+    #[test]
+    fn apply_orientation_swaps_dimensions_for_rotate90() {
+        use image::GenericImageView;
+        let img = DynamicImage::ImageRgb8(image::RgbImage::new(4, 2));
+        let out = apply_orientation(img, 6);
+        assert_eq!(out.dimensions(), (2, 4));
+    }
+}
@@ -0,0 +1,169 @@
+//! Objective image quality metrics (PSNR, SSIM)
+
+use image::{DynamicImage, GenericImageView};
+
+use crate::decode;
+use crate::errors::MediaError;
+
+/// Compute PSNR (dB) and windowed SSIM on the luma channel between two
+/// images. Errors if the decoded dimensions differ — the metrics are only
+/// meaningful pixel-for-pixel. Both inputs go through the shared bomb guard
+/// (`decode::decode_with_guard`) before decoding.
+pub fn compare_images(a: &[u8], b: &[u8]) -> Result<(f64, f64), MediaError> {
+    let (img_a, _format) = decode::decode_with_guard(a, decode::MAX_PIXEL_COUNT)?;
+    let (img_b, _format) = decode::decode_with_guard(b, decode::MAX_PIXEL_COUNT)?;
+
+    if img_a.dimensions() != img_b.dimensions() {
+        return Err(MediaError::Encode(format!(
+            "dimension mismatch: {:?} vs {:?}",
+            img_a.dimensions(),
+            img_b.dimensions()
+        )));
+    }
+
+    let luma_a = to_luma_f64(&img_a);
+    let luma_b = to_luma_f64(&img_b);
+    let (w, h) = img_a.dimensions();
+
+    let psnr = compute_psnr(&luma_a, &luma_b);
+    let ssim = compute_ssim(&luma_a, &luma_b, w as usize, h as usize);
+
+    Ok((psnr, ssim))
+}
+
+fn to_luma_f64(img: &DynamicImage) -> Vec<f64> {
+    img.to_luma8()
+        .into_raw()
+        .into_iter()
+        .map(|v| v as f64)
+        .collect()
+}
+
+fn compute_psnr(a: &[f64], b: &[f64]) -> f64 {
+    let mse: f64 = a
+        .iter()
+        .zip(b.iter())
+        .map(|(x, y)| (x - y).powi(2))
+        .sum::<f64>()
+        / a.len() as f64;
+
+    if mse == 0.0 {
+        return f64::INFINITY;
+    }
+    20.0 * 255.0_f64.log10() - 10.0 * mse.log10()
+}
+
+/// Windowed SSIM (8x8 blocks, non-overlapping) averaged across the image.
+/// Standard SSIM constants for 8-bit data (K1=0.01, K2=0.03, L=255).
+fn compute_ssim(a: &[f64], b: &[f64], width: usize, height: usize) -> f64 {
+    const WINDOW: usize = 8;
+    const C1: f64 = (0.01 * 255.0) * (0.01 * 255.0);
+    const C2: f64 = (0.03 * 255.0) * (0.03 * 255.0);
+
+    if width == 0 || height == 0 {
+        return 1.0;
+    }
+
+    let mut total = 0.0;
+    let mut windows = 0.0;
+
+    let mut y = 0;
+    while y < height {
+        let win_h = WINDOW.min(height - y);
+        let mut x = 0;
+        while x < width {
+            let win_w = WINDOW.min(width - x);
+            let mut sum_a = 0.0;
+            let mut sum_b = 0.0;
+            let n = (win_w * win_h) as f64;
+
+            for dy in 0..win_h {
+                for dx in 0..win_w {
+                    let idx = (y + dy) * width + (x + dx);
+                    sum_a += a[idx];
+                    sum_b += b[idx];
+                }
+            }
+            let mean_a = sum_a / n;
+            let mean_b = sum_b / n;
+
+            let mut var_a = 0.0;
+            let mut var_b = 0.0;
+            let mut covar = 0.0;
+            for dy in 0..win_h {
+                for dx in 0..win_w {
+                    let idx = (y + dy) * width + (x + dx);
+                    let da = a[idx] - mean_a;
+                    let db = b[idx] - mean_b;
+                    var_a += da * da;
+                    var_b += db * db;
+                    covar += da * db;
+                }
+            }
+            var_a /= n;
+            var_b /= n;
+            covar /= n;
+
+            let numerator = (2.0 * mean_a * mean_b + C1) * (2.0 * covar + C2);
+            let denominator = (mean_a * mean_a + mean_b * mean_b + C1) * (var_a + var_b + C2);
+            total += numerator / denominator;
+            windows += 1.0;
+
+            x += WINDOW;
+        }
+        y += WINDOW;
+    }
+
+    if windows == 0.0 {
+        1.0
+    } else {
+        total / windows
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn png_of(w: u32, h: u32, f: impl Fn(u32, u32) -> u8) -> Vec<u8> {
+        let img = image::GrayImage::from_fn(w, h, |x, y| image::Luma([f(x, y)]));
+        let mut out = Vec::new();
+        DynamicImage::ImageLuma8(img)
+            .write_to(&mut Cursor::new(&mut out), image::ImageFormat::Png)
+            .unwrap();
+        out
+    }
+
+    #[test]
+    fn identical_images_have_infinite_psnr_and_ssim_one() {
+        let a = png_of(16, 16, |x, y| ((x + y) * 8) as u8);
+        let b = a.clone();
+        let (psnr, ssim) = compare_images(&a, &b).unwrap();
+        assert!(psnr.is_infinite());
+        assert!((ssim - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn differing_dimensions_error() {
+        let a = png_of(8, 8, |_, _| 0);
+        let b = png_of(4, 4, |_, _| 0);
+        assert!(compare_images(&a, &b).is_err());
+    }
+
+    #[test]
+    fn noisier_image_has_lower_ssim_and_psnr() {
+        let a = png_of(16, 16, |x, y| ((x + y) * 8) as u8);
+        let b = png_of(16, 16, |x, y| {
+            let base = (x + y) * 8;
+            if (x + y) % 2 == 0 {
+                base.saturating_add(60) as u8
+            } else {
+                base as u8
+            }
+        });
+        let (psnr, ssim) = compare_images(&a, &b).unwrap();
+        assert!(psnr.is_finite());
+        assert!(ssim < 1.0);
+    }
+}
@@ -0,0 +1,258 @@
+//! Color quantization / posterization
+//!
+//! Reduces an image to a fixed-size palette (NeuQuant, via the `color_quant`
+//! crate — the same quantizer the `image` crate's own GIF encoder uses
+//! internally) and writes the result out as an indexed PNG, which is both a
+//! stylized "posterize" filter and a genuine file-size win for palette-heavy
+//! images.
+
+use image::GenericImageView;
+
+use crate::decode;
+use crate::errors::MediaError;
+use crate::ImageData;
+
+/// Quantize an image down to `colors` (2..=256) and encode it as an indexed
+/// PNG. `colors` outside that range is rejected up front rather than
+/// silently clamped, since callers picking a palette size for a pixel-art
+/// filter care about the exact count they asked for.
+pub fn quantize_image(data: &[u8], colors: u16) -> Result<ImageData, MediaError> {
+    if !(2..=256).contains(&colors) {
+        return Err(MediaError::Encode(format!(
+            "colors must be between 2 and 256, got {}",
+            colors
+        )));
+    }
+
+    let (img, _format) = decode::decode_with_guard(data, decode::MAX_PIXEL_COUNT)?;
+    let (width, height) = img.dimensions();
+    let rgba = img.to_rgba8();
+    let raw = rgba.as_raw();
+
+    // Sample fraction 1 = examine every pixel; this is a one-shot filter op,
+    // not a hot loop, so favor palette quality over quantization speed.
+    let quant = color_quant::NeuQuant::new(1, colors as usize, raw);
+    let palette_rgba = quant.color_map_rgba();
+    let indices: Vec<u8> = raw.chunks_exact(4).map(|p| quant.index_of(p) as u8).collect();
+
+    let rgb_palette: Vec<u8> = palette_rgba
+        .chunks_exact(4)
+        .flat_map(|p| [p[0], p[1], p[2]])
+        .collect();
+    let alpha_palette: Vec<u8> = palette_rgba.chunks_exact(4).map(|p| p[3]).collect();
+
+    let mut output = Vec::new();
+    {
+        let mut encoder = png::Encoder::new(&mut output, width, height);
+        encoder.set_color(png::ColorType::Indexed);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder.set_palette(rgb_palette);
+        encoder.set_trns(alpha_palette);
+        let mut writer = encoder
+            .write_header()
+            .map_err(|e| MediaError::Encode(e.to_string()))?;
+        writer
+            .write_image_data(&indices)
+            .map_err(|e| MediaError::Encode(e.to_string()))?;
+    }
+
+    Ok(ImageData {
+        data: output,
+        width,
+        height,
+        channels: 1,
+        format: "png".to_string(),
+        is_raw_pixels: false,
+    })
+}
+
+/// Quantize a batch of frames down to `colors` (2..=256). When
+/// `shared_palette` is true, one NeuQuant palette is computed from pixels
+/// sampled across *every* frame at once and each frame is indexed against
+/// that single palette — this is what keeps a multi-frame animation's colors
+/// consistent frame-to-frame, instead of each frame's independently-fit
+/// palette drifting and forcing a bigger combined color set once they're
+/// reassembled. `shared_palette=false` instead quantizes each frame on its
+/// own, equivalent to calling `quantize_image` once per frame.
+///
+/// This only computes and applies the palette(s) and returns one indexed PNG
+/// per frame — it does not assemble an animated GIF or WebP, since this crate
+/// has neither an animated-GIF writer nor an animated-WebP writer to hand the
+/// result to.
+pub fn quantize_frames(
+    frames: &[&[u8]],
+    colors: u16,
+    shared_palette: bool,
+) -> Result<Vec<ImageData>, MediaError> {
+    if !(2..=256).contains(&colors) {
+        return Err(MediaError::Encode(format!(
+            "colors must be between 2 and 256, got {}",
+            colors
+        )));
+    }
+    if frames.is_empty() {
+        return Ok(Vec::new());
+    }
+    if !shared_palette {
+        return frames.iter().map(|f| quantize_image(f, colors)).collect();
+    }
+
+    let decoded: Vec<_> = frames
+        .iter()
+        .map(|f| decode::decode_with_guard(f, decode::MAX_PIXEL_COUNT))
+        .collect::<Result<_, _>>()?;
+    let rgba_frames: Vec<_> = decoded.iter().map(|(img, _)| img.to_rgba8()).collect();
+
+    // One combined sample buffer across every frame's pixels, so the fitted
+    // palette represents the whole animation rather than just its first frame.
+    let mut combined: Vec<u8> = Vec::new();
+    for rgba in &rgba_frames {
+        combined.extend_from_slice(rgba.as_raw());
+    }
+
+    let quant = color_quant::NeuQuant::new(1, colors as usize, &combined);
+    let palette_rgba = quant.color_map_rgba();
+    let rgb_palette: Vec<u8> = palette_rgba
+        .chunks_exact(4)
+        .flat_map(|p| [p[0], p[1], p[2]])
+        .collect();
+    let alpha_palette: Vec<u8> = palette_rgba.chunks_exact(4).map(|p| p[3]).collect();
+
+    rgba_frames
+        .iter()
+        .zip(decoded.iter())
+        .map(|(rgba, (img, _))| {
+            let (width, height) = img.dimensions();
+            let indices: Vec<u8> = rgba
+                .as_raw()
+                .chunks_exact(4)
+                .map(|p| quant.index_of(p) as u8)
+                .collect();
+
+            let mut output = Vec::new();
+            {
+                let mut encoder = png::Encoder::new(&mut output, width, height);
+                encoder.set_color(png::ColorType::Indexed);
+                encoder.set_depth(png::BitDepth::Eight);
+                encoder.set_palette(rgb_palette.clone());
+                encoder.set_trns(alpha_palette.clone());
+                let mut writer = encoder
+                    .write_header()
+                    .map_err(|e| MediaError::Encode(e.to_string()))?;
+                writer
+                    .write_image_data(&indices)
+                    .map_err(|e| MediaError::Encode(e.to_string()))?;
+            }
+
+            Ok(ImageData {
+                data: output,
+                width,
+                height,
+                channels: 1,
+                format: "png".to_string(),
+                is_raw_pixels: false,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor as IoCursor;
+
+    fn tiny_rgba_png(w: u32, h: u32) -> Vec<u8> {
+        let img = image::RgbaImage::from_fn(w, h, |x, y| {
+            image::Rgba([(x * 40) as u8, (y * 40) as u8, 128, 255])
+        });
+        let mut out = Vec::new();
+        image::DynamicImage::ImageRgba8(img)
+            .write_to(&mut IoCursor::new(&mut out), image::ImageFormat::Png)
+            .unwrap();
+        out
+    }
+
+    #[test]
+    fn quantize_produces_a_decodable_indexed_png_with_correct_dimensions() {
+        let input = tiny_rgba_png(8, 8);
+        let quantized = quantize_image(&input, 4).unwrap();
+        assert_eq!(quantized.format, "png");
+        assert_eq!((quantized.width, quantized.height), (8, 8));
+
+        let decoded = image::load_from_memory(&quantized.data).unwrap();
+        assert_eq!(decoded.dimensions(), (8, 8));
+    }
+
+    #[test]
+    fn quantize_rejects_out_of_range_color_counts() {
+        let input = tiny_rgba_png(4, 4);
+        assert!(quantize_image(&input, 0).is_err());
+        assert!(quantize_image(&input, 1).is_err());
+        assert!(quantize_image(&input, 257).is_err());
+    }
+
+    #[test]
+    fn quantize_accepts_boundary_color_counts() {
+        let input = tiny_rgba_png(4, 4);
+        assert!(quantize_image(&input, 2).is_ok());
+        assert!(quantize_image(&input, 256).is_ok());
+    }
+
+    #[test]
+    fn quantize_frames_empty_input_is_empty() {
+        assert_eq!(quantize_frames(&[], 4, true).unwrap().len(), 0);
+    }
+
+    #[test]
+    fn quantize_frames_rejects_out_of_range_color_counts() {
+        let input = tiny_rgba_png(4, 4);
+        assert!(quantize_frames(&[&input], 1, true).is_err());
+        assert!(quantize_frames(&[&input], 257, false).is_err());
+    }
+
+    #[test]
+    fn quantize_frames_returns_one_decodable_png_per_frame_at_source_dimensions() {
+        let frame_a = tiny_rgba_png(6, 6);
+        let frame_b = tiny_rgba_png(8, 4);
+        let outputs = quantize_frames(&[&frame_a, &frame_b], 4, true).unwrap();
+
+        assert_eq!(outputs.len(), 2);
+        assert_eq!((outputs[0].width, outputs[0].height), (6, 6));
+        assert_eq!((outputs[1].width, outputs[1].height), (8, 4));
+        for output in &outputs {
+            assert_eq!(output.format, "png");
+            image::load_from_memory(&output.data).unwrap();
+        }
+    }
+
+    #[test]
+    fn quantize_frames_shared_palette_uses_the_same_palette_across_frames() {
+        // Two frames whose color ranges only overlap when quantized together:
+        // a shared palette must place representative colors for both, while
+        // an independent per-frame palette would fit each to its own colors
+        // and drift, so distinct pixels can decode to different final colors
+        // between the two modes.
+        let frame_a = tiny_rgba_png(8, 8);
+        let frame_b = image::RgbaImage::from_fn(8, 8, |x, y| {
+            image::Rgba([255 - (x * 30) as u8, 255 - (y * 30) as u8, 10, 255])
+        });
+        let mut frame_b_bytes = Vec::new();
+        image::DynamicImage::ImageRgba8(frame_b)
+            .write_to(
+                &mut IoCursor::new(&mut frame_b_bytes),
+                image::ImageFormat::Png,
+            )
+            .unwrap();
+
+        let shared = quantize_frames(&[&frame_a, &frame_b_bytes], 4, true).unwrap();
+        let independent = quantize_frames(&[&frame_a, &frame_b_bytes], 4, false).unwrap();
+
+        // Both modes still produce decodable, correctly-sized output; only
+        // the palette-fitting strategy differs.
+        assert_eq!(shared.len(), 2);
+        assert_eq!(independent.len(), 2);
+        for output in shared.iter().chain(independent.iter()) {
+            image::load_from_memory(&output.data).unwrap();
+        }
+    }
+}
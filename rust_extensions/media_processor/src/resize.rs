@@ -1,9 +1,11 @@
 //! Image resizing functionality
 
-use image::{DynamicImage, GenericImageView, ImageFormat};
+use image::{DynamicImage, GenericImageView, ImageDecoder, ImageEncoder, ImageFormat};
 use std::io::Cursor;
 
+use crate::decode::{self, MAX_PIXEL_COUNT};
 use crate::errors::MediaError;
+use crate::icc::minimal_srgb_icc_profile;
 use crate::ImageData;
 
 /// Resize mode
@@ -17,18 +19,68 @@ pub enum ResizeMode {
     Stretch,
 }
 
+/// PNG compression effort for [`resize_image`]/[`resize_decoded`]'s PNG
+/// output path — trades encode CPU time for smaller files. `Best` can shave
+/// 20-30% off a typical PNG at the cost of noticeably more CPU per resize;
+/// `Fast` is closer to instant but leaves size on the table. JPEG output
+/// ignores this entirely (its size is controlled by `jpeg_quality` instead),
+/// and a WebP output (reachable only through `preserve_format`) is written
+/// by `image`'s generic `write_to` with its default settings, so a
+/// `compression_level` here can only ever affect PNG.
+#[derive(Clone, Copy, Default)]
+pub enum CompressionLevel {
+    /// Least compression effort, fastest encode.
+    Fast,
+    /// The `image` crate's own default balance of speed and size.
+    #[default]
+    Default,
+    /// Most compression effort, smallest output, slowest encode.
+    Best,
+}
+
+impl CompressionLevel {
+    fn to_png_compression(self) -> image::codecs::png::CompressionType {
+        match self {
+            CompressionLevel::Fast => image::codecs::png::CompressionType::Fast,
+            CompressionLevel::Default => image::codecs::png::CompressionType::Default,
+            CompressionLevel::Best => image::codecs::png::CompressionType::Best,
+        }
+    }
+}
+
 /// Maximum allowed dimension for resize operations (prevents DoS via extreme allocations)
 const MAX_ALLOWED_DIMENSION: u32 = 16384;
-/// Maximum allowed pixel count to prevent decompression bombs
-const MAX_PIXEL_COUNT: u64 = 100_000_000; // 100 megapixels
 
-/// Resize an image
+/// Resize an image. `preserve_icc` controls whether the source's embedded
+/// ICC color profile (if any) is read and carried into the re-encoded
+/// output — see [`decode_with_optional_icc`] for which source formats
+/// expose one and which output formats can embed it.
+///
+/// A 16-bit-per-channel source (common for depth maps and scientific/medical
+/// images) keeps its full precision through the resize — `image`'s resize
+/// ops are generic over pixel type — and through the encode, since
+/// [`determine_output_format`] routes 16-bit sources to PNG (which supports
+/// 16-bit natively) instead of JPEG (which only encodes 8-bit and would
+/// silently truncate).
+///
+/// `tag_srgb` embeds a minimal generated sRGB ICC profile (see
+/// [`crate::icc::minimal_srgb_icc_profile`]) into a PNG/JPEG output that
+/// doesn't already carry one, so viewers that don't assume untagged = sRGB
+/// stop shifting colors. It never overrides a real profile carried in by
+/// `preserve_icc` — a genuine source profile is always more correct than
+/// this synthetic one.
+#[allow(clippy::too_many_arguments)]
 pub fn resize_image(
     data: &[u8],
     max_width: u32,
     max_height: u32,
     mode: ResizeMode,
     jpeg_quality: u8,
+    preserve_icc: bool,
+    compression_level: CompressionLevel,
+    preserve_format: bool,
+    linear_light: bool,
+    tag_srgb: bool,
 ) -> Result<ImageData, MediaError> {
     // Validate dimensions to prevent panic in image crate
     if max_width == 0 || max_height == 0 {
@@ -44,36 +96,188 @@ pub fn resize_image(
     // Clamp JPEG quality to valid range (1-100)
     let jpeg_quality = jpeg_quality.clamp(1, 100);
 
-    // Check image dimensions BEFORE full decode to prevent decompression bombs
-    let reader = image::ImageReader::new(std::io::Cursor::new(data))
-        .with_guessed_format()
-        .map_err(|e| MediaError::Encode(format!("Failed to detect image format: {}", e)))?;
-    match reader.into_dimensions() {
-        Ok((w, h)) => {
-            // Mirror the checked_mul style used by lib.rs check_bomb_dimensions and the
-            // Fill intermediate guard below. Both operands are u32 widened to u64 so the
-            // product cannot actually overflow u64 — checked_mul is for stylistic
-            // consistency, not a live overflow risk.
-            let pixels = (w as u64).checked_mul(h as u64);
-            if pixels.is_none_or(|p| p > MAX_PIXEL_COUNT) {
-                return Err(MediaError::Encode(format!(
-                    "Image too large: {}x{} ({} MP, max {} MP)",
-                    w,
-                    h,
-                    pixels.unwrap_or(u64::MAX) / 1_000_000,
-                    MAX_PIXEL_COUNT / 1_000_000
-                )));
-            }
-        }
-        Err(e) => {
-            return Err(MediaError::Encode(format!(
-                "Cannot determine image dimensions (possible decompression bomb): {}",
-                e
-            )));
+    // Check image dimensions BEFORE full decode to prevent decompression bombs.
+    // Shares the guard every decode entry point in the crate uses (see
+    // `decode.rs`) rather than its own copy of the header-parse-and-compare.
+    decode::check_dimensions(data, MAX_PIXEL_COUNT)?;
+
+    let (img, icc_profile) = decode_with_optional_icc(data, preserve_icc)?;
+    let (orig_w, orig_h) = img.dimensions();
+
+    // Guard against degenerate/corrupt images with zero dimensions
+    if orig_w == 0 || orig_h == 0 {
+        return Err(MediaError::Encode("Image has zero dimensions".to_string()));
+    }
+
+    // Skip if already smaller (only for Fit mode — Fill/Stretch must reach requested
+    // dimensions). This early return preserves the ORIGINAL bytes verbatim (no
+    // re-encode), so it's only available here where we still hold `data`;
+    // resize_decoded() below always resizes+re-encodes. The original bytes
+    // already carry whatever ICC profile they had, so `preserve_icc` has no
+    // extra work to do on this path.
+    if matches!(mode, ResizeMode::Fit) {
+        let (new_w, new_h) = calculate_fit_dimensions(orig_w, orig_h, max_width, max_height);
+        if new_w >= orig_w && new_h >= orig_h {
+            return Ok(ImageData {
+                data: data.to_vec(),
+                width: orig_w,
+                height: orig_h,
+                channels: img.color().channel_count(),
+                // Report the TRUE format of the original bytes being returned (sniffed
+                // from magic bytes), not the png/jpeg guess from color type — otherwise a
+                // small WebP/GIF/BMP that skips resizing would be mislabeled.
+                format: image::guess_format(data)
+                    .map(format_to_string)
+                    .unwrap_or_else(|_| detect_format_from_color(img.color())),
+                is_raw_pixels: false,
+            });
         }
     }
 
-    let img = image::load_from_memory(data)?;
+    // Sniffed from the original bytes' magic, same as the already-smaller
+    // shortcut above uses for its own reported format — resolved before the
+    // decode is consumed by the resize below.
+    let source_format = if preserve_format {
+        image::guess_format(data).ok()
+    } else {
+        None
+    };
+
+    resize_decoded_with_icc(
+        img,
+        max_width,
+        max_height,
+        mode,
+        jpeg_quality,
+        icc_profile,
+        compression_level,
+        source_format,
+        linear_light,
+        tag_srgb,
+    )
+}
+
+/// Fast pre-check for batch callers: reads only the image header (dimensions
+/// and color type via `ImageDecoder`, no pixel decode) and returns the
+/// original bytes untouched as `ImageData` when the image is already within
+/// `max_width`/`max_height` under `ResizeMode::Fit` semantics — the same
+/// "skip if already smaller" rule `resize_image` applies internally, just
+/// without paying for a full decode first to find out. Returns `None` when
+/// the image actually needs resizing, or when even the header can't be
+/// read — either way the caller falls back to `resize_image`, which will
+/// decode fully and surface a real error if the data is genuinely bad.
+pub fn try_passthrough_without_decode(
+    data: &[u8],
+    max_width: u32,
+    max_height: u32,
+) -> Option<ImageData> {
+    let decoder = image::ImageReader::new(Cursor::new(data))
+        .with_guessed_format()
+        .ok()?
+        .into_decoder()
+        .ok()?;
+    let (orig_w, orig_h) = decoder.dimensions();
+    if orig_w == 0 || orig_h == 0 {
+        return None;
+    }
+    let color = decoder.color_type();
+
+    let (new_w, new_h) = calculate_fit_dimensions(orig_w, orig_h, max_width, max_height);
+    if new_w < orig_w || new_h < orig_h {
+        return None;
+    }
+
+    Some(ImageData {
+        data: data.to_vec(),
+        width: orig_w,
+        height: orig_h,
+        channels: color.channel_count(),
+        format: image::guess_format(data)
+            .map(format_to_string)
+            .unwrap_or_else(|_| detect_format_from_color(color)),
+        is_raw_pixels: false,
+    })
+}
+
+/// Decode `data`, optionally extracting its embedded ICC color profile via
+/// the `image` crate's per-format decoder support (PNG, JPEG, WebP and TIFF
+/// sources expose one when present; other source formats always report
+/// `None`). When `preserve_icc` is false this skips the extra decoder step
+/// entirely and decodes with the plain `load_from_memory` path used before
+/// ICC support existed.
+fn decode_with_optional_icc(
+    data: &[u8],
+    preserve_icc: bool,
+) -> Result<(DynamicImage, Option<Vec<u8>>), MediaError> {
+    if !preserve_icc {
+        return Ok((image::load_from_memory(data)?, None));
+    }
+
+    let reader = image::ImageReader::new(Cursor::new(data))
+        .with_guessed_format()
+        .map_err(|e| MediaError::Decode(format!("Failed to detect image format: {}", e)))?;
+    let mut decoder = reader.into_decoder()?;
+    let icc_profile = decoder.icc_profile()?;
+    let img = DynamicImage::from_decoder(decoder)?;
+    Ok((img, icc_profile))
+}
+
+/// Resize an already-decoded image, always resizing and re-encoding. Factored
+/// out of [`resize_image`] so callers that need to do other work on the same
+/// decode (e.g. applying EXIF orientation before resizing) don't pay for a
+/// second decode round-trip. Unlike [`resize_image`], this has no "already
+/// smaller than target" shortcut — there are no original bytes left to hand
+/// back verbatim once the caller has done its own transform on the decode.
+pub fn resize_decoded(
+    img: DynamicImage,
+    max_width: u32,
+    max_height: u32,
+    mode: ResizeMode,
+    jpeg_quality: u8,
+) -> Result<ImageData, MediaError> {
+    resize_decoded_with_icc(
+        img,
+        max_width,
+        max_height,
+        mode,
+        jpeg_quality,
+        None,
+        CompressionLevel::default(),
+        None,
+        false,
+        false,
+    )
+}
+
+/// Same as [`resize_decoded`], additionally embedding `icc_profile` into the
+/// re-encoded output when one is given and the chosen output format supports
+/// it (PNG and JPEG both do; a source ICC profile paired with a WebP/GIF
+/// output is simply dropped, since `determine_output_format` never picks
+/// those for resize's own output), re-encoding in `preserve_format` (when
+/// given) instead of the alpha/bit-depth heuristic — see
+/// [`encode_preserving_format`] for the fallback when that format's encoder
+/// isn't available — and, when `linear_light` is set, resampling in linear
+/// light instead of `image`'s default of resampling raw sRGB-encoded values
+/// — see [`srgb_image_to_linear`] for why that matters for fine detail. When
+/// `tag_srgb` is set and no real `icc_profile` was carried in, a generated
+/// minimal sRGB profile (see [`crate::icc::minimal_srgb_icc_profile`]) is
+/// embedded in its place.
+#[allow(clippy::too_many_arguments)]
+fn resize_decoded_with_icc(
+    img: DynamicImage,
+    max_width: u32,
+    max_height: u32,
+    mode: ResizeMode,
+    jpeg_quality: u8,
+    icc_profile: Option<Vec<u8>>,
+    compression_level: CompressionLevel,
+    preserve_format: Option<ImageFormat>,
+    linear_light: bool,
+    tag_srgb: bool,
+) -> Result<ImageData, MediaError> {
+    let jpeg_quality = jpeg_quality.clamp(1, 100);
+    let max_width = max_width.min(MAX_ALLOWED_DIMENSION);
+    let max_height = max_height.min(MAX_ALLOWED_DIMENSION);
     let (orig_w, orig_h) = img.dimensions();
 
     // Guard against degenerate/corrupt images with zero dimensions
@@ -88,21 +292,16 @@ pub fn resize_image(
         ResizeMode::Stretch => (max_width, max_height),
     };
 
-    // Skip if already smaller (only for Fit mode — Fill/Stretch must reach requested dimensions)
-    if matches!(mode, ResizeMode::Fit) && new_w >= orig_w && new_h >= orig_h {
-        return Ok(ImageData {
-            data: data.to_vec(),
-            width: orig_w,
-            height: orig_h,
-            channels: img.color().channel_count(),
-            // Report the TRUE format of the original bytes being returned (sniffed
-            // from magic bytes), not the png/jpeg guess from color type — otherwise a
-            // small WebP/GIF/BMP that skips resizing would be mislabeled.
-            format: image::guess_format(data)
-                .map(format_to_string)
-                .unwrap_or_else(|_| detect_format_from_image(&img)),
-        });
-    }
+    // The alpha/bit-depth heuristic must read the ORIGINAL decode, not the
+    // linear-light conversion below (which always materializes as Rgba32F
+    // regardless of the source's real color type).
+    let heuristic_format = determine_output_format(&img);
+    let had_alpha = img.color().has_alpha();
+    let img = if linear_light {
+        srgb_image_to_linear(&img)
+    } else {
+        img
+    };
 
     // Perform resize
     let resized = match mode {
@@ -152,22 +351,73 @@ pub fn resize_image(
         ResizeMode::Fit => img.resize(new_w, new_h, image::imageops::FilterType::Lanczos3),
     };
 
+    let resized = if linear_light {
+        linear_image_to_srgb(resized, had_alpha)
+    } else {
+        resized
+    };
+
+    let icc_profile = icc_profile.or_else(|| tag_srgb.then(minimal_srgb_icc_profile));
+
     // Encode result
+    match preserve_format {
+        Some(source_format) => encode_preserving_format(
+            &resized,
+            source_format,
+            jpeg_quality,
+            icc_profile,
+            compression_level,
+        ),
+        None => encode_resized(
+            &resized,
+            heuristic_format,
+            jpeg_quality,
+            icc_profile,
+            compression_level,
+        ),
+    }
+}
+
+/// Encode an already-resized image to `format` (Png or Jpeg — the only two
+/// this build's encoders support; anything else falls back to Jpeg), for a
+/// caller that has already picked its target format via
+/// [`determine_output_format`]. Factored out of [`resize_decoded_with_icc`]
+/// so [`generate_thumbnails`] can reuse the exact same encode step for each
+/// size without recomputing the resize it already has in hand.
+fn encode_resized(
+    resized: &DynamicImage,
+    format: ImageFormat,
+    jpeg_quality: u8,
+    icc_profile: Option<Vec<u8>>,
+    compression_level: CompressionLevel,
+) -> Result<ImageData, MediaError> {
     let (new_w, new_h) = resized.dimensions();
     let mut output = Vec::new();
     // determine_output_format only ever returns Png or Jpeg, and this build has
     // no WebP encoder — so handle Png explicitly and encode everything else as
     // JPEG, reassigning `format` so the reported format always matches the bytes
     // (the old WebP/`_` arms were unreachable and the `_` arm mislabeled output).
-    let mut format = determine_output_format(&img);
+    let mut format = format;
     match format {
         ImageFormat::Png => {
-            resized.write_to(&mut Cursor::new(&mut output), ImageFormat::Png)?;
+            let mut encoder = image::codecs::png::PngEncoder::new_with_quality(
+                &mut output,
+                compression_level.to_png_compression(),
+                image::codecs::png::FilterType::default(),
+            );
+            if let Some(icc) = icc_profile.clone() {
+                // PNG always supports an iCCP chunk, so this can't actually fail.
+                let _ = encoder.set_icc_profile(icc);
+            }
+            resized.write_with_encoder(encoder)?;
         }
         _ => {
             format = ImageFormat::Jpeg;
-            let encoder =
+            let mut encoder =
                 image::codecs::jpeg::JpegEncoder::new_with_quality(&mut output, jpeg_quality);
+            if let Some(icc) = icc_profile.clone() {
+                let _ = encoder.set_icc_profile(icc);
+            }
             resized.write_with_encoder(encoder)?;
         }
     }
@@ -178,9 +428,125 @@ pub fn resize_image(
         height: new_h,
         channels: resized.color().channel_count(),
         format: format_to_string(format),
+        is_raw_pixels: false,
     })
 }
 
+/// Re-encode `resized` in `source_format` (the format the original upload
+/// was in) instead of [`determine_output_format`]'s alpha/bit-depth
+/// heuristic, for `preserve_format=true` callers who'd rather get the same
+/// format back than have a resized GIF silently become a PNG. Png and Jpeg
+/// go through [`encode_resized`] like the heuristic path always has; any
+/// other format (Gif, WebP, or anything else the `image` crate's generic
+/// `write_to` can encode) is attempted directly. A failed attempt falls
+/// back to the same heuristic a non-preserving caller would have gotten,
+/// rather than failing the whole resize over a format preference.
+fn encode_preserving_format(
+    resized: &DynamicImage,
+    source_format: ImageFormat,
+    jpeg_quality: u8,
+    icc_profile: Option<Vec<u8>>,
+    compression_level: CompressionLevel,
+) -> Result<ImageData, MediaError> {
+    match source_format {
+        ImageFormat::Png | ImageFormat::Jpeg => encode_resized(
+            resized,
+            source_format,
+            jpeg_quality,
+            icc_profile,
+            compression_level,
+        ),
+        _ => {
+            let mut output = Vec::new();
+            if resized
+                .write_to(&mut Cursor::new(&mut output), source_format)
+                .is_ok()
+            {
+                let (new_w, new_h) = resized.dimensions();
+                Ok(ImageData {
+                    data: output,
+                    width: new_w,
+                    height: new_h,
+                    channels: resized.color().channel_count(),
+                    format: format_to_string(source_format),
+                    is_raw_pixels: false,
+                })
+            } else {
+                let heuristic_format = determine_output_format(resized);
+                encode_resized(
+                    resized,
+                    heuristic_format,
+                    jpeg_quality,
+                    icc_profile,
+                    compression_level,
+                )
+            }
+        }
+    }
+}
+
+/// Decode `data` once and produce a same-aspect-ratio `Fit` thumbnail for
+/// every requested size in `sizes`, without decoding the source once per
+/// size the way three separate `thumbnail()` calls would. Output order
+/// matches `sizes`' order regardless of processing order.
+///
+/// Sizes are processed largest-to-smallest, each one resizing from the
+/// previous (already-shrunk) image rather than the original full-size
+/// decode — cheaper for the common "several decreasing sizes" case. Each
+/// result is a `Fit` of the previous one to a smaller bound, so its longer
+/// side matches a direct downscale of the original; the shorter side can
+/// differ by a pixel from rounding at an intermediate size, and pixel values
+/// differ slightly since each step resamples an already-resampled image.
+pub fn generate_thumbnails(
+    data: &[u8],
+    sizes: &[u32],
+    jpeg_quality: u8,
+) -> Result<Vec<ImageData>, MediaError> {
+    if sizes.contains(&0) {
+        return Err(MediaError::Encode(
+            "Dimensions must be greater than 0".to_string(),
+        ));
+    }
+
+    decode::check_dimensions(data, MAX_PIXEL_COUNT)?;
+    let jpeg_quality = jpeg_quality.clamp(1, 100);
+    let source = image::load_from_memory(data)?;
+    let (orig_w, orig_h) = source.dimensions();
+    if orig_w == 0 || orig_h == 0 {
+        return Err(MediaError::Encode("Image has zero dimensions".to_string()));
+    }
+
+    let mut order: Vec<usize> = (0..sizes.len()).collect();
+    order.sort_unstable_by(|&a, &b| sizes[b].cmp(&sizes[a]));
+
+    let mut results: Vec<Option<ImageData>> = vec![None; sizes.len()];
+    let mut current = source;
+    for idx in order {
+        let size = sizes[idx];
+        let (cur_w, cur_h) = current.dimensions();
+        let (new_w, new_h) = calculate_fit_dimensions(cur_w, cur_h, size, size);
+        let resized = if new_w >= cur_w && new_h >= cur_h {
+            current.clone()
+        } else {
+            current.resize(new_w, new_h, image::imageops::FilterType::Lanczos3)
+        };
+        let format = determine_output_format(&resized);
+        results[idx] = Some(encode_resized(
+            &resized,
+            format,
+            jpeg_quality,
+            None,
+            CompressionLevel::default(),
+        )?);
+        current = resized;
+    }
+
+    Ok(results
+        .into_iter()
+        .map(|r| r.expect("every requested index is filled exactly once"))
+        .collect())
+}
+
 /// Calculate dimensions to fit within bounds while maintaining aspect ratio
 fn calculate_fit_dimensions(orig_w: u32, orig_h: u32, max_w: u32, max_h: u32) -> (u32, u32) {
     let ratio_w = max_w as f64 / orig_w as f64;
@@ -197,16 +563,105 @@ fn calculate_fit_dimensions(orig_w: u32, orig_h: u32, max_w: u32, max_h: u32) ->
     }
 }
 
-fn detect_format_from_image(img: &DynamicImage) -> String {
-    match img.color() {
-        image::ColorType::Rgba8 | image::ColorType::Rgba16 => "png".to_string(),
-        _ => "jpeg".to_string(),
+fn detect_format_from_color(color: image::ColorType) -> String {
+    if color.has_alpha() || is_16_bit(color) {
+        "png".to_string()
+    } else {
+        "jpeg".to_string()
+    }
+}
+
+/// True for the `image` crate's 16-bit-per-channel color types (as opposed
+/// to 8-bit or the float variants, which this crate never decodes to). PNG
+/// can hold these natively; JPEG can't encode past 8 bits per channel.
+fn is_16_bit(color: image::ColorType) -> bool {
+    matches!(
+        color,
+        image::ColorType::L16
+            | image::ColorType::La16
+            | image::ColorType::Rgb16
+            | image::ColorType::Rgba16
+    )
+}
+
+/// sRGB electro-optical transfer function (EOTF): sRGB-encoded `[0, 1]` ->
+/// linear light. The standard piecewise formula (IEC 61966-2-1), not the
+/// `c.powf(2.2)` approximation — the linear segment near black matters for
+/// resize quality since that's exactly the low-signal region a pure gamma
+/// curve gets wrong.
+pub(crate) fn srgb_to_linear(c: f32) -> f32 {
+    if c <= 0.040_45 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+/// Inverse of [`srgb_to_linear`]: linear light -> sRGB-encoded `[0, 1]`.
+fn linear_to_srgb(c: f32) -> f32 {
+    let c = c.clamp(0.0, 1.0);
+    if c <= 0.003_130_8 {
+        c * 12.92
+    } else {
+        1.055 * c.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+/// `image`'s resize filters (Lanczos3 included) run directly on whatever
+/// values the pixel buffer holds — which, for an ordinary decoded image, are
+/// sRGB-encoded (gamma-compressed) samples, not linear light. Averaging
+/// gamma-compressed values under-weights bright pixels relative to dark ones,
+/// which darkens fine high-frequency detail on a downscale — a well-known
+/// correctness issue, not a stylistic one. This converts `img` to linear
+/// light (applying [`srgb_to_linear`] to each of R/G/B; alpha is already
+/// linear coverage and passes through unchanged) so the resize in
+/// [`resize_decoded_with_icc`] averages physically-correct light values
+/// instead. Always materializes as `Rgba32F` regardless of the source's
+/// original color type — [`linear_image_to_srgb`] converts back and flattens
+/// to a concrete 8-bit type afterward.
+fn srgb_image_to_linear(img: &DynamicImage) -> DynamicImage {
+    let mut buf = img.to_rgba32f();
+    for pixel in buf.pixels_mut() {
+        pixel[0] = srgb_to_linear(pixel[0]);
+        pixel[1] = srgb_to_linear(pixel[1]);
+        pixel[2] = srgb_to_linear(pixel[2]);
+    }
+    DynamicImage::ImageRgba32F(buf)
+}
+
+/// Inverse of [`srgb_image_to_linear`]: undo the EOTF (R/G/B only) on a
+/// resized `Rgba32F` buffer, then flatten to a concrete 8-bit type —
+/// `Rgba8` if the original decode had any transparency, `Rgb8` otherwise,
+/// so the encode step downstream sees the same shape of image it would have
+/// without `linear_light` (this always costs the 16-bit path's extra
+/// precision, the same tradeoff `linear_light`'s doc comment already gates
+/// behind an explicit opt-in for the extra resize cost).
+fn linear_image_to_srgb(resized: DynamicImage, had_alpha: bool) -> DynamicImage {
+    let DynamicImage::ImageRgba32F(mut buf) = resized else {
+        // Not a linear-light buffer (linear_light was false) -- nothing to do.
+        return resized;
+    };
+    for pixel in buf.pixels_mut() {
+        pixel[0] = linear_to_srgb(pixel[0]);
+        pixel[1] = linear_to_srgb(pixel[1]);
+        pixel[2] = linear_to_srgb(pixel[2]);
+    }
+    let srgb = DynamicImage::ImageRgba32F(buf);
+    if had_alpha {
+        DynamicImage::ImageRgba8(srgb.to_rgba8())
+    } else {
+        DynamicImage::ImageRgb8(srgb.to_rgb8())
     }
 }
 
 fn determine_output_format(img: &DynamicImage) -> ImageFormat {
-    // Keep PNG for images with transparency
-    if img.color().has_alpha() {
+    // Keep PNG for images with transparency, or for 16-bit-per-channel
+    // sources (depth maps, medical/scientific images) — JPEG only encodes
+    // 8-bit-per-channel, so routing a 16-bit image there would silently
+    // truncate precision on the way out. PNG's own encoder preserves
+    // whatever bit depth `resized.color()` reports (see the Png arm below),
+    // so picking PNG here is enough; no separate up/downconvert is needed.
+    if img.color().has_alpha() || is_16_bit(img.color()) {
         ImageFormat::Png
     } else {
         ImageFormat::Jpeg
@@ -256,7 +711,19 @@ mod tests {
     #[test]
     fn resize_fit_downscales_and_keeps_aspect() {
         let src = tiny_png(40, 20);
-        let out = resize_image(&src, 10, 10, ResizeMode::Fit, 85).unwrap();
+        let out = resize_image(
+            &src,
+            10,
+            10,
+            ResizeMode::Fit,
+            85,
+            false,
+            CompressionLevel::default(),
+            false,
+            false,
+            false,
+        )
+        .unwrap();
         // 40x20 fit into 10x10 -> 10x5.
         assert_eq!((out.width, out.height), (10, 5));
     }
@@ -264,16 +731,275 @@ mod tests {
     #[test]
     fn resize_fit_skips_when_already_smaller() {
         let src = tiny_png(8, 8);
-        let out = resize_image(&src, 100, 100, ResizeMode::Fit, 85).unwrap();
+        let out = resize_image(
+            &src,
+            100,
+            100,
+            ResizeMode::Fit,
+            85,
+            false,
+            CompressionLevel::default(),
+            false,
+            false,
+            false,
+        )
+        .unwrap();
         // Unchanged dimensions; original bytes returned.
         assert_eq!((out.width, out.height), (8, 8));
     }
 
+    #[test]
+    fn try_passthrough_without_decode_returns_original_bytes_when_within_bounds() {
+        let src = tiny_png(8, 8);
+        let out = try_passthrough_without_decode(&src, 100, 100).unwrap();
+        assert_eq!((out.width, out.height), (8, 8));
+        assert_eq!(out.data, src, "bytes must pass through untouched");
+        assert!(!out.is_raw_pixels);
+        assert_eq!(out.format, "png");
+    }
+
+    #[test]
+    fn try_passthrough_without_decode_returns_none_when_resize_needed() {
+        let src = tiny_png(40, 20);
+        assert!(try_passthrough_without_decode(&src, 10, 10).is_none());
+    }
+
+    #[test]
+    fn try_passthrough_without_decode_matches_resize_image_skip_decision() {
+        // For every already-in-bounds image, the header-only pre-check must
+        // agree with resize_image's own internal "skip if smaller" branch —
+        // it's meant to short-circuit before that branch runs, not diverge
+        // from it.
+        let src = tiny_png(8, 8);
+        let via_precheck = try_passthrough_without_decode(&src, 100, 100).unwrap();
+        let via_full_decode = resize_image(
+            &src,
+            100,
+            100,
+            ResizeMode::Fit,
+            85,
+            false,
+            CompressionLevel::default(),
+            false,
+            false,
+            false,
+        )
+        .unwrap();
+        assert_eq!(
+            (via_precheck.width, via_precheck.height),
+            (via_full_decode.width, via_full_decode.height)
+        );
+        assert_eq!(via_precheck.data, via_full_decode.data);
+    }
+
+    #[test]
+    fn try_passthrough_without_decode_returns_none_for_garbage_input() {
+        assert!(try_passthrough_without_decode(b"not an image", 100, 100).is_none());
+    }
+
     #[test]
     fn resize_rejects_zero_target_dimensions() {
         let src = tiny_png(8, 8);
-        assert!(resize_image(&src, 0, 10, ResizeMode::Fit, 85).is_err());
-        assert!(resize_image(&src, 10, 0, ResizeMode::Fit, 85).is_err());
+        assert!(resize_image(
+            &src,
+            0,
+            10,
+            ResizeMode::Fit,
+            85,
+            false,
+            CompressionLevel::default(),
+            false,
+            false,
+            false,
+        )
+        .is_err());
+        assert!(resize_image(
+            &src,
+            10,
+            0,
+            ResizeMode::Fit,
+            85,
+            false,
+            CompressionLevel::default(),
+            false,
+            false,
+            false,
+        )
+        .is_err());
+    }
+
+    /// Encode a tiny RGBA PNG with an embedded ICC profile, so
+    /// `preserve_icc` has something real to carry across the resize.
+    fn tiny_png_with_icc(w: u32, h: u32, icc: &[u8]) -> Vec<u8> {
+        let img = image::RgbaImage::from_fn(w, h, |x, y| {
+            image::Rgba([(x % 256) as u8, (y % 256) as u8, 64, 255])
+        });
+        let mut out = Vec::new();
+        let mut encoder = image::codecs::png::PngEncoder::new(&mut out);
+        encoder.set_icc_profile(icc.to_vec()).unwrap();
+        image::DynamicImage::ImageRgba8(img)
+            .write_with_encoder(encoder)
+            .unwrap();
+        out
+    }
+
+    /// Encode a tiny RGBA PNG with no embedded profile — unlike `tiny_png`
+    /// (RGB, no alpha), the alpha channel forces the "PNG for alpha" branch
+    /// of the format heuristic, so tests reading back a PNG-only feature
+    /// (like an embedded ICC profile) don't land on the JPEG output instead.
+    fn tiny_rgba_png(w: u32, h: u32) -> Vec<u8> {
+        let img = image::RgbaImage::from_fn(w, h, |x, y| {
+            image::Rgba([(x % 256) as u8, (y % 256) as u8, 64, 255])
+        });
+        let mut out = Vec::new();
+        image::DynamicImage::ImageRgba8(img)
+            .write_to(&mut Cursor::new(&mut out), ImageFormat::Png)
+            .unwrap();
+        out
+    }
+
+    fn read_png_icc_profile(data: &[u8]) -> Option<Vec<u8>> {
+        let mut decoder = image::codecs::png::PngDecoder::new(Cursor::new(data)).unwrap();
+        decoder.icc_profile().unwrap()
+    }
+
+    #[test]
+    fn resize_preserves_icc_profile_when_requested() {
+        let icc = b"fake-icc-profile-bytes".to_vec();
+        // 40x40 -> 10x10 forces a real resize+re-encode, not the
+        // already-smaller shortcut that hands back the original bytes.
+        let src = tiny_png_with_icc(40, 40, &icc);
+        let out = resize_image(
+            &src,
+            10,
+            10,
+            ResizeMode::Fit,
+            85,
+            true,
+            CompressionLevel::default(),
+            false,
+            false,
+            false,
+        )
+        .unwrap();
+        assert_eq!(read_png_icc_profile(&out.data), Some(icc));
+    }
+
+    #[test]
+    fn resize_strips_icc_profile_by_default() {
+        let icc = b"fake-icc-profile-bytes".to_vec();
+        let src = tiny_png_with_icc(40, 40, &icc);
+        let out = resize_image(
+            &src,
+            10,
+            10,
+            ResizeMode::Fit,
+            85,
+            false,
+            CompressionLevel::default(),
+            false,
+            false,
+            false,
+        )
+        .unwrap();
+        assert_eq!(read_png_icc_profile(&out.data), None);
+    }
+
+    #[test]
+    fn tag_srgb_embeds_a_profile_when_none_was_carried() {
+        let src = tiny_rgba_png(40, 40);
+        let out = resize_image(
+            &src,
+            10,
+            10,
+            ResizeMode::Fit,
+            85,
+            false,
+            CompressionLevel::default(),
+            false,
+            false,
+            true,
+        )
+        .unwrap();
+        assert_eq!(
+            read_png_icc_profile(&out.data),
+            Some(minimal_srgb_icc_profile())
+        );
+    }
+
+    #[test]
+    fn tag_srgb_does_not_override_a_real_preserved_profile() {
+        let icc = b"fake-icc-profile-bytes".to_vec();
+        let src = tiny_png_with_icc(40, 40, &icc);
+        let out = resize_image(
+            &src,
+            10,
+            10,
+            ResizeMode::Fit,
+            85,
+            true,
+            CompressionLevel::default(),
+            false,
+            false,
+            true,
+        )
+        .unwrap();
+        assert_eq!(read_png_icc_profile(&out.data), Some(icc));
+    }
+
+    #[test]
+    fn tag_srgb_is_off_by_default() {
+        let src = tiny_rgba_png(40, 40);
+        let out = resize_image(
+            &src,
+            10,
+            10,
+            ResizeMode::Fit,
+            85,
+            false,
+            CompressionLevel::default(),
+            false,
+            false,
+            false,
+        )
+        .unwrap();
+        assert_eq!(read_png_icc_profile(&out.data), None);
+    }
+
+    /// Encode a tiny 16-bit-per-channel grayscale PNG (no alpha — the case
+    /// that used to get routed to the JPEG encoder and truncated to 8-bit).
+    fn tiny_16bit_png(w: u32, h: u32) -> Vec<u8> {
+        let img = image::ImageBuffer::<image::Luma<u16>, Vec<u16>>::from_fn(w, h, |x, y| {
+            image::Luma([((x + y) as u16).wrapping_mul(4096)])
+        });
+        let mut out = Vec::new();
+        image::DynamicImage::ImageLuma16(img)
+            .write_to(&mut Cursor::new(&mut out), ImageFormat::Png)
+            .unwrap();
+        out
+    }
+
+    #[test]
+    fn resize_preserves_16_bit_depth_through_downscale() {
+        let src = tiny_16bit_png(40, 40);
+        // 40x40 -> 10x10 forces a real resize+re-encode, not the
+        // already-smaller shortcut that hands back the original bytes.
+        let out = resize_image(
+            &src,
+            10,
+            10,
+            ResizeMode::Fit,
+            85,
+            false,
+            CompressionLevel::default(),
+            false,
+            false,
+            false,
+        )
+        .unwrap();
+        assert_eq!(out.format, "png");
+        let decoded = image::load_from_memory(&out.data).unwrap();
+        assert_eq!(decoded.color(), image::ColorType::L16);
     }
 
     #[test]
@@ -286,7 +1012,18 @@ mod tests {
         let src = tiny_png(16384, 1);
         // Match on the Result directly: ImageData has no Debug impl, so
         // unwrap_err() won't compile — pull the error out via a match instead.
-        match resize_image(&src, 16384, 16384, ResizeMode::Fill, 85) {
+        match resize_image(
+            &src,
+            16384,
+            16384,
+            ResizeMode::Fill,
+            85,
+            false,
+            CompressionLevel::default(),
+            false,
+            false,
+            false,
+        ) {
             Ok(_) => panic!("explosive Fill intermediate must be rejected"),
             Err(e) => {
                 let msg = format!("{e}");
@@ -297,4 +1034,369 @@ mod tests {
             }
         }
     }
+
+    // ------- compression_level (#1683) -------
+
+    /// Higher-entropy source than `tiny_png`'s smooth gradient, so PNG's
+    /// filter/deflate stages actually have compression work to do — a flat
+    /// gradient already compresses to near-nothing at every level and can't
+    /// tell `Fast` and `Best` apart.
+    fn noisy_png(w: u32, h: u32) -> Vec<u8> {
+        let img = image::RgbImage::from_fn(w, h, |x, y| {
+            let n = (x.wrapping_mul(2654435761) ^ y.wrapping_mul(40503)) as u8;
+            image::Rgb([n, n.wrapping_add(x as u8), n.wrapping_add(y as u8)])
+        });
+        let mut out = Vec::new();
+        image::DynamicImage::ImageRgb8(img)
+            .write_to(&mut Cursor::new(&mut out), ImageFormat::Png)
+            .unwrap();
+        out
+    }
+
+    #[test]
+    fn compression_level_default_matches_unspecified_behavior() {
+        // Same 40x40 -> 10x10 real resize+re-encode as the ICC tests above,
+        // just without an ICC profile in the way. Default::default() must
+        // produce byte-identical output to the pre-#1683 always-default path.
+        let src = noisy_png(40, 40);
+        let explicit = resize_image(
+            &src,
+            10,
+            10,
+            ResizeMode::Fit,
+            85,
+            false,
+            CompressionLevel::Default,
+            false,
+            false,
+            false,
+        )
+        .unwrap();
+        let implicit = resize_image(
+            &src,
+            10,
+            10,
+            ResizeMode::Fit,
+            85,
+            false,
+            CompressionLevel::default(),
+            false,
+            false,
+            false,
+        )
+        .unwrap();
+        assert_eq!(explicit.data, implicit.data);
+    }
+
+    #[test]
+    fn compression_level_best_is_no_larger_than_fast() {
+        let src = noisy_png(64, 64);
+        let fast = resize_image(
+            &src,
+            32,
+            32,
+            ResizeMode::Fit,
+            85,
+            false,
+            CompressionLevel::Fast,
+            false,
+            false,
+            false,
+        )
+        .unwrap();
+        let best = resize_image(
+            &src,
+            32,
+            32,
+            ResizeMode::Fit,
+            85,
+            false,
+            CompressionLevel::Best,
+            false,
+            false,
+            false,
+        )
+        .unwrap();
+        assert!(
+            best.data.len() <= fast.data.len(),
+            "Best ({} bytes) should not be larger than Fast ({} bytes)",
+            best.data.len(),
+            fast.data.len()
+        );
+    }
+
+    #[test]
+    fn compression_level_does_not_change_pixel_content() {
+        let src = noisy_png(32, 32);
+        let fast = resize_image(
+            &src,
+            16,
+            16,
+            ResizeMode::Fit,
+            85,
+            false,
+            CompressionLevel::Fast,
+            false,
+            false,
+            false,
+        )
+        .unwrap();
+        let best = resize_image(
+            &src,
+            16,
+            16,
+            ResizeMode::Fit,
+            85,
+            false,
+            CompressionLevel::Best,
+            false,
+            false,
+            false,
+        )
+        .unwrap();
+        let fast_pixels = image::load_from_memory(&fast.data).unwrap().to_rgb8();
+        let best_pixels = image::load_from_memory(&best.data).unwrap().to_rgb8();
+        assert_eq!(
+            fast_pixels, best_pixels,
+            "compression level must be lossless"
+        );
+    }
+
+    #[test]
+    fn generate_thumbnails_produces_every_size_in_requested_order() {
+        let src = tiny_png(80, 40);
+        let out = generate_thumbnails(&src, &[10, 40, 20], 85).unwrap();
+        assert_eq!(out.len(), 3);
+        // Fit preserves the 2:1 aspect ratio at each size.
+        assert_eq!((out[0].width, out[0].height), (10, 5));
+        assert_eq!((out[1].width, out[1].height), (40, 20));
+        assert_eq!((out[2].width, out[2].height), (20, 10));
+    }
+
+    #[test]
+    fn generate_thumbnails_matches_independent_resize_image_calls() {
+        // For a square source, progressively downscaling from the largest
+        // must land on exactly the dimensions an independent resize_image
+        // call at that size produces.
+        let src = tiny_png(64, 64);
+        let sizes = [32, 16, 8];
+        let progressive = generate_thumbnails(&src, &sizes, 90).unwrap();
+        for (thumb, &size) in progressive.iter().zip(sizes.iter()) {
+            let independent = resize_image(
+                &src,
+                size,
+                size,
+                ResizeMode::Fit,
+                90,
+                false,
+                CompressionLevel::default(),
+                false,
+                false,
+                false,
+            )
+            .unwrap();
+            assert_eq!(
+                (thumb.width, thumb.height),
+                (independent.width, independent.height)
+            );
+        }
+    }
+
+    #[test]
+    fn generate_thumbnails_empty_sizes_returns_empty() {
+        let src = tiny_png(8, 8);
+        assert!(generate_thumbnails(&src, &[], 85).unwrap().is_empty());
+    }
+
+    #[test]
+    fn generate_thumbnails_rejects_zero_size() {
+        let src = tiny_png(8, 8);
+        assert!(generate_thumbnails(&src, &[10, 0], 85).is_err());
+    }
+
+    /// Encode a tiny RGB GIF -- the `image` crate's GIF decoder always
+    /// reports an alpha channel (GIF supports a transparent index even for
+    /// images that don't use one), so the heuristic path sends this to PNG
+    /// rather than back to GIF, a real divergence point for `preserve_format`.
+    fn tiny_gif(w: u32, h: u32) -> Vec<u8> {
+        let img = image::RgbImage::from_fn(w, h, |x, y| {
+            image::Rgb([(x % 256) as u8, (y % 256) as u8, 64])
+        });
+        let mut out = Vec::new();
+        image::DynamicImage::ImageRgb8(img)
+            .write_to(&mut Cursor::new(&mut out), ImageFormat::Gif)
+            .unwrap();
+        out
+    }
+
+    #[test]
+    fn resize_preserve_format_false_sends_a_gif_to_the_alpha_heuristic() {
+        let src = tiny_gif(40, 20);
+        let out = resize_image(
+            &src,
+            10,
+            10,
+            ResizeMode::Fit,
+            85,
+            false,
+            CompressionLevel::default(),
+            false,
+            false,
+            false,
+        )
+        .unwrap();
+        assert_eq!(out.format, "png");
+    }
+
+    #[test]
+    fn resize_preserve_format_true_keeps_a_gif_source_as_gif() {
+        let src = tiny_gif(40, 20);
+        let out = resize_image(
+            &src,
+            10,
+            10,
+            ResizeMode::Fit,
+            85,
+            false,
+            CompressionLevel::default(),
+            true,
+            false,
+            false,
+        )
+        .unwrap();
+        assert_eq!(out.format, "gif");
+        assert_eq!((out.width, out.height), (10, 5));
+    }
+
+    #[test]
+    fn encode_preserving_format_falls_back_to_heuristic_when_encoder_unavailable() {
+        // TIFF isn't in this crate's `image` feature list (see Cargo.toml),
+        // so `write_to` genuinely fails for it -- a stand-in here for any
+        // source format (e.g. WebP, if this build's encoder were ever
+        // dropped) whose encoder isn't available, which must fall back to
+        // the same png/jpeg heuristic a non-preserving caller gets rather
+        // than fail the resize outright.
+        let img = DynamicImage::ImageRgb8(image::RgbImage::from_fn(8, 8, |x, y| {
+            image::Rgb([x as u8, y as u8, 0])
+        }));
+        let out = encode_preserving_format(
+            &img,
+            ImageFormat::Tiff,
+            85,
+            None,
+            CompressionLevel::default(),
+        )
+        .unwrap();
+        assert_eq!(out.format, "jpeg");
+    }
+
+    // ------- linear_light -------
+
+    #[test]
+    fn srgb_linear_round_trip_is_approximately_identity() {
+        for i in 0..=255 {
+            let c = i as f32 / 255.0;
+            let round_tripped = linear_to_srgb(srgb_to_linear(c));
+            assert!(
+                (round_tripped - c).abs() < 1e-5,
+                "{c} round-tripped to {round_tripped}"
+            );
+        }
+    }
+
+    #[test]
+    fn linear_light_changes_pixel_output_on_a_real_downscale() {
+        // A genuine 2x downscale of high-contrast noise -- resampling in
+        // linear light must average different values than resampling the raw
+        // sRGB-encoded samples, so the two outputs shouldn't be pixel-identical.
+        let src = noisy_png(40, 40);
+        let gamma_space = resize_image(
+            &src,
+            10,
+            10,
+            ResizeMode::Fit,
+            90,
+            false,
+            CompressionLevel::default(),
+            false,
+            false,
+            false,
+        )
+        .unwrap();
+        let linear_space = resize_image(
+            &src,
+            10,
+            10,
+            ResizeMode::Fit,
+            90,
+            false,
+            CompressionLevel::default(),
+            false,
+            true,
+            false,
+        )
+        .unwrap();
+        assert_ne!(
+            gamma_space.data, linear_space.data,
+            "linear_light must change the resampled pixel values"
+        );
+    }
+
+    #[test]
+    fn linear_light_does_not_change_output_dimensions() {
+        let src = noisy_png(40, 20);
+        let gamma_space = resize_image(
+            &src,
+            10,
+            10,
+            ResizeMode::Fit,
+            85,
+            false,
+            CompressionLevel::default(),
+            false,
+            false,
+            false,
+        )
+        .unwrap();
+        let linear_space = resize_image(
+            &src,
+            10,
+            10,
+            ResizeMode::Fit,
+            85,
+            false,
+            CompressionLevel::default(),
+            false,
+            true,
+            false,
+        )
+        .unwrap();
+        assert_eq!(
+            (gamma_space.width, gamma_space.height),
+            (linear_space.width, linear_space.height)
+        );
+    }
+
+    #[test]
+    fn linear_light_preserves_the_alpha_heuristic_output_shape() {
+        // A GIF source's decoder-synthesized alpha channel must still send
+        // the resize to the alpha heuristic's PNG output under linear_light,
+        // same as it already does without it -- the Rgba32F round trip inside
+        // srgb_image_to_linear/linear_image_to_srgb must not lose that signal.
+        let src = tiny_gif(40, 20);
+        let out = resize_image(
+            &src,
+            10,
+            10,
+            ResizeMode::Fit,
+            85,
+            false,
+            CompressionLevel::default(),
+            false,
+            true,
+            false,
+        )
+        .unwrap();
+        assert_eq!(out.format, "png");
+    }
 }
@@ -0,0 +1,178 @@
+//! SVG dimension probing and rasterization
+//!
+//! `image` cannot decode SVGs at all, so `MediaProcessor::load`/`get_dimensions`
+//! previously errored with "unsupported format" on every SVG upload. This
+//! module adds two independent capabilities:
+//!
+//! - [`parse_svg_dimensions`] — a minimal, dependency-free scan of the root
+//!   `<svg>` tag's `width`/`height`/`viewBox` attributes, always available
+//!   (no cargo feature needed) since it's just a byte scan, not a renderer.
+//! - [`rasterize_svg`] — full rendering to a PNG `ImageData` via `resvg`,
+//!   gated behind the `svg` cargo feature (see Cargo.toml) since it pulls in
+//!   a real SVG/font stack that most callers never touch.
+
+/// Scan the root `<svg ...>` tag for `width`/`height` attributes (falling
+/// back to `viewBox`'s third/fourth numbers if either is missing) and return
+/// them rounded to the nearest pixel. Returns `None` if the input doesn't
+/// look like an SVG or no size could be determined — callers should treat
+/// that the same as any other "unsupported format" case.
+///
+/// This is a best-effort scan, not a real XML parser: it only looks at the
+/// first `<svg` tag it finds and reads plain `name="value"` attributes, so
+/// unusual formatting (namespaced attributes, CSS-set dimensions, entities
+/// inside the tag) is not handled. Good enough to report "the intended size"
+/// for the common case of an authoring-tool-exported SVG.
+pub fn parse_svg_dimensions(data: &[u8]) -> Option<(u32, u32)> {
+    let text = std::str::from_utf8(data).ok()?;
+    let tag_start = text.find("<svg")?;
+    let tag_end = text[tag_start..].find('>')? + tag_start;
+    let tag = &text[tag_start..tag_end];
+
+    let width = find_attr_number(tag, "width");
+    let height = find_attr_number(tag, "height");
+
+    if let (Some(w), Some(h)) = (width, height) {
+        return Some((w.round() as u32, h.round() as u32));
+    }
+
+    // Fall back to viewBox="min-x min-y width height" for either missing dimension.
+    let view_box = find_attr(tag, "viewBox")?;
+    let mut parts = view_box.split_whitespace();
+    let (_min_x, _min_y, vb_w, vb_h) = (
+        parts.next()?,
+        parts.next()?,
+        parts.next()?.parse::<f64>().ok()?,
+        parts.next()?.parse::<f64>().ok()?,
+    );
+
+    let w = width.unwrap_or(vb_w);
+    let h = height.unwrap_or(vb_h);
+    if w <= 0.0 || h <= 0.0 {
+        return None;
+    }
+    Some((w.round() as u32, h.round() as u32))
+}
+
+fn find_attr<'a>(tag: &'a str, name: &str) -> Option<&'a str> {
+    let needle = format!("{name}=\"");
+    let start = tag.find(&needle)? + needle.len();
+    let end = tag[start..].find('"')? + start;
+    Some(&tag[start..end])
+}
+
+fn find_attr_number(tag: &str, name: &str) -> Option<f64> {
+    // Strip a trailing CSS unit ("px", "pt", ...) — percentages have no
+    // absolute pixel size and are intentionally left unparsed (None).
+    let raw = find_attr(tag, name)?;
+    let numeric: String = raw
+        .chars()
+        .take_while(|c| c.is_ascii_digit() || *c == '.' || *c == '-')
+        .collect();
+    if numeric.is_empty() || raw.trim_end().ends_with('%') {
+        return None;
+    }
+    numeric.parse::<f64>().ok().filter(|v| *v > 0.0)
+}
+
+#[cfg(feature = "svg")]
+use crate::errors::MediaError;
+#[cfg(feature = "svg")]
+use crate::ImageData;
+
+/// Render SVG `data` to a `width`x`height` PNG. Requires the `svg` feature.
+#[cfg(feature = "svg")]
+pub fn rasterize_svg(data: &[u8], width: u32, height: u32) -> Result<ImageData, MediaError> {
+    use resvg::tiny_skia;
+    use resvg::usvg;
+
+    let tree = usvg::Tree::from_data(data, &usvg::Options::default())
+        .map_err(|e| MediaError::Decode(format!("invalid SVG: {e}")))?;
+
+    let mut pixmap = tiny_skia::Pixmap::new(width, height)
+        .ok_or_else(|| MediaError::Encode("target dimensions must be non-zero".to_string()))?;
+
+    let size = tree.size();
+    let scale_x = width as f32 / size.width();
+    let scale_y = height as f32 / size.height();
+    let transform = tiny_skia::Transform::from_scale(scale_x, scale_y);
+
+    resvg::render(&tree, transform, &mut pixmap.as_mut());
+
+    let png_bytes = pixmap
+        .encode_png()
+        .map_err(|e| MediaError::Encode(format!("PNG encode failed: {e}")))?;
+
+    Ok(ImageData {
+        data: png_bytes,
+        width,
+        height,
+        channels: 4,
+        format: "png".to_string(),
+        is_raw_pixels: false,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SVG_WITH_WH: &str = r#"<?xml version="1.0"?><svg xmlns="http://www.w3.org/2000/svg" width="120" height="80"><rect/></svg>"#;
+    const SVG_WITH_VIEWBOX_ONLY: &str =
+        r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 64 32"><rect/></svg>"#;
+    const SVG_WITH_PX_UNITS: &str =
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="10.5px" height="20px"></svg>"#;
+    const SVG_WITH_PERCENT: &str = r#"<svg xmlns="http://www.w3.org/2000/svg" width="100%" height="100%" viewBox="0 0 10 10"></svg>"#;
+
+    #[test]
+    fn reads_width_and_height_attrs() {
+        assert_eq!(
+            parse_svg_dimensions(SVG_WITH_WH.as_bytes()),
+            Some((120, 80))
+        );
+    }
+
+    #[test]
+    fn falls_back_to_view_box() {
+        assert_eq!(
+            parse_svg_dimensions(SVG_WITH_VIEWBOX_ONLY.as_bytes()),
+            Some((64, 32))
+        );
+    }
+
+    #[test]
+    fn rounds_fractional_px_units() {
+        assert_eq!(
+            parse_svg_dimensions(SVG_WITH_PX_UNITS.as_bytes()),
+            Some((11, 20))
+        );
+    }
+
+    #[test]
+    fn percent_dimensions_fall_back_to_view_box() {
+        assert_eq!(
+            parse_svg_dimensions(SVG_WITH_PERCENT.as_bytes()),
+            Some((10, 10))
+        );
+    }
+
+    #[test]
+    fn non_svg_input_returns_none() {
+        assert_eq!(parse_svg_dimensions(b"<html></html>"), None);
+        assert_eq!(parse_svg_dimensions(&[0xFF, 0xD8, 0xFF]), None);
+    }
+
+    #[cfg(feature = "svg")]
+    #[test]
+    fn rasterize_svg_produces_requested_size_png() {
+        let img = rasterize_svg(SVG_WITH_WH.as_bytes(), 32, 16).unwrap();
+        assert_eq!(img.format, "png");
+        assert_eq!((img.width, img.height), (32, 16));
+        assert_eq!(&img.data[1..4], b"PNG");
+    }
+
+    #[cfg(feature = "svg")]
+    #[test]
+    fn rasterize_svg_rejects_malformed_input() {
+        assert!(rasterize_svg(b"not an svg", 8, 8).is_err());
+    }
+}
@@ -0,0 +1,235 @@
+//! Auto-crop (trim): detect and remove a uniform-color border.
+
+use image::{DynamicImage, GenericImageView, RgbaImage};
+
+use crate::decode;
+use crate::errors::MediaError;
+use crate::ImageData;
+
+/// Detect the background color from the image's corner pixels, then crop to
+/// the tightest bounding box of pixels differing from it by more than
+/// `tolerance` (per-channel, worst channel wins), re-encoding in the source
+/// format. An image with no differing pixels (solid color, or a border-free
+/// image where the box already spans the whole thing) is returned unchanged
+/// rather than as a degenerate crop.
+pub fn trim_image(data: &[u8], tolerance: u8) -> Result<ImageData, MediaError> {
+    let (img, format) = decode::decode_with_guard(data, decode::MAX_PIXEL_COUNT)?;
+    let (width, height) = img.dimensions();
+    let rgba = img.to_rgba8();
+
+    let (x0, y0, x1, y1) = if width == 0 || height == 0 {
+        (0, 0, width, height)
+    } else {
+        let background = corner_background(&rgba, width, height, tolerance);
+        bounding_box(&rgba, background, tolerance).unwrap_or((0, 0, width, height))
+    };
+
+    let (crop_w, crop_h) = (x1 - x0, y1 - y0);
+    let cropped = if (x0, y0, crop_w, crop_h) == (0, 0, width, height) {
+        DynamicImage::ImageRgba8(rgba)
+    } else {
+        DynamicImage::ImageRgba8(rgba).crop_imm(x0, y0, crop_w, crop_h)
+    };
+
+    let output = encode_to_format(&cropped, &format)?;
+    Ok(ImageData {
+        data: output,
+        width: crop_w,
+        height: crop_h,
+        channels: cropped.color().channel_count(),
+        format,
+        is_raw_pixels: false,
+    })
+}
+
+/// The background reference used to decide what counts as "border": the
+/// average of the four corner pixels when they all agree within `tolerance`
+/// of each other, so one slightly-off corner (compression noise, a stray
+/// watermark pixel) doesn't skew it — otherwise falls back to the top-left
+/// corner alone, since disagreeing corners mean there's no single uniform
+/// background to average in the first place.
+fn corner_background(rgba: &RgbaImage, width: u32, height: u32, tolerance: u8) -> [u8; 4] {
+    let corners = [
+        rgba.get_pixel(0, 0).0,
+        rgba.get_pixel(width - 1, 0).0,
+        rgba.get_pixel(0, height - 1).0,
+        rgba.get_pixel(width - 1, height - 1).0,
+    ];
+    let top_left = corners[0];
+    if corners.iter().all(|&c| !differs(c, top_left, tolerance)) {
+        average(&corners)
+    } else {
+        top_left
+    }
+}
+
+fn average(corners: &[[u8; 4]; 4]) -> [u8; 4] {
+    let mut sums = [0u32; 4];
+    for corner in corners {
+        for (sum, &channel) in sums.iter_mut().zip(corner.iter()) {
+            *sum += channel as u32;
+        }
+    }
+    sums.map(|sum| (sum / corners.len() as u32) as u8)
+}
+
+fn differs(pixel: [u8; 4], background: [u8; 4], tolerance: u8) -> bool {
+    pixel
+        .iter()
+        .zip(background.iter())
+        .any(|(&p, &b)| p.abs_diff(b) > tolerance)
+}
+
+/// The tightest `(min_x, min_y, max_x_exclusive, max_y_exclusive)` box
+/// covering every pixel that differs from `background` by more than
+/// `tolerance`, or `None` if every pixel is within tolerance of it (a
+/// uniform image, or one that's entirely background — same outcome either
+/// way: nothing to crop to).
+fn bounding_box(
+    rgba: &RgbaImage,
+    background: [u8; 4],
+    tolerance: u8,
+) -> Option<(u32, u32, u32, u32)> {
+    let (width, height) = rgba.dimensions();
+    let mut min_x = width;
+    let mut min_y = height;
+    let mut max_x = 0u32;
+    let mut max_y = 0u32;
+    let mut found = false;
+
+    for (x, y, pixel) in rgba.enumerate_pixels() {
+        if differs(pixel.0, background, tolerance) {
+            found = true;
+            min_x = min_x.min(x);
+            min_y = min_y.min(y);
+            max_x = max_x.max(x);
+            max_y = max_y.max(y);
+        }
+    }
+
+    if found {
+        Some((min_x, min_y, max_x + 1, max_y + 1))
+    } else {
+        None
+    }
+}
+
+/// Re-encode `img` to `format` (one of `decode_with_guard`'s detected
+/// `"png"`/`"jpeg"`/`"gif"`/`"webp"` strings), matching `auto_contrast_image`'s
+/// "keep the source format" behavior rather than always converting to PNG.
+fn encode_to_format(img: &DynamicImage, format: &str) -> Result<Vec<u8>, MediaError> {
+    let image_format = match format {
+        "png" => image::ImageFormat::Png,
+        "jpeg" => image::ImageFormat::Jpeg,
+        "gif" => image::ImageFormat::Gif,
+        "webp" => image::ImageFormat::WebP,
+        other => return Err(MediaError::UnsupportedFormat(other.to_string())),
+    };
+
+    let mut output = Vec::new();
+    img.write_to(&mut std::io::Cursor::new(&mut output), image_format)?;
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn png_of(w: u32, h: u32, f: impl Fn(u32, u32) -> [u8; 3]) -> Vec<u8> {
+        let img = image::RgbImage::from_fn(w, h, |x, y| image::Rgb(f(x, y)));
+        let mut out = Vec::new();
+        DynamicImage::ImageRgb8(img)
+            .write_to(&mut Cursor::new(&mut out), image::ImageFormat::Png)
+            .unwrap();
+        out
+    }
+
+    #[test]
+    fn crops_a_white_border_down_to_the_content_square() {
+        // 20x20 white canvas with a 4x4 black square at (8, 8).
+        let bordered = png_of(20, 20, |x, y| {
+            if (8..12).contains(&x) && (8..12).contains(&y) {
+                [0, 0, 0]
+            } else {
+                [255, 255, 255]
+            }
+        });
+
+        let result = trim_image(&bordered, 10).unwrap();
+        assert_eq!((result.width, result.height), (4, 4));
+
+        let decoded = image::load_from_memory(&result.data).unwrap().to_rgb8();
+        assert!(decoded.pixels().all(|p| *p == image::Rgb([0, 0, 0])));
+    }
+
+    #[test]
+    fn solid_color_image_is_returned_unchanged() {
+        let solid = png_of(10, 10, |_, _| [200, 200, 200]);
+        let result = trim_image(&solid, 5).unwrap();
+        assert_eq!((result.width, result.height), (10, 10));
+    }
+
+    #[test]
+    fn an_image_with_no_border_is_returned_unchanged() {
+        // Content touches every edge, so the bounding box is already the
+        // full image.
+        let no_border = png_of(6, 6, |x, y| [(x * 40) as u8, (y * 40) as u8, 0]);
+        let result = trim_image(&no_border, 0).unwrap();
+        assert_eq!((result.width, result.height), (6, 6));
+    }
+
+    #[test]
+    fn tolerance_controls_whether_a_faint_border_speck_counts_as_content() {
+        // Pure white background, a 2x2 black content square in the middle,
+        // and a single speck at (1, 1) only 12 shades off white -- a low
+        // tolerance treats the speck as content (widening the crop to
+        // include it), a high tolerance treats it as background noise
+        // (cropping down to just the content square).
+        let with_speck = png_of(10, 10, |x, y| {
+            if (4..6).contains(&x) && (4..6).contains(&y) {
+                [0, 0, 0]
+            } else if (x, y) == (1, 1) {
+                [243, 243, 243]
+            } else {
+                [255, 255, 255]
+            }
+        });
+
+        let strict = trim_image(&with_speck, 5).unwrap();
+        assert_eq!((strict.width, strict.height), (5, 5));
+
+        let lenient = trim_image(&with_speck, 15).unwrap();
+        assert_eq!((lenient.width, lenient.height), (2, 2));
+    }
+
+    #[test]
+    fn disagreeing_corners_fall_back_to_the_top_left_corner_as_background() {
+        // Top-left is black, the other three corners are white -- with no
+        // single agreed-upon background, top-left wins, so the white region
+        // (everything but the top-left pixel) counts as "content" here.
+        let mismatched_corners = png_of(6, 6, |x, y| {
+            if x == 0 && y == 0 {
+                [0, 0, 0]
+            } else {
+                [255, 255, 255]
+            }
+        });
+
+        let result = trim_image(&mismatched_corners, 10).unwrap();
+        assert_eq!((result.width, result.height), (6, 6));
+    }
+
+    #[test]
+    fn rejects_a_pixel_count_bomb_header() {
+        let mut png = vec![
+            0x89, 0x50, 0x4E, 0x47, 0x0D, 0x0A, 0x1A, 0x0A, // PNG signature
+            0x00, 0x00, 0x00, 0x0D, b'I', b'H', b'D', b'R',
+        ];
+        png.extend_from_slice(&20_000u32.to_be_bytes());
+        png.extend_from_slice(&20_000u32.to_be_bytes());
+        png.extend_from_slice(&[8, 6, 0, 0, 0, 0, 0, 0, 0]); // rest of IHDR + bogus CRC
+
+        assert!(trim_image(&png, 5).is_err());
+    }
+}
@@ -0,0 +1,220 @@
+//! WebP animation detection
+//!
+//! Parallels `gif.rs`: a WebP file is a RIFF container (`"RIFF" <size:u32 LE>
+//! "WEBP"`) of FourCC-tagged chunks (`<fourcc:4 bytes> <size:u32 LE>
+//! <data:size bytes, padded to an even length>`). Animation is carried in
+//! `ANMF` chunks, one per frame, each starting with a 16-byte frame header
+//! (`x/y/width-1/height-1` as 3-byte LE fields, a 3-byte LE duration in
+//! milliseconds, then a flags byte) before its nested image sub-chunks. A
+//! global `ANIM` chunk (background color + loop count) precedes the `ANMF`
+//! chunks on an animated file; its loop count has no bearing on a
+//! single-loop duration and must be walked past like any other chunk, never
+//! misread as part of a frame.
+
+const RIFF_HEADER_LEN: usize = 12;
+const CHUNK_HEADER_LEN: usize = 8;
+const ANMF_FRAME_HEADER_LEN: usize = 16;
+
+fn is_webp(data: &[u8]) -> bool {
+    data.len() >= RIFF_HEADER_LEN && &data[0..4] == b"RIFF" && &data[8..12] == b"WEBP"
+}
+
+/// Walk the RIFF chunk list starting right after the 12-byte `RIFF`/`WEBP`
+/// header, calling `on_chunk(fourcc, chunk_data)` for each well-formed chunk.
+/// Bounds-checked against truncated/malformed input; a safety iteration cap
+/// (mirroring `gif.rs`'s `max_iterations`) prevents a corrupt size field from
+/// spinning forever.
+fn walk_chunks(data: &[u8], mut on_chunk: impl FnMut(&[u8; 4], &[u8])) {
+    let mut i = RIFF_HEADER_LEN;
+    let max_iterations = data.len().min(100_000);
+    let mut iterations = 0;
+
+    while i + CHUNK_HEADER_LEN <= data.len() && iterations < max_iterations {
+        iterations += 1;
+
+        let fourcc: [u8; 4] = data[i..i + 4].try_into().unwrap();
+        let chunk_size = u32::from_le_bytes(data[i + 4..i + 8].try_into().unwrap()) as usize;
+        let data_start = i + CHUNK_HEADER_LEN;
+        let Some(data_end) = data_start.checked_add(chunk_size) else {
+            break;
+        };
+        if data_end > data.len() {
+            break;
+        }
+
+        on_chunk(&fourcc, &data[data_start..data_end]);
+
+        // Chunks are padded to an even length; the pad byte isn't covered by
+        // `chunk_size` itself.
+        let padded_size = chunk_size + (chunk_size & 1);
+        i = data_start + padded_size;
+    }
+}
+
+/// Check if data is an animated WebP (has one or more `ANMF` frame chunks).
+pub fn is_animated_webp(data: &[u8]) -> bool {
+    if !is_webp(data) {
+        return false;
+    }
+    let mut animated = false;
+    walk_chunks(data, |fourcc, _| {
+        if fourcc == b"ANMF" {
+            animated = true;
+        }
+    });
+    animated
+}
+
+/// Number of animation frames. Returns `0` for non-WebP data, `1` for a
+/// static (non-animated) WebP, or the number of `ANMF` chunks for an
+/// animated one — mirrors `gif::get_gif_frame_count`'s "one Image Descriptor
+/// = static" baseline.
+pub fn webp_frame_count(data: &[u8]) -> usize {
+    if !is_webp(data) {
+        return 0;
+    }
+    let mut frame_count = 0usize;
+    walk_chunks(data, |fourcc, _| {
+        if fourcc == b"ANMF" {
+            frame_count += 1;
+        }
+    });
+    frame_count.max(1)
+}
+
+/// Total single-loop playback duration in milliseconds, summing each
+/// `ANMF` frame's 3-byte little-endian duration field (offset 12 within the
+/// frame header — unlike GIF's centiseconds-with-a-0/1 floor, WebP's
+/// duration is already plain milliseconds, so no unit conversion or floor is
+/// applied). The `ANIM` chunk's loop count is walked past like any other
+/// chunk and never read as a duration. Returns `0` for non-WebP data or a
+/// static WebP with no `ANMF` chunks.
+pub fn webp_duration_ms(data: &[u8]) -> u32 {
+    if !is_webp(data) {
+        return 0;
+    }
+    let mut total_ms: u32 = 0;
+    walk_chunks(data, |fourcc, chunk_data| {
+        if fourcc == b"ANMF" && chunk_data.len() >= ANMF_FRAME_HEADER_LEN {
+            let duration = u32::from_le_bytes([
+                chunk_data[12],
+                chunk_data[13],
+                chunk_data[14],
+                0,
+            ]);
+            total_ms += duration;
+        }
+    });
+    total_ms
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chunk(fourcc: &[u8; 4], data: &[u8]) -> Vec<u8> {
+        let mut out = Vec::new();
+        out.extend_from_slice(fourcc);
+        out.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        out.extend_from_slice(data);
+        if data.len() % 2 == 1 {
+            out.push(0);
+        }
+        out
+    }
+
+    fn anmf_frame(duration_ms: u32) -> Vec<u8> {
+        let mut header = vec![0u8; ANMF_FRAME_HEADER_LEN];
+        let d = duration_ms.to_le_bytes();
+        header[12] = d[0];
+        header[13] = d[1];
+        header[14] = d[2];
+        // Nested VP8L sub-chunk so the frame isn't a bare header (not
+        // required for parsing, but keeps the fixture realistic).
+        header.extend_from_slice(&chunk(b"VP8L", &[0x2F, 0x00, 0x00, 0x00]));
+        header
+    }
+
+    fn riff_webp(chunks: &[Vec<u8>]) -> Vec<u8> {
+        let mut payload = Vec::new();
+        for c in chunks {
+            payload.extend_from_slice(c);
+        }
+        let mut out = Vec::new();
+        out.extend_from_slice(b"RIFF");
+        out.extend_from_slice(&((4 + payload.len()) as u32).to_le_bytes());
+        out.extend_from_slice(b"WEBP");
+        out.extend_from_slice(&payload);
+        out
+    }
+
+    fn animated_webp(durations_ms: &[u32]) -> Vec<u8> {
+        let mut chunks = vec![
+            chunk(b"VP8X", &[0x10, 0, 0, 0, 9, 0, 0, 9, 0, 0]),
+            // Background color + a nonzero loop count, which must be walked
+            // past intact and never misread as frame data.
+            chunk(b"ANIM", &[0, 0, 0, 0, 0x05, 0x00]),
+        ];
+        for &d in durations_ms {
+            chunks.push(chunk(b"ANMF", &anmf_frame(d)));
+        }
+        riff_webp(&chunks)
+    }
+
+    fn static_webp() -> Vec<u8> {
+        riff_webp(&[chunk(b"VP8L", &[0x2F, 0x00, 0x00, 0x00])])
+    }
+
+    #[test]
+    fn non_webp_data_is_not_animated() {
+        let png_header = [0x89, 0x50, 0x4E, 0x47, 0x0D, 0x0A, 0x1A, 0x0A];
+        assert!(!is_animated_webp(&png_header));
+        assert_eq!(webp_frame_count(&png_header), 0);
+        assert_eq!(webp_duration_ms(&png_header), 0);
+    }
+
+    #[test]
+    fn empty_data_is_not_animated() {
+        assert!(!is_animated_webp(&[]));
+        assert_eq!(webp_frame_count(&[]), 0);
+    }
+
+    #[test]
+    fn static_webp_is_not_animated_and_has_one_frame() {
+        let webp = static_webp();
+        assert!(!is_animated_webp(&webp));
+        assert_eq!(webp_frame_count(&webp), 1);
+        assert_eq!(webp_duration_ms(&webp), 0);
+    }
+
+    #[test]
+    fn animated_webp_counts_anmf_frames() {
+        let webp = animated_webp(&[100, 150, 200]);
+        assert!(is_animated_webp(&webp));
+        assert_eq!(webp_frame_count(&webp), 3);
+    }
+
+    #[test]
+    fn animated_webp_sums_frame_durations_in_milliseconds() {
+        let webp = animated_webp(&[100, 150, 200]);
+        assert_eq!(webp_duration_ms(&webp), 450);
+    }
+
+    #[test]
+    fn animated_webp_ignores_anim_loop_count_as_a_duration() {
+        // The ANIM chunk's loop count byte (0x05) sits right before the
+        // first ANMF chunk; a walker that mis-parses chunk boundaries would
+        // either double-count it or throw off every later offset.
+        let webp = animated_webp(&[40]);
+        assert_eq!(webp_duration_ms(&webp), 40);
+    }
+
+    #[test]
+    fn truncated_webp_does_not_panic() {
+        let mut webp = animated_webp(&[100]);
+        webp.truncate(webp.len() - 3);
+        let _ = is_animated_webp(&webp);
+        let _ = webp_frame_count(&webp);
+        let _ = webp_duration_ms(&webp);
+    }
+}
@@ -37,6 +37,19 @@ pub fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
         }
     }
 
+    // simsimd occasionally falls back silently on some aarch64 builds. When
+    // built with the `neon-cosine` feature, try a directly-written NEON
+    // implementation before giving up to the scalar path, so ARM callers get
+    // a guaranteed vectorized result whenever the CPU actually has NEON.
+    #[cfg(all(target_arch = "aarch64", feature = "neon-cosine"))]
+    {
+        if std::arch::is_aarch64_feature_detected!("neon") {
+            if let Some(score) = unsafe { neon_cosine(a, b) } {
+                return score;
+            }
+        }
+    }
+
     // Fallback to scalar
     scalar_cosine(a, b)
 }
@@ -57,6 +70,82 @@ fn simd_cosine(a: &[f32], b: &[f32]) -> Option<f32> {
     })
 }
 
+/// Directly-written NEON cosine similarity, as an alternative to the
+/// simsimd-provided SIMD path on aarch64. Gated behind the `neon-cosine`
+/// cargo feature and a runtime `is_aarch64_feature_detected!` check — callers
+/// must confirm NEON support before calling this. Mirrors the accumulate
+/// dot/norm_a/norm_b structure of [`scalar_cosine`], including the same
+/// non-finite floor, so results agree within float rounding.
+#[cfg(all(target_arch = "aarch64", feature = "neon-cosine"))]
+#[target_feature(enable = "neon")]
+unsafe fn neon_cosine(a: &[f32], b: &[f32]) -> Option<f32> {
+    use std::arch::aarch64::*;
+
+    let len = a.len();
+    let lanes = len / 4;
+    let remainder = len % 4;
+
+    let mut dot_v = vdupq_n_f32(0.0);
+    let mut norm_a_v = vdupq_n_f32(0.0);
+    let mut norm_b_v = vdupq_n_f32(0.0);
+
+    for i in 0..lanes {
+        let idx = i * 4;
+        let va = vld1q_f32(a.as_ptr().add(idx));
+        let vb = vld1q_f32(b.as_ptr().add(idx));
+        dot_v = vfmaq_f32(dot_v, va, vb);
+        norm_a_v = vfmaq_f32(norm_a_v, va, va);
+        norm_b_v = vfmaq_f32(norm_b_v, vb, vb);
+    }
+
+    let mut dot = vaddvq_f32(dot_v);
+    let mut norm_a = vaddvq_f32(norm_a_v);
+    let mut norm_b = vaddvq_f32(norm_b_v);
+
+    let start = lanes * 4;
+    for i in 0..remainder {
+        let idx = start + i;
+        dot += a[idx] * b[idx];
+        norm_a += a[idx] * a[idx];
+        norm_b += b[idx] * b[idx];
+    }
+
+    let denom = (norm_a * norm_b).sqrt();
+    if denom > 1e-10 {
+        let sim = dot / denom;
+        if sim.is_finite() {
+            Some(sim)
+        } else {
+            None
+        }
+    } else {
+        Some(0.0)
+    }
+}
+
+/// Report whether `cosine_similarity` will actually take the SIMD path on
+/// this CPU, for startup diagnostics (`RagEngine::capabilities`). Mirrors
+/// the arch-gated structure of `cosine_similarity` itself rather than
+/// simsimd's own `capabilities::uses_*` functions, so the reported flag
+/// matches the specific runtime check this crate cares about: AVX2 on x86_64,
+/// NEON on aarch64. A `true` result doesn't guarantee simsimd never falls
+/// back per-call (see `cosine_similarity`'s fallback chain) — it means the
+/// hardware this process is running on supports it.
+pub fn simd_active() -> bool {
+    #[cfg(target_arch = "x86_64")]
+    {
+        is_x86_feature_detected!("avx2")
+    }
+    #[cfg(target_arch = "aarch64")]
+    {
+        std::arch::is_aarch64_feature_detected!("neon")
+    }
+    #[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+    {
+        false
+    }
+}
+
 /// Scalar fallback for cosine similarity
 fn scalar_cosine(a: &[f32], b: &[f32]) -> f32 {
     let mut dot = 0.0f32;
@@ -114,6 +203,119 @@ fn scalar_cosine(a: &[f32], b: &[f32]) -> f32 {
     }
 }
 
+/// Cosine similarity computed over only the components where `mask[i]` is
+/// `true` — masked-out components contribute to neither the dot product nor
+/// either norm, as if that subspace didn't exist. Lets a caller ignore
+/// dimensions that encode metadata rather than semantic content (e.g. a
+/// language-tag block) without re-embedding to drop them.
+///
+/// `mask.len()` must equal `a.len()`/`b.len()`; like [`cosine_similarity`],
+/// a mismatch returns `0.0` rather than panicking (`RagEngine::search`'s
+/// `dimension_mask` parameter validates the length up front and raises a
+/// `PyValueError` instead of relying on this silent fallback). No SIMD path
+/// — the mask makes this a cold, low-QPS knob rather than the hot default,
+/// so it stays a straightforward scalar loop.
+#[inline]
+pub fn cosine_similarity_masked(a: &[f32], b: &[f32], mask: &[bool]) -> f32 {
+    if a.len() != b.len() || a.len() != mask.len() || a.is_empty() {
+        return 0.0;
+    }
+
+    let mut dot = 0.0f32;
+    let mut norm_a = 0.0f32;
+    let mut norm_b = 0.0f32;
+    for ((&x, &y), &keep) in a.iter().zip(b.iter()).zip(mask.iter()) {
+        if !keep {
+            continue;
+        }
+        dot += x * y;
+        norm_a += x * x;
+        norm_b += y * y;
+    }
+
+    // Same non-finite floor and zero-denom guard as `scalar_cosine`, so a
+    // masked call agrees with the unmasked one on degenerate inputs.
+    let denom = (norm_a * norm_b).sqrt();
+    if denom > 1e-10 {
+        let sim = dot / denom;
+        if sim.is_finite() {
+            sim
+        } else {
+            0.0
+        }
+    } else {
+        0.0
+    }
+}
+
+/// Euclidean norm of a vector (`sqrt(sum(x^2))`), used by
+/// [`cosine_similarity_with_floor`] to get the query's exact norm once up
+/// front rather than re-deriving it per entry.
+#[inline]
+pub fn vector_norm(v: &[f32]) -> f32 {
+    v.iter().map(|x| x * x).sum::<f32>().sqrt()
+}
+
+/// Cosine similarity with an early-exit floor, for pruning brute-force
+/// search over a large store (`RagEngine::search`'s `prune` flag). Pays one
+/// extra norm-only pass over `b` up front so a Cauchy-Schwarz bound on the
+/// *unprocessed remainder* of the dot product can be checked periodically
+/// while accumulating it: `dot_so_far + ||remaining_a|| * ||b||` is always
+/// at least as large as the true final dot product (since
+/// `dot(remaining_a, remaining_b) <= ||remaining_a|| * ||remaining_b|| <=
+/// ||remaining_a|| * ||b||`), so once even that upper bound can't reach
+/// `floor * ||a|| * ||b||`, the entry is
+/// reported as pruned without finishing the pass. The bound only tightens
+/// via `||remaining_a||` shrinking (checking `b`'s own remaining sub-vector
+/// norm would need a second interleaved accumulator for no asymptotic
+/// win, since it's already the same three-accumulator cost as
+/// `scalar_cosine`, just reordered into two passes).
+///
+/// `norm_a` MUST be the caller's exact precomputed `vector_norm(a)` — an
+/// approximate or stale norm would make the bound unsound (an entry could
+/// be pruned even though its true score clears `floor`). Returns `None`
+/// when pruned, `Some(score)` otherwise, matching `cosine_similarity`'s
+/// "never leak a non-finite score" contract.
+pub fn cosine_similarity_with_floor(a: &[f32], b: &[f32], norm_a: f32, floor: f32) -> Option<f32> {
+    if a.len() != b.len() || a.is_empty() {
+        return Some(cosine_similarity(a, b));
+    }
+    if a.iter().all(|&x| x == 0.0) || b.iter().all(|&x| x == 0.0) {
+        return Some(0.0);
+    }
+
+    let norm_b = vector_norm(b);
+    let denom = norm_a * norm_b;
+    if denom <= 1e-10 {
+        return Some(0.0);
+    }
+    let target_dot = floor * denom;
+    let norm_a_sq_total = norm_a * norm_a;
+
+    const STRIDE: usize = 32;
+    let mut dot = 0.0f32;
+    let mut norm_a_sq = 0.0f32;
+    for (chunk_a, chunk_b) in a.chunks(STRIDE).zip(b.chunks(STRIDE)) {
+        for (x, y) in chunk_a.iter().zip(chunk_b.iter()) {
+            dot += x * y;
+            norm_a_sq += x * x;
+        }
+
+        let remaining_a_sq = (norm_a_sq_total - norm_a_sq).max(0.0);
+        let remaining_bound = remaining_a_sq.sqrt() * norm_b;
+        if dot + remaining_bound < target_dot {
+            return None;
+        }
+    }
+
+    let sim = dot / denom;
+    if sim.is_finite() {
+        Some(sim)
+    } else {
+        Some(0.0)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -145,6 +347,35 @@ mod tests {
         assert!(sim.abs() < 1e-6);
     }
 
+    #[test]
+    fn test_masked_cosine_ignores_masked_out_components() {
+        // Last component disagrees wildly, but the mask zeroes it out, so the
+        // masked similarity should match the unmasked similarity of the
+        // truncated (mask-only) vectors, not the full ones.
+        let a = vec![1.0_f32, 2.0, 3.0, 100.0];
+        let b = vec![1.0_f32, 2.0, 3.0, -100.0];
+        let mask = [true, true, true, false];
+        let masked = cosine_similarity_masked(&a, &b, &mask);
+        let unmasked = cosine_similarity(&a[..3], &b[..3]);
+        assert!((masked - unmasked).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_masked_cosine_all_false_mask_is_zero() {
+        let a = vec![1.0_f32, 2.0, 3.0];
+        let b = vec![1.0_f32, 2.0, 3.0];
+        let mask = [false, false, false];
+        assert_eq!(cosine_similarity_masked(&a, &b, &mask), 0.0);
+    }
+
+    #[test]
+    fn test_masked_cosine_length_mismatch_returns_zero() {
+        let a = vec![1.0_f32, 2.0, 3.0];
+        let b = vec![1.0_f32, 2.0, 3.0];
+        let mask = [true, true];
+        assert_eq!(cosine_similarity_masked(&a, &b, &mask), 0.0);
+    }
+
     #[test]
     fn test_opposite_vectors() {
         let a = vec![1.0, 2.0, 3.0];
@@ -174,6 +405,36 @@ mod tests {
         assert_eq!(sim3, 0.0);
     }
 
+    #[cfg(all(target_arch = "aarch64", feature = "neon-cosine"))]
+    #[test]
+    fn test_neon_cosine_matches_scalar_within_tolerance() {
+        if !std::arch::is_aarch64_feature_detected!("neon") {
+            return;
+        }
+        let a = vec![1.0_f32, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0];
+        let b = vec![4.0_f32, 3.0, 2.0, 1.0, 0.5, 6.5, -1.0];
+        let neon = unsafe { neon_cosine(&a, &b) }.unwrap();
+        let scalar = scalar_cosine(&a, &b);
+        assert!((neon - scalar).abs() < 1e-5, "neon={neon} scalar={scalar}");
+    }
+
+    #[test]
+    fn test_simd_active_matches_arch_feature_detection() {
+        // Just pin that it doesn't panic and returns the same runtime
+        // detection cosine_similarity's own arch-gated branch would use —
+        // the exact value is CPU-dependent, so there's nothing more specific
+        // to assert cross-platform.
+        #[cfg(target_arch = "x86_64")]
+        assert_eq!(simd_active(), is_x86_feature_detected!("avx2"));
+        #[cfg(target_arch = "aarch64")]
+        assert_eq!(
+            simd_active(),
+            std::arch::is_aarch64_feature_detected!("neon")
+        );
+        #[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+        assert!(!simd_active());
+    }
+
     #[test]
     fn test_public_entry_never_leaks_nonfinite_for_huge_inputs() {
         // The public cosine_similarity tries SIMD first; simsimd may return a
@@ -186,4 +447,63 @@ mod tests {
         let huge3 = vec![3.0e38_f32; 3];
         assert!(cosine_similarity(&huge3, &huge3).is_finite());
     }
+
+    #[test]
+    fn test_vector_norm() {
+        assert!((vector_norm(&[3.0, 4.0]) - 5.0).abs() < 1e-6);
+        assert_eq!(vector_norm(&[0.0, 0.0, 0.0]), 0.0);
+    }
+
+    #[test]
+    fn test_cosine_similarity_with_floor_matches_full_scan_when_not_pruned() {
+        // A big enough vector to exercise several STRIDE=32 chunks.
+        let a: Vec<f32> = (0..100).map(|i| (i as f32 * 0.37).sin()).collect();
+        let b: Vec<f32> = (0..100).map(|i| (i as f32 * 0.71).cos()).collect();
+        let norm_a = vector_norm(&a);
+        let exact = cosine_similarity(&a, &b);
+
+        // A floor low enough that nothing should be pruned.
+        let bounded = cosine_similarity_with_floor(&a, &b, norm_a, -1.0).unwrap();
+        assert!(
+            (bounded - exact).abs() < 1e-4,
+            "bounded={bounded} exact={exact}"
+        );
+    }
+
+    #[test]
+    fn test_cosine_similarity_with_floor_prunes_when_unreachable() {
+        let a = vec![1.0_f32, 0.0, 0.0, 0.0];
+        let b = vec![0.0_f32, 1.0, 0.0, 0.0]; // orthogonal -> true score 0.0
+        let norm_a = vector_norm(&a);
+
+        // No vector can score above its own maximum of 1.0, let alone an
+        // orthogonal pair's true score of 0.0 clearing a 0.99 floor.
+        assert!(cosine_similarity_with_floor(&a, &b, norm_a, 0.99).is_none());
+    }
+
+    #[test]
+    fn test_cosine_similarity_with_floor_never_prunes_a_true_positive() {
+        let a = vec![1.0_f32, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0];
+        let b = a.clone(); // identical -> true score 1.0
+        let norm_a = vector_norm(&a);
+
+        assert_eq!(cosine_similarity_with_floor(&a, &b, norm_a, 1.0), Some(1.0));
+    }
+
+    #[test]
+    fn test_cosine_similarity_with_floor_handles_mismatched_and_zero_vectors() {
+        let a = vec![1.0_f32, 2.0];
+        let b = vec![1.0_f32];
+        assert_eq!(
+            cosine_similarity_with_floor(&a, &b, vector_norm(&a), 0.0),
+            Some(0.0)
+        );
+
+        let zero = vec![0.0_f32, 0.0];
+        let v = vec![1.0_f32, 2.0];
+        assert_eq!(
+            cosine_similarity_with_floor(&zero, &v, vector_norm(&zero), 0.0),
+            Some(0.0)
+        );
+    }
 }
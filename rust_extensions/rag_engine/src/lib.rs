@@ -3,16 +3,67 @@
 //! A Rust-based RAG (Retrieval-Augmented Generation) engine with:
 //! - SIMD-optimized cosine similarity
 //! - Parallel search with Rayon
+//!
+//! There is currently no keyword/BM25-style inverted index in this crate
+//! (`search`/`search_impl` only rank by embedding cosine similarity) — a
+//! `VectorIndex` with a tunable minimum keyword length and a posting-list
+//! cap per the request that prompted this note doesn't have anything to
+//! attach to yet. Revisit once keyword search lands.
+//!
+//! A follow-up request asked for a `search_keywords(keywords, mode)`
+//! AND/OR combinator (intersect/union posting lists, shortest list first)
+//! on top of that same not-yet-existing `VectorIndex::search_keyword` — same
+//! blocker, nothing to extend yet either.
+//!
+//! A later request asked to min-max normalize the keyword-match and cosine
+//! components of a `search_hybrid(alpha, ...)` blend to [0, 1] and expose
+//! them separately on the result. There is no `search_hybrid` (or any
+//! keyword-scored search) here to normalize — the alpha-blended hybrid
+//! search this crate's Python caller uses (`RagMemoryManager.hybrid_search`
+//! in `cogs/ai_core/memory/rag.py`) combines semantic and keyword hits via
+//! Reciprocal Rank Fusion instead, which has no raw component scores to
+//! normalize in the first place. Revisit once keyword search lands here and
+//! an alpha-blended combinator (as opposed to RRF) is actually built on top
+//! of it.
+//!
+//! A later request asked for a bloom filter over indexed keywords, with a
+//! `might_contain_keyword(word) -> bool` fast-negative check rebuilt during
+//! `VectorIndex::rebuild`. Same blocker as above: there is no `VectorIndex`,
+//! no keyword indexing, and no `rebuild` step here to hook a bloom filter
+//! into — this crate's entries are plain embeddings, keyed by id, with no
+//! per-entry text/keyword extraction at all. Revisit once keyword search
+//! lands here; a bloom filter over its posting-list keys would slot in as
+//! part of that same rebuild step.
+//!
+//! A later request asked for `similarity_threshold` validation to switch on
+//! "the active metric" (cosine range `[-1, 1]` vs. euclidean range `[0, ∞)`)
+//! and for a per-query threshold override. Cosine is the only distance
+//! metric this crate implements (`search`/`search_buffer` always call
+//! `cosine_similarity`; `capabilities()`'s `"metric"` key is hard-coded to
+//! `"cosine"`) and there is no per-query threshold parameter, only the
+//! engine-wide value `set_similarity_threshold` retunes — so the metric
+//! switch and per-query override can't be built yet. What *is* implemented:
+//! `new`/`set_similarity_threshold` now reject a threshold outside cosine's
+//! valid `[-1, 1]` range, closing the "nothing ever matches" failure mode
+//! for the one metric that exists today. Revisit the metric-aware branch if
+//! a second distance metric (e.g. euclidean) actually lands here.
 
 mod cosine;
+mod storage;
 
-use parking_lot::RwLock;
-use pyo3::exceptions::PyValueError;
+use arc_swap::ArcSwap;
+use parking_lot::Mutex;
+use pyo3::exceptions::{PyRuntimeError, PyValueError};
 use pyo3::prelude::*;
-use std::collections::HashMap;
+use pyo3::types::PyDict;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 
-pub use cosine::cosine_similarity;
+pub use cosine::{
+    cosine_similarity, cosine_similarity_masked, cosine_similarity_with_floor, simd_active,
+    vector_norm,
+};
+pub use storage::{migrate_dimension, RagError, VectorStorage};
 
 /// A single memory entry with embedding and metadata
 #[pyclass(from_py_object)]
@@ -27,24 +78,43 @@ pub struct MemoryEntry {
     #[pyo3(get)]
     pub importance: f32,
     pub embedding: Vec<f32>,
+    /// Arbitrary caller-defined string tags (e.g. a source conversation id)
+    /// — not used by scoring, only by `search`'s `group_by` deduplication.
+    /// `None` rather than an always-present empty map so an old saved file
+    /// with no metadata at all loads back byte-for-byte equivalent instead
+    /// of gaining a spurious `{}` on every entry.
+    pub metadata: Option<HashMap<String, String>>,
 }
 
 #[pymethods]
 impl MemoryEntry {
+    #[pyo3(signature = (id, text, embedding, timestamp, importance, metadata=None))]
     #[new]
-    fn new(id: String, text: String, embedding: Vec<f32>, timestamp: f64, importance: f32) -> Self {
+    fn new(
+        id: String,
+        text: String,
+        embedding: Vec<f32>,
+        timestamp: f64,
+        importance: f32,
+        metadata: Option<HashMap<String, String>>,
+    ) -> Self {
         Self {
             id,
             text,
             embedding,
             timestamp,
             importance,
+            metadata,
         }
     }
 
     fn get_embedding(&self) -> Vec<f32> {
         self.embedding.clone()
     }
+
+    fn get_metadata(&self) -> Option<HashMap<String, String>> {
+        self.metadata.clone()
+    }
 }
 
 /// Search result with score
@@ -59,6 +129,24 @@ pub struct SearchResult {
     pub score: f32,
     #[pyo3(get)]
     pub timestamp: f64,
+    /// The source entry's metadata, carried along so a caller using
+    /// `search`'s `filter` doesn't need a second `get()` round trip to see
+    /// what it matched on.
+    #[pyo3(get)]
+    pub metadata: Option<HashMap<String, String>>,
+}
+
+/// Elapsed microseconds for the two phases of `search(..., return_timing=True)`:
+/// cloning the entries snapshot under the read lock, and the parallel scoring
+/// pass. Kept as a companion struct rather than fields on `SearchResult` so
+/// the (identical for every result) timing isn't duplicated per-row.
+#[pyclass(from_py_object)]
+#[derive(Clone, Copy)]
+pub struct SearchTiming {
+    #[pyo3(get)]
+    pub snapshot_clone_us: u64,
+    #[pyo3(get)]
+    pub compute_us: u64,
 }
 
 /// Reject path-traversal attempts on user-supplied save/load paths.
@@ -140,440 +228,554 @@ fn reject_symlinked_components(p: &std::path::Path) -> PyResult<()> {
     Ok(())
 }
 
-/// Main RAG Engine class
-#[pyclass]
-pub struct RagEngine {
-    entries: Arc<RwLock<HashMap<String, MemoryEntry>>>,
-    dimension: usize,
-    similarity_threshold: f32,
+/// Fsync the directory containing `path` so a rename's directory-entry
+/// update is durable, not just the file data. Best-effort: on POSIX,
+/// opening a directory with `File::open` and calling `sync_all()` is the
+/// standard way to fsync a directory; on Windows there is no equivalent
+/// (opening a directory as a file fails), so this is a no-op there — the
+/// file-data fsync `save_impl` already does covers the common case.
+#[cfg(unix)]
+fn fsync_parent_dir(path: &std::path::Path) -> std::io::Result<()> {
+    let dir = path.parent().filter(|p| !p.as_os_str().is_empty());
+    let dir = dir.unwrap_or_else(|| std::path::Path::new("."));
+    std::fs::File::open(dir)?.sync_all()
 }
 
-#[pymethods]
-impl RagEngine {
-    #[new]
-    #[pyo3(signature = (dimension=384, similarity_threshold=0.7))]
-    fn new(dimension: usize, similarity_threshold: f32) -> Self {
-        Self {
-            entries: Arc::new(RwLock::new(HashMap::new())),
-            dimension,
-            similarity_threshold,
-        }
-    }
+#[cfg(not(unix))]
+fn fsync_parent_dir(_path: &std::path::Path) -> std::io::Result<()> {
+    Ok(())
+}
 
-    /// Add a memory entry
-    fn add(&self, entry: MemoryEntry) -> PyResult<()> {
-        if entry.embedding.len() != self.dimension {
-            return Err(PyValueError::new_err(format!(
-                "Embedding dimension mismatch: expected {}, got {}",
-                self.dimension,
-                entry.embedding.len()
-            )));
-        }
-        // Validate importance is finite to prevent NaN/Infinity score corruption
-        if !entry.importance.is_finite() {
-            return Err(PyValueError::new_err("importance must be a finite number"));
-        }
-        // Importance is a non-negative weight (calculate_importance clamps to
-        // [0.0, 2.0]). A negative importance flips the sign of final_score in
-        // search() (final_score = base_score * decay * importance); since the
-        // cosine base_score is in [-1, 1], a negative weight on an OPPOSITE-meaning
-        // memory (base_score < 0) yields a POSITIVE score that can pass the
-        // threshold and surface a maximally-irrelevant hit. Enforce the invariant
-        // at the trust boundary.
-        if entry.importance < 0.0 {
-            return Err(PyValueError::new_err("importance must be non-negative"));
-        }
-        // Embedding values must also be finite — a single NaN/Inf in the
-        // vector would later make save() fail (serde_json refuses non-finite
-        // floats) and silently degrades cosine similarity at query time.
-        if entry.embedding.iter().any(|v| !v.is_finite()) {
-            return Err(PyValueError::new_err(
-                "embedding contains non-finite values (NaN/Inf)",
-            ));
-        }
-        // Timestamp must be finite too — a non-finite value serializes to JSON
-        // null in save() and is silently dropped on the next load(), so guard it
-        // here to keep the stored-data invariant consistent with importance/embedding.
-        if !entry.timestamp.is_finite() {
-            return Err(PyValueError::new_err("timestamp must be a finite number"));
-        }
+/// Valid range for `similarity_threshold` under the cosine metric -- the
+/// only distance metric this crate implements today (see the module-level
+/// doc comment). A threshold outside `[-1, 1]` can never be met (or is
+/// always met) by a cosine score, which is the "nothing ever matches"
+/// confusion `new`/`set_similarity_threshold` guard against.
+const COSINE_THRESHOLD_RANGE: std::ops::RangeInclusive<f32> = -1.0..=1.0;
 
-        let mut entries = self.entries.write();
-        entries.insert(entry.id.clone(), entry);
-        Ok(())
+fn validate_similarity_threshold(similarity_threshold: f32) -> PyResult<()> {
+    if !similarity_threshold.is_finite() {
+        return Err(PyValueError::new_err("similarity_threshold must be finite"));
     }
+    if !COSINE_THRESHOLD_RANGE.contains(&similarity_threshold) {
+        return Err(PyValueError::new_err(format!(
+            "similarity_threshold {similarity_threshold} is outside cosine similarity's valid range [{}, {}]",
+            COSINE_THRESHOLD_RANGE.start(),
+            COSINE_THRESHOLD_RANGE.end(),
+        )));
+    }
+    Ok(())
+}
 
-    /// Add multiple entries in batch
-    ///
-    /// Silent-skip contract: unlike single-entry `add()` (which raises
-    /// PyValueError on a bad entry), this method silently drops any entry that
-    /// fails dimension / finite-importance / finite-embedding validation and
-    /// returns only the count actually inserted. The returned count can
-    /// therefore be less than `entries_list.len()` for a malformed batch.
-    fn add_batch(&self, entries_list: Vec<MemoryEntry>) -> PyResult<usize> {
-        let mut entries = self.entries.write();
-        let mut added = 0;
-
-        for entry in entries_list {
-            if entry.embedding.len() == self.dimension
-                && entry.importance.is_finite()
-                && entry.importance >= 0.0
-                && entry.embedding.iter().all(|v| v.is_finite())
-                && entry.timestamp.is_finite()
-            {
-                // Count only newly inserted ids — HashMap::insert returns
-                // Some(old) when the id already existed (de-dupe replace), so a
-                // batch with duplicate ids must not over-report. Keeps the
-                // returned count == net growth in engine size (parity with load()).
-                if entries.insert(entry.id.clone(), entry).is_none() {
-                    added += 1;
-                }
-            }
+/// Map a `VectorStorage` error onto the same `PyValueError`/`PyRuntimeError`
+/// split the rest of this file uses: a mismatch or full store is something
+/// the caller can fix by changing an argument (`PyValueError`), while a
+/// corrupt file or I/O failure is an environment problem the caller can only
+/// report (`PyRuntimeError`), matching `add`'s own embedding-dimension check
+/// a few lines down.
+fn rag_error_to_pyerr(e: RagError) -> PyErr {
+    match e {
+        RagError::DimensionMismatch { expected, got } => PyValueError::new_err(format!(
+            "embedding dimension mismatch: expected {expected}, got {got}"
+        )),
+        RagError::CapacityExceeded { capacity } => {
+            PyValueError::new_err(format!("mmap storage capacity ({capacity}) exceeded"))
         }
+        other => PyRuntimeError::new_err(other.to_string()),
+    }
+}
 
-        Ok(added)
+/// Sort `results` by score descending, then by `id`/`timestamp` so entries
+/// with identical scores (routine when the underlying data has ties, e.g.
+/// several zero-score filtered results at the threshold boundary) come back
+/// in a deterministic order instead of whatever order the HashMap snapshot
+/// happened to iterate in — ranking is unaffected for any pair with distinct
+/// scores. Truncates to `top_k` after sorting, except `top_k == 0`, which
+/// means "no truncation — return every result above the threshold" (already
+/// naturally bounded by the store size, since `results` only ever holds one
+/// entry per matching store entry).
+fn rank_and_truncate(results: &mut Vec<SearchResult>, top_k: usize) {
+    results.sort_by(|a, b| {
+        b.score
+            .partial_cmp(&a.score)
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then_with(|| a.id.cmp(&b.id))
+            .then_with(|| {
+                a.timestamp
+                    .partial_cmp(&b.timestamp)
+                    .unwrap_or(std::cmp::Ordering::Equal)
+            })
+    });
+    if top_k > 0 {
+        results.truncate(top_k);
     }
+}
 
-    /// Remove an entry by ID
-    fn remove(&self, id: &str) -> bool {
-        let mut entries = self.entries.write();
-        entries.remove(id).is_some()
+/// `rank_and_truncate`'s counterpart for `search_full`'s `(MemoryEntry, f32)`
+/// pairs — same descending-score-then-id-then-timestamp order, same
+/// `top_k == 0` means "no truncation" rule, just reading the score/id/
+/// timestamp out of the tuple's `MemoryEntry` instead of a `SearchResult`.
+fn rank_and_truncate_full(results: &mut Vec<(MemoryEntry, f32)>, top_k: usize) {
+    results.sort_by(|(a, a_score), (b, b_score)| {
+        b_score
+            .partial_cmp(a_score)
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then_with(|| a.id.cmp(&b.id))
+            .then_with(|| {
+                a.timestamp
+                    .partial_cmp(&b.timestamp)
+                    .unwrap_or(std::cmp::Ordering::Equal)
+            })
+    });
+    if top_k > 0 {
+        results.truncate(top_k);
     }
+}
 
-    /// Search for similar entries (parallel SIMD-optimized)
-    #[pyo3(signature = (query_embedding, top_k=5, time_decay_factor=0.0))]
-    fn search(
-        &self,
-        py: Python<'_>,
-        query_embedding: Vec<f32>,
-        top_k: usize,
-        time_decay_factor: f64,
-    ) -> PyResult<Vec<SearchResult>> {
-        if query_embedding.len() != self.dimension {
-            return Err(PyValueError::new_err(format!(
-                "Query dimension mismatch: expected {}, got {}",
-                self.dimension,
-                query_embedding.len()
-            )));
-        }
-        // Validate query is finite — match add()'s guarantees so we never
-        // silently let a NaN slip into cosine_similarity. The threshold filter
-        // below would catch NaN scores by accident (NaN >= x is false), but
-        // an Inf in the query produces an Inf score that passes the filter
-        // and torpedoes the rank order.
-        if query_embedding.iter().any(|v| !v.is_finite()) {
-            return Err(PyValueError::new_err(
-                "query_embedding contains non-finite values (NaN/Inf)",
-            ));
-        }
+/// `search_mmr`'s greedy selection step. `candidates` is already ranked and
+/// truncated to the candidate pool (relevance-scored the same way `search`
+/// scores its results, `similarity_threshold` already applied); this picks
+/// `top_k` of them one at a time, each round choosing whichever remaining
+/// candidate maximizes `lambda_mult * relevance - (1 - lambda_mult) *
+/// max_similarity_to_selected`, where the diversity term reuses
+/// `cosine_similarity` against every embedding already selected (`0.0` for
+/// the first pick, when nothing's selected yet, so the first pick is always
+/// the most relevant candidate). `lambda_mult=1.0` collapses this to plain
+/// top-k by relevance; `lambda_mult=0.0` ignores relevance and only
+/// maximizes distance from what's already picked. Ties break on higher
+/// relevance, then ascending `id` (as in `rank_and_truncate`), so results
+/// are deterministic when candidates score identically.
+fn mmr_select(
+    mut candidates: Vec<(MemoryEntry, f32)>,
+    top_k: usize,
+    lambda_mult: f32,
+) -> Vec<SearchResult> {
+    let mut selected = Vec::with_capacity(top_k.min(candidates.len()));
+    let mut selected_embeddings: Vec<Vec<f32>> = Vec::with_capacity(top_k.min(candidates.len()));
 
-        // Clone data under read lock so we can release GIL during computation
-        let entries_snapshot: Vec<_> = {
-            let entries = self.entries.read();
-            entries.values().cloned().collect()
-        };
-        let similarity_threshold = self.similarity_threshold;
-
-        // Release GIL during CPU-intensive parallel computation
-        py.detach(|| {
-            use rayon::prelude::*;
-
-            let current_time = std::time::SystemTime::now()
-                .duration_since(std::time::UNIX_EPOCH)
-                .map(|d| d.as_secs_f64())
-                .unwrap_or(0.0);
-
-            // Filter out NaN scores and below-threshold results
-            let mut results: Vec<SearchResult> = entries_snapshot
-                .par_iter()
-                .map(|entry| {
-                    let base_score = cosine_similarity(&query_embedding, &entry.embedding);
-
-                    // Apply time decay if factor > 0
-                    let final_score = if time_decay_factor > 0.0 {
-                        // Clamp age to >= 0 to prevent score inflation for future timestamps
-                        // Clamp time_decay_factor to sane range to prevent overflow
-                        let clamped_decay = time_decay_factor.clamp(0.0, 1.0);
-                        let age_hours = ((current_time - entry.timestamp) / 3600.0).max(0.0);
-                        let decay = (-clamped_decay * age_hours).exp() as f32;
-                        base_score * decay * entry.importance
-                    } else {
-                        base_score * entry.importance
-                    };
-
-                    SearchResult {
-                        id: entry.id.clone(),
-                        text: entry.text.clone(),
-                        score: if final_score.is_finite() {
-                            final_score
-                        } else {
-                            0.0
-                        },
-                        timestamp: entry.timestamp,
-                    }
-                })
-                .filter(|r| r.score >= similarity_threshold)
-                .collect();
+    while !candidates.is_empty() && selected.len() < top_k {
+        let scores: Vec<f32> = candidates
+            .iter()
+            .map(|(entry, relevance)| {
+                let max_similarity_to_selected = selected_embeddings
+                    .iter()
+                    .map(|sel| cosine_similarity(&entry.embedding, sel))
+                    .fold(0.0f32, f32::max);
+                lambda_mult * relevance - (1.0 - lambda_mult) * max_similarity_to_selected
+            })
+            .collect();
 
-            // Sort by score descending
-            results.sort_by(|a, b| {
-                b.score
-                    .partial_cmp(&a.score)
+        let best_idx = (0..candidates.len())
+            .max_by(|&a, &b| {
+                scores[a]
+                    .partial_cmp(&scores[b])
                     .unwrap_or(std::cmp::Ordering::Equal)
-            });
-            results.truncate(top_k);
+                    .then_with(|| {
+                        candidates[a]
+                            .1
+                            .partial_cmp(&candidates[b].1)
+                            .unwrap_or(std::cmp::Ordering::Equal)
+                    })
+                    .then_with(|| candidates[b].0.id.cmp(&candidates[a].0.id))
+            })
+            .expect("candidates is non-empty inside the loop guard");
 
-            Ok(results)
-        })
+        let (entry, score) = candidates.remove(best_idx);
+        selected_embeddings.push(entry.embedding.clone());
+        selected.push(SearchResult {
+            id: entry.id,
+            text: entry.text,
+            score,
+            timestamp: entry.timestamp,
+            metadata: entry.metadata,
+        });
     }
 
-    /// Get entry count
-    fn len(&self) -> usize {
-        self.entries.read().len()
-    }
+    selected
+}
 
-    /// Check if empty
-    fn is_empty(&self) -> bool {
-        self.entries.read().is_empty()
-    }
+/// `search`'s `group_by` step — runs after scoring, before `rank_and_truncate`
+/// truncates to `top_k`. Keeps only the highest-scoring result per distinct
+/// value of `entry.metadata[group_by]` (`entry` looked up in `entries` by
+/// id), so five near-duplicate hits from the same source collapse to their
+/// single best representative instead of crowding out other sources under a
+/// flat `top_k`. A no-op when `group_by` is `None`.
+///
+/// An entry with no `metadata`, or missing the `group_by` key, either forms
+/// its own singleton group (kept, `drop_ungrouped=false`) or is dropped
+/// entirely (`drop_ungrouped=true`) — there's no sane way to lump every
+/// ungrouped entry into one shared "no group" bucket, since that would
+/// arbitrarily keep only one ungrouped entry out of possibly many unrelated
+/// ones.
+fn apply_group_by(
+    results: Vec<SearchResult>,
+    entries: &[MemoryEntry],
+    group_by: Option<&str>,
+    drop_ungrouped: bool,
+) -> Vec<SearchResult> {
+    let Some(group_by) = group_by else {
+        return results;
+    };
+    let entries_by_id: HashMap<&str, &MemoryEntry> =
+        entries.iter().map(|e| (e.id.as_str(), e)).collect();
 
-    /// Clear all entries
-    fn clear(&self) {
-        self.entries.write().clear();
-    }
+    let mut best: HashMap<String, SearchResult> = HashMap::new();
+    for result in results {
+        let group_value = entries_by_id
+            .get(result.id.as_str())
+            .and_then(|e| e.metadata.as_ref())
+            .and_then(|m| m.get(group_by))
+            .cloned();
 
-    /// Get all entry IDs
-    fn get_ids(&self) -> Vec<String> {
-        self.entries.read().keys().cloned().collect()
-    }
+        let key = match group_value {
+            Some(value) => value,
+            None if drop_ungrouped => continue,
+            // No metadata key: key by id instead of a shared sentinel, so
+            // each ungrouped entry survives as its own group rather than
+            // colliding with every other ungrouped entry.
+            None => format!("\u{0}ungrouped:{}", result.id),
+        };
 
-    /// Get entry by ID
-    fn get(&self, id: &str) -> Option<MemoryEntry> {
-        self.entries.read().get(id).cloned()
+        best.entry(key)
+            .and_modify(|kept| {
+                if result.score > kept.score {
+                    *kept = result.clone();
+                }
+            })
+            .or_insert(result);
     }
+    best.into_values().collect()
+}
 
-    /// Compute cosine similarity between two vectors
-    #[staticmethod]
-    fn compute_similarity(a: Vec<f32>, b: Vec<f32>) -> PyResult<f32> {
-        if a.len() != b.len() {
-            return Err(PyValueError::new_err("Vector dimensions must match"));
-        }
-        // Match the finite-value guarantee enforced by add()/search()/load() —
-        // an Inf/NaN here would otherwise leak a non-finite/misleading score
-        // (Inf norm -> denom=Inf -> dot/denom = 0.0 or NaN) back to Python.
-        if a.iter().chain(b.iter()).any(|v| !v.is_finite()) {
-            return Err(PyValueError::new_err(
-                "vectors contain non-finite values (NaN/Inf)",
-            ));
-        }
-        Ok(cosine_similarity(&a, &b))
-    }
+/// `search`'s `filter` gate — applied alongside `allowed_ids` while building
+/// the candidate snapshot, before any cosine computation, so a non-matching
+/// entry never reaches `score_entry`. `None` (no filter given) matches
+/// everything; an empty filter map also matches everything (vacuous `all`).
+/// An entry with no `metadata` at all only passes an empty filter, since
+/// there's nothing in it any non-empty key/value pair could match.
+fn matches_metadata_filter(entry: &MemoryEntry, filter: Option<&HashMap<String, String>>) -> bool {
+    let Some(filter) = filter else {
+        return true;
+    };
+    filter
+        .iter()
+        .all(|(k, v)| entry.metadata.as_ref().and_then(|m| m.get(k)) == Some(v))
+}
 
-    /// Save to JSON file (atomic write via temp file + rename)
-    fn save(&self, path: &str) -> PyResult<()> {
-        // Path traversal protection: reject ".." components, absolute paths,
-        // and Windows drive prefixes (Component::Prefix) — the previous check
-        // missed Prefix, so on Windows a relative path starting with a drive
-        // letter (e.g. "C:foo") could escape the project root.
-        let save_path = std::path::Path::new(path);
-        validate_relative_path(save_path)?;
-        // Defense-in-depth: refuse a symlinked intermediate directory (a
-        // lexically-clean relative path can still point outside the project
-        // root through a directory symlink), and refuse to write THROUGH an
-        // existing leaf symlink (File::create follows symlinks). load() has
-        // had the leaf check; save() previously had none, so even a final-
-        // component symlink was followed on write.
-        reject_symlinked_components(save_path)?;
-        if let Ok(meta) = std::fs::symlink_metadata(save_path) {
-            if meta.file_type().is_symlink() {
-                return Err(PyValueError::new_err(
-                    "Path traversal blocked: symlinked save path not allowed",
-                ));
+/// Plain-Rust core of `RagEngine::search`'s per-entry scoring, split out of
+/// the `#[pymethods]` block so it's unit-testable without a Python
+/// interpreter and so `search_with_query`'s Rayon closure stays a single
+/// readable call.
+///
+/// When `prune` is true, uses `cosine_similarity_with_floor` instead of the
+/// exact `cosine_similarity` to skip entries that provably can't clear
+/// `similarity_threshold`. The floor passed in is
+/// `similarity_threshold / entry.importance` rather than the bare
+/// threshold: `final_score = base_score * decay * importance` and `decay`
+/// is always `<= 1`, so `final_score <= base_score * importance`, meaning
+/// `base_score < threshold / importance` already proves `final_score <
+/// threshold` regardless of what `decay` turns out to be. An
+/// `importance <= 0.0` entry can only ever score `0.0` (or the query
+/// itself is degenerate), so it's resolved without touching the
+/// embeddings at all. This keeps pruning sound: it only ever discards
+/// entries that the exact computation would also filter out.
+///
+/// `dimension_mask`, if given, routes to `cosine_similarity_masked` instead
+/// and ignores `prune` — the early-exit floor bound is derived for the
+/// unmasked dot product and doesn't hold once components are being zeroed
+/// out, so a masked score always takes the exact path.
+#[allow(clippy::too_many_arguments)]
+fn score_entry(
+    query_embedding: &[f32],
+    query_norm: f32,
+    entry: &MemoryEntry,
+    time_decay_factor: f64,
+    current_time: f64,
+    similarity_threshold: f32,
+    prune: bool,
+    dimension_mask: Option<&[bool]>,
+) -> Option<SearchResult> {
+    let score = score_entry_value(
+        query_embedding,
+        query_norm,
+        entry,
+        time_decay_factor,
+        current_time,
+        similarity_threshold,
+        prune,
+        dimension_mask,
+    )?;
+    Some(SearchResult {
+        id: entry.id.clone(),
+        text: entry.text.clone(),
+        score,
+        timestamp: entry.timestamp,
+        metadata: entry.metadata.clone(),
+    })
+}
+
+/// Core of `score_entry`, minus the `SearchResult` clone-out — shared with
+/// `search_full`'s scoring pass, which needs the score attached to the whole
+/// `MemoryEntry` (importance and embedding included) rather than
+/// `SearchResult`'s id/text/score/timestamp subset. Returns `None` when the
+/// entry doesn't clear `similarity_threshold` (or, on the pruned path, when
+/// the early-exit floor already proves it can't).
+#[allow(clippy::too_many_arguments)]
+fn score_entry_value(
+    query_embedding: &[f32],
+    query_norm: f32,
+    entry: &MemoryEntry,
+    time_decay_factor: f64,
+    current_time: f64,
+    similarity_threshold: f32,
+    prune: bool,
+    dimension_mask: Option<&[bool]>,
+) -> Option<f32> {
+    let base_score = if let Some(mask) = dimension_mask {
+        cosine_similarity_masked(query_embedding, &entry.embedding, mask)
+    } else if prune {
+        if entry.importance <= 0.0 {
+            if similarity_threshold > 0.0 {
+                return None;
             }
+            0.0
+        } else {
+            let floor = similarity_threshold / entry.importance;
+            cosine_similarity_with_floor(query_embedding, &entry.embedding, query_norm, floor)?
         }
+    } else {
+        cosine_similarity(query_embedding, &entry.embedding)
+    };
 
-        let entries = self.entries.read();
-        let data: Vec<_> = entries
-            .values()
-            .map(|e| {
-                serde_json::json!({
-                    "id": e.id,
-                    "text": e.text,
-                    "embedding": e.embedding,
-                    "timestamp": e.timestamp,
-                    "importance": e.importance,
-                })
-            })
-            .collect();
+    // Apply time decay if factor > 0
+    let final_score = if time_decay_factor > 0.0 {
+        // Clamp age to >= 0 to prevent score inflation for future timestamps
+        // Clamp time_decay_factor to sane range to prevent overflow
+        let clamped_decay = time_decay_factor.clamp(0.0, 1.0);
+        let age_hours = ((current_time - entry.timestamp) / 3600.0).max(0.0);
+        let decay = (-clamped_decay * age_hours).exp() as f32;
+        base_score * decay * entry.importance
+    } else {
+        base_score * entry.importance
+    };
+    let score = if final_score.is_finite() { final_score } else { 0.0 };
 
-        let json = serde_json::to_string_pretty(&data)
-            .map_err(|e| PyValueError::new_err(e.to_string()))?;
+    if score >= similarity_threshold {
+        Some(score)
+    } else {
+        None
+    }
+}
 
-        // Atomic write: write to a *unique* temp file, then rename. The
-        // previous implementation always used `<path>.tmp`, so two save()
-        // calls racing on the same path would clobber each other's temp file
-        // mid-write, producing a corrupt JSON. We append the OS PID, the wall
-        // clock nanos, AND a process-wide atomic counter. The counter is the
-        // load-bearing part: SystemTime::now().as_nanos() does NOT advance on
-        // every read on Windows (coarse clock — consecutive reads can return
-        // identical nanos), so two same-process threads saving the same path
-        // within one clock tick would otherwise get an identical pid+nanos and
-        // thus the SAME temp name. fetch_add guarantees each save() in this
-        // process gets a distinct suffix regardless of clock resolution.
-        static TEMP_COUNTER: std::sync::atomic::AtomicU64 = std::sync::atomic::AtomicU64::new(0);
-        let pid = std::process::id();
-        let nanos = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .map(|d| d.as_nanos())
-            .unwrap_or(0);
-        let seq = TEMP_COUNTER.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
-        let temp_path = format!("{}.tmp.{}.{}.{}", path, pid, nanos, seq);
-
-        // Write + fsync the temp file before renaming. Without sync_all(),
-        // the bytes may live only in the OS page cache; a power loss after
-        // the rename leaves the live file truncated/empty because the
-        // rename was atomic on the directory entry but the data pages were
-        // never flushed to stable storage. Use the explicit File API so we
-        // can call sync_all() on the handle.
-        //
-        // Durability caveat: we fsync the file *data* (temp here, and the
-        // destination on the copy fallback below) but do NOT fsync the
-        // containing directory after the rename/create. On POSIX the
-        // rename's directory entry can therefore still be lost on power
-        // loss even though the data pages are durable, leaving either the
-        // old file or no file. This gap is acceptable here: the target
-        // platform is Windows (different ReplaceFile/rename durability
-        // semantics) and RAG dumps are cheaply regenerable.
-        {
-            use std::io::Write;
-            let mut f = std::fs::File::create(&temp_path)
-                .map_err(|e| PyValueError::new_err(format!("create temp: {}", e)))?;
-            // Clean up the partial temp on any write/fsync failure so repeated
-            // errors don't pile up orphaned `.tmp.*` files (unique suffix per
-            // call), matching the copy-fallback cleanup below. Drop the handle
-            // first so remove_file succeeds on Windows (open handle has no
-            // FILE_SHARE_DELETE).
-            let write_res = f
-                .write_all(json.as_bytes())
-                .map_err(|e| format!("write temp: {}", e))
-                .and_then(|()| f.sync_all().map_err(|e| format!("fsync temp: {}", e)));
-            drop(f);
-            if let Err(msg) = write_res {
-                let _ = std::fs::remove_file(&temp_path);
-                return Err(PyValueError::new_err(msg));
-            }
+/// Score used to pick eviction candidates when `max_entries` is set: recent,
+/// important entries score high and survive; old, unimportant ones score low
+/// and are evicted first. Mirrors the `importance * decay` shape `search()`
+/// already uses for ranking, but as a plain product (no threshold/query term
+/// applies here — there's no query to decay against).
+fn eviction_score(entry: &MemoryEntry, current_time: f64) -> f32 {
+    let age_hours = ((current_time - entry.timestamp) / 3600.0).max(0.0) as f32;
+    entry.importance / (1.0 + age_hours)
+}
+
+/// Resolve `search`/`search_buffer`'s `time_decay_factor` against the
+/// alternative `half_life_hours` convenience parameter, shared by both
+/// methods so the mutual-exclusion rule and the conversion formula only
+/// live in one place. `half_life_hours` converts to the equivalent
+/// `time_decay_factor` via `ln(2) / half_life_hours` — the rate at which
+/// `score_entry`'s `exp(-decay * age_hours)` term reaches exactly `0.5` at
+/// `age_hours == half_life_hours`, the same half-life shape `apply_decay`
+/// already applies as a persisted mutation.
+///
+/// A non-zero `time_decay_factor` together with `half_life_hours` is
+/// rejected — there's no sane way to combine two decay rates into one, and
+/// silently picking one would surprise whichever caller lost.
+fn resolve_time_decay_factor(
+    time_decay_factor: f64,
+    half_life_hours: Option<f64>,
+) -> PyResult<f64> {
+    match half_life_hours {
+        None => Ok(time_decay_factor),
+        Some(_) if time_decay_factor != 0.0 => Err(PyValueError::new_err(
+            "time_decay_factor and half_life_hours are mutually exclusive",
+        )),
+        Some(half_life_hours) if !half_life_hours.is_finite() || half_life_hours <= 0.0 => {
+            Err(PyValueError::new_err(
+                "half_life_hours must be a finite, positive number",
+            ))
         }
+        Some(half_life_hours) => Ok(std::f64::consts::LN_2 / half_life_hours),
+    }
+}
 
-        // rename may fail on Windows if file is locked; fall back to copy+delete.
-        if let Err(rename_err) = std::fs::rename(&temp_path, path) {
-            match std::fs::copy(&temp_path, path) {
-                Ok(_) => {
-                    // copy() does NOT fsync the destination. fsync the
-                    // destination file before deleting the temp so the new
-                    // bytes are durable; otherwise a crash here can leave
-                    // both copies present but the destination empty.
-                    {
-                        match std::fs::OpenOptions::new().write(true).open(path) {
-                            Ok(f) => {
-                                if let Err(e) = f.sync_all() {
-                                    // Clean up the temp before bailing, matching
-                                    // every other save() exit path; the temp name
-                                    // is unique per call, so leaving it here piles
-                                    // up orphaned `.tmp.*` files on repeat failures.
-                                    let _ = std::fs::remove_file(&temp_path);
-                                    return Err(PyValueError::new_err(format!(
-                                        "fsync after copy failed: {}",
-                                        e
-                                    )));
-                                }
-                            }
-                            Err(e) => {
-                                let _ = std::fs::remove_file(&temp_path);
-                                return Err(PyValueError::new_err(format!(
-                                    "open dest for fsync failed: {}",
-                                    e
-                                )));
-                            }
-                        }
-                    }
-                    let _ = std::fs::remove_file(&temp_path);
-                }
-                Err(copy_err) => {
-                    let _ = std::fs::remove_file(&temp_path);
-                    return Err(PyValueError::new_err(format!(
-                        "rename failed: {}, copy fallback failed: {}",
-                        rename_err, copy_err
-                    )));
+/// Truncate `text` in place to at most `max_len` **characters** (not bytes,
+/// so it's always a valid char-boundary cut), appending an ellipsis when
+/// truncation actually happens. Returns whether it did. If `max_len` is too
+/// small to fit the ellipsis itself, falls back to a bare hard cut with no
+/// ellipsis rather than producing something longer than `max_len`.
+fn truncate_text(text: &mut String, max_len: usize) -> bool {
+    if text.chars().count() <= max_len {
+        return false;
+    }
+    const ELLIPSIS: &str = "...";
+    let ellipsis_len = ELLIPSIS.chars().count();
+    *text = if max_len > ellipsis_len {
+        let mut kept: String = text.chars().take(max_len - ellipsis_len).collect();
+        kept.push_str(ELLIPSIS);
+        kept
+    } else {
+        text.chars().take(max_len).collect()
+    };
+    true
+}
+
+/// Plain-Rust core of `RagEngine::add_batch` — validates and inserts each
+/// entry (truncating `text` first when `max_text_len` is set), then evicts
+/// down to `max_entries` if set. Split out from the `#[pymethods]` wrapper so
+/// it's unit-testable without a Python interpreter. Returns
+/// `(added, evicted_ids, truncated_ids)`.
+fn add_batch_impl(
+    entries: &mut HashMap<String, MemoryEntry>,
+    dimension: usize,
+    max_entries: Option<usize>,
+    max_text_len: Option<usize>,
+    entries_list: Vec<MemoryEntry>,
+) -> (usize, Vec<String>, Vec<String>) {
+    let mut added: usize = 0;
+    let mut truncated_ids: Vec<String> = Vec::new();
+
+    for mut entry in entries_list {
+        if entry.embedding.len() == dimension
+            && entry.importance.is_finite()
+            && entry.importance >= 0.0
+            && entry.embedding.iter().all(|v| v.is_finite())
+            && entry.timestamp.is_finite()
+        {
+            if let Some(max_len) = max_text_len {
+                if truncate_text(&mut entry.text, max_len) {
+                    truncated_ids.push(entry.id.clone());
                 }
             }
+            // Count only newly inserted ids — HashMap::insert returns
+            // Some(old) when the id already existed (de-dupe replace), so a
+            // batch with duplicate ids must not over-report. Keeps the
+            // returned count == net growth in engine size (parity with load()).
+            if entries.insert(entry.id.clone(), entry).is_none() {
+                added += 1;
+            }
         }
+    }
 
-        Ok(())
+    let evicted = match max_entries {
+        Some(max_entries) => evict_to_capacity(entries, max_entries),
+        None => Vec::new(),
+    };
+    (added, evicted, truncated_ids)
+}
+
+/// Plain-Rust core of `RagEngine::add_matrix` — slices a flat `(n, dimension)`
+/// embedding buffer into `n` `MemoryEntry` values and delegates to
+/// `add_batch_impl` for validation/truncation/insertion/eviction. Split out
+/// for the same reason as `add_batch_impl`: unit-testable without a Python
+/// interpreter.
+#[allow(clippy::too_many_arguments)]
+fn add_matrix_impl(
+    entries: &mut HashMap<String, MemoryEntry>,
+    dimension: usize,
+    max_entries: Option<usize>,
+    max_text_len: Option<usize>,
+    ids: Vec<String>,
+    texts: Vec<String>,
+    embeddings_flat: Vec<f32>,
+    timestamps: Vec<f64>,
+    importances: Vec<f32>,
+) -> Result<(usize, Vec<String>, Vec<String>), String> {
+    let n = ids.len();
+    if texts.len() != n || timestamps.len() != n || importances.len() != n {
+        return Err(format!(
+            "ids, texts, timestamps and importances must all have the same length \
+             (got {} ids, {} texts, {} timestamps, {} importances)",
+            n,
+            texts.len(),
+            timestamps.len(),
+            importances.len()
+        ));
+    }
+    if embeddings_flat.len() != n * dimension {
+        return Err(format!(
+            "embeddings_flat length {} does not match {} rows x dimension {} (expected {})",
+            embeddings_flat.len(),
+            n,
+            dimension,
+            n * dimension
+        ));
     }
 
-    /// Load from JSON file (replaces all existing entries)
-    fn load(&self, path: &str) -> PyResult<usize> {
-        let load_path = std::path::Path::new(path);
-        validate_relative_path(load_path)?;
-        // Defense-in-depth: the leaf symlink_metadata check below only stats
-        // the final component, so a symlinked intermediate directory would let
-        // a lexically-clean relative path resolve outside the project root.
-        // Refuse any symlinked ancestor directory before the leaf check.
-        reject_symlinked_components(load_path)?;
-
-        // Size limit (256 MiB) to prevent OOM from malicious/corrupt files.
-        // RAG dumps are expected to be small (few MB); 256 MiB is a generous cap.
-        const MAX_LOAD_BYTES: u64 = 256 * 1024 * 1024;
-
-        // Use ``symlink_metadata`` rather than ``metadata`` so we can refuse
-        // to follow symlinks — combined with the path-component check above,
-        // a relative ``subdir/symlink_to_outside`` would otherwise pass the
-        // traversal check and resolve to anywhere on disk via stat.
-        let symlink_meta = std::fs::symlink_metadata(path)
-            .map_err(|e| PyValueError::new_err(format!("stat failed: {}", e)))?;
-        if symlink_meta.file_type().is_symlink() {
-            return Err(PyValueError::new_err(
-                "Path traversal blocked: symlinked load path not allowed",
-            ));
-        }
-        if symlink_meta.len() > MAX_LOAD_BYTES {
-            return Err(PyValueError::new_err(format!(
-                "File too large to load: {} bytes (max {})",
-                symlink_meta.len(),
-                MAX_LOAD_BYTES
-            )));
-        }
+    let entries_list: Vec<MemoryEntry> = (0..n)
+        .map(|i| MemoryEntry {
+            id: ids[i].clone(),
+            text: texts[i].clone(),
+            embedding: embeddings_flat[i * dimension..(i + 1) * dimension].to_vec(),
+            timestamp: timestamps[i],
+            importance: importances[i],
+            metadata: None,
+        })
+        .collect();
+    Ok(add_batch_impl(
+        entries,
+        dimension,
+        max_entries,
+        max_text_len,
+        entries_list,
+    ))
+}
 
-        // Read with an explicit byte cap rather than ``read_to_string``, so a
-        // file that grows between the size check above and this read can't
-        // silently exceed our cap (a TOCTOU window). Reading one extra byte
-        // beyond the cap lets us detect attempted overflow and reject it.
-        use std::io::Read;
-        let mut file = std::fs::File::open(path)
-            .map_err(|e| PyValueError::new_err(format!("open failed: {}", e)))?;
-        let mut buf =
-            Vec::with_capacity((symlink_meta.len() as usize).min(MAX_LOAD_BYTES as usize));
-        let read_cap = MAX_LOAD_BYTES.saturating_add(1);
-        file.by_ref()
-            .take(read_cap)
-            .read_to_end(&mut buf)
-            .map_err(|e| PyValueError::new_err(format!("read failed: {}", e)))?;
-        if buf.len() as u64 > MAX_LOAD_BYTES {
-            return Err(PyValueError::new_err(format!(
-                "File grew past size cap mid-read (max {} bytes)",
-                MAX_LOAD_BYTES
-            )));
+/// Plain-Rust core of `RagEngine::save`'s serialization step — builds the
+/// JSON array of entries, invoking `on_progress(processed, total)` every
+/// `progress_every` entries and once more at completion. Split out from the
+/// `#[pymethods]` wrapper so it's unit-testable without a Python interpreter;
+/// the wrapper's `on_progress` forwards into the optional Python callback.
+fn build_entries_json(
+    entries: &HashMap<String, MemoryEntry>,
+    progress_every: usize,
+    mut on_progress: impl FnMut(usize, usize),
+) -> Vec<serde_json::Value> {
+    let total = entries.len();
+    let progress_every = progress_every.max(1);
+    let mut data = Vec::with_capacity(total);
+    for (processed, e) in entries.values().enumerate() {
+        data.push(serde_json::json!({
+            "id": e.id,
+            "text": e.text,
+            "embedding": e.embedding,
+            "timestamp": e.timestamp,
+            "importance": e.importance,
+            "metadata": e.metadata,
+        }));
+        if (processed + 1) % progress_every == 0 {
+            on_progress(processed + 1, total);
         }
-        let data = String::from_utf8(buf)
-            .map_err(|e| PyValueError::new_err(format!("file is not UTF-8: {}", e)))?;
-
-        let entries_data: Vec<serde_json::Value> =
-            serde_json::from_str(&data).map_err(|e| PyValueError::new_err(e.to_string()))?;
+    }
+    on_progress(total, total);
+    data
+}
 
-        // Build new entries in a temporary map first to avoid data loss on bad files
-        let mut new_entries = HashMap::new();
+/// Plain-Rust core of `RagEngine::load`'s parsing step — validates and
+/// collects entries from the raw JSON values, invoking `on_progress(processed,
+/// total)` every `progress_every` items and once more at completion. Split
+/// out from the `#[pymethods]` wrapper for the same reason as
+/// [`build_entries_json`].
+fn parse_entries_json(
+    entries_data: &[serde_json::Value],
+    dimension: usize,
+    progress_every: usize,
+    mut on_progress: impl FnMut(usize, usize),
+) -> HashMap<String, MemoryEntry> {
+    let mut new_entries = HashMap::new();
+    let total = entries_data.len();
+    let progress_every = progress_every.max(1);
 
-        for item in &entries_data {
+    for (processed, item) in entries_data.iter().enumerate() {
+        'entry: {
             if let (Some(id), Some(text), Some(embedding), Some(timestamp), Some(importance)) = (
                 item["id"].as_str(),
                 item["text"].as_str(),
@@ -586,30 +788,39 @@ impl RagEngine {
                 // OVER-LENGTH array whose surplus elements were non-finite (e.g.
                 // [null, e0, e1, ..., e_{d-1}]) collapse to exactly `dimension` finite
                 // values and load positionally-shifted data instead of being rejected.
-                if embedding.len() != self.dimension {
-                    continue;
+                if embedding.len() != dimension {
+                    break 'entry;
                 }
                 let Some(emb) = embedding
                     .iter()
                     .map(|v| v.as_f64().map(|f| f as f32).filter(|val| val.is_finite()))
                     .collect::<Option<Vec<f32>>>()
                 else {
-                    continue; // non-numeric or non-finite element -> reject whole entry
+                    break 'entry; // non-numeric or non-finite element -> reject whole entry
                 };
 
                 let imp = importance as f32;
                 if !imp.is_finite() {
-                    continue; // Skip entries with NaN/Infinity importance
+                    break 'entry; // Skip entries with NaN/Infinity importance
                 }
                 if imp < 0.0 {
-                    continue; // Skip negative importance — a negative weight
-                              // flips final_score's sign in search() and can
-                              // rank an opposite-meaning memory above threshold.
+                    break 'entry; // Skip negative importance — a negative weight
+                                  // flips final_score's sign in search() and can
+                                  // rank an opposite-meaning memory above threshold.
                 }
                 if !timestamp.is_finite() {
-                    continue; // Skip entries with NaN/Infinity timestamp — keep
-                              // the stored-data invariant consistent with importance/embedding
+                    break 'entry; // Skip entries with NaN/Infinity timestamp — keep
+                                  // the stored-data invariant consistent with importance/embedding
                 }
+                // Missing/null/non-object "metadata" (including every file
+                // saved before this field existed) becomes None rather than
+                // rejecting the entry — unlike embedding/importance/timestamp,
+                // it doesn't feed scoring, only search()'s optional group_by.
+                let metadata = item.get("metadata").and_then(|v| v.as_object()).map(|obj| {
+                    obj.iter()
+                        .filter_map(|(k, v)| v.as_str().map(|s| (k.clone(), s.to_string())))
+                        .collect::<HashMap<String, String>>()
+                });
                 new_entries.insert(
                     id.to_string(),
                     MemoryEntry {
@@ -618,141 +829,3577 @@ impl RagEngine {
                         embedding: emb,
                         timestamp,
                         importance: imp,
+                        metadata,
                     },
                 );
             }
         }
-
-        // Only replace existing data if we loaded at least some entries,
-        // or if the source file was intentionally empty
-        if new_entries.is_empty() && !entries_data.is_empty() {
-            return Err(PyValueError::new_err(
-                "No entries matched the expected dimension; refusing to replace existing data",
-            ));
+        if (processed + 1) % progress_every == 0 {
+            on_progress(processed + 1, total);
         }
+    }
+    on_progress(total, total);
+    new_entries
+}
 
-        // Swap in the new entries atomically. Report the ACTUAL stored count —
-        // HashMap de-dupes by id, so a file with duplicate ids stores fewer than
-        // the iteration count; len() keeps the reported count == engine size.
-        let count = new_entries.len();
-        let mut entries = self.entries.write();
-        *entries = new_entries;
+/// The sidecar path an `open_mmap` engine at `vector_path` reads/appends
+/// id/text/timestamp/importance/metadata to — `VectorStorage` only knows
+/// about raw f32 vectors, so everything else rides alongside it here.
+fn mmap_sidecar_path(vector_path: &std::path::Path) -> std::path::PathBuf {
+    let mut os = vector_path.as_os_str().to_owned();
+    os.push(".meta.jsonl");
+    std::path::PathBuf::from(os)
+}
 
-        Ok(count)
+/// Rebuild `open_mmap`'s in-memory `entries` from its append-only sidecar
+/// file, pairing each row with the embedding at its recorded `VectorStorage`
+/// index. A missing sidecar file (fresh `open_mmap`) yields an empty map,
+/// same as `RagEngine::new`.
+///
+/// Last line for a given id wins on collision, same rule `load_append`
+/// already uses for merging shards — a later line always describes the
+/// current state of that id (e.g. a future `update_importance`/`update_text`
+/// appending a fresh row rather than rewriting the file in place).
+///
+/// A row whose `index` is `>= storage.len()` is dropped rather than trusted:
+/// that combination only arises if the process crashed between appending
+/// the sidecar line and `VectorStorage::flush` durably committing the
+/// matching push, so the vector at that index was never actually
+/// persisted — the sidecar outran the mmap's authoritative `count`.
+fn load_mmap_sidecar(
+    sidecar_path: &std::path::Path,
+    storage: &VectorStorage,
+) -> std::io::Result<HashMap<String, MemoryEntry>> {
+    let mut entries = HashMap::new();
+    let Ok(contents) = std::fs::read_to_string(sidecar_path) else {
+        return Ok(entries);
+    };
+    for line in contents.lines() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let Ok(value) = serde_json::from_str::<serde_json::Value>(line) else {
+            continue; // truncated last line from a crash mid-append — skip it
+        };
+        let (Some(id), Some(text), Some(index), Some(timestamp), Some(importance)) = (
+            value["id"].as_str(),
+            value["text"].as_str(),
+            value["index"].as_u64(),
+            value["timestamp"].as_f64(),
+            value["importance"].as_f64(),
+        ) else {
+            continue;
+        };
+        let Some(embedding) = storage.get(index as usize) else {
+            continue;
+        };
+        let metadata = value
+            .get("metadata")
+            .and_then(|v| v.as_object())
+            .map(|obj| {
+                obj.iter()
+                    .filter_map(|(k, v)| v.as_str().map(|s| (k.clone(), s.to_string())))
+                    .collect::<HashMap<String, String>>()
+            });
+        entries.insert(
+            id.to_string(),
+            MemoryEntry {
+                id: id.to_string(),
+                text: text.to_string(),
+                embedding: embedding.to_vec(),
+                timestamp,
+                importance: importance as f32,
+                metadata,
+            },
+        );
     }
+    Ok(entries)
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use std::path::Path;
-    use std::sync::Mutex;
+/// Append one row to an `open_mmap` engine's sidecar file and fsync it —
+/// the metadata half of `add`'s durability story, mirrored after `save`'s
+/// own fsync-before-considering-it-durable convention. Appending (rather
+/// than rewriting the whole file, which is what plain `save` does for its
+/// single JSON document) keeps a single `add` call's cost independent of
+/// how many entries already exist, matching `VectorStorage::push`'s own
+/// append-only shape.
+fn append_mmap_sidecar_line(
+    sidecar_path: &std::path::Path,
+    entry: &MemoryEntry,
+    index: usize,
+) -> std::io::Result<()> {
+    use std::io::Write;
+    let line = serde_json::json!({
+        "id": entry.id,
+        "text": entry.text,
+        "index": index,
+        "timestamp": entry.timestamp,
+        "importance": entry.importance,
+        "metadata": entry.metadata,
+    });
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(sidecar_path)?;
+    writeln!(file, "{line}")?;
+    file.sync_all()
+}
+
+/// Plain-Rust core of `RagEngine::list_entries` — sorts a snapshot by
+/// `timestamp` or `importance` (descending, no similarity computation) and
+/// paginates with `limit`/`offset`. `SearchResult.score` carries the sort
+/// key's value so callers can see what they sorted on. Kept as a free
+/// function for the same reason as [`rank_and_truncate`]: no `Python<'_>`
+/// token needed, so it's directly unit-testable.
+fn list_entries_impl(
+    entries: &HashMap<String, MemoryEntry>,
+    sort_by: &str,
+    limit: usize,
+    offset: usize,
+) -> PyResult<Vec<SearchResult>> {
+    let mut results: Vec<SearchResult> = match sort_by {
+        "timestamp" => entries
+            .values()
+            .map(|e| SearchResult {
+                id: e.id.clone(),
+                text: e.text.clone(),
+                score: e.timestamp as f32,
+                timestamp: e.timestamp,
+                metadata: e.metadata.clone(),
+            })
+            .collect(),
+        "importance" => entries
+            .values()
+            .map(|e| SearchResult {
+                id: e.id.clone(),
+                text: e.text.clone(),
+                score: e.importance,
+                timestamp: e.timestamp,
+                metadata: e.metadata.clone(),
+            })
+            .collect(),
+        other => {
+            return Err(PyValueError::new_err(format!(
+                "sort_by must be \"timestamp\" or \"importance\", got {other:?}"
+            )))
+        }
+    };
+
+    // Descending by score, with the same tie-break as rank_and_truncate so
+    // pagination is stable across repeated calls with unchanged data.
+    results.sort_by(|a, b| {
+        b.score
+            .partial_cmp(&a.score)
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then_with(|| a.id.cmp(&b.id))
+    });
+
+    Ok(results.into_iter().skip(offset).take(limit).collect())
+}
+
+/// Plain-Rust core of `RagEngine::iter_entries` — sorts a snapshot by `id`
+/// and paginates with `offset`/`limit`, for a caller streaming through a
+/// large store (re-embedding, export) one page at a time rather than
+/// materializing every id (`get_ids`) or the whole store at once. Sorting
+/// by `id` rather than `list_entries`'s score-based order means pagination
+/// is stable without needing a tie-break: ids are already unique. Kept as
+/// a free function for the same reason as [`list_entries_impl`]: no
+/// `Python<'_>` token needed, so it's directly unit-testable.
+fn iter_entries_impl(
+    entries: &HashMap<String, MemoryEntry>,
+    offset: usize,
+    limit: usize,
+) -> Vec<MemoryEntry> {
+    let mut ids: Vec<&String> = entries.keys().collect();
+    ids.sort();
+    ids.into_iter()
+        .skip(offset)
+        .take(limit)
+        .filter_map(|id| entries.get(id).cloned())
+        .collect()
+}
+
+/// Plain-Rust core of `RagEngine::scores_for` — raw cosine score per `id`, in
+/// `ids` order, `f32::NAN` for an id with no matching entry. Kept as a free
+/// function for the same reason as [`list_entries_impl`]: no `Python<'_>`
+/// token needed, so it's directly unit-testable.
+fn scores_for_impl(
+    entries: &HashMap<String, MemoryEntry>,
+    query_embedding: &[f32],
+    ids: &[String],
+) -> Vec<f32> {
+    ids.iter()
+        .map(|id| match entries.get(id) {
+            Some(entry) => cosine_similarity(query_embedding, &entry.embedding),
+            None => f32::NAN,
+        })
+        .collect()
+}
+
+/// Runs `f` and, if it panics, converts the panic into a catchable
+/// `PyRuntimeError` instead of letting it propagate to PyO3's own
+/// panic-to-exception wrapper. That default wrapper (`PanicException`) is
+/// deliberately a `BaseException` subclass, the same as `SystemExit` — it's
+/// meant to crash the interpreter rather than be swallowed by an
+/// `except Exception:` — which is right for a genuine bug inside this
+/// crate but wrong for the closures passed here, where a future custom
+/// `rerank_fn`/scoring hook run through Rayon could panic on bad
+/// caller-supplied logic. Used to wrap the `py.detach` scoring blocks in
+/// `search`, `find_duplicates`, and `scores_for` so a panic there surfaces
+/// as an ordinary, catchable Python exception instead.
+fn catch_panic_as_runtime_error<T>(
+    f: impl FnOnce() -> T + std::panic::UnwindSafe,
+) -> PyResult<T> {
+    std::panic::catch_unwind(f).map_err(|payload| {
+        let message = payload
+            .downcast_ref::<&str>()
+            .map(|s| s.to_string())
+            .or_else(|| payload.downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "panic in scoring closure".to_string());
+        PyRuntimeError::new_err(format!("internal error: {message}"))
+    })
+}
+
+/// Plain-Rust core of `RagEngine::find_duplicates` — takes an already-cloned
+/// snapshot so it's unit-testable without a Python interpreter or the entries
+/// lock. Blocked brute-force scan: each Rayon task takes one entry's whole
+/// row of comparisons against every entry after it in `snapshot`, which is
+/// cheaper than parallelizing one pair at a time. Returns pairs scoring at
+/// least `threshold`, sorted by descending score and truncated to `max_pairs`.
+fn find_duplicates_impl(
+    snapshot: &[MemoryEntry],
+    threshold: f32,
+    max_pairs: usize,
+) -> Vec<(String, String, f32)> {
+    use rayon::prelude::*;
+
+    let n = snapshot.len();
+    let mut pairs: Vec<(String, String, f32)> = (0..n)
+        .into_par_iter()
+        .flat_map(|i| {
+            let a = &snapshot[i];
+            let mut row = Vec::new();
+            for b in &snapshot[(i + 1)..] {
+                let score = cosine_similarity(&a.embedding, &b.embedding);
+                if score.is_finite() && score >= threshold {
+                    row.push((a.id.clone(), b.id.clone(), score));
+                }
+            }
+            row
+        })
+        .collect();
+
+    pairs.sort_by(|a, b| b.2.partial_cmp(&a.2).unwrap_or(std::cmp::Ordering::Equal));
+    pairs.truncate(max_pairs);
+    pairs
+}
+
+/// Evict lowest-`eviction_score` entries until `entries.len() <= max_entries`.
+/// Returns the evicted ids in eviction order. No-op if already at/under capacity.
+fn evict_to_capacity(entries: &mut HashMap<String, MemoryEntry>, max_entries: usize) -> Vec<String> {
+    let mut evicted = Vec::new();
+    if entries.len() <= max_entries {
+        return evicted;
+    }
+    let current_time = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs_f64())
+        .unwrap_or(0.0);
+
+    while entries.len() > max_entries {
+        let worst_id = entries
+            .iter()
+            .min_by(|(_, a), (_, b)| {
+                eviction_score(a, current_time)
+                    .partial_cmp(&eviction_score(b, current_time))
+                    .unwrap_or(std::cmp::Ordering::Equal)
+            })
+            .map(|(id, _)| id.clone());
+        match worst_id {
+            Some(id) => {
+                entries.remove(&id);
+                evicted.push(id);
+            }
+            None => break,
+        }
+    }
+    evicted
+}
+
+/// Main RAG Engine class
+#[pyclass]
+pub struct RagEngine {
+    /// Lock-free-read snapshot of the store: readers (`search`,
+    /// `find_duplicates`, `get`, `len`, ...) call `.load()`/`.load_full()`
+    /// and never block on a writer, no matter how long a write takes. A
+    /// writer clone-modifies-swaps: read the current snapshot, clone the
+    /// whole map, mutate the clone, `.store()` the new `Arc`. Writers still
+    /// need to serialize against EACH OTHER (a naive clone-modify-swap loses
+    /// updates if two writers race), which is what `write_lock` is for.
+    entries: ArcSwap<HashMap<String, MemoryEntry>>,
+    /// Serializes writers (`add`/`add_batch`/`add_matrix`/`remove`/
+    /// `apply_decay`/`clear`/`load`) against each other so their
+    /// load-clone-mutate-store sequence can't race and drop a concurrent
+    /// insert. Readers never take this lock — that's the whole point of the
+    /// `ArcSwap` split from the old `RwLock<HashMap<_>>`, which starved
+    /// readers behind a writer holding the lock for the full clone+mutate.
+    ///
+    /// `parking_lot::Mutex` doesn't poison on panic (unlike
+    /// `std::sync::Mutex`), so a panic while a writer holds this lock can't
+    /// leave it permanently unlockable for the next writer. That alone isn't
+    /// what keeps `entries` consistent, though: every writer's mutation
+    /// happens on a local clone, and `entries.store(...)` — the single call
+    /// that publishes it — is always the writer's last statement. A panic
+    /// anywhere before that point just drops the clone and unlocks the
+    /// mutex; the previously published snapshot is untouched either way.
+    write_lock: Mutex<()>,
+    dimension: usize,
+    /// Bit-cast `f32` behind an atomic instead of a plain field so
+    /// `set_similarity_threshold` can retune it from an admin command while
+    /// `search` reads it concurrently — no lock needed since it's a single,
+    /// independent word (contrast `entries`, where multiple fields must
+    /// change together and so go through `write_lock` + `ArcSwap` instead).
+    similarity_threshold: std::sync::atomic::AtomicU32,
+    /// Bounded working-memory cap. When `Some`, `add`/`add_batch` evict the
+    /// lowest-`eviction_score` entries after insertion so the engine never
+    /// exceeds this many entries. `None` (default) keeps the old unbounded
+    /// behavior.
+    max_entries: Option<usize>,
+    /// Per-entry text length cap, in characters. When `Some`, `add`/
+    /// `add_batch`/`add_matrix` truncate `MemoryEntry.text` to this many
+    /// characters (on a char boundary, with an ellipsis) before storing it,
+    /// so pasted logs or other oversized text can't bloat RAM or the JSON
+    /// save. `None` (default) stores text as given. The embedding is never
+    /// affected, since it's supplied separately from the text.
+    max_text_len: Option<usize>,
+    /// Present only for an engine opened via `open_mmap`: durable,
+    /// memory-mapped backing for embeddings. `add` pushes into this and
+    /// calls `VectorStorage::flush` before returning, so a crash right
+    /// after `add()` returns can never leave the on-disk `count` pointing
+    /// past a partially written vector (see `add`'s doc comment). `entries`
+    /// still holds every embedding too — this pass wires up durable,
+    /// fast-to-reopen persistence, not the RAM reduction a fully
+    /// index-based `MemoryEntry` would need; see `open_mmap`'s doc comment.
+    /// `None` for a plain `new()`-constructed engine.
+    mmap_storage: Option<Mutex<VectorStorage>>,
+    /// The sidecar file `open_mmap` appends id/text/timestamp/importance/
+    /// metadata to — everything a raw `VectorStorage` f32 slab doesn't carry
+    /// — one JSON object per line. `Some` iff `mmap_storage` is `Some`.
+    mmap_sidecar_path: Option<std::path::PathBuf>,
+}
+
+#[pymethods]
+impl RagEngine {
+    #[new]
+    #[pyo3(signature = (dimension=384, similarity_threshold=0.7, max_entries=None, max_text_len=None))]
+    fn new(
+        dimension: usize,
+        similarity_threshold: f32,
+        max_entries: Option<usize>,
+        max_text_len: Option<usize>,
+    ) -> PyResult<Self> {
+        validate_similarity_threshold(similarity_threshold)?;
+        Ok(Self {
+            entries: ArcSwap::from_pointee(HashMap::new()),
+            write_lock: Mutex::new(()),
+            dimension,
+            similarity_threshold: std::sync::atomic::AtomicU32::new(
+                similarity_threshold.to_bits(),
+            ),
+            max_entries,
+            max_text_len,
+            mmap_storage: None,
+            mmap_sidecar_path: None,
+        })
+    }
+
+    /// Open (or create) a `VectorStorage`-backed engine: embeddings are
+    /// memory-mapped at `path` instead of living only in the JSON `save()`
+    /// format, and text/timestamp/importance/metadata go to a `path`-derived
+    /// sidecar file (`{path}.meta.jsonl`, append-only, last line per id
+    /// wins — same collision rule as `load_append`). If `path` already
+    /// exists it's reopened via `VectorStorage::open` (its stored dimension
+    /// must match `dimension`); otherwise a fresh file is created with room
+    /// for `capacity` vectors, which `add` cannot grow past.
+    ///
+    /// `capacity` must cover every `add()` call expected over the file's
+    /// lifetime, not just the number of distinct ids: `VectorStorage` is
+    /// append-only with no compaction or slot reuse, so re-`add()`-ing an id
+    /// that already exists (an "update") still consumes a fresh slot —
+    /// see `add`'s doc comment for why that's safe but not free.
+    ///
+    /// `add` on an engine opened this way pushes the embedding into the
+    /// mmap and calls `VectorStorage::flush` (msync) before returning, so a
+    /// crash immediately after `add()` returns can never leave the on-disk
+    /// header `count` pointing past a partially written vector — `flush`
+    /// only returns once the header and vector bytes are durably on disk
+    /// together. A crash *during* `add()`, before `flush` completes, may
+    /// leave the vector unflushed (the pre-existing state is still intact
+    /// either way, since `push` writes past `count`, never over it); the
+    /// sidecar line for that entry is written before the flush, so on
+    /// reopen a sidecar row referencing an index the mmap's `count` hasn't
+    /// caught up to is dropped rather than trusted with a partially
+    /// committed vector (see the reload loop in `load_mmap_sidecar`).
+    ///
+    /// This wires up durable, JSON-parse-free persistence and restart
+    /// speed for the vector data — it does NOT yet make `entries` itself
+    /// index-based, so RAM usage is unchanged from a plain `new()` engine
+    /// (every embedding still lives in the `entries` `HashMap` for
+    /// `search`'s existing scoring path). `capabilities()`'s `mmap_backed`
+    /// key reports `true` for an engine opened this way.
+    #[staticmethod]
+    #[pyo3(signature = (path, dimension, capacity, similarity_threshold=0.7, max_entries=None, max_text_len=None))]
+    fn open_mmap(
+        path: &str,
+        dimension: usize,
+        capacity: usize,
+        similarity_threshold: f32,
+        max_entries: Option<usize>,
+        max_text_len: Option<usize>,
+    ) -> PyResult<Self> {
+        validate_similarity_threshold(similarity_threshold)?;
+        let vector_path = std::path::Path::new(path);
+        validate_relative_path(vector_path)?;
+        reject_symlinked_components(vector_path)?;
+
+        let storage = if vector_path.exists() {
+            VectorStorage::open(vector_path)
+        } else {
+            VectorStorage::create(vector_path, dimension, capacity)
+        }
+        .map_err(rag_error_to_pyerr)?;
+
+        if storage.dimension() != dimension {
+            return Err(PyValueError::new_err(format!(
+                "open_mmap: existing file at {path} has dimension {}, requested {}",
+                storage.dimension(),
+                dimension
+            )));
+        }
+
+        let sidecar_path = mmap_sidecar_path(vector_path);
+        let entries = load_mmap_sidecar(&sidecar_path, &storage)
+            .map_err(|e| PyRuntimeError::new_err(format!("open_mmap: reading sidecar: {e}")))?;
+
+        Ok(Self {
+            entries: ArcSwap::from_pointee(entries),
+            write_lock: Mutex::new(()),
+            dimension,
+            similarity_threshold: std::sync::atomic::AtomicU32::new(
+                similarity_threshold.to_bits(),
+            ),
+            max_entries,
+            max_text_len,
+            mmap_storage: Some(Mutex::new(storage)),
+            mmap_sidecar_path: Some(sidecar_path),
+        })
+    }
+
+    /// Retune the minimum cosine-similarity score `search` keeps, without
+    /// discarding or rebuilding a populated engine. Takes effect for every
+    /// `search` call that starts after this returns; any already in-flight
+    /// `search` (mid-`py.detach`, GIL released) keeps using whatever value it
+    /// already copied out — see the field's doc comment for why this is a
+    /// plain atomic rather than a lock. Rejects non-finite values, or values
+    /// outside cosine similarity's valid `[-1, 1]` range, so a caller's typo
+    /// can't put the engine into an unusable state where every search
+    /// compares scores against a threshold that can never (or always) be met.
+    fn set_similarity_threshold(&self, similarity_threshold: f32) -> PyResult<()> {
+        validate_similarity_threshold(similarity_threshold)?;
+        self.similarity_threshold
+            .store(similarity_threshold.to_bits(), std::sync::atomic::Ordering::Relaxed);
+        Ok(())
+    }
+
+    /// Add a memory entry. Returns whether `text` was truncated to fit
+    /// `max_text_len` (always `false` when the engine has no text length cap).
+    ///
+    /// For a plain `new()`-constructed engine that's the whole return value,
+    /// same as before. For an engine opened via `open_mmap`, the return
+    /// shape grows the same way `add_batch`'s already does as options are
+    /// enabled: `(truncated, index)`, where `index` is the position
+    /// `VectorStorage::push` assigned the embedding — stable and positional
+    /// (the Nth push always lands at N, and that holds across a process
+    /// restart against the same backing file), so a caller building a
+    /// secondary index on top of it can treat it the same way a database
+    /// row id: assign once, keep forever. In `open_mmap` mode this also
+    /// calls `VectorStorage::flush` before returning, so a successful
+    /// `add()` return means the vector is durably on disk, not just visible
+    /// in this process's mapping.
+    ///
+    /// In `open_mmap` mode, re-`add()`-ing an id that's already present is
+    /// still a fresh `VectorStorage::push`, not an in-place update: the old
+    /// slot's embedding becomes unreachable (`entries` and the sidecar's
+    /// "last line wins" rule both move on to the new index) but is never
+    /// reclaimed, since `VectorStorage` has no compaction or slot-reuse
+    /// mechanism. The result is correct — searches only ever see the latest
+    /// embedding for an id — but each such "update" permanently spends one
+    /// unit of the file's `capacity` on the orphaned slot. Callers doing
+    /// frequent upserts (re-embedding after an edit, touching importance via
+    /// a fresh `add` rather than `update_importance`) should size `capacity`
+    /// for total lifetime `add()` calls, not just the distinct-id count.
+    fn add(&self, py: Python<'_>, entry: MemoryEntry) -> PyResult<Py<PyAny>> {
+        let (truncated, index) = self.add_impl(entry)?;
+        match index {
+            Some(index) => Ok((truncated, index).into_pyobject(py)?.into_any().unbind()),
+            None => Ok(truncated.into_pyobject(py)?.to_owned().into_any().unbind()),
+        }
+    }
+
+    /// Add multiple entries in batch.
+    ///
+    /// Silent-skip contract: unlike single-entry `add()` (which raises
+    /// PyValueError on a bad entry), this method silently drops any entry that
+    /// fails dimension / finite-importance / finite-embedding validation and
+    /// returns only the count actually inserted. The returned count can
+    /// therefore be less than `entries_list.len()` for a malformed batch.
+    ///
+    /// The return shape grows a field at a time as bounded-mode options are
+    /// enabled, so unconfigured engines keep the old plain-`int` shape:
+    /// `added` alone, or `(added, evicted_ids)` when `max_entries` is set, or
+    /// `(added, truncated_ids)` when `max_text_len` is set instead, or
+    /// `(added, evicted_ids, truncated_ids)` when both are set.
+    fn add_batch(&self, py: Python<'_>, entries_list: Vec<MemoryEntry>) -> PyResult<Py<PyAny>> {
+        let _write_guard = self.write_lock.lock();
+        let mut entries = (*self.entries.load_full()).clone();
+        let (added, evicted, truncated) = add_batch_impl(
+            &mut entries,
+            self.dimension,
+            self.max_entries,
+            self.max_text_len,
+            entries_list,
+        );
+        self.entries.store(Arc::new(entries));
+
+        match (self.max_entries.is_some(), self.max_text_len.is_some()) {
+            (false, false) => Ok(added.into_pyobject(py)?.into_any().unbind()),
+            (true, false) => Ok((added, evicted).into_pyobject(py)?.into_any().unbind()),
+            (false, true) => Ok((added, truncated).into_pyobject(py)?.into_any().unbind()),
+            (true, true) => Ok((added, evicted, truncated).into_pyobject(py)?.into_any().unbind()),
+        }
+    }
+
+    /// Add multiple entries directly from a contiguous embedding matrix,
+    /// avoiding per-entry `MemoryEntry` construction across the Python/Rust
+    /// boundary. `embeddings_flat` is a flattened `(len(ids), dimension)`
+    /// row-major buffer (e.g. `ndarray.flatten().tolist()`); `ids`, `texts`,
+    /// `timestamps` and `importances` must all have the same length, one per
+    /// row. Shares `add_batch`'s silent-skip contract and return shape
+    /// (growing from plain `int` to include `evicted_ids` and/or
+    /// `truncated_ids` depending on `max_entries`/`max_text_len`) —
+    /// malformed individual rows are dropped, but a length mismatch across
+    /// the whole batch is a hard `PyValueError` since it usually means the
+    /// caller reshaped or sliced something incorrectly upstream.
+    #[pyo3(signature = (ids, texts, embeddings_flat, timestamps, importances))]
+    #[allow(clippy::too_many_arguments)]
+    fn add_matrix(
+        &self,
+        py: Python<'_>,
+        ids: Vec<String>,
+        texts: Vec<String>,
+        embeddings_flat: Vec<f32>,
+        timestamps: Vec<f64>,
+        importances: Vec<f32>,
+    ) -> PyResult<Py<PyAny>> {
+        let _write_guard = self.write_lock.lock();
+        let mut entries = (*self.entries.load_full()).clone();
+        let (added, evicted, truncated) = add_matrix_impl(
+            &mut entries,
+            self.dimension,
+            self.max_entries,
+            self.max_text_len,
+            ids,
+            texts,
+            embeddings_flat,
+            timestamps,
+            importances,
+        )
+        .map_err(PyValueError::new_err)?;
+        self.entries.store(Arc::new(entries));
+
+        match (self.max_entries.is_some(), self.max_text_len.is_some()) {
+            (false, false) => Ok(added.into_pyobject(py)?.into_any().unbind()),
+            (true, false) => Ok((added, evicted).into_pyobject(py)?.into_any().unbind()),
+            (false, true) => Ok((added, truncated).into_pyobject(py)?.into_any().unbind()),
+            (true, true) => Ok((added, evicted, truncated).into_pyobject(py)?.into_any().unbind()),
+        }
+    }
+
+    /// Remove an entry by ID
+    fn remove(&self, id: &str) -> bool {
+        let _write_guard = self.write_lock.lock();
+        let mut entries = (*self.entries.load_full()).clone();
+        let removed = entries.remove(id).is_some();
+        self.entries.store(Arc::new(entries));
+        removed
+    }
+
+    /// Recompute an entry's `importance` in place without resupplying (or
+    /// re-cloning across the Python/Rust boundary) its embedding — the
+    /// `get()`-reconstruct-`add()` dance this replaces round-trips the whole
+    /// embedding for no reason when only the weight changed. Returns whether
+    /// `id` existed; a no-op, not an error, for an unknown id, matching
+    /// `remove`'s existence-reporting convention.
+    ///
+    /// Goes through the same clone-modify-store sequence as every other
+    /// writer (see `entries`' doc comment): a concurrent `search`/`get`
+    /// always sees either the pre- or the post-update snapshot of the whole
+    /// map, never a half-written `MemoryEntry` — there's no in-place field
+    /// mutation visible to a reader still holding the old `Arc`.
+    fn update_importance(&self, id: &str, importance: f32) -> PyResult<bool> {
+        if !importance.is_finite() {
+            return Err(PyValueError::new_err("importance must be a finite number"));
+        }
+        // Same non-negative invariant add() enforces — see add's comment on
+        // why a negative weight can flip a search() score's sign.
+        if importance < 0.0 {
+            return Err(PyValueError::new_err("importance must be non-negative"));
+        }
+        let _write_guard = self.write_lock.lock();
+        let mut entries = (*self.entries.load_full()).clone();
+        let Some(entry) = entries.get_mut(id) else {
+            return Ok(false);
+        };
+        entry.importance = importance;
+        self.entries.store(Arc::new(entries));
+        Ok(true)
+    }
+
+    /// Recompute an entry's `text` in place without resupplying its
+    /// embedding, same rationale as `update_importance`. Subject to the same
+    /// `max_text_len` truncation `add`/`add_batch`/`add_matrix` already
+    /// apply when the engine has one configured — silently, matching
+    /// `add_batch`'s truncate-without-a-second-return-value convention for
+    /// this method's single-bool return shape. Returns whether `id` existed.
+    fn update_text(&self, id: &str, mut text: String) -> PyResult<bool> {
+        let _write_guard = self.write_lock.lock();
+        let mut entries = (*self.entries.load_full()).clone();
+        let Some(entry) = entries.get_mut(id) else {
+            return Ok(false);
+        };
+        if let Some(max_len) = self.max_text_len {
+            truncate_text(&mut text, max_len);
+        }
+        entry.text = text;
+        self.entries.store(Arc::new(entries));
+        Ok(true)
+    }
+
+    /// Age every entry's `importance` in place by an exponential half-life
+    /// decay based on its timestamp, under the write lock. Bakes the same
+    /// half-life shape `search()`'s `time_decay_factor` already applies
+    /// per-query into a persisted mutation, and the 10% floor on the decay
+    /// factor matches `MemoryMetadata.calculate_importance` on the Python
+    /// side (rag.py) so a memory's importance never fully vanishes. Returns
+    /// how many entries actually changed.
+    #[pyo3(signature = (half_life_hours))]
+    fn apply_decay(&self, half_life_hours: f64) -> PyResult<usize> {
+        if !half_life_hours.is_finite() || half_life_hours <= 0.0 {
+            return Err(PyValueError::new_err(
+                "half_life_hours must be a finite, positive number",
+            ));
+        }
+        let current_time = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs_f64())
+            .unwrap_or(0.0);
+
+        let _write_guard = self.write_lock.lock();
+        let mut entries = (*self.entries.load_full()).clone();
+        let mut changed = 0;
+        for entry in entries.values_mut() {
+            let age_hours = ((current_time - entry.timestamp) / 3600.0).max(0.0);
+            let decay = (0.5_f64.powf(age_hours / half_life_hours)).max(0.1) as f32;
+            let new_importance = entry.importance * decay;
+            if new_importance != entry.importance {
+                entry.importance = new_importance;
+                changed += 1;
+            }
+        }
+        self.entries.store(Arc::new(entries));
+        Ok(changed)
+    }
+
+    /// Scan the whole store for near-duplicate pairs, for periodic dedup
+    /// maintenance (a caller pruning redundant memories on a schedule,
+    /// rather than relative to any one query). There is no ANN index in
+    /// this crate to narrow the candidate set (see the module-level note),
+    /// so this is a blocked brute-force scan: entries are snapshotted once
+    /// (a lock-free `entries` load), then each Rayon task takes one entry's whole
+    /// row of comparisons against every entry after it, which keeps
+    /// per-task overhead low compared to parallelizing one pair at a time.
+    /// Pairs scoring at least `threshold` are sorted by descending score
+    /// and truncated to `max_pairs`, so a mostly-duplicate store can't
+    /// return an unbounded result. Releases the GIL for the whole scan,
+    /// matching `search`'s pattern — this is O(n^2) and meant to be run
+    /// occasionally, not on the hot query path.
+    #[pyo3(signature = (threshold, max_pairs=1000))]
+    fn find_duplicates(
+        &self,
+        py: Python<'_>,
+        threshold: f32,
+        max_pairs: usize,
+    ) -> PyResult<Vec<(String, String, f32)>> {
+        if !threshold.is_finite() {
+            return Err(PyValueError::new_err("threshold must be a finite number"));
+        }
+
+        let entries_snapshot_arc = self.entries.load_full();
+        let pairs = py.detach(move || {
+            catch_panic_as_runtime_error(move || {
+                let entries_snapshot: Vec<MemoryEntry> =
+                    entries_snapshot_arc.values().cloned().collect();
+                find_duplicates_impl(&entries_snapshot, threshold, max_pairs)
+            })
+        })?;
+
+        Ok(pairs)
+    }
+
+    /// Search for similar entries (parallel SIMD-optimized).
+    ///
+    /// `top_k=0` means "no truncation — return every result above
+    /// `similarity_threshold`, sorted", for callers (export/analysis flows)
+    /// that want the full matching set rather than an arbitrary top-N; the
+    /// result is naturally bounded by the store size regardless.
+    ///
+    /// When `return_timing` is true, returns `(results, SearchTiming)` instead
+    /// of a bare list, attaching elapsed microseconds for the snapshot-clone
+    /// phase and the compute phase — useful for capacity planning (cloning
+    /// dominating argues for mmap-backed storage; compute dominating argues
+    /// for ANN). Opt-in and zero-cost when left false: the default return
+    /// shape is unchanged for existing callers.
+    ///
+    /// `prune=true` enables an early-exit optimization
+    /// (`cosine_similarity_with_floor`) that can skip the full cosine
+    /// computation for entries that provably can't clear
+    /// `similarity_threshold`, at the cost of a small amount of extra work
+    /// per entry that ISN'T pruned. Off by default so the exact full scan
+    /// (identical results, just without the bound checks) stays available
+    /// for verifying `prune=true` doesn't change the result set.
+    ///
+    /// `allowed_ids`, if given, restricts the scan to entries whose `id` is
+    /// in the set — applied before scoring, not as a post-hoc filter on the
+    /// ranked results, so a caller (e.g. enforcing a multi-tenant permission
+    /// check) has a hard guarantee that an excluded entry can never surface
+    /// no matter how similar it is. `None` (default) scans every entry, same
+    /// as before this parameter existed.
+    ///
+    /// `rerank_fn`, if given, replaces the fixed `base_score * decay *
+    /// importance` formula with a Python callable of your own:
+    /// `rerank_fn(base_score, importance, age_hours) -> final_score`, called
+    /// once per candidate after the base cosine similarity and before
+    /// sorting/`top_k` truncation. `time_decay_factor` is ignored in this
+    /// mode — recompute your own decay from `age_hours` inside `rerank_fn`.
+    /// `prune` is also ignored, since the early-exit floor only proves
+    /// anything about the built-in formula. Because calling back into Python
+    /// per entry needs the GIL, `rerank_fn` disables the `allow_threads`/
+    /// Rayon fast path entirely and scores candidates one at a time on the
+    /// calling thread — fine for experimentation, not for the high-QPS path
+    /// (use the default formula, or `search_buffer`, there instead).
+    ///
+    /// `dimension_mask`, if given, must be a `bool` list of exactly
+    /// `dimension` elements; components where it's `false` are excluded from
+    /// the cosine computation entirely (in both the query and every entry),
+    /// as if that subspace didn't exist — useful when some dimensions encode
+    /// metadata (e.g. a language tag) rather than semantic content you want
+    /// similarity to consider. Combining it with `prune` skips the early-exit
+    /// floor for the masked score, since that bound is derived for the
+    /// unmasked dot product.
+    ///
+    /// `half_life_hours`, if given, is an alternative to `time_decay_factor`
+    /// for callers who'd rather think in "memories lose half their weight
+    /// after N hours" than an opaque exponential coefficient: it's converted
+    /// to the equivalent factor internally (`ln(2) / half_life_hours`), the
+    /// same half-life shape `apply_decay` already bakes in as a persisted
+    /// mutation. Mutually exclusive with a non-zero `time_decay_factor` —
+    /// passing both raises `ValueError`, since there'd be no sane way to
+    /// combine two decay rates into one.
+    ///
+    /// `group_by`, if given, is a `MemoryEntry.metadata` key: after scoring
+    /// but before `top_k` truncation, only the highest-scoring result per
+    /// distinct value of that key is kept (e.g. `group_by="conversation_id"`
+    /// collapses five near-duplicate hits from the same thread down to
+    /// their single best one), so `top_k` diversifies across groups instead
+    /// of one group crowding it out. `drop_ungrouped` decides what happens
+    /// to an entry with no metadata or missing that key: kept as its own
+    /// singleton group (`false`, the default) or dropped entirely (`true`).
+    /// Both are no-ops when `group_by` is `None`.
+    ///
+    /// `filter`, if given, is a set of `MemoryEntry.metadata` key/value pairs
+    /// an entry must match *all* of to be scored at all — applied at the same
+    /// point as `allowed_ids` (before cosine computation, under
+    /// `allow_threads`), not as a post-hoc filter on the ranked results, so a
+    /// non-matching entry never costs a similarity computation. An entry with
+    /// no `metadata`, or missing one of the filtered keys, doesn't match
+    /// unless `filter` is empty. Each returned `SearchResult` carries the
+    /// matched entry's full `metadata` so a caller doesn't need a second
+    /// `get()` to see what it matched on.
+    #[pyo3(signature = (query_embedding, top_k=5, time_decay_factor=0.0, return_timing=false, prune=false, allowed_ids=None, dimension_mask=None, half_life_hours=None, rerank_fn=None, group_by=None, drop_ungrouped=false, filter=None))]
+    #[allow(clippy::too_many_arguments)]
+    fn search(
+        &self,
+        py: Python<'_>,
+        query_embedding: Vec<f32>,
+        top_k: usize,
+        time_decay_factor: f64,
+        return_timing: bool,
+        prune: bool,
+        allowed_ids: Option<HashSet<String>>,
+        dimension_mask: Option<Vec<bool>>,
+        half_life_hours: Option<f64>,
+        rerank_fn: Option<Py<PyAny>>,
+        group_by: Option<String>,
+        drop_ungrouped: bool,
+        filter: Option<HashMap<String, String>>,
+    ) -> PyResult<Py<PyAny>> {
+        if let Some(mask) = &dimension_mask {
+            if mask.len() != self.dimension {
+                return Err(PyValueError::new_err(format!(
+                    "dimension_mask length mismatch: expected {}, got {}",
+                    self.dimension,
+                    mask.len()
+                )));
+            }
+        }
+        let time_decay_factor = resolve_time_decay_factor(time_decay_factor, half_life_hours)?;
+        self.search_with_query(
+            py,
+            &query_embedding,
+            top_k,
+            time_decay_factor,
+            return_timing,
+            prune,
+            allowed_ids.as_ref(),
+            dimension_mask.as_deref(),
+            rerank_fn.as_ref(),
+            group_by.as_deref(),
+            drop_ungrouped,
+            filter.as_ref(),
+        )
+    }
+
+    /// `search`, but pairs each result with its full `MemoryEntry`
+    /// (importance and embedding included) instead of `SearchResult`'s
+    /// id/text/score/timestamp subset — for a reranker that needs the whole
+    /// entry per hit and would otherwise pay a `get`/`get_many` round trip
+    /// after every search. Scores it under the exact same snapshot `search`
+    /// would take, so there's no second lock acquisition to fetch the full
+    /// entries afterward.
+    ///
+    /// Excludes `return_timing` and `rerank_fn`: this is a convenience
+    /// wrapper around the default `base_score * decay * importance` formula,
+    /// not a replacement for `search`'s full parameter set. `top_k`, `prune`,
+    /// `allowed_ids`, `dimension_mask`, and `half_life_hours` behave exactly
+    /// as they do on `search`.
+    #[pyo3(signature = (query_embedding, top_k=5, time_decay_factor=0.0, prune=false, allowed_ids=None, dimension_mask=None, half_life_hours=None))]
+    #[allow(clippy::too_many_arguments)]
+    fn search_full(
+        &self,
+        py: Python<'_>,
+        query_embedding: Vec<f32>,
+        top_k: usize,
+        time_decay_factor: f64,
+        prune: bool,
+        allowed_ids: Option<HashSet<String>>,
+        dimension_mask: Option<Vec<bool>>,
+        half_life_hours: Option<f64>,
+    ) -> PyResult<Vec<(MemoryEntry, f32)>> {
+        if query_embedding.len() != self.dimension {
+            return Err(PyValueError::new_err(format!(
+                "Query dimension mismatch: expected {}, got {}",
+                self.dimension,
+                query_embedding.len()
+            )));
+        }
+        if query_embedding.iter().any(|v| !v.is_finite()) {
+            return Err(PyValueError::new_err(
+                "query_embedding contains non-finite values (NaN/Inf)",
+            ));
+        }
+        if let Some(mask) = &dimension_mask {
+            if mask.len() != self.dimension {
+                return Err(PyValueError::new_err(format!(
+                    "dimension_mask length mismatch: expected {}, got {}",
+                    self.dimension,
+                    mask.len()
+                )));
+            }
+        }
+        let time_decay_factor = resolve_time_decay_factor(time_decay_factor, half_life_hours)?;
+        let similarity_threshold = f32::from_bits(
+            self.similarity_threshold
+                .load(std::sync::atomic::Ordering::Relaxed),
+        );
+
+        let entries_snapshot_arc = self.entries.load_full();
+        let mut results = py.detach(move || {
+            catch_panic_as_runtime_error(move || {
+                use rayon::prelude::*;
+
+                let entries_snapshot: Vec<MemoryEntry> = entries_snapshot_arc
+                    .values()
+                    .filter(|entry| allowed_ids.as_ref().is_none_or(|ids| ids.contains(&entry.id)))
+                    .cloned()
+                    .collect();
+
+                let current_time = std::time::SystemTime::now()
+                    .duration_since(std::time::UNIX_EPOCH)
+                    .map(|d| d.as_secs_f64())
+                    .unwrap_or(0.0);
+                let query_norm = vector_norm(&query_embedding);
+
+                entries_snapshot
+                    .into_par_iter()
+                    .filter_map(|entry| {
+                        let score = score_entry_value(
+                            &query_embedding,
+                            query_norm,
+                            &entry,
+                            time_decay_factor,
+                            current_time,
+                            similarity_threshold,
+                            prune,
+                            dimension_mask.as_deref(),
+                        )?;
+                        Some((entry, score))
+                    })
+                    .collect::<Vec<_>>()
+            })
+        })?;
+
+        rank_and_truncate_full(&mut results, top_k);
+        Ok(results)
+    }
+
+    /// MMR (maximal marginal relevance) re-ranking of `search`'s ordinary
+    /// top-k, so near-duplicate memories that all say the same thing don't
+    /// crowd out everything else. Builds a candidate pool of `3 * top_k`
+    /// entries ranked by `search`'s default relevance formula
+    /// (`base_score * decay * importance`, `time_decay_factor=0.0` here, so
+    /// just `base_score * importance`; `similarity_threshold` applied to this
+    /// pool exactly like a plain `search`), then greedily selects `top_k` of
+    /// them via `mmr_select` — see its doc comment for the selection formula.
+    /// `lambda_mult=1.0` degenerates to plain top-k by relevance;
+    /// `lambda_mult=0.0` ignores relevance and only maximizes diversity from
+    /// what's already picked.
+    ///
+    /// Like `search_full`, this is a focused convenience wrapper rather than
+    /// a `search` replacement: no `time_decay_factor`, `prune`,
+    /// `allowed_ids`, `dimension_mask`, `rerank_fn`, `group_by`, or `filter`.
+    #[pyo3(signature = (query_embedding, top_k=5, lambda_mult=0.5))]
+    fn search_mmr(
+        &self,
+        py: Python<'_>,
+        query_embedding: Vec<f32>,
+        top_k: usize,
+        lambda_mult: f32,
+    ) -> PyResult<Vec<SearchResult>> {
+        if query_embedding.len() != self.dimension {
+            return Err(PyValueError::new_err(format!(
+                "Query dimension mismatch: expected {}, got {}",
+                self.dimension,
+                query_embedding.len()
+            )));
+        }
+        if query_embedding.iter().any(|v| !v.is_finite()) {
+            return Err(PyValueError::new_err(
+                "query_embedding contains non-finite values (NaN/Inf)",
+            ));
+        }
+        if !(0.0..=1.0).contains(&lambda_mult) {
+            return Err(PyValueError::new_err(
+                "lambda_mult must be in the range [0.0, 1.0]",
+            ));
+        }
+        if top_k == 0 {
+            return Ok(Vec::new());
+        }
+
+        let similarity_threshold = f32::from_bits(
+            self.similarity_threshold
+                .load(std::sync::atomic::Ordering::Relaxed),
+        );
+
+        let entries_snapshot_arc = self.entries.load_full();
+        let candidates = py.detach(move || {
+            catch_panic_as_runtime_error(move || {
+                use rayon::prelude::*;
+
+                let entries_snapshot: Vec<MemoryEntry> =
+                    entries_snapshot_arc.values().cloned().collect();
+                let current_time = std::time::SystemTime::now()
+                    .duration_since(std::time::UNIX_EPOCH)
+                    .map(|d| d.as_secs_f64())
+                    .unwrap_or(0.0);
+                let query_norm = vector_norm(&query_embedding);
+
+                let mut candidates: Vec<(MemoryEntry, f32)> = entries_snapshot
+                    .into_par_iter()
+                    .filter_map(|entry| {
+                        let score = score_entry_value(
+                            &query_embedding,
+                            query_norm,
+                            &entry,
+                            0.0,
+                            current_time,
+                            similarity_threshold,
+                            false,
+                            None,
+                        )?;
+                        Some((entry, score))
+                    })
+                    .collect();
+                rank_and_truncate_full(&mut candidates, top_k.saturating_mul(3));
+                candidates
+            })
+        })?;
+
+        Ok(mmr_select(candidates, top_k, lambda_mult))
+    }
+
+    /// Run `queries.len()` independent searches against one shared snapshot
+    /// of `entries`, in parallel with Rayon — for a caller re-embedding a
+    /// whole conversation and issuing dozens of `search` calls back-to-back,
+    /// each of which would otherwise re-clone the entire entry map under its
+    /// own snapshot. `entries` is cloned exactly once here regardless of how
+    /// many queries are given, then every query scores against the same
+    /// `Vec<MemoryEntry>` — meaningfully cheaper than N separate `search`
+    /// calls when the store is large.
+    ///
+    /// Every query's dimension is validated up front, before any scoring
+    /// starts, and a mismatch is reported with the offending index
+    /// (`queries[i]`) so a caller re-embedding a batch can tell which input
+    /// was bad without a linear scan of its own.
+    ///
+    /// Like `search_full`/`search_mmr`, a focused convenience wrapper around
+    /// the default `base_score * importance` formula (`time_decay_factor=0`)
+    /// rather than a replacement for `search`'s full parameter set —
+    /// `similarity_threshold` still applies to every query the same way it
+    /// does on a plain `search`.
+    #[pyo3(signature = (queries, top_k=5))]
+    fn search_batch(
+        &self,
+        py: Python<'_>,
+        queries: Vec<Vec<f32>>,
+        top_k: usize,
+    ) -> PyResult<Vec<Vec<SearchResult>>> {
+        for (index, query) in queries.iter().enumerate() {
+            if query.len() != self.dimension {
+                return Err(PyValueError::new_err(format!(
+                    "queries[{index}] dimension mismatch: expected {}, got {}",
+                    self.dimension,
+                    query.len()
+                )));
+            }
+            if query.iter().any(|v| !v.is_finite()) {
+                return Err(PyValueError::new_err(format!(
+                    "queries[{index}] contains non-finite values (NaN/Inf)"
+                )));
+            }
+        }
+
+        let similarity_threshold = f32::from_bits(
+            self.similarity_threshold
+                .load(std::sync::atomic::Ordering::Relaxed),
+        );
+
+        let entries_snapshot_arc = self.entries.load_full();
+        py.detach(move || {
+            catch_panic_as_runtime_error(move || {
+                use rayon::prelude::*;
+
+                let entries_snapshot: Vec<MemoryEntry> =
+                    entries_snapshot_arc.values().cloned().collect();
+                let current_time = std::time::SystemTime::now()
+                    .duration_since(std::time::UNIX_EPOCH)
+                    .map(|d| d.as_secs_f64())
+                    .unwrap_or(0.0);
+
+                queries
+                    .par_iter()
+                    .map(|query_embedding| {
+                        let query_norm = vector_norm(query_embedding);
+                        let mut results: Vec<SearchResult> = entries_snapshot
+                            .iter()
+                            .filter_map(|entry| {
+                                score_entry(
+                                    query_embedding,
+                                    query_norm,
+                                    entry,
+                                    0.0,
+                                    current_time,
+                                    similarity_threshold,
+                                    false,
+                                    None,
+                                )
+                            })
+                            .collect();
+                        rank_and_truncate(&mut results, top_k);
+                        results
+                    })
+                    .collect()
+            })
+        })
+    }
+
+    /// Search variant for callers holding the query in a buffer-protocol
+    /// object (a NumPy `float32` array, `array.array('f')`, etc.) rather than
+    /// a Python list. `search`'s `Vec<f32>` parameter is populated through
+    /// pyo3's generic sequence extraction, which visits and converts one
+    /// Python float object at a time; going through `PyBuffer` instead reads
+    /// the query directly out of the array's underlying memory, avoiding that
+    /// per-element conversion on the hottest path in high-QPS callers.
+    /// Requires a C-contiguous `float32` buffer of exactly `dimension`
+    /// elements — anything else (wrong dtype, a transposed/strided view, a
+    /// mismatched length) is a `PyValueError` rather than a silent copy or
+    /// reinterpret. No `rerank_fn` here — this variant exists for the
+    /// high-QPS path, and a per-entry GIL callback would defeat the point;
+    /// use `search` if you need custom reranking. `top_k`, `dimension_mask`,
+    /// and `half_life_hours` behave exactly as they do on `search`.
+    #[pyo3(signature = (query_embedding, top_k=5, time_decay_factor=0.0, return_timing=false, prune=false, allowed_ids=None, dimension_mask=None, half_life_hours=None))]
+    #[allow(clippy::too_many_arguments)]
+    fn search_buffer(
+        &self,
+        py: Python<'_>,
+        query_embedding: &Bound<'_, PyAny>,
+        top_k: usize,
+        time_decay_factor: f64,
+        return_timing: bool,
+        prune: bool,
+        allowed_ids: Option<HashSet<String>>,
+        dimension_mask: Option<Vec<bool>>,
+        half_life_hours: Option<f64>,
+    ) -> PyResult<Py<PyAny>> {
+        if let Some(mask) = &dimension_mask {
+            if mask.len() != self.dimension {
+                return Err(PyValueError::new_err(format!(
+                    "dimension_mask length mismatch: expected {}, got {}",
+                    self.dimension,
+                    mask.len()
+                )));
+            }
+        }
+        let time_decay_factor = resolve_time_decay_factor(time_decay_factor, half_life_hours)?;
+        let buffer = pyo3::buffer::PyBuffer::<f32>::get(query_embedding)?;
+        if !buffer.is_c_contiguous() {
+            return Err(PyValueError::new_err(
+                "query_embedding buffer must be C-contiguous (got a strided/Fortran view)",
+            ));
+        }
+        if buffer.item_count() != self.dimension {
+            return Err(PyValueError::new_err(format!(
+                "Query dimension mismatch: expected {}, got {}",
+                self.dimension,
+                buffer.item_count()
+            )));
+        }
+        // `to_vec` reads straight out of the buffer's memory (one bulk copy),
+        // unlike `Vec<f32>`'s sequence-protocol extraction which would visit
+        // and convert one Python float object per element.
+        let query_embedding = buffer.to_vec(py).map_err(|e| {
+            PyValueError::new_err(format!("Failed to read query_embedding buffer: {e}"))
+        })?;
+        self.search_with_query(
+            py,
+            &query_embedding,
+            top_k,
+            time_decay_factor,
+            return_timing,
+            prune,
+            allowed_ids.as_ref(),
+            dimension_mask.as_deref(),
+            None,
+            None,
+            false,
+            None,
+        )
+    }
+
+    /// Stream results one at a time via `callback` as they clear
+    /// `similarity_threshold`, instead of collecting and sorting the whole
+    /// result set before returning anything — for an interactive
+    /// "searching…" UI over a large store where perceived latency matters
+    /// more than raw throughput. Uses the engine's configured
+    /// `similarity_threshold` (see `set_similarity_threshold`), the same as
+    /// `search` — there is no per-call threshold override, since none of
+    /// this engine's other search methods have one either.
+    ///
+    /// Entries are scored in `chunk_size`-sized batches: each batch is
+    /// scored in parallel with the GIL released (the same `rayon`/
+    /// `py.detach` fast path `search` uses), then `callback` is invoked once
+    /// per qualifying result in that batch with the GIL held before the next
+    /// batch starts. Calling back into Python needs the GIL, so unlike
+    /// `search`'s "release once for the whole scan" approach, the GIL is
+    /// reacquired roughly `len() / chunk_size` times instead of once — more
+    /// hand-off overhead, but each `callback` call can render or log its
+    /// result immediately rather than waiting for the entire store to be
+    /// scored first. A smaller `chunk_size` delivers results sooner at the
+    /// cost of more hand-offs; a larger one amortizes hand-off cost at the
+    /// cost of latency until the first callback.
+    ///
+    /// Results are **not** sorted or `top_k`-truncated — ordering is
+    /// whatever each batch happens to preserve (batches are delivered in
+    /// scan order, entries within a batch in their snapshot order). Rank
+    /// them yourself if you need `search`'s descending-by-score order.
+    /// Returns the total number of results delivered to `callback`.
+    #[pyo3(signature = (query_embedding, callback, time_decay_factor=0.0, prune=false, allowed_ids=None, dimension_mask=None, half_life_hours=None, chunk_size=256))]
+    #[allow(clippy::too_many_arguments)]
+    fn search_streaming(
+        &self,
+        py: Python<'_>,
+        query_embedding: Vec<f32>,
+        callback: Py<PyAny>,
+        time_decay_factor: f64,
+        prune: bool,
+        allowed_ids: Option<HashSet<String>>,
+        dimension_mask: Option<Vec<bool>>,
+        half_life_hours: Option<f64>,
+        chunk_size: usize,
+    ) -> PyResult<usize> {
+        if query_embedding.len() != self.dimension {
+            return Err(PyValueError::new_err(format!(
+                "Query dimension mismatch: expected {}, got {}",
+                self.dimension,
+                query_embedding.len()
+            )));
+        }
+        if query_embedding.iter().any(|v| !v.is_finite()) {
+            return Err(PyValueError::new_err(
+                "query_embedding contains non-finite values (NaN/Inf)",
+            ));
+        }
+        if let Some(mask) = &dimension_mask {
+            if mask.len() != self.dimension {
+                return Err(PyValueError::new_err(format!(
+                    "dimension_mask length mismatch: expected {}, got {}",
+                    self.dimension,
+                    mask.len()
+                )));
+            }
+        }
+        if chunk_size == 0 {
+            return Err(PyValueError::new_err("chunk_size must be greater than 0"));
+        }
+        let time_decay_factor = resolve_time_decay_factor(time_decay_factor, half_life_hours)?;
+        let similarity_threshold = f32::from_bits(
+            self.similarity_threshold
+                .load(std::sync::atomic::Ordering::Relaxed),
+        );
+
+        let entries_snapshot: Vec<MemoryEntry> = self
+            .entries
+            .load()
+            .values()
+            .filter(|entry| allowed_ids.as_ref().is_none_or(|ids| ids.contains(&entry.id)))
+            .cloned()
+            .collect();
+        let query_norm = vector_norm(&query_embedding);
+        let current_time = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs_f64())
+            .unwrap_or(0.0);
+        let dimension_mask = dimension_mask.as_deref();
+
+        let mut delivered = 0usize;
+        for chunk in entries_snapshot.chunks(chunk_size) {
+            let scored: Vec<SearchResult> = py.detach(|| {
+                catch_panic_as_runtime_error(|| {
+                    use rayon::prelude::*;
+                    chunk
+                        .par_iter()
+                        .filter_map(|entry| {
+                            score_entry(
+                                &query_embedding,
+                                query_norm,
+                                entry,
+                                time_decay_factor,
+                                current_time,
+                                similarity_threshold,
+                                prune,
+                                dimension_mask,
+                            )
+                        })
+                        .collect()
+                })
+            })?;
+            for result in scored {
+                callback.call1(py, (result,))?;
+                delivered += 1;
+            }
+        }
+        Ok(delivered)
+    }
+
+    /// Get entry count
+    fn len(&self) -> usize {
+        self.entries.load().len()
+    }
+
+    /// Check if empty
+    fn is_empty(&self) -> bool {
+        self.entries.load().is_empty()
+    }
+
+    /// Clear all entries
+    fn clear(&self) {
+        let _write_guard = self.write_lock.lock();
+        self.entries.store(Arc::new(HashMap::new()));
+    }
+
+    /// Get all entry IDs
+    fn get_ids(&self) -> Vec<String> {
+        self.entries.load().keys().cloned().collect()
+    }
+
+    /// Get entry by ID
+    fn get(&self, id: &str) -> Option<MemoryEntry> {
+        self.entries.load().get(id).cloned()
+    }
+
+    /// Bulk similarity primitive for a caller doing its own reranking: raw
+    /// cosine scores for a caller-supplied, fixed candidate order, skipping
+    /// both the `SearchResult` allocation (id/text clones, per-entry decay
+    /// and importance weighting) and `search`'s sort/top_k truncation that a
+    /// custom reranking pipeline would just throw away anyway.
+    ///
+    /// An `id` with no matching entry gets `f32::NAN` in its slot rather than
+    /// shrinking the output (which would break the "same order as `ids`"
+    /// contract) or growing the return type to `Option<f32>` — a real score
+    /// is always finite (`add`/`search` already enforce that on stored
+    /// embeddings and the query), so `NAN` unambiguously means "missing" and
+    /// a caller can filter with `is_nan()`.
+    fn scores_for(
+        &self,
+        py: Python<'_>,
+        query_embedding: Vec<f32>,
+        ids: Vec<String>,
+    ) -> PyResult<Vec<f32>> {
+        if query_embedding.len() != self.dimension {
+            return Err(PyValueError::new_err(format!(
+                "Query dimension mismatch: expected {}, got {}",
+                self.dimension,
+                query_embedding.len()
+            )));
+        }
+        if query_embedding.iter().any(|v| !v.is_finite()) {
+            return Err(PyValueError::new_err(
+                "query_embedding contains non-finite values (NaN/Inf)",
+            ));
+        }
+        let entries = self.entries.load();
+        py.detach(move || {
+            catch_panic_as_runtime_error(move || scores_for_impl(&entries, &query_embedding, &ids))
+        })
+    }
+
+    /// List entries sorted by `"timestamp"` or `"importance"` (descending),
+    /// no query vector or similarity computation involved. `score` on each
+    /// result carries the sort key's value. Paginate with `limit`/`offset`.
+    fn list_entries(&self, sort_by: &str, limit: usize, offset: usize) -> PyResult<Vec<SearchResult>> {
+        list_entries_impl(&self.entries.load(), sort_by, limit, offset)
+    }
+
+    /// Page through every entry (full `MemoryEntry`, embedding included) in
+    /// stable `id`-sorted order — for streaming a large store (re-embedding,
+    /// export) without `get_ids()`'s whole-store id allocation or
+    /// `list_entries`'s whole-store clone. Each call still touches every key
+    /// to sort (one `entries.load()` snapshot, no write lock involved, same
+    /// as `get_ids`/`list_entries`), so every page costs O(n log n) and a
+    /// caller paging through the whole store repeats that sort per page —
+    /// this saves memory over materializing everything at once, not CPU.
+    fn iter_entries(&self, offset: usize, limit: usize) -> Vec<MemoryEntry> {
+        iter_entries_impl(&self.entries.load(), offset, limit)
+    }
+
+    /// Startup diagnostic: report whether the SIMD cosine path is active on
+    /// this CPU (see `cosine::simd_active`) plus the configured dimension,
+    /// similarity metric, and threshold, so a caller can log e.g. "RAG
+    /// running with SIMD: true" instead of silently landing on the scalar
+    /// fallback in production. `mmap_backed` is `true` for an engine opened
+    /// via `open_mmap`, `false` for a plain `new()` engine — either way
+    /// `entries` still holds every embedding in RAM for `search`, so this
+    /// reflects durable-persistence mode, not a RAM-usage difference.
+    fn capabilities<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        let dict = PyDict::new(py);
+        dict.set_item("simd", simd_active())?;
+        dict.set_item("dimension", self.dimension)?;
+        dict.set_item("metric", "cosine")?;
+        dict.set_item(
+            "similarity_threshold",
+            f32::from_bits(
+                self.similarity_threshold
+                    .load(std::sync::atomic::Ordering::Relaxed),
+            ),
+        )?;
+        dict.set_item("mmap_backed", self.mmap_storage.is_some())?;
+        dict.set_item("max_entries", self.max_entries)?;
+        dict.set_item("max_text_len", self.max_text_len)?;
+        Ok(dict)
+    }
+
+    /// Compute cosine similarity between two vectors
+    #[staticmethod]
+    fn compute_similarity(a: Vec<f32>, b: Vec<f32>) -> PyResult<f32> {
+        if a.len() != b.len() {
+            return Err(PyValueError::new_err("Vector dimensions must match"));
+        }
+        // Match the finite-value guarantee enforced by add()/search()/load() —
+        // an Inf/NaN here would otherwise leak a non-finite/misleading score
+        // (Inf norm -> denom=Inf -> dot/denom = 0.0 or NaN) back to Python.
+        if a.iter().chain(b.iter()).any(|v| !v.is_finite()) {
+            return Err(PyValueError::new_err(
+                "vectors contain non-finite values (NaN/Inf)",
+            ));
+        }
+        Ok(cosine_similarity(&a, &b))
+    }
+
+    /// Save to JSON file (atomic write via temp file + rename).
+    ///
+    /// `progress_callback`, if given, is invoked as `callback(processed,
+    /// total)` every `progress_every` entries while serializing (default
+    /// 1000), so a caller can drive a progress bar on a large store. The
+    /// callback runs with the GIL held — it is called directly, not through
+    /// `allow_threads` — so it must be quick; heavy work inside it will stall
+    /// this call just like any other Python code holding the GIL.
+    ///
+    /// `fsync`, default true, flushes and fsyncs the written file (and, on
+    /// POSIX, the parent directory's rename entry) before returning —
+    /// belt-and-suspenders durability against a crash/power loss right
+    /// after `save()` returns. Set false to skip both fsyncs when the
+    /// caller can tolerate losing a save on unclean shutdown in exchange
+    /// for lower write latency.
+    ///
+    /// `compress`, default false, gzips the JSON before writing it to
+    /// `{path}.gz` instead of `path` — for a store whose size is dominated
+    /// by verbose float-array text, this typically cuts disk usage by ~70%.
+    /// Off by default so plain JSON stays the interop-friendly default;
+    /// `load` auto-detects gzip by content (its magic bytes), regardless of
+    /// filename, so loading a compressed save back needs no extra argument.
+    #[pyo3(signature = (path, progress_callback=None, progress_every=1000, fsync=true, compress=false))]
+    fn save(
+        &self,
+        py: Python<'_>,
+        path: &str,
+        progress_callback: Option<Py<PyAny>>,
+        progress_every: usize,
+        fsync: bool,
+        compress: bool,
+    ) -> PyResult<()> {
+        let mut callback_err = None;
+        let result = self.save_impl(path, progress_every, fsync, compress, |processed, total| {
+            if callback_err.is_some() {
+                return;
+            }
+            if let Some(callback) = &progress_callback {
+                if let Err(e) = callback.call1(py, (processed, total)) {
+                    callback_err = Some(e);
+                }
+            }
+        });
+        if let Some(e) = callback_err {
+            return Err(e);
+        }
+        result
+    }
+
+    /// Load from JSON file (replaces all existing entries).
+    ///
+    /// `progress_callback`, if given, is invoked as `callback(processed,
+    /// total)` every `progress_every` entries while parsing (default 1000).
+    /// Same GIL caveat as `save`'s callback: it runs synchronously with the
+    /// GIL held, so keep it cheap.
+    ///
+    /// Transparently decompresses a gzip-compressed file (as produced by
+    /// `save(compress=True)`) — detected by its magic bytes, not by a
+    /// `.gz` filename, so passing either `path` works regardless of what
+    /// it's named.
+    #[pyo3(signature = (path, progress_callback=None, progress_every=1000))]
+    fn load(
+        &self,
+        py: Python<'_>,
+        path: &str,
+        progress_callback: Option<Py<PyAny>>,
+        progress_every: usize,
+    ) -> PyResult<usize> {
+        let mut callback_err = None;
+        let result = self.load_impl(path, progress_every, |processed, total| {
+            if callback_err.is_some() {
+                return;
+            }
+            if let Some(callback) = &progress_callback {
+                if let Err(e) = callback.call1(py, (processed, total)) {
+                    callback_err = Some(e);
+                }
+            }
+        });
+        if let Some(e) = callback_err {
+            return Err(e);
+        }
+        result
+    }
+
+    /// Load from JSON file and MERGE into the existing store instead of
+    /// replacing it — last-write-wins on id collisions between the file and
+    /// the current store. For callers who keep sharded save files (e.g. one
+    /// per day) and want to assemble one engine cumulatively across several
+    /// `load_append` calls, which `load`'s full-replace forces them to work
+    /// around in Python.
+    ///
+    /// Returns how many entries from THIS file were merged in — same
+    /// "actual stored count" semantics as `load`'s return value (a file
+    /// with duplicate ids inside itself stores fewer than its raw entry
+    /// count). Dimension validation, path-traversal guards, the 256 MiB
+    /// size cap, and the progress-callback contract are all identical to
+    /// `load`.
+    #[pyo3(signature = (path, progress_callback=None, progress_every=1000))]
+    fn load_append(
+        &self,
+        py: Python<'_>,
+        path: &str,
+        progress_callback: Option<Py<PyAny>>,
+        progress_every: usize,
+    ) -> PyResult<usize> {
+        let mut callback_err = None;
+        let result = self.load_append_impl(path, progress_every, |processed, total| {
+            if callback_err.is_some() {
+                return;
+            }
+            if let Some(callback) = &progress_callback {
+                if let Err(e) = callback.call1(py, (processed, total)) {
+                    callback_err = Some(e);
+                }
+            }
+        });
+        if let Some(e) = callback_err {
+            return Err(e);
+        }
+        result
+    }
+
+    /// Save to a compact binary file (atomic write via temp file + rename,
+    /// same durability story as `save`) instead of JSON. Every field is
+    /// fixed-width or length-prefixed and embeddings are raw little-endian
+    /// `f32`s rather than decimal text, so this is both smaller on disk and
+    /// much faster to write/read back than `save`/`load` for large stores —
+    /// at the cost of not being human-readable or interoperable with other
+    /// tools the way plain JSON is.
+    ///
+    /// `fsync`, default true, has the same meaning as `save`'s `fsync`.
+    /// There is no `compress` option — the format is already dense, and no
+    /// `progress_callback` — encoding a `Vec<f32>` per entry is cheap enough
+    /// that a large store doesn't need one.
+    #[pyo3(signature = (path, fsync=true))]
+    fn save_binary(&self, path: &str, fsync: bool) -> PyResult<()> {
+        self.save_binary_impl(path, fsync)
+    }
+
+    /// Load from a binary file written by `save_binary` (replaces all
+    /// existing entries, mirroring `load`). Rejects the file if its stored
+    /// dimension doesn't match this engine's `dimension`, same as `load`'s
+    /// JSON path. Unlike `load`, a corrupt or truncated record fails the
+    /// whole load rather than skipping just that entry — the binary layout
+    /// has no per-entry boundary to resynchronize on, so a bad record
+    /// invalidates every record after it.
+    #[pyo3(signature = (path,))]
+    fn load_binary(&self, path: &str) -> PyResult<usize> {
+        self.load_binary_impl(path)
+    }
+
+    /// Bulk-import every `.jsonl`/`.json` shard in `dir`, merging each into
+    /// the store the same way `load_append` does (dimension-checked,
+    /// last-write-wins on id collisions), in filename-sorted order for
+    /// determinism. `pattern`, if given, is a `*`-wildcard glob over the
+    /// filename (e.g. `"2024-*.jsonl"`) instead of the default "any
+    /// `.jsonl`/`.json` file" filter.
+    ///
+    /// Returns the total entries loaded across every matched shard. Runs
+    /// entirely with the GIL released — unlike `load`/`load_append` there's
+    /// no per-entry `progress_callback` here that would need it back.
+    #[pyo3(signature = (dir, pattern=None))]
+    fn load_directory(
+        &self,
+        py: Python<'_>,
+        dir: &str,
+        pattern: Option<String>,
+    ) -> PyResult<usize> {
+        py.detach(|| self.load_directory_impl(dir, pattern.as_deref()))
+    }
+
+    /// Force a durability checkpoint for the mmap-backed vector store,
+    /// separate from `save()`'s full JSON rewrite.
+    ///
+    /// For an engine built by `open_mmap` (`capabilities()`'s `mmap_backed`
+    /// is `true`) this calls `VectorStorage::flush` on the vector file.
+    /// `VectorStorage::push` itself does not fsync, but `add` already
+    /// flushes the store after each insert and every sidecar line is written
+    /// as it is appended, so this is an explicit checkpoint on top of that.
+    /// For a plain `new()` engine (HashMap+JSON only) there is no mmap and
+    /// this is a no-op; `save()` is its durability point.
+    fn flush(&self) -> PyResult<()> {
+        if let Some(storage) = &self.mmap_storage {
+            storage.lock().flush().map_err(rag_error_to_pyerr)?;
+        }
+        Ok(())
+    }
+}
+
+/// Shared core of `search`/`search_buffer` — both public overloads validate
+/// and obtain an owned `&[f32]` query their own way, then delegate here for
+/// the actual snapshot-clone + parallel scoring. Kept off the `#[pymethods]`
+/// block since it isn't itself a Python-facing method.
+impl RagEngine {
+    #[allow(clippy::too_many_arguments)]
+    fn search_with_query(
+        &self,
+        py: Python<'_>,
+        query_embedding: &[f32],
+        top_k: usize,
+        time_decay_factor: f64,
+        return_timing: bool,
+        prune: bool,
+        allowed_ids: Option<&HashSet<String>>,
+        dimension_mask: Option<&[bool]>,
+        rerank_fn: Option<&Py<PyAny>>,
+        group_by: Option<&str>,
+        drop_ungrouped: bool,
+        filter: Option<&HashMap<String, String>>,
+    ) -> PyResult<Py<PyAny>> {
+        if query_embedding.len() != self.dimension {
+            return Err(PyValueError::new_err(format!(
+                "Query dimension mismatch: expected {}, got {}",
+                self.dimension,
+                query_embedding.len()
+            )));
+        }
+        // Validate query is finite — match add()'s guarantees so we never
+        // silently let a NaN slip into cosine_similarity. The threshold filter
+        // below would catch NaN scores by accident (NaN >= x is false), but
+        // an Inf in the query produces an Inf score that passes the filter
+        // and torpedoes the rank order.
+        if query_embedding.iter().any(|v| !v.is_finite()) {
+            return Err(PyValueError::new_err(
+                "query_embedding contains non-finite values (NaN/Inf)",
+            ));
+        }
+
+        let similarity_threshold = f32::from_bits(
+            self.similarity_threshold
+                .load(std::sync::atomic::Ordering::Relaxed),
+        );
+
+        if let Some(rerank_fn) = rerank_fn {
+            return self.search_with_rerank(
+                py,
+                query_embedding,
+                top_k,
+                similarity_threshold,
+                allowed_ids,
+                dimension_mask,
+                rerank_fn,
+                return_timing,
+                group_by,
+                drop_ungrouped,
+                filter,
+            );
+        }
+
+        let entries_snapshot_arc = self.entries.load_full();
+
+        // Release GIL during the snapshot clone AND the parallel computation
+        // so both phases can be timed without holding the GIL over the clone.
+        // `load_full()` above already took the snapshot lock-free (no
+        // `entries_lock.read()` to wait on), so `snapshot_clone_us` now times
+        // only the per-entry `Vec` clone, not any contention with a writer.
+        let (results, timing) = py.detach(move || {
+            catch_panic_as_runtime_error(move || {
+                use rayon::prelude::*;
+
+                let clone_start = std::time::Instant::now();
+                let entries_snapshot: Vec<_> = entries_snapshot_arc
+                    .values()
+                    .filter(|entry| allowed_ids.is_none_or(|ids| ids.contains(&entry.id)))
+                    .filter(|entry| matches_metadata_filter(entry, filter))
+                    .cloned()
+                    .collect();
+                let snapshot_clone_us = clone_start.elapsed().as_micros() as u64;
+
+                let compute_start = std::time::Instant::now();
+                let current_time = std::time::SystemTime::now()
+                    .duration_since(std::time::UNIX_EPOCH)
+                    .map(|d| d.as_secs_f64())
+                    .unwrap_or(0.0);
+
+                // Only needed by the pruning path (score_entry ignores it otherwise),
+                // but it's a single cheap pass so there's no reason to gate it.
+                let query_norm = vector_norm(query_embedding);
+
+                let results: Vec<SearchResult> = entries_snapshot
+                    .par_iter()
+                    .filter_map(|entry| {
+                        score_entry(
+                            query_embedding,
+                            query_norm,
+                            entry,
+                            time_decay_factor,
+                            current_time,
+                            similarity_threshold,
+                            prune,
+                            dimension_mask,
+                        )
+                    })
+                    .collect();
+
+                let mut results =
+                    apply_group_by(results, &entries_snapshot, group_by, drop_ungrouped);
+                rank_and_truncate(&mut results, top_k);
+                let compute_us = compute_start.elapsed().as_micros() as u64;
+
+                (
+                    results,
+                    SearchTiming {
+                        snapshot_clone_us,
+                        compute_us,
+                    },
+                )
+            })
+        })?;
+
+        if return_timing {
+            Ok((results, timing).into_pyobject(py)?.into_any().unbind())
+        } else {
+            Ok(results.into_pyobject(py)?.into_any().unbind())
+        }
+    }
+
+    /// `rerank_fn` branch of `search_with_query` — takes over scoring
+    /// entirely, calling `rerank_fn(base_score, importance, age_hours)` once
+    /// per candidate with the GIL held. Runs on the calling thread with a
+    /// plain sequential loop rather than the Rayon `par_iter` the default
+    /// formula uses: Python callables aren't safe to invoke concurrently
+    /// from multiple Rayon threads without each one separately acquiring the
+    /// GIL, which would serialize on the GIL anyway while paying thread
+    /// hop-off overhead for nothing. `allowed_ids` still applies before
+    /// scoring, same as the default path. `dimension_mask`, if given, is
+    /// applied to the base cosine score the same way the default formula's
+    /// path applies it, before `rerank_fn` ever sees `base_score`.
+    #[allow(clippy::too_many_arguments)]
+    fn search_with_rerank(
+        &self,
+        py: Python<'_>,
+        query_embedding: &[f32],
+        top_k: usize,
+        similarity_threshold: f32,
+        allowed_ids: Option<&HashSet<String>>,
+        dimension_mask: Option<&[bool]>,
+        rerank_fn: &Py<PyAny>,
+        return_timing: bool,
+        group_by: Option<&str>,
+        drop_ungrouped: bool,
+        filter: Option<&HashMap<String, String>>,
+    ) -> PyResult<Py<PyAny>> {
+        let clone_start = std::time::Instant::now();
+        let entries_snapshot: Vec<MemoryEntry> = self
+            .entries
+            .load()
+            .values()
+            .filter(|entry| allowed_ids.is_none_or(|ids| ids.contains(&entry.id)))
+            .filter(|entry| matches_metadata_filter(entry, filter))
+            .cloned()
+            .collect();
+        let snapshot_clone_us = clone_start.elapsed().as_micros() as u64;
+
+        let compute_start = std::time::Instant::now();
+        let current_time = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs_f64())
+            .unwrap_or(0.0);
+
+        let mut results = Vec::with_capacity(entries_snapshot.len());
+        for entry in &entries_snapshot {
+            let base_score = if let Some(mask) = dimension_mask {
+                cosine_similarity_masked(query_embedding, &entry.embedding, mask)
+            } else {
+                cosine_similarity(query_embedding, &entry.embedding)
+            };
+            let age_hours = ((current_time - entry.timestamp) / 3600.0).max(0.0);
+            let score: f32 = rerank_fn
+                .call1(py, (base_score, entry.importance, age_hours))?
+                .extract(py)?;
+            let score = if score.is_finite() { score } else { 0.0 };
+            if score >= similarity_threshold {
+                results.push(SearchResult {
+                    id: entry.id.clone(),
+                    text: entry.text.clone(),
+                    score,
+                    timestamp: entry.timestamp,
+                    metadata: entry.metadata.clone(),
+                });
+            }
+        }
+
+        let mut results = apply_group_by(results, &entries_snapshot, group_by, drop_ungrouped);
+        rank_and_truncate(&mut results, top_k);
+        let compute_us = compute_start.elapsed().as_micros() as u64;
+        let timing = SearchTiming { snapshot_clone_us, compute_us };
+
+        if return_timing {
+            Ok((results, timing).into_pyobject(py)?.into_any().unbind())
+        } else {
+            Ok(results.into_pyobject(py)?.into_any().unbind())
+        }
+    }
+}
+
+/// Plain-Rust core of `RagEngine::save`/`load` — kept off the `#[pymethods]`
+/// block so it's unit-testable without a Python interpreter (same rationale
+/// as `add_batch_impl`). `on_progress(processed, total)` is invoked every
+/// `progress_every` items and once more at completion; the `#[pymethods]`
+/// wrappers forward it into an optional Python callback.
+impl RagEngine {
+    /// Plain-Rust core of `RagEngine::add` — kept off the `#[pymethods]`
+    /// block so it's unit-testable without a Python interpreter (same
+    /// rationale as `save_impl`/`add_batch_impl`; `add` itself needs a
+    /// `Python<'_>` token only to build its growing return shape). Returns
+    /// `(truncated, mmap_index)`; `mmap_index` is `Some` iff this engine was
+    /// opened via `open_mmap`.
+    fn add_impl(&self, mut entry: MemoryEntry) -> PyResult<(bool, Option<usize>)> {
+        if entry.embedding.len() != self.dimension {
+            return Err(PyValueError::new_err(format!(
+                "Embedding dimension mismatch: expected {}, got {}",
+                self.dimension,
+                entry.embedding.len()
+            )));
+        }
+        // Validate importance is finite to prevent NaN/Infinity score corruption
+        if !entry.importance.is_finite() {
+            return Err(PyValueError::new_err("importance must be a finite number"));
+        }
+        // Importance is a non-negative weight (calculate_importance clamps to
+        // [0.0, 2.0]). A negative importance flips the sign of final_score in
+        // search() (final_score = base_score * decay * importance); since the
+        // cosine base_score is in [-1, 1], a negative weight on an OPPOSITE-meaning
+        // memory (base_score < 0) yields a POSITIVE score that can pass the
+        // threshold and surface a maximally-irrelevant hit. Enforce the invariant
+        // at the trust boundary.
+        if entry.importance < 0.0 {
+            return Err(PyValueError::new_err("importance must be non-negative"));
+        }
+        // Embedding values must also be finite — a single NaN/Inf in the
+        // vector would later make save() fail (serde_json refuses non-finite
+        // floats) and silently degrades cosine similarity at query time.
+        if entry.embedding.iter().any(|v| !v.is_finite()) {
+            return Err(PyValueError::new_err(
+                "embedding contains non-finite values (NaN/Inf)",
+            ));
+        }
+        // Timestamp must be finite too — a non-finite value serializes to JSON
+        // null in save() and is silently dropped on the next load(), so guard it
+        // here to keep the stored-data invariant consistent with importance/embedding.
+        if !entry.timestamp.is_finite() {
+            return Err(PyValueError::new_err("timestamp must be a finite number"));
+        }
+
+        let truncated = self
+            .max_text_len
+            .is_some_and(|max_len| truncate_text(&mut entry.text, max_len));
+
+        let _write_guard = self.write_lock.lock();
+
+        let index = match (&self.mmap_storage, &self.mmap_sidecar_path) {
+            (Some(storage), Some(sidecar_path)) => {
+                let mut storage = storage.lock();
+                let index = storage.push(&entry.embedding).map_err(rag_error_to_pyerr)?;
+                append_mmap_sidecar_line(sidecar_path, &entry, index)
+                    .map_err(|e| PyRuntimeError::new_err(format!("add: writing sidecar: {e}")))?;
+                storage.flush().map_err(rag_error_to_pyerr)?;
+                Some(index)
+            }
+            _ => None,
+        };
+
+        let mut entries = (*self.entries.load_full()).clone();
+        entries.insert(entry.id.clone(), entry);
+        if let Some(max_entries) = self.max_entries {
+            evict_to_capacity(&mut entries, max_entries);
+        }
+        self.entries.store(Arc::new(entries));
+
+        Ok((truncated, index))
+    }
+
+    fn save_impl(
+        &self,
+        path: &str,
+        progress_every: usize,
+        fsync: bool,
+        compress: bool,
+        mut on_progress: impl FnMut(usize, usize),
+    ) -> PyResult<()> {
+        // When compressing, the actual file written is `{path}.gz`, not
+        // `path` itself — every guard/write/rename below operates on this
+        // resolved path so a compressed save can't collide with (or escape
+        // the same traversal checks as) an uncompressed one at the same
+        // `path`.
+        let owned_gz_path;
+        let path: &str = if compress {
+            owned_gz_path = format!("{}.gz", path);
+            &owned_gz_path
+        } else {
+            path
+        };
+
+        let entries = self.entries.load();
+        let data = build_entries_json(&entries, progress_every, &mut on_progress);
+        drop(entries);
+
+        let json = serde_json::to_string_pretty(&data)
+            .map_err(|e| PyValueError::new_err(e.to_string()))?;
+
+        let bytes: Vec<u8> = if compress {
+            use std::io::Write;
+            let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+            encoder
+                .write_all(json.as_bytes())
+                .map_err(|e| PyValueError::new_err(format!("gzip encode: {}", e)))?;
+            encoder
+                .finish()
+                .map_err(|e| PyValueError::new_err(format!("gzip finish: {}", e)))?
+        } else {
+            json.into_bytes()
+        };
+
+        write_file_atomically(path, &bytes, fsync)
+    }
+
+    fn load_impl(
+        &self,
+        path: &str,
+        progress_every: usize,
+        mut on_progress: impl FnMut(usize, usize),
+    ) -> PyResult<usize> {
+        // Build new entries in a temporary map first to avoid data loss on bad files.
+        let new_entries =
+            read_and_parse_entries_file(path, self.dimension, progress_every, &mut on_progress)?;
+
+        // Swap in the new entries atomically. Report the ACTUAL stored count —
+        // HashMap de-dupes by id, so a file with duplicate ids stores fewer than
+        // the iteration count; len() keeps the reported count == engine size.
+        let count = new_entries.len();
+        let _write_guard = self.write_lock.lock();
+        self.entries.store(Arc::new(new_entries));
+
+        Ok(count)
+    }
+
+    fn load_append_impl(
+        &self,
+        path: &str,
+        progress_every: usize,
+        mut on_progress: impl FnMut(usize, usize),
+    ) -> PyResult<usize> {
+        let new_entries =
+            read_and_parse_entries_file(path, self.dimension, progress_every, &mut on_progress)?;
+        let count = new_entries.len();
+
+        // Merge under the same writer lock load()/add()/add_batch() use, so a
+        // concurrent writer can't interleave with the read-modify-store below.
+        // last-write-wins: entries from `path` override any existing id, since
+        // that matches HashMap::extend's overwrite-on-collision behavior.
+        let _write_guard = self.write_lock.lock();
+        let mut merged = (*self.entries.load_full()).clone();
+        merged.extend(new_entries);
+        self.entries.store(Arc::new(merged));
+
+        Ok(count)
+    }
+
+    fn save_binary_impl(&self, path: &str, fsync: bool) -> PyResult<()> {
+        let entries = self.entries.load();
+        let bytes = encode_entries_binary(&entries, self.dimension);
+        drop(entries);
+        write_file_atomically(path, &bytes, fsync)
+    }
+
+    fn load_binary_impl(&self, path: &str) -> PyResult<usize> {
+        let buf = read_file_with_size_cap(path)?;
+        let new_entries =
+            decode_entries_binary(&buf, self.dimension).map_err(PyValueError::new_err)?;
+        let count = new_entries.len();
+
+        let _write_guard = self.write_lock.lock();
+        self.entries.store(Arc::new(new_entries));
+
+        Ok(count)
+    }
+
+    fn load_directory_impl(&self, dir: &str, pattern: Option<&str>) -> PyResult<usize> {
+        let dir_path = std::path::Path::new(dir);
+        validate_relative_path(dir_path)?;
+        reject_symlinked_components(dir_path)?;
+
+        let mut shard_paths: Vec<std::path::PathBuf> = std::fs::read_dir(dir_path)
+            .map_err(|e| PyValueError::new_err(format!("read_dir failed: {}", e)))?
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|path| path.is_file())
+            .filter(|path| match path.file_name().and_then(|n| n.to_str()) {
+                Some(name) => match pattern {
+                    Some(p) => matches_glob(name, p),
+                    None => name.ends_with(".jsonl") || name.ends_with(".json"),
+                },
+                None => false,
+            })
+            .collect();
+        shard_paths.sort();
+
+        let mut total = 0usize;
+        for shard in &shard_paths {
+            let shard_str = shard
+                .to_str()
+                .ok_or_else(|| PyValueError::new_err("shard path is not valid UTF-8"))?;
+            total += self.load_append_impl(shard_str, usize::MAX, |_, _| {})?;
+        }
+        Ok(total)
+    }
+}
+
+/// Minimal `*`-only glob match against a bare filename (no path separators,
+/// no `?`/character-class support) — enough for `load_directory`'s `pattern`
+/// filter (e.g. `"2024-*.jsonl"`) without pulling in a full glob crate for
+/// one bulk-import knob.
+fn matches_glob(name: &str, pattern: &str) -> bool {
+    let parts: Vec<&str> = pattern.split('*').collect();
+    if parts.len() == 1 {
+        return name == pattern;
+    }
+    let Some(mut rest) = name.strip_prefix(parts[0]) else {
+        return false;
+    };
+    for part in &parts[1..parts.len() - 1] {
+        match rest.find(part) {
+            Some(idx) => rest = &rest[idx + part.len()..],
+            None => return false,
+        }
+    }
+    rest.ends_with(parts[parts.len() - 1])
+}
+
+// Size limit (256 MiB) to prevent OOM from malicious/corrupt files. RAG
+// dumps (JSON or binary) are expected to be small (few MB); 256 MiB is a
+// generous cap. Shared by `read_file_with_size_cap` and `load_binary_impl`.
+const MAX_LOAD_BYTES: u64 = 256 * 1024 * 1024;
+
+/// Validate `path` as a safe relative load target and read it into memory
+/// under `MAX_LOAD_BYTES`. Shared by `read_and_parse_entries_file` (JSON) and
+/// `load_binary_impl` (binary) — both need the identical traversal/symlink
+/// checks and size cap before they diverge on how to parse the bytes.
+fn read_file_with_size_cap(path: &str) -> PyResult<Vec<u8>> {
+    let load_path = std::path::Path::new(path);
+    validate_relative_path(load_path)?;
+    // Defense-in-depth: the leaf symlink_metadata check below only stats
+    // the final component, so a symlinked intermediate directory would let
+    // a lexically-clean relative path resolve outside the project root.
+    // Refuse any symlinked ancestor directory before the leaf check.
+    reject_symlinked_components(load_path)?;
+
+    // Use ``symlink_metadata`` rather than ``metadata`` so we can refuse
+    // to follow symlinks — combined with the path-component check above,
+    // a relative ``subdir/symlink_to_outside`` would otherwise pass the
+    // traversal check and resolve to anywhere on disk via stat.
+    let symlink_meta = std::fs::symlink_metadata(path)
+        .map_err(|e| PyValueError::new_err(format!("stat failed: {}", e)))?;
+    if symlink_meta.file_type().is_symlink() {
+        return Err(PyValueError::new_err(
+            "Path traversal blocked: symlinked load path not allowed",
+        ));
+    }
+    if symlink_meta.len() > MAX_LOAD_BYTES {
+        return Err(PyValueError::new_err(format!(
+            "File too large to load: {} bytes (max {})",
+            symlink_meta.len(),
+            MAX_LOAD_BYTES
+        )));
+    }
+
+    // Read with an explicit byte cap rather than ``read_to_string``, so a
+    // file that grows between the size check above and this read can't
+    // silently exceed our cap (a TOCTOU window). Reading one extra byte
+    // beyond the cap lets us detect attempted overflow and reject it.
+    use std::io::Read;
+    let mut file = std::fs::File::open(path)
+        .map_err(|e| PyValueError::new_err(format!("open failed: {}", e)))?;
+    let mut buf = Vec::with_capacity((symlink_meta.len() as usize).min(MAX_LOAD_BYTES as usize));
+    let read_cap = MAX_LOAD_BYTES.saturating_add(1);
+    file.by_ref()
+        .take(read_cap)
+        .read_to_end(&mut buf)
+        .map_err(|e| PyValueError::new_err(format!("read failed: {}", e)))?;
+    if buf.len() as u64 > MAX_LOAD_BYTES {
+        return Err(PyValueError::new_err(format!(
+            "File grew past size cap mid-read (max {} bytes)",
+            MAX_LOAD_BYTES
+        )));
+    }
+
+    Ok(buf)
+}
+
+/// Plain-Rust core shared by `load_impl`/`load_append_impl`: validates the
+/// path, enforces the 256 MiB size cap, reads the file, and parses+validates
+/// its entries against `dimension`. Neither caller stores the result — that
+/// differs between full-replace and merge — so this stops at returning the
+/// parsed map.
+fn read_and_parse_entries_file(
+    path: &str,
+    dimension: usize,
+    progress_every: usize,
+    on_progress: &mut impl FnMut(usize, usize),
+) -> PyResult<HashMap<String, MemoryEntry>> {
+    let buf = read_file_with_size_cap(path)?;
+    let read_cap = MAX_LOAD_BYTES.saturating_add(1);
+
+    // Auto-detect gzip by its magic bytes (0x1f 0x8b) rather than the `.gz`
+    // filename, so a compressed save loads back transparently regardless of
+    // what the caller named it — see `save`'s `compress` option. Decompress
+    // under the same MAX_LOAD_BYTES cap (via the same over-by-one trick) so
+    // a small compressed file can't decompress into an unbounded gzip bomb.
+    use std::io::Read;
+    const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+    let data = if buf.starts_with(&GZIP_MAGIC) {
+        let mut decompressed = Vec::new();
+        flate2::read::GzDecoder::new(buf.as_slice())
+            .take(read_cap)
+            .read_to_end(&mut decompressed)
+            .map_err(|e| PyValueError::new_err(format!("gzip decode failed: {}", e)))?;
+        if decompressed.len() as u64 > MAX_LOAD_BYTES {
+            return Err(PyValueError::new_err(format!(
+                "Decompressed file exceeds size cap (max {} bytes)",
+                MAX_LOAD_BYTES
+            )));
+        }
+        String::from_utf8(decompressed)
+            .map_err(|e| PyValueError::new_err(format!("decompressed file is not UTF-8: {}", e)))?
+    } else {
+        String::from_utf8(buf)
+            .map_err(|e| PyValueError::new_err(format!("file is not UTF-8: {}", e)))?
+    };
+
+    let entries_data: Vec<serde_json::Value> =
+        serde_json::from_str(&data).map_err(|e| PyValueError::new_err(e.to_string()))?;
+
+    let new_entries = parse_entries_json(&entries_data, dimension, progress_every, on_progress);
+
+    // Only report entries if we parsed at least some, or if the source file
+    // was intentionally empty.
+    if new_entries.is_empty() && !entries_data.is_empty() {
+        return Err(PyValueError::new_err(
+            "No entries matched the expected dimension; refusing to load",
+        ));
+    }
+
+    Ok(new_entries)
+}
+
+/// Validate `path` is a safe relative save target, then atomically write
+/// `bytes` to it: write to a unique temp file, fsync it (if `fsync`),
+/// rename into place (falling back to copy+delete if the platform can't
+/// rename over an open/locked file, e.g. Windows), and fsync the parent
+/// directory's rename entry (if `fsync`). Split out of `save_impl` so
+/// `save_binary_impl` gets the exact same atomicity/durability story for
+/// free — the two only differ in how `bytes` gets built.
+///
+/// The unique temp name (PID + wall-clock nanos + a process-wide atomic
+/// counter) matters because two `save`/`save_binary` calls racing on the
+/// same path must not clobber each other's temp file mid-write. The counter
+/// is the load-bearing part: `SystemTime::now().as_nanos()` does NOT
+/// advance on every read on Windows (coarse clock — consecutive reads can
+/// return identical nanos), so two same-process threads within one clock
+/// tick would otherwise get an identical pid+nanos and thus the SAME temp
+/// name. `fetch_add` guarantees each call in this process gets a distinct
+/// suffix regardless of clock resolution.
+fn write_file_atomically(path: &str, bytes: &[u8], fsync: bool) -> PyResult<()> {
+    // Path traversal protection: reject ".." components, absolute paths,
+    // and Windows drive prefixes (Component::Prefix) — the previous check
+    // missed Prefix, so on Windows a relative path starting with a drive
+    // letter (e.g. "C:foo") could escape the project root.
+    let save_path = std::path::Path::new(path);
+    validate_relative_path(save_path)?;
+    // Defense-in-depth: refuse a symlinked intermediate directory (a
+    // lexically-clean relative path can still point outside the project
+    // root through a directory symlink), and refuse to write THROUGH an
+    // existing leaf symlink (File::create follows symlinks).
+    reject_symlinked_components(save_path)?;
+    if let Ok(meta) = std::fs::symlink_metadata(save_path) {
+        if meta.file_type().is_symlink() {
+            return Err(PyValueError::new_err(
+                "Path traversal blocked: symlinked save path not allowed",
+            ));
+        }
+    }
+
+    static TEMP_COUNTER: std::sync::atomic::AtomicU64 = std::sync::atomic::AtomicU64::new(0);
+    let pid = std::process::id();
+    let nanos = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or(0);
+    let seq = TEMP_COUNTER.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+    let temp_path = format!("{}.tmp.{}.{}.{}", path, pid, nanos, seq);
+
+    // Write + fsync the temp file before renaming. Without sync_all(), the
+    // bytes may live only in the OS page cache; a power loss after the
+    // rename leaves the live file truncated/empty because the rename was
+    // atomic on the directory entry but the data pages were never flushed
+    // to stable storage. Use the explicit File API so we can call
+    // sync_all() on the handle.
+    //
+    // `fsync` gates all of this: when false, skip the file-data fsyncs
+    // below AND the directory fsync after rename, trading durability on
+    // unclean shutdown for lower write latency. Default true.
+    {
+        use std::io::Write;
+        let mut f = std::fs::File::create(&temp_path)
+            .map_err(|e| PyValueError::new_err(format!("create temp: {}", e)))?;
+        // Clean up the partial temp on any write/fsync failure so repeated
+        // errors don't pile up orphaned `.tmp.*` files (unique suffix per
+        // call), matching the copy-fallback cleanup below. Drop the handle
+        // first so remove_file succeeds on Windows (open handle has no
+        // FILE_SHARE_DELETE).
+        let write_res = f
+            .write_all(bytes)
+            .map_err(|e| format!("write temp: {}", e))
+            .and_then(|()| {
+                if fsync {
+                    f.sync_all().map_err(|e| format!("fsync temp: {}", e))
+                } else {
+                    Ok(())
+                }
+            });
+        drop(f);
+        if let Err(msg) = write_res {
+            let _ = std::fs::remove_file(&temp_path);
+            return Err(PyValueError::new_err(msg));
+        }
+    }
+
+    // rename may fail on Windows if file is locked; fall back to copy+delete.
+    if let Err(rename_err) = std::fs::rename(&temp_path, path) {
+        match std::fs::copy(&temp_path, path) {
+            Ok(_) => {
+                // copy() does NOT fsync the destination. fsync the
+                // destination file before deleting the temp so the new
+                // bytes are durable; otherwise a crash here can leave
+                // both copies present but the destination empty.
+                if fsync {
+                    match std::fs::OpenOptions::new().write(true).open(path) {
+                        Ok(f) => {
+                            if let Err(e) = f.sync_all() {
+                                // Clean up the temp before bailing, matching
+                                // every other exit path; the temp name is
+                                // unique per call, so leaving it here piles up
+                                // orphaned `.tmp.*` files on repeat failures.
+                                let _ = std::fs::remove_file(&temp_path);
+                                return Err(PyValueError::new_err(format!(
+                                    "fsync after copy failed: {}",
+                                    e
+                                )));
+                            }
+                        }
+                        Err(e) => {
+                            let _ = std::fs::remove_file(&temp_path);
+                            return Err(PyValueError::new_err(format!(
+                                "open dest for fsync failed: {}",
+                                e
+                            )));
+                        }
+                    }
+                }
+                let _ = std::fs::remove_file(&temp_path);
+            }
+            Err(copy_err) => {
+                let _ = std::fs::remove_file(&temp_path);
+                return Err(PyValueError::new_err(format!(
+                    "rename failed: {}, copy fallback failed: {}",
+                    rename_err, copy_err
+                )));
+            }
+        }
+    }
+
+    // Fsync the parent directory so the rename's directory-entry update (or
+    // the create() from the copy fallback) survives a crash, not just the
+    // file data above. Best-effort on POSIX only — see fsync_parent_dir's
+    // doc comment for why Windows is a no-op there. The file's own data is
+    // already durable at this point, so treat a directory-fsync failure as
+    // an error rather than swallow it: the caller asked for `fsync=true`
+    // and deserves to know durability wasn't fully achieved.
+    if fsync {
+        fsync_parent_dir(save_path)
+            .map_err(|e| PyValueError::new_err(format!("fsync directory: {}", e)))?;
+    }
+
+    Ok(())
+}
+
+// Magic distinct from `storage.rs`'s `VectorStorage` header (`0x5241_4753`,
+// "RAGS") — the two are unrelated formats that happen to share a crate, and
+// a mismatched magic should say so rather than look like a truncated RAGS
+// file. "RAGE" for the same reason RAGS is "RAGS": a `MemoryEntry` dump is a
+// RAG _e_ngine snapshot, not the raw vector store.
+const BINARY_MAGIC: u32 = 0x5241_4745;
+const BINARY_FORMAT_VERSION: u32 = 1;
+
+/// Encode `entries` into the layout `save_binary` writes to disk: a fixed
+/// header (magic, version, dimension, count) followed by one variable-length
+/// record per entry — length-prefixed id/text UTF-8 bytes, an `f64`
+/// timestamp, an `f32` importance, the embedding as raw little-endian
+/// `f32`s, and a metadata block. Every field is fixed-width or
+/// length-prefixed with no delimiters to scan for, so `decode_entries_binary`
+/// parses it by walking a byte offset rather than tokenizing — much less
+/// work per entry than `build_entries_json`'s `serde_json::Value` construction
+/// and, on the read side, `serde_json::from_str`'s text parsing.
+fn encode_entries_binary(entries: &HashMap<String, MemoryEntry>, dimension: usize) -> Vec<u8> {
+    let mut out = Vec::new();
+    out.extend_from_slice(&BINARY_MAGIC.to_le_bytes());
+    out.extend_from_slice(&BINARY_FORMAT_VERSION.to_le_bytes());
+    out.extend_from_slice(&(dimension as u32).to_le_bytes());
+    out.extend_from_slice(&(entries.len() as u32).to_le_bytes());
+
+    for entry in entries.values() {
+        write_len_prefixed(&mut out, entry.id.as_bytes());
+        write_len_prefixed(&mut out, entry.text.as_bytes());
+        out.extend_from_slice(&entry.timestamp.to_le_bytes());
+        out.extend_from_slice(&entry.importance.to_le_bytes());
+        for value in &entry.embedding {
+            out.extend_from_slice(&value.to_le_bytes());
+        }
+        match &entry.metadata {
+            Some(metadata) => {
+                out.extend_from_slice(&(metadata.len() as u32).to_le_bytes());
+                for (key, value) in metadata {
+                    write_len_prefixed(&mut out, key.as_bytes());
+                    write_len_prefixed(&mut out, value.as_bytes());
+                }
+            }
+            // u32::MAX marks "no metadata at all" (`None`), distinct from
+            // "metadata present but empty" (`Some(HashMap::new())`, encoded
+            // above as a real count of 0) — the same distinction
+            // build_entries_json/parse_entries_json preserve via JSON null
+            // vs `{}` so a round trip doesn't turn one into the other.
+            None => out.extend_from_slice(&u32::MAX.to_le_bytes()),
+        }
+    }
+
+    out
+}
+
+fn write_len_prefixed(out: &mut Vec<u8>, bytes: &[u8]) {
+    out.extend_from_slice(&(bytes.len() as u32).to_le_bytes());
+    out.extend_from_slice(bytes);
+}
+
+/// Bounds-checked slice read used throughout `decode_entries_binary`: every
+/// field of a binary dump is read this way rather than indexed directly, so
+/// a truncated or hand-edited file fails with a clear error instead of
+/// panicking the whole process on an out-of-bounds slice.
+fn read_bytes<'a>(bytes: &'a [u8], pos: &mut usize, len: usize) -> Result<&'a [u8], String> {
+    let end = pos
+        .checked_add(len)
+        .ok_or_else(|| "length overflow while reading binary file".to_string())?;
+    let slice = bytes
+        .get(*pos..end)
+        .ok_or_else(|| "truncated binary file".to_string())?;
+    *pos = end;
+    Ok(slice)
+}
+
+fn read_len_prefixed_string(bytes: &[u8], pos: &mut usize, field: &str) -> Result<String, String> {
+    let len = u32::from_le_bytes(read_bytes(bytes, pos, 4)?.try_into().unwrap()) as usize;
+    let data = read_bytes(bytes, pos, len)?;
+    String::from_utf8(data.to_vec()).map_err(|e| format!("{field} is not UTF-8: {e}"))
+}
+
+/// Decode `encode_entries_binary`'s layout back into an entry map. Checks
+/// the header's magic and format version, and rejects a stored `dimension`
+/// that doesn't match `expected_dimension` — the same guarantee `load`'s
+/// JSON path enforces per-entry via `parse_entries_json`. Unlike the JSON
+/// loader, which skips individually-malformed entries and keeps the rest, a
+/// corrupt binary record has no self-describing boundary to resume parsing
+/// from, so any read failure here fails the whole file rather than
+/// returning a partial map.
+fn decode_entries_binary(
+    bytes: &[u8],
+    expected_dimension: usize,
+) -> Result<HashMap<String, MemoryEntry>, String> {
+    let mut pos = 0usize;
+    let magic = u32::from_le_bytes(read_bytes(bytes, &mut pos, 4)?.try_into().unwrap());
+    let version = u32::from_le_bytes(read_bytes(bytes, &mut pos, 4)?.try_into().unwrap());
+    let dimension =
+        u32::from_le_bytes(read_bytes(bytes, &mut pos, 4)?.try_into().unwrap()) as usize;
+    let count = u32::from_le_bytes(read_bytes(bytes, &mut pos, 4)?.try_into().unwrap()) as usize;
+
+    if magic != BINARY_MAGIC {
+        return Err(format!(
+            "bad magic {:#010x} (expected {:#010x}) — not a RagEngine binary dump",
+            magic, BINARY_MAGIC
+        ));
+    }
+    if version != BINARY_FORMAT_VERSION {
+        return Err(format!(
+            "unsupported binary format version {} (expected {})",
+            version, BINARY_FORMAT_VERSION
+        ));
+    }
+    if dimension != expected_dimension {
+        return Err(format!(
+            "dimension mismatch: file has {}, engine expects {}",
+            dimension, expected_dimension
+        ));
+    }
+
+    // Not `HashMap::with_capacity(count)`: `count` is an untrusted u32 read
+    // straight from the file header, so a corrupt/truncated file claiming
+    // billions of entries would abort the process on the allocation before
+    // the per-field `read_bytes` bounds checks below ever get a chance to
+    // fail cleanly. Let normal insert growth size the map instead.
+    let mut entries = HashMap::new();
+    for _ in 0..count {
+        let id = read_len_prefixed_string(bytes, &mut pos, "id")?;
+        let text = read_len_prefixed_string(bytes, &mut pos, "text")?;
+        let timestamp = f64::from_le_bytes(read_bytes(bytes, &mut pos, 8)?.try_into().unwrap());
+        let importance = f32::from_le_bytes(read_bytes(bytes, &mut pos, 4)?.try_into().unwrap());
+
+        let mut embedding = Vec::with_capacity(dimension);
+        for _ in 0..dimension {
+            embedding.push(f32::from_le_bytes(
+                read_bytes(bytes, &mut pos, 4)?.try_into().unwrap(),
+            ));
+        }
+
+        let metadata_count =
+            u32::from_le_bytes(read_bytes(bytes, &mut pos, 4)?.try_into().unwrap());
+        let metadata = if metadata_count == u32::MAX {
+            None
+        } else {
+            // Same reasoning as `entries` above: `metadata_count` is an
+            // untrusted per-record u32, so don't pre-size from it.
+            let mut map = HashMap::new();
+            for _ in 0..metadata_count {
+                let key = read_len_prefixed_string(bytes, &mut pos, "metadata key")?;
+                let value = read_len_prefixed_string(bytes, &mut pos, "metadata value")?;
+                map.insert(key, value);
+            }
+            Some(map)
+        };
+
+        entries.insert(
+            id.clone(),
+            MemoryEntry {
+                id,
+                text,
+                embedding,
+                timestamp,
+                importance,
+                metadata,
+            },
+        );
+    }
+
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+    use std::sync::Mutex;
 
     // ---- Lexical path-traversal guard (interpreter-free) ----------------
 
     #[test]
-    fn validate_relative_path_accepts_clean_relative() {
-        assert!(validate_relative_path(Path::new("data.json")).is_ok());
-        assert!(validate_relative_path(Path::new("subdir/data.json")).is_ok());
-        assert!(validate_relative_path(Path::new("a/b/c.json")).is_ok());
+    fn validate_relative_path_accepts_clean_relative() {
+        assert!(validate_relative_path(Path::new("data.json")).is_ok());
+        assert!(validate_relative_path(Path::new("subdir/data.json")).is_ok());
+        assert!(validate_relative_path(Path::new("a/b/c.json")).is_ok());
+    }
+
+    #[test]
+    fn validate_relative_path_rejects_absolute() {
+        // POSIX-style absolute.
+        assert!(validate_relative_path(Path::new("/etc/passwd")).is_err());
+        // Windows absolute drive path.
+        #[cfg(windows)]
+        assert!(validate_relative_path(Path::new("C:\\Windows\\system32")).is_err());
+    }
+
+    #[test]
+    fn validate_relative_path_rejects_parent_dir() {
+        assert!(validate_relative_path(Path::new("../secret")).is_err());
+        assert!(validate_relative_path(Path::new("subdir/../../secret")).is_err());
+    }
+
+    #[test]
+    fn validate_relative_path_rejects_rootdir() {
+        // A rooted-but-driveless path. On Windows Path::is_absolute() reports
+        // these as relative, so the RootDir arm is what actually rejects them.
+        assert!(validate_relative_path(Path::new("/foo")).is_err());
+        #[cfg(windows)]
+        assert!(validate_relative_path(Path::new("\\foo")).is_err());
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn validate_relative_path_rejects_drive_prefix() {
+        // "C:foo" is a drive-relative path that Path::is_absolute() reports as
+        // RELATIVE on Windows — only the Component::Prefix arm catches it. This
+        // arm is unreachable from non-Windows hosts, so it MUST be exercised by
+        // a Windows Rust test (the audit's rs-rag-1 point).
+        assert!(validate_relative_path(Path::new("C:foo")).is_err());
+        assert!(validate_relative_path(Path::new("c:bar\\baz.json")).is_err());
+    }
+
+    // ---- Filesystem-dependent tests ------------------------------------
+    //
+    // RagEngine::save_impl/load_impl/add_impl/open_mmap are plain Rust methods (no
+    // Python<'_> arg) so they are callable from cargo test without a Python
+    // interpreter. They use RELATIVE paths, which validate_relative_path
+    // requires, so every test here chdir's into a throwaway tempdir first.
+    // chdir is process-global and cargo runs tests on parallel threads, so
+    // all CWD-mutating tests share one lock to avoid racing on the working
+    // directory.
+    static CWD_LOCK: Mutex<()> = Mutex::new(());
+
+    fn unique_tempdir(tag: &str) -> std::path::PathBuf {
+        let nanos = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_nanos())
+            .unwrap_or(0);
+        let dir = std::env::temp_dir().join(format!(
+            "rag_engine_test_{}_{}_{}",
+            tag,
+            std::process::id(),
+            nanos
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn sample_entry(id: &str, dim: usize) -> MemoryEntry {
+        MemoryEntry {
+            id: id.to_string(),
+            text: format!("text-{id}"),
+            embedding: vec![0.1_f32; dim],
+            timestamp: 1.0,
+            importance: 0.5,
+            metadata: None,
+        }
+    }
+
+    fn sample_entry_with_metadata(id: &str, dim: usize, key: &str, value: &str) -> MemoryEntry {
+        let mut entry = sample_entry(id, dim);
+        entry.metadata = Some(HashMap::from([(key.to_string(), value.to_string())]));
+        entry
+    }
+
+    #[test]
+    fn save_load_round_trip_relative_path() {
+        let _g = CWD_LOCK.lock().unwrap();
+        let dir = unique_tempdir("roundtrip");
+        let prev = std::env::current_dir().unwrap();
+        std::env::set_current_dir(&dir).unwrap();
+
+        let result = std::panic::catch_unwind(|| {
+            let engine = RagEngine::new(4, 0.0, None, None).unwrap();
+            engine.add_impl(sample_entry("a", 4)).unwrap();
+            engine.add_impl(sample_entry("b", 4)).unwrap();
+            engine
+                .save_impl("dump.json", 1000, true, false, |_, _| {})
+                .unwrap();
+
+            let loaded = RagEngine::new(4, 0.0, None, None).unwrap();
+            let count = loaded.load_impl("dump.json", 1000, |_, _| {}).unwrap();
+            assert_eq!(count, 2);
+            assert_eq!(loaded.len(), 2);
+
+            // The success path must not orphan the atomic-write temp file.
+            let leftover = std::fs::read_dir(".").unwrap().any(|e| {
+                e.unwrap()
+                    .file_name()
+                    .to_string_lossy()
+                    .starts_with("dump.json.tmp.")
+            });
+            assert!(!leftover, "save() must not leave a .tmp.* file on success");
+        });
+
+        std::env::set_current_dir(prev).unwrap();
+        let _ = std::fs::remove_dir_all(&dir);
+        result.unwrap();
+    }
+
+    #[test]
+    fn save_load_round_trip_preserves_metadata() {
+        let _g = CWD_LOCK.lock().unwrap();
+        let dir = unique_tempdir("roundtrip_metadata");
+        let prev = std::env::current_dir().unwrap();
+        std::env::set_current_dir(&dir).unwrap();
+
+        let result = std::panic::catch_unwind(|| {
+            let engine = RagEngine::new(4, 0.0, None, None).unwrap();
+            engine
+                .add_impl(sample_entry_with_metadata("a", 4, "source", "doc1"))
+                .unwrap();
+            engine.add_impl(sample_entry("b", 4)).unwrap();
+            engine
+                .save_impl("dump.json", 1000, true, false, |_, _| {})
+                .unwrap();
+
+            let loaded = RagEngine::new(4, 0.0, None, None).unwrap();
+            loaded.load_impl("dump.json", 1000, |_, _| {}).unwrap();
+            assert_eq!(
+                loaded.get("a").unwrap().metadata,
+                Some(HashMap::from([("source".to_string(), "doc1".to_string())]))
+            );
+            assert_eq!(loaded.get("b").unwrap().metadata, None);
+        });
+
+        std::env::set_current_dir(prev).unwrap();
+        let _ = std::fs::remove_dir_all(&dir);
+        result.unwrap();
+    }
+
+    #[test]
+    fn save_binary_load_binary_round_trip_preserves_metadata() {
+        let _g = CWD_LOCK.lock().unwrap();
+        let dir = unique_tempdir("roundtrip_binary");
+        let prev = std::env::current_dir().unwrap();
+        std::env::set_current_dir(&dir).unwrap();
+
+        let result = std::panic::catch_unwind(|| {
+            let engine = RagEngine::new(4, 0.0, None, None).unwrap();
+            engine
+                .add_impl(sample_entry_with_metadata("a", 4, "source", "doc1"))
+                .unwrap();
+            engine.add_impl(sample_entry("b", 4)).unwrap();
+            engine.save_binary_impl("dump.bin", true).unwrap();
+
+            let loaded = RagEngine::new(4, 0.0, None, None).unwrap();
+            let count = loaded.load_binary_impl("dump.bin").unwrap();
+            assert_eq!(count, 2);
+            assert_eq!(loaded.len(), 2);
+            assert_eq!(
+                loaded.get("a").unwrap().metadata,
+                Some(HashMap::from([("source".to_string(), "doc1".to_string())]))
+            );
+            assert_eq!(loaded.get("b").unwrap().metadata, None);
+            assert_eq!(loaded.get("a").unwrap().embedding, vec![0.1_f32; 4]);
+
+            // Same atomic-write guarantee as save_impl: no leftover temp file.
+            let leftover = std::fs::read_dir(".").unwrap().any(|e| {
+                e.unwrap()
+                    .file_name()
+                    .to_string_lossy()
+                    .starts_with("dump.bin.tmp.")
+            });
+            assert!(
+                !leftover,
+                "save_binary() must not leave a .tmp.* file on success"
+            );
+        });
+
+        std::env::set_current_dir(prev).unwrap();
+        let _ = std::fs::remove_dir_all(&dir);
+        result.unwrap();
+    }
+
+    #[test]
+    fn load_binary_rejects_dimension_mismatch() {
+        // Exercises decode_entries_binary directly (a plain String error)
+        // rather than load_binary_impl's PyErr, since PyErr::to_string()
+        // needs a real GIL token — unavailable here, same as every other
+        // test in this file (see catch_panic_as_runtime_error's tests).
+        let mut entries = HashMap::new();
+        entries.insert("a".to_string(), sample_entry("a", 4));
+        let bytes = encode_entries_binary(&entries, 4);
+
+        match decode_entries_binary(&bytes, 8) {
+            Ok(_) => panic!("expected a dimension mismatch error"),
+            Err(e) => assert!(e.contains("dimension mismatch")),
+        }
+    }
+
+    #[test]
+    fn load_binary_rejects_truncated_file() {
+        let mut entries = HashMap::new();
+        entries.insert("a".to_string(), sample_entry("a", 4));
+        let bytes = encode_entries_binary(&entries, 4);
+
+        assert!(decode_entries_binary(&bytes[..bytes.len() - 4], 4).is_err());
+    }
+
+    #[test]
+    fn load_binary_rejects_huge_count_on_a_short_buffer_without_aborting() {
+        // A header claiming ~4 billion entries on a 16-byte (header-only)
+        // buffer must fail via the normal truncation error, not attempt to
+        // `HashMap::with_capacity` a huge untrusted count and abort the
+        // process before any bounds-checked read runs.
+        let mut header = Vec::new();
+        header.extend_from_slice(&BINARY_MAGIC.to_le_bytes());
+        header.extend_from_slice(&BINARY_FORMAT_VERSION.to_le_bytes());
+        header.extend_from_slice(&4u32.to_le_bytes());
+        header.extend_from_slice(&0xFFFF_FFFEu32.to_le_bytes());
+
+        match decode_entries_binary(&header, 4) {
+            Ok(_) => panic!("expected a truncated-file error"),
+            Err(e) => assert!(e.contains("truncated")),
+        }
+    }
+
+    #[test]
+    fn load_binary_rejects_huge_metadata_count_on_a_short_buffer_without_aborting() {
+        // Same reasoning as the entry-count test above, but for a single
+        // entry's metadata_count field claiming ~4 billion key/value pairs
+        // right after a validly-encoded entry (no trailing bytes for them).
+        let mut entries = HashMap::new();
+        entries.insert("a".to_string(), sample_entry("a", 4));
+        let mut bytes = encode_entries_binary(&entries, 4);
+        // The single entry's metadata_count (u32::MAX sentinel for `None`)
+        // is the last 4 bytes written; overwrite it with a huge real count.
+        let len = bytes.len();
+        bytes[len - 4..].copy_from_slice(&0xFFFF_FFFEu32.to_le_bytes());
+
+        match decode_entries_binary(&bytes, 4) {
+            Ok(_) => panic!("expected a truncated-file error"),
+            Err(e) => assert!(e.contains("truncated")),
+        }
+    }
+
+    #[test]
+    fn load_binary_rejects_bad_magic() {
+        match decode_entries_binary(b"this is not a RagEngine dump at all!!!", 4) {
+            Ok(_) => panic!("expected a bad-magic error"),
+            Err(e) => assert!(e.contains("bad magic")),
+        }
+    }
+
+    #[test]
+    fn load_binary_round_trips_entries_and_metadata() {
+        let mut entries = HashMap::new();
+        entries.insert(
+            "a".to_string(),
+            sample_entry_with_metadata("a", 4, "source", "doc1"),
+        );
+        entries.insert("b".to_string(), sample_entry("b", 4));
+        let bytes = encode_entries_binary(&entries, 4);
+
+        let decoded = match decode_entries_binary(&bytes, 4) {
+            Ok(decoded) => decoded,
+            Err(e) => panic!("decode_entries_binary failed: {e}"),
+        };
+        assert_eq!(decoded.len(), 2);
+        assert_eq!(
+            decoded.get("a").unwrap().metadata,
+            Some(HashMap::from([("source".to_string(), "doc1".to_string())]))
+        );
+        assert_eq!(decoded.get("b").unwrap().metadata, None);
+        assert_eq!(decoded.get("a").unwrap().embedding, vec![0.1_f32; 4]);
+    }
+
+    #[test]
+    fn open_mmap_add_persists_embeddings_and_metadata_across_reopen() {
+        let _g = CWD_LOCK.lock().unwrap();
+        let dir = unique_tempdir("open_mmap_roundtrip");
+        let prev = std::env::current_dir().unwrap();
+        std::env::set_current_dir(&dir).unwrap();
+
+        let result = std::panic::catch_unwind(|| {
+            let engine = RagEngine::open_mmap("vectors.bin", 4, 8, 0.0, None, None).unwrap();
+            assert!(engine.mmap_storage.is_some());
+            let (_, index_a) = engine
+                .add_impl(sample_entry_with_metadata("a", 4, "source", "doc1"))
+                .unwrap();
+            assert_eq!(index_a, Some(0));
+            let (_, index_b) = engine.add_impl(sample_entry("b", 4)).unwrap();
+            assert_eq!(index_b, Some(1));
+            drop(engine);
+
+            let reopened = RagEngine::open_mmap("vectors.bin", 4, 8, 0.0, None, None).unwrap();
+            assert_eq!(reopened.len(), 2);
+            assert_eq!(reopened.get("a").unwrap().embedding, vec![0.1_f32; 4]);
+            assert_eq!(
+                reopened.get("a").unwrap().metadata,
+                Some(HashMap::from([("source".to_string(), "doc1".to_string())]))
+            );
+            assert_eq!(reopened.get("b").unwrap().metadata, None);
+        });
+
+        std::env::set_current_dir(prev).unwrap();
+        let _ = std::fs::remove_dir_all(&dir);
+        result.unwrap();
+    }
+
+    #[test]
+    fn open_mmap_rejects_reopening_with_a_different_dimension() {
+        let _g = CWD_LOCK.lock().unwrap();
+        let dir = unique_tempdir("open_mmap_dim_mismatch");
+        let prev = std::env::current_dir().unwrap();
+        std::env::set_current_dir(&dir).unwrap();
+
+        let result = std::panic::catch_unwind(|| {
+            RagEngine::open_mmap("vectors.bin", 4, 8, 0.0, None, None).unwrap();
+            assert!(RagEngine::open_mmap("vectors.bin", 8, 8, 0.0, None, None).is_err());
+        });
+
+        std::env::set_current_dir(prev).unwrap();
+        let _ = std::fs::remove_dir_all(&dir);
+        result.unwrap();
+    }
+
+    #[test]
+    fn open_mmap_add_errors_once_capacity_is_exhausted() {
+        let _g = CWD_LOCK.lock().unwrap();
+        let dir = unique_tempdir("open_mmap_capacity");
+        let prev = std::env::current_dir().unwrap();
+        std::env::set_current_dir(&dir).unwrap();
+
+        let result = std::panic::catch_unwind(|| {
+            let engine = RagEngine::open_mmap("vectors.bin", 4, 1, 0.0, None, None).unwrap();
+            engine.add_impl(sample_entry("a", 4)).unwrap();
+            assert!(engine.add_impl(sample_entry("b", 4)).is_err());
+            // The rejected push must not have touched `entries` either.
+            assert_eq!(engine.len(), 1);
+        });
+
+        std::env::set_current_dir(prev).unwrap();
+        let _ = std::fs::remove_dir_all(&dir);
+        result.unwrap();
+    }
+
+    #[test]
+    fn open_mmap_drops_a_sidecar_row_whose_index_outran_the_storage_count() {
+        // Simulates a crash between appending the sidecar line and
+        // VectorStorage::flush durably committing the matching push: the
+        // sidecar claims an index the reopened storage's count doesn't cover.
+        let _g = CWD_LOCK.lock().unwrap();
+        let dir = unique_tempdir("open_mmap_torn_write");
+        let prev = std::env::current_dir().unwrap();
+        std::env::set_current_dir(&dir).unwrap();
+
+        let result = std::panic::catch_unwind(|| {
+            let engine = RagEngine::open_mmap("vectors.bin", 4, 8, 0.0, None, None).unwrap();
+            engine.add_impl(sample_entry("a", 4)).unwrap();
+            drop(engine);
+
+            // Hand-append a sidecar row pointing at an index (5) the storage
+            // file's on-disk count (1, from the single successful add above)
+            // never reached.
+            let sidecar_path = mmap_sidecar_path(std::path::Path::new("vectors.bin"));
+            append_mmap_sidecar_line(&sidecar_path, &sample_entry("orphan", 4), 5).unwrap();
+
+            let reopened = RagEngine::open_mmap("vectors.bin", 4, 8, 0.0, None, None).unwrap();
+            assert_eq!(reopened.len(), 1);
+            assert!(reopened.get("orphan").is_none());
+            assert!(reopened.get("a").is_some());
+        });
+
+        std::env::set_current_dir(prev).unwrap();
+        let _ = std::fs::remove_dir_all(&dir);
+        result.unwrap();
+    }
+
+    #[test]
+    fn save_and_load_report_progress_every_n_entries_and_at_completion() {
+        let _g = CWD_LOCK.lock().unwrap();
+        let dir = unique_tempdir("progress");
+        let prev = std::env::current_dir().unwrap();
+        std::env::set_current_dir(&dir).unwrap();
+
+        let result = std::panic::catch_unwind(|| {
+            let engine = RagEngine::new(4, 0.0, None, None).unwrap();
+            for i in 0..5 {
+                engine.add_impl(sample_entry(&format!("id{i}"), 4)).unwrap();
+            }
+
+            let mut save_progress = Vec::new();
+            engine
+                .save_impl("dump.json", 2, true, false, |processed, total| {
+                    save_progress.push((processed, total));
+                })
+                .unwrap();
+            // Every 2nd of 5 entries (2, 4), plus one final (5, 5) call.
+            assert_eq!(save_progress, vec![(2, 5), (4, 5), (5, 5)]);
+
+            let loaded = RagEngine::new(4, 0.0, None, None).unwrap();
+            let mut load_progress = Vec::new();
+            loaded
+                .load_impl("dump.json", 2, |processed, total| {
+                    load_progress.push((processed, total));
+                })
+                .unwrap();
+            assert_eq!(load_progress, vec![(2, 5), (4, 5), (5, 5)]);
+        });
+
+        std::env::set_current_dir(prev).unwrap();
+        let _ = std::fs::remove_dir_all(&dir);
+        result.unwrap();
+    }
+
+    #[test]
+    fn save_load_round_trips_with_fsync_disabled() {
+        // fsync=false must not change the written data or the load-back
+        // result — only whether save_impl calls sync_all()/fsync_parent_dir.
+        let _g = CWD_LOCK.lock().unwrap();
+        let dir = unique_tempdir("nofsync");
+        let prev = std::env::current_dir().unwrap();
+        std::env::set_current_dir(&dir).unwrap();
+
+        let result = std::panic::catch_unwind(|| {
+            let engine = RagEngine::new(4, 0.0, None, None).unwrap();
+            engine.add_impl(sample_entry("a", 4)).unwrap();
+            engine
+                .save_impl("dump.json", 1000, false, false, |_, _| {})
+                .unwrap();
+
+            let loaded = RagEngine::new(4, 0.0, None, None).unwrap();
+            let count = loaded
+                .load_impl("dump.json", 1000, |_, _| {})
+                .unwrap();
+            assert_eq!(count, 1);
+            assert!(loaded.get("a").is_some());
+        });
+
+        std::env::set_current_dir(prev).unwrap();
+        let _ = std::fs::remove_dir_all(&dir);
+        result.unwrap();
+    }
+
+    #[test]
+    fn save_compress_writes_a_gz_suffixed_file_that_load_auto_decompresses() {
+        let _g = CWD_LOCK.lock().unwrap();
+        let dir = unique_tempdir("compress");
+        let prev = std::env::current_dir().unwrap();
+        std::env::set_current_dir(&dir).unwrap();
+
+        let result = std::panic::catch_unwind(|| {
+            let engine = RagEngine::new(4, 0.0, None, None).unwrap();
+            engine.add_impl(sample_entry("a", 4)).unwrap();
+            engine.add_impl(sample_entry("b", 4)).unwrap();
+            engine
+                .save_impl("dump.json", 1000, true, true, |_, _| {})
+                .unwrap();
+
+            assert!(
+                std::path::Path::new("dump.json.gz").exists(),
+                "compress=true must write to {{path}}.gz"
+            );
+            assert!(
+                !std::path::Path::new("dump.json").exists(),
+                "compress=true must not also write the uncompressed path"
+            );
+
+            let loaded = RagEngine::new(4, 0.0, None, None).unwrap();
+            let count = loaded.load_impl("dump.json.gz", 1000, |_, _| {}).unwrap();
+            assert_eq!(count, 2);
+            assert!(loaded.get("a").is_some() && loaded.get("b").is_some());
+        });
+
+        std::env::set_current_dir(prev).unwrap();
+        let _ = std::fs::remove_dir_all(&dir);
+        result.unwrap();
+    }
+
+    #[test]
+    fn save_compress_produces_a_smaller_file_than_uncompressed() {
+        let _g = CWD_LOCK.lock().unwrap();
+        let dir = unique_tempdir("compresssize");
+        let prev = std::env::current_dir().unwrap();
+        std::env::set_current_dir(&dir).unwrap();
+
+        let result = std::panic::catch_unwind(|| {
+            let engine = RagEngine::new(16, 0.0, None, None).unwrap();
+            for i in 0..50 {
+                engine.add_impl(sample_entry(&format!("id{i}"), 16)).unwrap();
+            }
+            engine
+                .save_impl("plain.json", 1000, true, false, |_, _| {})
+                .unwrap();
+            engine
+                .save_impl("plain.json", 1000, true, true, |_, _| {})
+                .unwrap();
+
+            let plain_len = std::fs::metadata("plain.json").unwrap().len();
+            let gz_len = std::fs::metadata("plain.json.gz").unwrap().len();
+            assert!(
+                gz_len < plain_len,
+                "gzip output ({gz_len}) must be smaller than plain JSON ({plain_len})"
+            );
+        });
+
+        std::env::set_current_dir(prev).unwrap();
+        let _ = std::fs::remove_dir_all(&dir);
+        result.unwrap();
     }
 
     #[test]
-    fn validate_relative_path_rejects_absolute() {
-        // POSIX-style absolute.
-        assert!(validate_relative_path(Path::new("/etc/passwd")).is_err());
-        // Windows absolute drive path.
-        #[cfg(windows)]
-        assert!(validate_relative_path(Path::new("C:\\Windows\\system32")).is_err());
+    fn add_rejects_non_finite_embedding_and_importance() {
+        // Interpreter-free: add_impl() is a plain method. Finite-value rejection is a
+        // security/robustness guard (a NaN/Inf in storage breaks save()/search).
+        let engine = RagEngine::new(3, 0.0, None, None).unwrap();
+        let mut bad_emb = sample_entry("x", 3);
+        bad_emb.embedding = vec![1.0, f32::NAN, 2.0];
+        assert!(engine.add_impl(bad_emb).is_err());
+
+        let mut bad_inf = sample_entry("y", 3);
+        bad_inf.embedding = vec![1.0, f32::INFINITY, 2.0];
+        assert!(engine.add_impl(bad_inf).is_err());
+
+        let mut bad_imp = sample_entry("z", 3);
+        bad_imp.importance = f32::NAN;
+        assert!(engine.add_impl(bad_imp).is_err());
+
+        // Dimension mismatch is also rejected.
+        assert!(engine.add_impl(sample_entry("w", 2)).is_err());
+
+        assert_eq!(engine.len(), 0);
     }
 
+    // ------- set_similarity_threshold (#1685) -------
+
     #[test]
-    fn validate_relative_path_rejects_parent_dir() {
-        assert!(validate_relative_path(Path::new("../secret")).is_err());
-        assert!(validate_relative_path(Path::new("subdir/../../secret")).is_err());
+    fn set_similarity_threshold_rejects_non_finite() {
+        let engine = RagEngine::new(3, 0.5, None, None).unwrap();
+        assert!(engine.set_similarity_threshold(f32::NAN).is_err());
+        assert!(engine.set_similarity_threshold(f32::INFINITY).is_err());
+        assert!(engine.set_similarity_threshold(f32::NEG_INFINITY).is_err());
     }
 
     #[test]
-    fn validate_relative_path_rejects_rootdir() {
-        // A rooted-but-driveless path. On Windows Path::is_absolute() reports
-        // these as relative, so the RootDir arm is what actually rejects them.
-        assert!(validate_relative_path(Path::new("/foo")).is_err());
-        #[cfg(windows)]
-        assert!(validate_relative_path(Path::new("\\foo")).is_err());
+    fn set_similarity_threshold_updates_the_stored_value() {
+        // Interpreter-free: reads the atomic field directly rather than going
+        // through `search` (which needs a real Python GIL token for its
+        // `rerank_fn`/callback args), same tradeoff `capabilities()` makes by
+        // not having a dedicated test either.
+        let engine = RagEngine::new(3, 0.5, None, None).unwrap();
+        engine.set_similarity_threshold(0.9).unwrap();
+        assert_eq!(
+            f32::from_bits(
+                engine
+                    .similarity_threshold
+                    .load(std::sync::atomic::Ordering::Relaxed)
+            ),
+            0.9
+        );
     }
 
-    #[cfg(windows)]
+    // ------- similarity_threshold cosine-range validation (#1695) -------
+
     #[test]
-    fn validate_relative_path_rejects_drive_prefix() {
-        // "C:foo" is a drive-relative path that Path::is_absolute() reports as
-        // RELATIVE on Windows — only the Component::Prefix arm catches it. This
-        // arm is unreachable from non-Windows hosts, so it MUST be exercised by
-        // a Windows Rust test (the audit's rs-rag-1 point).
-        assert!(validate_relative_path(Path::new("C:foo")).is_err());
-        assert!(validate_relative_path(Path::new("c:bar\\baz.json")).is_err());
+    fn set_similarity_threshold_rejects_values_outside_cosine_range() {
+        let engine = RagEngine::new(3, 0.5, None, None).unwrap();
+        assert!(engine.set_similarity_threshold(1.1).is_err());
+        assert!(engine.set_similarity_threshold(-1.1).is_err());
+        // The boundary values themselves are valid cosine scores.
+        assert!(engine.set_similarity_threshold(1.0).is_ok());
+        assert!(engine.set_similarity_threshold(-1.0).is_ok());
     }
 
-    // ---- Filesystem-dependent tests ------------------------------------
-    //
-    // RagEngine::save/load/add are plain Rust methods (no Python<'_> arg) so
-    // they are callable from cargo test without a Python interpreter. They use
-    // RELATIVE paths, which validate_relative_path requires, so every test here
-    // chdir's into a throwaway tempdir first. chdir is process-global and cargo
-    // runs tests on parallel threads, so all CWD-mutating tests share one lock
-    // to avoid racing on the working directory.
-    static CWD_LOCK: Mutex<()> = Mutex::new(());
+    #[test]
+    fn new_rejects_a_similarity_threshold_outside_cosine_range() {
+        assert!(RagEngine::new(3, 1.5, None, None).is_err());
+        assert!(RagEngine::new(3, -1.5, None, None).is_err());
+        assert!(RagEngine::new(3, 0.7, None, None).is_ok());
+    }
 
-    fn unique_tempdir(tag: &str) -> std::path::PathBuf {
-        let nanos = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .map(|d| d.as_nanos())
-            .unwrap_or(0);
-        let dir = std::env::temp_dir().join(format!(
-            "rag_engine_test_{}_{}_{}",
-            tag,
-            std::process::id(),
-            nanos
-        ));
-        std::fs::create_dir_all(&dir).unwrap();
-        dir
+    // ------- catch_panic_as_runtime_error (#1686) -------
+
+    #[test]
+    fn catch_panic_as_runtime_error_passes_through_a_non_panicking_result() {
+        assert_eq!(catch_panic_as_runtime_error(|| 1 + 1).unwrap(), 2);
     }
 
-    fn sample_entry(id: &str, dim: usize) -> MemoryEntry {
-        MemoryEntry {
-            id: id.to_string(),
-            text: format!("text-{id}"),
-            embedding: vec![0.1_f32; dim],
-            timestamp: 1.0,
-            importance: 0.5,
+    #[test]
+    fn catch_panic_as_runtime_error_turns_a_panic_into_a_catchable_err() {
+        // Interpreter-free: `PyErr::is_instance_of` needs a real GIL token to
+        // compare against a Python exception class (unavailable here, same
+        // as every other test in this file), so this only checks that the
+        // panic is caught at all rather than propagating past `catch_unwind`
+        // and aborting the test binary. `search`/`find_duplicates`/
+        // `scores_for` route their `py.detach` closures through this same
+        // helper, so a panic there is converted the same way rather than
+        // reaching PyO3's uncatchable `PanicException`.
+        let default_hook = std::panic::take_hook();
+        std::panic::set_hook(Box::new(|_| {}));
+        let result = catch_panic_as_runtime_error(|| -> i32 { panic!("boom") });
+        std::panic::set_hook(default_hook);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn add_batch_dedups_and_counts_net_growth() {
+        let mut entries = HashMap::new();
+        // Two distinct ids + one duplicate id + one malformed (wrong dim) entry.
+        let mut malformed = sample_entry("bad", 2);
+        malformed.text = "wrong-dim".to_string();
+        let (added, evicted, _truncated) = add_batch_impl(
+            &mut entries,
+            3,
+            None,
+            None,
+            vec![
+                sample_entry("a", 3),
+                sample_entry("b", 3),
+                sample_entry("a", 3), // duplicate id -> replace, not +1
+                malformed,            // dropped silently
+            ],
+        );
+        // Net growth is 2 (a, b); duplicate replaces, malformed dropped.
+        assert_eq!(added, 2);
+        assert!(evicted.is_empty());
+        assert_eq!(entries.len(), 2);
+    }
+
+    #[test]
+    fn add_rejects_negative_importance() {
+        // A negative importance is finite but flips final_score's sign — reject it
+        // at the trust boundary (rust-rag-M2). Distinct from the NaN/Inf check.
+        let engine = RagEngine::new(3, 0.0, None, None).unwrap();
+        let mut neg = sample_entry("neg", 3);
+        neg.importance = -0.5;
+        assert!(engine.add_impl(neg).is_err(), "negative importance must be rejected");
+        // Zero is a valid weight (clamp lower bound) and must still be accepted.
+        let mut zero = sample_entry("zero", 3);
+        zero.importance = 0.0;
+        assert!(engine.add_impl(zero).is_ok(), "zero importance must be accepted");
+        assert_eq!(engine.len(), 1);
+    }
+
+    #[test]
+    fn add_batch_drops_negative_importance() {
+        // Silent-skip contract: a negative-importance entry is dropped, not raised.
+        let mut entries = HashMap::new();
+        let mut neg = sample_entry("neg", 3);
+        neg.importance = -1.0;
+        let (added, _evicted, _truncated) =
+            add_batch_impl(&mut entries, 3, None, None, vec![sample_entry("ok", 3), neg]);
+        assert_eq!(added, 1, "only the non-negative entry should be inserted");
+        assert_eq!(entries.len(), 1);
+    }
+
+    #[test]
+    fn add_matrix_slices_flat_buffer_into_entries() {
+        let mut entries = HashMap::new();
+        let ids = vec!["a".to_string(), "b".to_string()];
+        let texts = vec!["hello".to_string(), "world".to_string()];
+        // Row-major (2, 3): row 0 = [1,2,3], row 1 = [4,5,6].
+        let embeddings_flat = vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0];
+        let timestamps = vec![100.0, 200.0];
+        let importances = vec![1.0, 0.5];
+
+        let (added, evicted, _truncated) = add_matrix_impl(
+            &mut entries,
+            3,
+            None,
+            None,
+            ids,
+            texts,
+            embeddings_flat,
+            timestamps,
+            importances,
+        )
+        .unwrap();
+
+        assert_eq!(added, 2);
+        assert!(evicted.is_empty());
+        assert_eq!(entries["a"].embedding, vec![1.0, 2.0, 3.0]);
+        assert_eq!(entries["b"].embedding, vec![4.0, 5.0, 6.0]);
+        assert_eq!(entries["b"].importance, 0.5);
+    }
+
+    #[test]
+    fn add_matrix_rejects_mismatched_side_array_lengths() {
+        let mut entries = HashMap::new();
+        let err = add_matrix_impl(
+            &mut entries,
+            3,
+            None,
+            None,
+            vec!["a".to_string(), "b".to_string()],
+            vec!["only-one".to_string()],
+            vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0],
+            vec![100.0, 200.0],
+            vec![1.0, 1.0],
+        )
+        .unwrap_err();
+        assert!(err.contains("same length"));
+        assert!(entries.is_empty());
+    }
+
+    #[test]
+    fn add_matrix_rejects_flat_buffer_not_matching_rows_times_dimension() {
+        let mut entries = HashMap::new();
+        let err = add_matrix_impl(
+            &mut entries,
+            3,
+            None,
+            None,
+            vec!["a".to_string()],
+            vec!["hi".to_string()],
+            vec![1.0, 2.0], // 2 floats, but dimension 3 needs 3
+            vec![100.0],
+            vec![1.0],
+        )
+        .unwrap_err();
+        assert!(err.contains("dimension"));
+    }
+
+    #[test]
+    fn add_matrix_reuses_add_batch_validation_and_dedup() {
+        // Duplicate id across rows should replace, not double-count; per-row
+        // validation (finite importance) still applies via add_batch_impl.
+        let mut entries = HashMap::new();
+        let (added, _, _) = add_matrix_impl(
+            &mut entries,
+            2,
+            None,
+            None,
+            vec!["x".to_string(), "x".to_string()],
+            vec!["first".to_string(), "second".to_string()],
+            vec![1.0, 1.0, 2.0, 2.0],
+            vec![1.0, 2.0],
+            vec![1.0, 1.0],
+        )
+        .unwrap();
+        assert_eq!(added, 1);
+        assert_eq!(entries["x"].text, "second");
+    }
+
+    #[test]
+    fn find_duplicates_returns_pairs_at_or_above_threshold_sorted_descending() {
+        let mut a = sample_entry("a", 2);
+        a.embedding = vec![1.0, 0.0];
+        let mut b = sample_entry("b", 2); // identical to a -> score 1.0
+        b.embedding = vec![1.0, 0.0];
+        let mut c = sample_entry("c", 2); // orthogonal to a/b -> score 0.0
+        c.embedding = vec![0.0, 1.0];
+
+        let pairs = find_duplicates_impl(&[a, b, c], 0.5, 10);
+        assert_eq!(pairs.len(), 1);
+        assert_eq!((pairs[0].0.as_str(), pairs[0].1.as_str()), ("a", "b"));
+        assert!((pairs[0].2 - 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn find_duplicates_respects_max_pairs_cap() {
+        // Four mutually-identical entries -> 6 pairs, all scoring 1.0.
+        let entries: Vec<MemoryEntry> = ["a", "b", "c", "d"]
+            .iter()
+            .map(|id| {
+                let mut e = sample_entry(id, 2);
+                e.embedding = vec![1.0, 0.0];
+                e
+            })
+            .collect();
+
+        let pairs = find_duplicates_impl(&entries, 0.0, 2);
+        assert_eq!(pairs.len(), 2, "result must be capped at max_pairs");
+    }
+
+    #[test]
+    fn find_duplicates_empty_below_threshold() {
+        let mut a = sample_entry("a", 2);
+        a.embedding = vec![1.0, 0.0];
+        let mut b = sample_entry("b", 2);
+        b.embedding = vec![0.0, 1.0];
+
+        assert!(find_duplicates_impl(&[a, b], 0.9, 10).is_empty());
+    }
+
+    #[test]
+    fn max_entries_evicts_lowest_importance_on_add() {
+        let engine = RagEngine::new(3, 0.0, Some(2), None).unwrap();
+        let mut low = sample_entry("low", 3);
+        low.importance = 0.1;
+        let mut high = sample_entry("high", 3);
+        high.importance = 0.9;
+        let mut newest = sample_entry("newest", 3);
+        newest.importance = 0.5;
+
+        engine.add_impl(low).unwrap();
+        engine.add_impl(high).unwrap();
+        assert_eq!(engine.len(), 2);
+
+        // Adding a third entry over the cap of 2 must evict the lowest-scoring
+        // one ("low"), not just refuse the insert.
+        engine.add_impl(newest).unwrap();
+        assert_eq!(engine.len(), 2);
+        assert!(engine.get("low").is_none());
+        assert!(engine.get("high").is_some());
+        assert!(engine.get("newest").is_some());
+    }
+
+    #[test]
+    fn max_entries_add_batch_reports_evicted_ids() {
+        let mut entries = HashMap::new();
+        let mut low = sample_entry("low", 3);
+        low.importance = 0.1;
+        let mut high = sample_entry("high", 3);
+        high.importance = 0.9;
+
+        let (added, evicted, _truncated) =
+            add_batch_impl(&mut entries, 3, Some(1), None, vec![low, high]);
+
+        assert_eq!(added, 2);
+        assert_eq!(evicted, vec!["low".to_string()]);
+        assert_eq!(entries.len(), 1);
+        assert!(entries.contains_key("high"));
+    }
+
+    #[test]
+    fn truncate_text_leaves_short_text_untouched() {
+        let mut text = "short".to_string();
+        assert!(!truncate_text(&mut text, 10));
+        assert_eq!(text, "short");
+    }
+
+    #[test]
+    fn truncate_text_cuts_on_char_boundary_and_appends_ellipsis() {
+        // Multi-byte chars throughout, so a byte-oriented cut would panic or
+        // split a codepoint; truncate_text counts chars, not bytes.
+        let mut text = "héllo wörld".to_string();
+        assert!(truncate_text(&mut text, 5));
+        assert_eq!(text.chars().count(), 5);
+        assert!(text.ends_with("..."));
+    }
+
+    #[test]
+    fn truncate_text_falls_back_to_hard_cut_when_max_len_too_small_for_ellipsis() {
+        let mut text = "hello world".to_string();
+        assert!(truncate_text(&mut text, 2));
+        assert_eq!(text, "he");
+    }
+
+    #[test]
+    fn add_truncates_text_and_reports_it() {
+        let engine = RagEngine::new(3, 0.0, None, Some(5)).unwrap();
+        let mut long_entry = sample_entry("long", 3);
+        long_entry.text = "this is way too long".to_string();
+        assert!(engine.add_impl(long_entry).unwrap().0);
+        assert_eq!(engine.get("long").unwrap().text.chars().count(), 5);
+
+        let mut short_entry = sample_entry("short", 3);
+        short_entry.text = "hi".to_string();
+        assert!(!engine.add_impl(short_entry).unwrap().0);
+        assert_eq!(engine.get("short").unwrap().text, "hi");
+    }
+
+    #[test]
+    fn add_batch_impl_reports_truncated_ids_without_touching_embedding() {
+        let mut entries = HashMap::new();
+        let mut long_entry = sample_entry("long", 3);
+        long_entry.text = "this is way too long".to_string();
+        let embedding = long_entry.embedding.clone();
+
+        let (added, _evicted, truncated) =
+            add_batch_impl(&mut entries, 3, None, Some(5), vec![long_entry]);
+
+        assert_eq!(added, 1);
+        assert_eq!(truncated, vec!["long".to_string()]);
+        assert_eq!(entries["long"].embedding, embedding);
+    }
+
+    #[test]
+    fn concurrent_readers_never_block_on_a_writer() {
+        // Regression coverage for the ArcSwap migration: readers (`len`,
+        // `get_ids`, `get`) must stay fast even while a writer thread is
+        // continuously inserting. With the old `RwLock<HashMap<_>>`, a
+        // reader could stall for the writer's full clone-under-lock
+        // duration; with `ArcSwap`, a reader only ever does a handful of
+        // atomic loads, so no single read call should ever balloon toward
+        // the writer loop's total runtime.
+        use std::sync::atomic::{AtomicU64, Ordering};
+        use std::thread;
+        use std::time::Instant;
+
+        let engine = Arc::new(RagEngine::new(3, 0.0, None, None).unwrap());
+        engine.add_impl(sample_entry("seed", 3)).unwrap();
+
+        let writer = {
+            let engine = Arc::clone(&engine);
+            thread::spawn(move || {
+                for i in 0..200 {
+                    engine.add_impl(sample_entry(&format!("w{i}"), 3)).unwrap();
+                }
+            })
+        };
+
+        let max_read_us = Arc::new(AtomicU64::new(0));
+        let readers: Vec<_> = (0..8)
+            .map(|_| {
+                let engine = Arc::clone(&engine);
+                let max_read_us = Arc::clone(&max_read_us);
+                thread::spawn(move || {
+                    for _ in 0..500 {
+                        let start = Instant::now();
+                        let _ = engine.len();
+                        let _ = engine.get_ids();
+                        let _ = engine.get("seed");
+                        let elapsed_us = start.elapsed().as_micros() as u64;
+                        max_read_us.fetch_max(elapsed_us, Ordering::Relaxed);
+                    }
+                })
+            })
+            .collect();
+
+        for reader in readers {
+            reader.join().unwrap();
         }
+        writer.join().unwrap();
+
+        assert_eq!(engine.len(), 201);
+        let max_read_us = max_read_us.load(Ordering::Relaxed);
+        assert!(
+            max_read_us < 1_000_000,
+            "a reader round-trip took {max_read_us}us — looks like it blocked on the writer"
+        );
     }
 
     #[test]
-    fn save_load_round_trip_relative_path() {
+    fn update_importance_updates_in_place_and_reports_existence() {
+        let engine = RagEngine::new(3, 0.0, None, None).unwrap();
+        engine.add_impl(sample_entry("a", 3)).unwrap();
+        let original_embedding = engine.get("a").unwrap().embedding;
+
+        assert!(engine.update_importance("a", 1.5).unwrap());
+        assert_eq!(engine.get("a").unwrap().importance, 1.5);
+        assert_eq!(engine.get("a").unwrap().embedding, original_embedding);
+
+        assert!(!engine.update_importance("missing", 1.0).unwrap());
+    }
+
+    #[test]
+    fn update_importance_rejects_non_finite_and_negative_values() {
+        let engine = RagEngine::new(3, 0.0, None, None).unwrap();
+        engine.add_impl(sample_entry("a", 3)).unwrap();
+        assert!(engine.update_importance("a", f32::NAN).is_err());
+        assert!(engine.update_importance("a", -1.0).is_err());
+        // Rejected updates must not have touched the stored value.
+        assert_eq!(engine.get("a").unwrap().importance, 0.5);
+    }
+
+    #[test]
+    fn update_text_updates_in_place_truncates_and_reports_existence() {
+        let engine = RagEngine::new(3, 0.0, None, Some(5)).unwrap();
+        engine.add_impl(sample_entry("a", 3)).unwrap();
+
+        assert!(engine
+            .update_text("a", "this is way too long".to_string())
+            .unwrap());
+        assert_eq!(engine.get("a").unwrap().text.chars().count(), 5);
+
+        assert!(!engine.update_text("missing", "x".to_string()).unwrap());
+    }
+
+    #[test]
+    fn apply_decay_ages_old_entries_and_floors_at_ten_percent() {
+        let engine = RagEngine::new(3, 0.0, None, None).unwrap();
+        let current_time = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs_f64();
+
+        let mut fresh = sample_entry("fresh", 3);
+        fresh.timestamp = current_time;
+        fresh.importance = 1.0;
+        engine.add_impl(fresh).unwrap();
+
+        let mut ancient = sample_entry("ancient", 3);
+        ancient.timestamp = 1.0; // decades old -> decay floors at 0.1
+        ancient.importance = 1.0;
+        engine.add_impl(ancient).unwrap();
+
+        let changed = engine.apply_decay(1.0).unwrap(); // 1-hour half-life
+        assert!(changed >= 1, "the ancient entry's importance must move");
+
+        let fresh_after = engine.get("fresh").unwrap();
+        assert!(
+            (fresh_after.importance - 1.0).abs() < 1e-3,
+            "a fresh entry should barely decay: {}",
+            fresh_after.importance
+        );
+
+        let ancient_after = engine.get("ancient").unwrap();
+        assert!(
+            (ancient_after.importance - 0.1).abs() < 1e-6,
+            "an ancient entry should floor at 10%: {}",
+            ancient_after.importance
+        );
+    }
+
+    #[test]
+    fn apply_decay_rejects_non_positive_half_life() {
+        let engine = RagEngine::new(3, 0.0, None, None).unwrap();
+        assert!(engine.apply_decay(0.0).is_err());
+        assert!(engine.apply_decay(-1.0).is_err());
+        assert!(engine.apply_decay(f64::NAN).is_err());
+    }
+
+    #[test]
+    fn load_drops_negative_importance_entries() {
         let _g = CWD_LOCK.lock().unwrap();
-        let dir = unique_tempdir("roundtrip");
+        let dir = unique_tempdir("negimp");
         let prev = std::env::current_dir().unwrap();
         std::env::set_current_dir(&dir).unwrap();
 
         let result = std::panic::catch_unwind(|| {
-            let engine = RagEngine::new(4, 0.0);
-            engine.add(sample_entry("a", 4)).unwrap();
-            engine.add(sample_entry("b", 4)).unwrap();
-            engine.save("dump.json").unwrap();
+            // Two entries, one with a negative importance which load() must skip.
+            let json = r#"[
+              {"id":"good","text":"g","embedding":[0.1,0.2,0.3,0.4],"timestamp":1.0,"importance":0.5},
+              {"id":"neg","text":"n","embedding":[0.1,0.2,0.3,0.4],"timestamp":1.0,"importance":-0.5}
+            ]"#;
+            std::fs::write("neg.json", json).unwrap();
 
-            let loaded = RagEngine::new(4, 0.0);
-            let count = loaded.load("dump.json").unwrap();
-            assert_eq!(count, 2);
-            assert_eq!(loaded.len(), 2);
+            let engine = RagEngine::new(4, 0.0, None, None).unwrap();
+            let count = engine.load_impl("neg.json", 1000, |_, _| {}).unwrap();
+            assert_eq!(count, 1, "only the non-negative-importance entry should load");
+        });
 
-            // The success path must not orphan the atomic-write temp file.
-            let leftover = std::fs::read_dir(".").unwrap().any(|e| {
-                e.unwrap()
-                    .file_name()
-                    .to_string_lossy()
-                    .starts_with("dump.json.tmp.")
-            });
-            assert!(!leftover, "save() must not leave a .tmp.* file on success");
+        std::env::set_current_dir(prev).unwrap();
+        let _ = std::fs::remove_dir_all(&dir);
+        result.unwrap();
+    }
+
+    #[test]
+    fn load_append_merges_without_clearing_and_last_write_wins_on_collision() {
+        let _g = CWD_LOCK.lock().unwrap();
+        let dir = unique_tempdir("appendmerge");
+        let prev = std::env::current_dir().unwrap();
+        std::env::set_current_dir(&dir).unwrap();
+
+        let result = std::panic::catch_unwind(|| {
+            let shard_a = r#"[
+              {"id":"a","text":"from shard a","embedding":[0.1,0.2,0.3,0.4],"timestamp":1.0,"importance":0.5},
+              {"id":"shared","text":"old","embedding":[0.1,0.2,0.3,0.4],"timestamp":1.0,"importance":0.5}
+            ]"#;
+            let shard_b = r#"[
+              {"id":"b","text":"from shard b","embedding":[0.5,0.6,0.7,0.8],"timestamp":2.0,"importance":0.5},
+              {"id":"shared","text":"new","embedding":[0.5,0.6,0.7,0.8],"timestamp":2.0,"importance":0.5}
+            ]"#;
+            std::fs::write("shard_a.json", shard_a).unwrap();
+            std::fs::write("shard_b.json", shard_b).unwrap();
+
+            let engine = RagEngine::new(4, 0.0, None, None).unwrap();
+            let count_a = engine.load_append_impl("shard_a.json", 1000, |_, _| {}).unwrap();
+            assert_eq!(count_a, 2);
+            let count_b = engine.load_append_impl("shard_b.json", 1000, |_, _| {}).unwrap();
+            assert_eq!(count_b, 2);
+
+            // 3 distinct ids: "a", "b", and one "shared" (last-write-wins).
+            assert_eq!(engine.len(), 3);
+            let entries = engine.entries.load();
+            assert_eq!(entries.get("a").unwrap().text, "from shard a");
+            assert_eq!(entries.get("b").unwrap().text, "from shard b");
+            assert_eq!(entries.get("shared").unwrap().text, "new");
         });
 
         std::env::set_current_dir(prev).unwrap();
@@ -761,93 +4408,105 @@ mod tests {
     }
 
     #[test]
-    fn add_rejects_non_finite_embedding_and_importance() {
-        // Interpreter-free: add() is a plain method. Finite-value rejection is a
-        // security/robustness guard (a NaN/Inf in storage breaks save()/search).
-        let engine = RagEngine::new(3, 0.0);
-        let mut bad_emb = sample_entry("x", 3);
-        bad_emb.embedding = vec![1.0, f32::NAN, 2.0];
-        assert!(engine.add(bad_emb).is_err());
-
-        let mut bad_inf = sample_entry("y", 3);
-        bad_inf.embedding = vec![1.0, f32::INFINITY, 2.0];
-        assert!(engine.add(bad_inf).is_err());
+    fn load_append_refuses_when_nothing_matches_dimension_and_keeps_existing_data() {
+        let _g = CWD_LOCK.lock().unwrap();
+        let dir = unique_tempdir("appendnodim");
+        let prev = std::env::current_dir().unwrap();
+        std::env::set_current_dir(&dir).unwrap();
 
-        let mut bad_imp = sample_entry("z", 3);
-        bad_imp.importance = f32::NAN;
-        assert!(engine.add(bad_imp).is_err());
+        let result = std::panic::catch_unwind(|| {
+            let json = r#"[{"id":"a","text":"t","embedding":[0.1,0.2],"timestamp":1.0,"importance":0.5}]"#;
+            std::fs::write("mismatch.json", json).unwrap();
 
-        // Dimension mismatch is also rejected.
-        assert!(engine.add(sample_entry("w", 2)).is_err());
+            let engine = RagEngine::new(4, 0.0, None, None).unwrap();
+            engine.add_impl(sample_entry("existing", 4)).unwrap();
+            let res = engine.load_append_impl("mismatch.json", 1000, |_, _| {});
+            assert!(res.is_err(), "must refuse when nothing matched");
+            assert_eq!(engine.len(), 1, "existing data must be untouched");
+        });
 
-        assert_eq!(engine.len(), 0);
+        std::env::set_current_dir(prev).unwrap();
+        let _ = std::fs::remove_dir_all(&dir);
+        result.unwrap();
     }
 
     #[test]
-    fn add_batch_dedups_and_counts_net_growth() {
-        let engine = RagEngine::new(3, 0.0);
-        // Two distinct ids + one duplicate id + one malformed (wrong dim) entry.
-        let mut malformed = sample_entry("bad", 2);
-        malformed.text = "wrong-dim".to_string();
-        let added = engine
-            .add_batch(vec![
-                sample_entry("a", 3),
-                sample_entry("b", 3),
-                sample_entry("a", 3), // duplicate id -> replace, not +1
-                malformed,            // dropped silently
-            ])
-            .unwrap();
-        // Net growth is 2 (a, b); duplicate replaces, malformed dropped.
-        assert_eq!(added, 2);
-        assert_eq!(engine.len(), 2);
+    fn matches_glob_supports_a_single_leading_or_trailing_wildcard() {
+        assert!(matches_glob("shard.jsonl", "*.jsonl"));
+        assert!(!matches_glob("shard.json", "*.jsonl"));
+        assert!(matches_glob("2024-01-01.jsonl", "2024-*.jsonl"));
+        assert!(!matches_glob("2023-01-01.jsonl", "2024-*.jsonl"));
+        assert!(matches_glob("exact.json", "exact.json"));
+        assert!(!matches_glob("not-exact.json", "exact.json"));
     }
 
     #[test]
-    fn add_rejects_negative_importance() {
-        // A negative importance is finite but flips final_score's sign — reject it
-        // at the trust boundary (rust-rag-M2). Distinct from the NaN/Inf check.
-        let engine = RagEngine::new(3, 0.0);
-        let mut neg = sample_entry("neg", 3);
-        neg.importance = -0.5;
-        assert!(engine.add(neg).is_err(), "negative importance must be rejected");
-        // Zero is a valid weight (clamp lower bound) and must still be accepted.
-        let mut zero = sample_entry("zero", 3);
-        zero.importance = 0.0;
-        assert!(engine.add(zero).is_ok(), "zero importance must be accepted");
-        assert_eq!(engine.len(), 1);
-    }
+    fn load_directory_merges_every_matching_shard_in_sorted_order() {
+        let _g = CWD_LOCK.lock().unwrap();
+        let dir = unique_tempdir("loaddir");
+        let prev = std::env::current_dir().unwrap();
+        std::env::set_current_dir(&dir).unwrap();
 
-    #[test]
-    fn add_batch_drops_negative_importance() {
-        // Silent-skip contract: a negative-importance entry is dropped, not raised.
-        let engine = RagEngine::new(3, 0.0);
-        let mut neg = sample_entry("neg", 3);
-        neg.importance = -1.0;
-        let added = engine
-            .add_batch(vec![sample_entry("ok", 3), neg])
+        let result = std::panic::catch_unwind(|| {
+            std::fs::create_dir("shards").unwrap();
+            std::fs::write(
+                "shards/2024-01-01.jsonl",
+                r#"[{"id":"a","text":"day one","embedding":[0.1,0.2,0.3,0.4],"timestamp":1.0,"importance":0.5}]"#,
+            )
             .unwrap();
-        assert_eq!(added, 1, "only the non-negative entry should be inserted");
-        assert_eq!(engine.len(), 1);
+            std::fs::write(
+                "shards/2024-01-02.json",
+                r#"[{"id":"b","text":"day two","embedding":[0.5,0.6,0.7,0.8],"timestamp":2.0,"importance":0.5},
+                    {"id":"a","text":"day two override","embedding":[0.5,0.6,0.7,0.8],"timestamp":2.0,"importance":0.5}]"#,
+            )
+            .unwrap();
+            // Not a shard: wrong extension, must be skipped by the default filter.
+            std::fs::write("shards/notes.txt", "ignore me").unwrap();
+
+            let engine = RagEngine::new(4, 0.0, None, None).unwrap();
+            let total = engine.load_directory_impl("shards", None).unwrap();
+            assert_eq!(
+                total, 3,
+                "1 entry from the first shard + 2 from the second; the collision on \"a\" counts in each shard's total"
+            );
+            assert_eq!(engine.len(), 2);
+            let entries = engine.entries.load();
+            assert_eq!(entries.get("a").unwrap().text, "day two override");
+            assert_eq!(entries.get("b").unwrap().text, "day two");
+        });
+
+        std::env::set_current_dir(prev).unwrap();
+        let _ = std::fs::remove_dir_all(&dir);
+        result.unwrap();
     }
 
     #[test]
-    fn load_drops_negative_importance_entries() {
+    fn load_directory_pattern_filters_to_matching_shards_only() {
         let _g = CWD_LOCK.lock().unwrap();
-        let dir = unique_tempdir("negimp");
+        let dir = unique_tempdir("loaddirpattern");
         let prev = std::env::current_dir().unwrap();
         std::env::set_current_dir(&dir).unwrap();
 
         let result = std::panic::catch_unwind(|| {
-            // Two entries, one with a negative importance which load() must skip.
-            let json = r#"[
-              {"id":"good","text":"g","embedding":[0.1,0.2,0.3,0.4],"timestamp":1.0,"importance":0.5},
-              {"id":"neg","text":"n","embedding":[0.1,0.2,0.3,0.4],"timestamp":1.0,"importance":-0.5}
-            ]"#;
-            std::fs::write("neg.json", json).unwrap();
+            std::fs::create_dir("shards").unwrap();
+            std::fs::write(
+                "shards/2024-01-01.jsonl",
+                r#"[{"id":"a","text":"kept","embedding":[0.1,0.2,0.3,0.4],"timestamp":1.0,"importance":0.5}]"#,
+            )
+            .unwrap();
+            std::fs::write(
+                "shards/2023-12-31.jsonl",
+                r#"[{"id":"b","text":"filtered out","embedding":[0.5,0.6,0.7,0.8],"timestamp":1.0,"importance":0.5}]"#,
+            )
+            .unwrap();
 
-            let engine = RagEngine::new(4, 0.0);
-            let count = engine.load("neg.json").unwrap();
-            assert_eq!(count, 1, "only the non-negative-importance entry should load");
+            let engine = RagEngine::new(4, 0.0, None, None).unwrap();
+            let total = engine
+                .load_directory_impl("shards", Some("2024-*.jsonl"))
+                .unwrap();
+            assert_eq!(total, 1);
+            assert_eq!(engine.len(), 1);
+            assert!(engine.entries.load().contains_key("a"));
         });
 
         std::env::set_current_dir(prev).unwrap();
@@ -872,8 +4531,8 @@ mod tests {
             f.flush().unwrap();
             drop(f);
 
-            let engine = RagEngine::new(4, 0.0);
-            let err = engine.load("big.json");
+            let engine = RagEngine::new(4, 0.0, None, None).unwrap();
+            let err = engine.load_impl("big.json", 1000, |_, _| {});
             assert!(err.is_err(), "oversized file must be rejected");
         });
 
@@ -895,9 +4554,9 @@ mod tests {
             let json = r#"[{"id":"a","text":"t","embedding":[0.1,0.2],"timestamp":1.0,"importance":0.5}]"#;
             std::fs::write("mismatch.json", json).unwrap();
 
-            let engine = RagEngine::new(4, 0.0);
-            engine.add(sample_entry("existing", 4)).unwrap();
-            let res = engine.load("mismatch.json");
+            let engine = RagEngine::new(4, 0.0, None, None).unwrap();
+            engine.add_impl(sample_entry("existing", 4)).unwrap();
+            let res = engine.load_impl("mismatch.json", 1000, |_, _| {});
             assert!(res.is_err(), "must refuse to replace when nothing matched");
             // Existing data must be untouched.
             assert_eq!(engine.len(), 1);
@@ -927,8 +4586,8 @@ mod tests {
             ]"#;
             std::fs::write("mixed.json", json).unwrap();
 
-            let engine = RagEngine::new(4, 0.0);
-            let count = engine.load("mixed.json").unwrap();
+            let engine = RagEngine::new(4, 0.0, None, None).unwrap();
+            let count = engine.load_impl("mixed.json", 1000, |_, _| {}).unwrap();
             assert_eq!(count, 1, "only the all-finite entry should load");
         });
 
@@ -958,8 +4617,8 @@ mod tests {
             ]"#;
             std::fs::write("overlen.json", json).unwrap();
 
-            let engine = RagEngine::new(4, 0.0);
-            let count = engine.load("overlen.json").unwrap();
+            let engine = RagEngine::new(4, 0.0, None, None).unwrap();
+            let count = engine.load_impl("overlen.json", 1000, |_, _| {}).unwrap();
             assert_eq!(count, 1, "only the exact-length entry should load; over-length rejected");
         });
 
@@ -982,8 +4641,11 @@ mod tests {
         let result = std::panic::catch_unwind(|| {
             std::fs::write("real.json", "[]").unwrap();
             symlink("real.json", "link.json").unwrap();
-            let engine = RagEngine::new(4, 0.0);
-            assert!(engine.load("link.json").is_err(), "leaf symlink must be refused");
+            let engine = RagEngine::new(4, 0.0, None, None).unwrap();
+            assert!(
+                engine.load_impl("link.json", 1000, |_, _| {}).is_err(),
+                "leaf symlink must be refused"
+            );
         });
 
         std::env::set_current_dir(prev).unwrap();
@@ -1007,19 +4669,21 @@ mod tests {
             std::fs::create_dir("outside").unwrap();
             symlink("outside", "link_dir").unwrap();
 
-            let engine = RagEngine::new(4, 0.0);
-            engine.add(sample_entry("a", 4)).unwrap();
+            let engine = RagEngine::new(4, 0.0, None, None).unwrap();
+            engine.add_impl(sample_entry("a", 4)).unwrap();
             // save() must refuse to write THROUGH the symlinked dir.
             assert!(
-                engine.save("link_dir/dump.json").is_err(),
+                engine
+                    .save_impl("link_dir/dump.json", 1000, true, false, |_, _| {})
+                    .is_err(),
                 "save through symlinked parent dir must be refused"
             );
 
             // And load() must refuse to read through it.
             std::fs::write("outside/dump.json", "[]").unwrap();
-            let loader = RagEngine::new(4, 0.0);
+            let loader = RagEngine::new(4, 0.0, None, None).unwrap();
             assert!(
-                loader.load("link_dir/dump.json").is_err(),
+                loader.load_impl("link_dir/dump.json", 1000, |_, _| {}).is_err(),
                 "load through symlinked parent dir must be refused"
             );
         });
@@ -1048,6 +4712,613 @@ mod tests {
         let _ = std::fs::remove_dir_all(&dir);
         result.unwrap();
     }
+
+    // ---- rank_and_truncate: deterministic tie-breaking -------------------
+
+    fn result(id: &str, score: f32, timestamp: f64) -> SearchResult {
+        SearchResult {
+            id: id.to_string(),
+            text: String::new(),
+            score,
+            timestamp,
+            metadata: None,
+        }
+    }
+
+    #[test]
+    fn rank_and_truncate_orders_by_score_descending() {
+        let mut results = vec![result("a", 0.2, 0.0), result("b", 0.9, 0.0), result("c", 0.5, 0.0)];
+        rank_and_truncate(&mut results, 10);
+        assert_eq!(
+            results.iter().map(|r| r.id.as_str()).collect::<Vec<_>>(),
+            vec!["b", "c", "a"]
+        );
+    }
+
+    #[test]
+    fn rank_and_truncate_breaks_score_ties_by_id_then_timestamp() {
+        // Same score, inserted in an order that would survive an unstable
+        // sort's default (HashMap-iteration-dependent) ordering if the
+        // secondary keys weren't applied.
+        let mut results = vec![
+            result("zebra", 0.5, 100.0),
+            result("apple", 0.5, 200.0),
+            result("apple", 0.5, 50.0),
+        ];
+        rank_and_truncate(&mut results, 10);
+        // "apple" sorts before "zebra"; between the two "apple" entries the
+        // earlier timestamp comes first.
+        assert_eq!(results[0].id, "apple");
+        assert_eq!(results[0].timestamp, 50.0);
+        assert_eq!(results[1].id, "apple");
+        assert_eq!(results[1].timestamp, 200.0);
+        assert_eq!(results[2].id, "zebra");
+    }
+
+    #[test]
+    fn rank_and_truncate_is_stable_across_repeated_calls() {
+        // Same input, run twice, must yield byte-identical output order —
+        // the property the request cares about (reproducible pagination).
+        let make = || {
+            vec![
+                result("b", 0.5, 1.0),
+                result("a", 0.5, 1.0),
+                result("c", 0.9, 1.0),
+            ]
+        };
+        let mut first = make();
+        let mut second = make();
+        rank_and_truncate(&mut first, 10);
+        rank_and_truncate(&mut second, 10);
+        let ids_first: Vec<_> = first.iter().map(|r| r.id.clone()).collect();
+        let ids_second: Vec<_> = second.iter().map(|r| r.id.clone()).collect();
+        assert_eq!(ids_first, ids_second);
+    }
+
+    #[test]
+    fn rank_and_truncate_respects_top_k_after_sorting() {
+        let mut results = vec![result("a", 0.1, 0.0), result("b", 0.9, 0.0), result("c", 0.5, 0.0)];
+        rank_and_truncate(&mut results, 2);
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].id, "b");
+        assert_eq!(results[1].id, "c");
+    }
+
+    #[test]
+    fn rank_and_truncate_top_k_zero_returns_every_result_sorted() {
+        let mut results = vec![result("a", 0.1, 0.0), result("b", 0.9, 0.0), result("c", 0.5, 0.0)];
+        rank_and_truncate(&mut results, 0);
+        assert_eq!(
+            results.iter().map(|r| r.id.as_str()).collect::<Vec<_>>(),
+            vec!["b", "c", "a"]
+        );
+    }
+
+    // ---- rank_and_truncate_full: search_full()'s (MemoryEntry, f32) pairs -
+
+    #[test]
+    fn rank_and_truncate_full_orders_by_score_descending() {
+        let mut results = vec![
+            (sample_entry("a", 3), 0.2),
+            (sample_entry("b", 3), 0.9),
+            (sample_entry("c", 3), 0.5),
+        ];
+        rank_and_truncate_full(&mut results, 10);
+        assert_eq!(
+            results.iter().map(|(e, _)| e.id.as_str()).collect::<Vec<_>>(),
+            vec!["b", "c", "a"]
+        );
+    }
+
+    #[test]
+    fn rank_and_truncate_full_matches_rank_and_truncate_tie_break_order() {
+        // Same score, same tie-break inputs as
+        // rank_and_truncate_breaks_score_ties_by_id_then_timestamp, so the two
+        // ranking functions can't silently drift apart.
+        let mut zebra = sample_entry("zebra", 3);
+        zebra.timestamp = 100.0;
+        let mut apple_late = sample_entry("apple", 3);
+        apple_late.timestamp = 200.0;
+        let mut apple_early = sample_entry("apple", 3);
+        apple_early.timestamp = 50.0;
+
+        let mut results = vec![(zebra, 0.5), (apple_late, 0.5), (apple_early, 0.5)];
+        rank_and_truncate_full(&mut results, 10);
+        assert_eq!(results[0].0.id, "apple");
+        assert_eq!(results[0].0.timestamp, 50.0);
+        assert_eq!(results[1].0.id, "apple");
+        assert_eq!(results[1].0.timestamp, 200.0);
+        assert_eq!(results[2].0.id, "zebra");
+    }
+
+    #[test]
+    fn rank_and_truncate_full_respects_top_k_after_sorting() {
+        let mut results = vec![
+            (sample_entry("a", 3), 0.1),
+            (sample_entry("b", 3), 0.9),
+            (sample_entry("c", 3), 0.5),
+        ];
+        rank_and_truncate_full(&mut results, 2);
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].0.id, "b");
+        assert_eq!(results[1].0.id, "c");
+    }
+
+    // ---- mmr_select: search_mmr()'s greedy diversity-aware selection ----
+
+    fn entry_with_embedding(id: &str, embedding: Vec<f32>) -> MemoryEntry {
+        let mut entry = sample_entry(id, embedding.len());
+        entry.embedding = embedding;
+        entry
+    }
+
+    #[test]
+    fn mmr_select_lambda_mult_one_matches_plain_relevance_ranking() {
+        let candidates = vec![
+            (entry_with_embedding("a", vec![1.0, 0.0]), 0.9),
+            (entry_with_embedding("b", vec![1.0, 0.0]), 0.5),
+            (entry_with_embedding("c", vec![0.0, 1.0]), 0.7),
+        ];
+        let selected = mmr_select(candidates, 3, 1.0);
+        assert_eq!(
+            selected.iter().map(|r| r.id.as_str()).collect::<Vec<_>>(),
+            vec!["a", "c", "b"]
+        );
+    }
+
+    #[test]
+    fn mmr_select_penalizes_near_duplicates_of_already_selected() {
+        // "b" is an exact duplicate embedding of "a" and scores nearly as
+        // well on relevance, but "c" is equally relevant and orthogonal to
+        // "a" — a mid lambda_mult should prefer "c" over the redundant "b".
+        let candidates = vec![
+            (entry_with_embedding("a", vec![1.0, 0.0]), 0.9),
+            (entry_with_embedding("b", vec![1.0, 0.0]), 0.85),
+            (entry_with_embedding("c", vec![0.0, 1.0]), 0.8),
+        ];
+        let selected = mmr_select(candidates, 2, 0.5);
+        assert_eq!(
+            selected.iter().map(|r| r.id.as_str()).collect::<Vec<_>>(),
+            vec!["a", "c"]
+        );
+    }
+
+    #[test]
+    fn mmr_select_lambda_mult_zero_ignores_relevance_after_the_first_pick() {
+        // The first pick always has nothing selected yet (diversity term is
+        // 0.0 for everyone), so it still goes to the most relevant
+        // candidate; only subsequent picks ignore relevance entirely.
+        let candidates = vec![
+            (entry_with_embedding("a", vec![1.0, 0.0]), 0.9),
+            (entry_with_embedding("b", vec![1.0, 0.0]), 0.1),
+            (entry_with_embedding("c", vec![0.0, 1.0]), 0.5),
+        ];
+        let selected = mmr_select(candidates, 2, 0.0);
+        assert_eq!(selected[0].id, "a");
+        assert_eq!(selected[1].id, "c");
+    }
+
+    #[test]
+    fn mmr_select_top_k_larger_than_candidates_returns_all_of_them() {
+        let candidates = vec![
+            (entry_with_embedding("a", vec![1.0, 0.0]), 0.9),
+            (entry_with_embedding("b", vec![0.0, 1.0]), 0.5),
+        ];
+        let selected = mmr_select(candidates, 10, 0.5);
+        assert_eq!(selected.len(), 2);
+    }
+
+    #[test]
+    fn mmr_select_breaks_score_ties_by_ascending_id() {
+        let candidates = vec![
+            (entry_with_embedding("zebra", vec![1.0, 0.0]), 0.5),
+            (entry_with_embedding("apple", vec![1.0, 0.0]), 0.5),
+        ];
+        let selected = mmr_select(candidates, 2, 1.0);
+        assert_eq!(selected[0].id, "apple");
+        assert_eq!(selected[1].id, "zebra");
+    }
+
+    // ---- apply_group_by: search()'s group_by/drop_ungrouped dedup step ----
+
+    #[test]
+    fn apply_group_by_none_is_a_no_op() {
+        let entries = vec![sample_entry("a", 3)];
+        let results = vec![result("a", 0.5, 0.0)];
+        let grouped = apply_group_by(results, &entries, None, false);
+        assert_eq!(grouped.len(), 1);
+        assert_eq!(grouped[0].id, "a");
+        assert_eq!(grouped[0].score, 0.5);
+    }
+
+    #[test]
+    fn apply_group_by_keeps_only_the_best_score_per_group_value() {
+        let entries = vec![
+            sample_entry_with_metadata("a", 3, "source", "doc1"),
+            sample_entry_with_metadata("b", 3, "source", "doc1"),
+            sample_entry_with_metadata("c", 3, "source", "doc2"),
+        ];
+        let results = vec![
+            result("a", 0.2, 0.0),
+            result("b", 0.9, 0.0),
+            result("c", 0.5, 0.0),
+        ];
+        let mut grouped = apply_group_by(results, &entries, Some("source"), false);
+        grouped.sort_by(|x, y| x.id.cmp(&y.id));
+        assert_eq!(grouped.len(), 2);
+        assert_eq!(grouped[0].id, "b");
+        assert_eq!(grouped[1].id, "c");
+    }
+
+    #[test]
+    fn apply_group_by_ungrouped_entries_each_survive_as_singleton_groups() {
+        // Neither entry has "source" metadata, and drop_ungrouped is false, so
+        // both survive rather than colliding into one shared "no group" slot.
+        let entries = vec![sample_entry("a", 3), sample_entry("b", 3)];
+        let results = vec![result("a", 0.2, 0.0), result("b", 0.9, 0.0)];
+        let mut grouped = apply_group_by(results, &entries, Some("source"), false);
+        grouped.sort_by(|x, y| x.id.cmp(&y.id));
+        assert_eq!(grouped.len(), 2);
+    }
+
+    #[test]
+    fn apply_group_by_drop_ungrouped_removes_entries_missing_the_key() {
+        let entries = vec![
+            sample_entry_with_metadata("a", 3, "source", "doc1"),
+            sample_entry("b", 3),
+        ];
+        let results = vec![result("a", 0.2, 0.0), result("b", 0.9, 0.0)];
+        let grouped = apply_group_by(results, &entries, Some("source"), true);
+        assert_eq!(grouped.len(), 1);
+        assert_eq!(grouped[0].id, "a");
+    }
+
+    #[test]
+    fn apply_group_by_missing_entry_lookup_is_treated_as_ungrouped() {
+        // A result whose id has no matching entry (shouldn't happen via
+        // search(), but apply_group_by shouldn't panic on it) behaves the
+        // same as an entry with no metadata.
+        let entries: Vec<MemoryEntry> = vec![];
+        let kept = apply_group_by(
+            vec![result("missing", 0.5, 0.0)],
+            &entries,
+            Some("source"),
+            false,
+        );
+        assert_eq!(kept.len(), 1);
+        assert_eq!(kept[0].id, "missing");
+
+        let dropped = apply_group_by(
+            vec![result("missing", 0.5, 0.0)],
+            &entries,
+            Some("source"),
+            true,
+        );
+        assert!(dropped.is_empty());
+    }
+
+    // ---- matches_metadata_filter: search()'s filter gate -------------------
+
+    #[test]
+    fn matches_metadata_filter_none_matches_everything() {
+        assert!(matches_metadata_filter(&sample_entry("a", 3), None));
+        assert!(matches_metadata_filter(
+            &sample_entry_with_metadata("a", 3, "source", "doc1"),
+            None
+        ));
+    }
+
+    #[test]
+    fn matches_metadata_filter_empty_map_matches_everything() {
+        let empty = HashMap::new();
+        assert!(matches_metadata_filter(&sample_entry("a", 3), Some(&empty)));
+    }
+
+    #[test]
+    fn matches_metadata_filter_requires_every_key_to_match() {
+        let mut entry = sample_entry("a", 3);
+        entry.metadata = Some(HashMap::from([
+            ("source".to_string(), "doc1".to_string()),
+            ("lang".to_string(), "en".to_string()),
+        ]));
+
+        let matching = HashMap::from([("source".to_string(), "doc1".to_string())]);
+        assert!(matches_metadata_filter(&entry, Some(&matching)));
+
+        let all_matching = HashMap::from([
+            ("source".to_string(), "doc1".to_string()),
+            ("lang".to_string(), "en".to_string()),
+        ]);
+        assert!(matches_metadata_filter(&entry, Some(&all_matching)));
+
+        let one_mismatched = HashMap::from([
+            ("source".to_string(), "doc1".to_string()),
+            ("lang".to_string(), "fr".to_string()),
+        ]);
+        assert!(!matches_metadata_filter(&entry, Some(&one_mismatched)));
+    }
+
+    #[test]
+    fn matches_metadata_filter_entry_with_no_metadata_only_matches_an_empty_filter() {
+        let entry = sample_entry("a", 3);
+        let filter = HashMap::from([("source".to_string(), "doc1".to_string())]);
+        assert!(!matches_metadata_filter(&entry, Some(&filter)));
+        assert!(matches_metadata_filter(&entry, Some(&HashMap::new())));
+    }
+
+    // ---- score_entry_value: score_entry's core, without the SearchResult
+    // clone-out -- what search_full's scoring pass reuses.
+
+    #[test]
+    fn score_entry_value_matches_score_entry_score() {
+        let query = vec![1.0_f32, 0.0, 0.0];
+        let query_norm = vector_norm(&query);
+        let mut entry = sample_entry("a", 3);
+        entry.embedding = vec![1.0, 0.0, 0.0];
+        entry.importance = 0.8;
+
+        let via_score_entry = score_entry(&query, query_norm, &entry, 0.0, 0.0, 0.0, false, None)
+            .expect("should clear a zero threshold");
+        let via_value = score_entry_value(&query, query_norm, &entry, 0.0, 0.0, 0.0, false, None)
+            .expect("should clear a zero threshold");
+        assert_eq!(via_score_entry.score, via_value);
+    }
+
+    #[test]
+    fn score_entry_value_returns_none_below_threshold() {
+        let query = vec![1.0_f32, 0.0, 0.0];
+        let query_norm = vector_norm(&query);
+        let mut entry = sample_entry("a", 3);
+        entry.embedding = vec![0.0, 1.0, 0.0]; // orthogonal -> score 0.0
+        assert!(score_entry_value(&query, query_norm, &entry, 0.0, 0.0, 0.5, false, None).is_none());
+    }
+
+    // ---- score_entry: search()'s per-entry scoring, with and without prune
+
+    #[test]
+    fn score_entry_matches_between_pruned_and_exact_paths() {
+        let query = vec![1.0_f32, 0.0, 0.0];
+        let query_norm = vector_norm(&query);
+        let mut entry = sample_entry("a", 3);
+        entry.embedding = vec![1.0, 0.0, 0.0]; // identical -> base score 1.0
+        entry.importance = 1.0;
+
+        let exact = score_entry(&query, query_norm, &entry, 0.0, 0.0, 0.5, false, None).unwrap();
+        let pruned = score_entry(&query, query_norm, &entry, 0.0, 0.0, 0.5, true, None).unwrap();
+        assert_eq!(exact.score, pruned.score);
+        assert_eq!(exact.id, "a");
+    }
+
+    #[test]
+    fn score_entry_prunes_below_threshold_without_changing_the_result_set() {
+        let query = vec![1.0_f32, 0.0, 0.0];
+        let query_norm = vector_norm(&query);
+        let mut entry = sample_entry("a", 3);
+        entry.embedding = vec![0.0, 1.0, 0.0]; // orthogonal -> base score 0.0
+        entry.importance = 1.0;
+
+        assert!(score_entry(&query, query_norm, &entry, 0.0, 0.0, 0.5, false, None).is_none());
+        assert!(score_entry(&query, query_norm, &entry, 0.0, 0.0, 0.5, true, None).is_none());
+    }
+
+    #[test]
+    fn score_entry_pruning_accounts_for_importance_amplification() {
+        // base cosine score 0.5 alone would fail a 0.6 threshold, but this
+        // entry's importance (2.0) amplifies it past the threshold -- pruning
+        // must not discard it just because base_score < threshold.
+        let query = vec![1.0_f32, 0.0];
+        let query_norm = vector_norm(&query);
+        // cos(60deg) = 0.5, times importance 2.0 = 1.0, above 0.6.
+        let mut entry = sample_entry("a", 2);
+        entry.embedding = vec![1.0, 3.0_f32.sqrt()];
+        entry.importance = 2.0;
+
+        let pruned = score_entry(&query, query_norm, &entry, 0.0, 0.0, 0.6, true, None);
+        assert!(pruned.is_some(), "importance amplification must survive pruning");
+    }
+
+    #[test]
+    fn score_entry_zero_importance_short_circuits_without_a_positive_threshold() {
+        let query = vec![1.0_f32, 0.0];
+        let query_norm = vector_norm(&query);
+        let mut entry = sample_entry("a", 2);
+        entry.embedding = vec![1.0, 0.0];
+        entry.importance = 0.0;
+
+        assert!(score_entry(&query, query_norm, &entry, 0.0, 0.0, 0.1, true, None).is_none());
+        // A threshold of 0.0 (or below) is satisfiable by a guaranteed-zero score.
+        assert!(score_entry(&query, query_norm, &entry, 0.0, 0.0, 0.0, true, None).is_some());
+    }
+
+    #[test]
+    fn score_entry_dimension_mask_ignores_masked_component_and_prune() {
+        // Third dimension disagrees wildly, but the mask zeroes it out. Also
+        // pass prune=true to confirm masked scoring takes the exact path
+        // regardless (the floor bound doesn't apply to masked scores).
+        let query = vec![1.0_f32, 0.0, 999.0];
+        let query_norm = vector_norm(&query);
+        let mut entry = sample_entry("a", 3);
+        entry.embedding = vec![1.0, 0.0, -999.0];
+        entry.importance = 1.0;
+        let mask = [true, true, false];
+
+        let masked =
+            score_entry(&query, query_norm, &entry, 0.0, 0.0, 0.5, true, Some(&mask)).unwrap();
+        assert!((masked.score - 1.0).abs() < 1e-5);
+    }
+
+    // ---- resolve_time_decay_factor: search()'s half_life_hours ergonomics
+
+    #[test]
+    fn resolve_time_decay_factor_defaults_to_the_raw_factor() {
+        assert_eq!(resolve_time_decay_factor(0.05, None).unwrap(), 0.05);
+        assert_eq!(resolve_time_decay_factor(0.0, None).unwrap(), 0.0);
+    }
+
+    #[test]
+    fn resolve_time_decay_factor_converts_half_life_to_ln2_over_half_life() {
+        let factor = resolve_time_decay_factor(0.0, Some(24.0)).unwrap();
+        assert!((factor - std::f64::consts::LN_2 / 24.0).abs() < 1e-12);
+        // Sanity check the intuitive meaning: at age == half_life_hours, the
+        // resulting decay term is exactly 0.5.
+        assert!(((-factor * 24.0).exp() - 0.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn resolve_time_decay_factor_rejects_both_params_set() {
+        assert!(resolve_time_decay_factor(0.1, Some(24.0)).is_err());
+    }
+
+    #[test]
+    fn resolve_time_decay_factor_rejects_non_positive_half_life() {
+        assert!(resolve_time_decay_factor(0.0, Some(0.0)).is_err());
+        assert!(resolve_time_decay_factor(0.0, Some(-1.0)).is_err());
+        assert!(resolve_time_decay_factor(0.0, Some(f64::NAN)).is_err());
+    }
+
+    // ---- list_entries_impl: sort-without-a-query pagination --------------
+
+    fn entry_at(id: &str, timestamp: f64, importance: f32) -> MemoryEntry {
+        MemoryEntry {
+            id: id.to_string(),
+            text: format!("text-{id}"),
+            embedding: vec![0.1],
+            timestamp,
+            importance,
+            metadata: None,
+        }
+    }
+
+    fn entries_map(entries: Vec<MemoryEntry>) -> HashMap<String, MemoryEntry> {
+        entries.into_iter().map(|e| (e.id.clone(), e)).collect()
+    }
+
+    #[test]
+    fn list_entries_sorts_by_timestamp_descending() {
+        let entries = entries_map(vec![
+            entry_at("old", 1.0, 0.5),
+            entry_at("new", 3.0, 0.5),
+            entry_at("mid", 2.0, 0.5),
+        ]);
+        let results = list_entries_impl(&entries, "timestamp", 10, 0).unwrap();
+        assert_eq!(
+            results.iter().map(|r| r.id.as_str()).collect::<Vec<_>>(),
+            vec!["new", "mid", "old"]
+        );
+        assert_eq!(results[0].score, 3.0);
+    }
+
+    #[test]
+    fn list_entries_sorts_by_importance_descending() {
+        let entries = entries_map(vec![
+            entry_at("low", 1.0, 0.1),
+            entry_at("high", 1.0, 0.9),
+            entry_at("mid", 1.0, 0.5),
+        ]);
+        let results = list_entries_impl(&entries, "importance", 10, 0).unwrap();
+        assert_eq!(
+            results.iter().map(|r| r.id.as_str()).collect::<Vec<_>>(),
+            vec!["high", "mid", "low"]
+        );
+        assert_eq!(results[0].score, 0.9);
+    }
+
+    #[test]
+    fn list_entries_paginates_with_limit_and_offset() {
+        let entries = entries_map(vec![
+            entry_at("a", 4.0, 0.5),
+            entry_at("b", 3.0, 0.5),
+            entry_at("c", 2.0, 0.5),
+            entry_at("d", 1.0, 0.5),
+        ]);
+        let page = list_entries_impl(&entries, "timestamp", 2, 1).unwrap();
+        assert_eq!(
+            page.iter().map(|r| r.id.as_str()).collect::<Vec<_>>(),
+            vec!["b", "c"]
+        );
+    }
+
+    #[test]
+    fn list_entries_rejects_unknown_sort_key() {
+        let entries = entries_map(vec![entry_at("a", 1.0, 0.5)]);
+        assert!(list_entries_impl(&entries, "relevance", 10, 0).is_err());
+    }
+
+    #[test]
+    fn list_entries_breaks_ties_by_id() {
+        let entries = entries_map(vec![
+            entry_at("zebra", 1.0, 0.5),
+            entry_at("apple", 1.0, 0.5),
+        ]);
+        let results = list_entries_impl(&entries, "timestamp", 10, 0).unwrap();
+        assert_eq!(results[0].id, "apple");
+        assert_eq!(results[1].id, "zebra");
+    }
+
+    #[test]
+    fn iter_entries_orders_by_id_ascending() {
+        let entries = entries_map(vec![
+            entry_at("zebra", 3.0, 0.5),
+            entry_at("apple", 1.0, 0.5),
+            entry_at("mango", 2.0, 0.5),
+        ]);
+        let page = iter_entries_impl(&entries, 0, 10);
+        assert_eq!(
+            page.iter().map(|e| e.id.as_str()).collect::<Vec<_>>(),
+            vec!["apple", "mango", "zebra"]
+        );
+    }
+
+    #[test]
+    fn iter_entries_paginates_with_offset_and_limit() {
+        let entries = entries_map(vec![
+            entry_at("a", 1.0, 0.5),
+            entry_at("b", 1.0, 0.5),
+            entry_at("c", 1.0, 0.5),
+            entry_at("d", 1.0, 0.5),
+        ]);
+        let page = iter_entries_impl(&entries, 1, 2);
+        assert_eq!(
+            page.iter().map(|e| e.id.as_str()).collect::<Vec<_>>(),
+            vec!["b", "c"]
+        );
+    }
+
+    #[test]
+    fn iter_entries_offset_past_the_end_is_empty() {
+        let entries = entries_map(vec![entry_at("a", 1.0, 0.5)]);
+        assert!(iter_entries_impl(&entries, 5, 10).is_empty());
+    }
+
+    #[test]
+    fn iter_entries_carries_the_full_entry_including_embedding() {
+        let entries = entries_map(vec![entry_at("a", 1.0, 0.5)]);
+        let page = iter_entries_impl(&entries, 0, 10);
+        assert_eq!(page[0].embedding, vec![0.1]);
+    }
+
+    #[test]
+    fn scores_for_impl_matches_cosine_similarity_in_query_order() {
+        let entries = entries_map(vec![entry_at("a", 1.0, 0.5), entry_at("b", 1.0, 0.5)]);
+        let ids = vec!["b".to_string(), "a".to_string()];
+        let scores = scores_for_impl(&entries, &[0.1], &ids);
+        assert_eq!(scores, vec![1.0, 1.0]);
+    }
+
+    #[test]
+    fn scores_for_impl_returns_nan_for_a_missing_id() {
+        let entries = entries_map(vec![entry_at("a", 1.0, 0.5)]);
+        let ids = vec!["a".to_string(), "missing".to_string()];
+        let scores = scores_for_impl(&entries, &[0.1], &ids);
+        assert_eq!(scores[0], 1.0);
+        assert!(scores[1].is_nan());
+    }
+
+    #[test]
+    fn scores_for_impl_empty_ids_is_empty() {
+        let entries = entries_map(vec![entry_at("a", 1.0, 0.5)]);
+        assert!(scores_for_impl(&entries, &[0.1], &[]).is_empty());
+    }
 }
 
 /// Python module
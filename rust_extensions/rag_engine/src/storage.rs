@@ -0,0 +1,886 @@
+//! Fixed-width vector storage with an optional memory-mapped file backing.
+//!
+//! `VectorStorage` stores `dimension`-wide `f32` vectors in a preallocated,
+//! append-only slab. [`VectorStorage::create`]/[`VectorStorage::open`] back
+//! the slab with a memory-mapped file (a small [`StorageHeader`] followed by
+//! `capacity * dimension` `f32`s); [`VectorStorage::new`] backs it with a
+//! plain `Vec<f32>` instead, so callers that don't need persistence (mainly
+//! unit tests) get the same `push`/`get`/`iter`/`len` semantics without
+//! touching the filesystem.
+//!
+//! The on-disk format (header fields and vector floats alike) is explicitly
+//! **little-endian**, regardless of the host's native byte order — a store
+//! written on one machine must load correctly on another with different
+//! endianness. `create`/`open` convert header fields with `to_le`/`from_le`.
+//! For the bulk vector data, [`VectorStorage::open`] byte-swaps every
+//! existing vector from disk-little-endian to this host's native order
+//! in-place once, up front, so `push`/`get` can stay zero-copy and operate
+//! purely in native order; [`VectorStorage::flush`] swaps back to
+//! little-endian, fsyncs, then swaps back to native so the object remains
+//! usable afterward. All of this is a no-op on little-endian hosts (the
+//! overwhelming common case), where native and on-disk order already match.
+
+use bytemuck::{Pod, Zeroable};
+use memmap2::{MmapMut, MmapOptions};
+use std::fs::{File, OpenOptions};
+use std::path::Path;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum RagError {
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("dimension mismatch: expected {expected}, got {got}")]
+    DimensionMismatch { expected: usize, got: usize },
+
+    #[error("storage capacity ({capacity}) exceeded")]
+    CapacityExceeded { capacity: usize },
+
+    #[error("dimension/capacity too large to allocate")]
+    CapacityOverflow,
+
+    #[error("invalid storage header: {0}")]
+    InvalidHeader(String),
+
+    #[error("corrupt storage: {0}")]
+    Corrupt(String),
+}
+
+const MAGIC: u32 = 0x5241_4753; // "RAGS"
+const FORMAT_VERSION: u32 = 1;
+
+#[repr(C)]
+#[derive(Clone, Copy, Pod, Zeroable)]
+struct StorageHeader {
+    magic: u32,
+    version: u32,
+    dimension: u32,
+    count: u32,
+}
+
+const HEADER_SIZE: usize = std::mem::size_of::<StorageHeader>();
+
+/// Byte-swap the first `count` stored vectors (each `dimension` floats) of a
+/// disk-backed mapping between little-endian and native order, in place.
+/// `cfg!(target_endian = "big")` is a runtime-evaluated constant, not a
+/// `#[cfg(...)]` attribute, so both branches stay type/borrow-checked by
+/// `cargo build`/`clippy` on every host -- the compiler simply eliminates
+/// this whole call as dead code on little-endian targets, where the swap
+/// would be a no-op anyway.
+fn swap_stored_vectors_endian(mmap: &mut MmapMut, count: usize, dimension: usize) {
+    if !cfg!(target_endian = "big") {
+        return;
+    }
+    let vector_bytes = dimension * std::mem::size_of::<f32>();
+    let data = &mut mmap[HEADER_SIZE..HEADER_SIZE + count * vector_bytes];
+    let floats: &mut [f32] = bytemuck::cast_slice_mut(data);
+    for value in floats {
+        *value = f32::from_bits(value.to_bits().swap_bytes());
+    }
+}
+
+enum Backing {
+    Mmap {
+        mmap: MmapMut,
+        // Kept alive alongside the mapping; never read directly once mapped.
+        _file: File,
+    },
+    Memory(Vec<f32>),
+}
+
+/// Preallocated, append-only store of fixed-width `f32` vectors.
+pub struct VectorStorage {
+    dimension: usize,
+    capacity: usize,
+    count: usize,
+    backing: Backing,
+}
+
+impl VectorStorage {
+    /// An in-memory store with the same semantics as a file-backed one, minus
+    /// persistence. Intended for tests and other callers that don't want a
+    /// filesystem dependency.
+    pub fn new(dimension: usize, capacity: usize) -> Self {
+        Self {
+            dimension,
+            capacity,
+            count: 0,
+            backing: Backing::Memory(vec![0.0; dimension.saturating_mul(capacity)]),
+        }
+    }
+
+    /// Create a new memory-mapped store at `path`, preallocated to hold up to
+    /// `capacity` vectors of `dimension` floats. Overwrites any existing file.
+    pub fn create(path: &Path, dimension: usize, capacity: usize) -> Result<Self, RagError> {
+        let vector_bytes = dimension
+            .checked_mul(std::mem::size_of::<f32>())
+            .ok_or(RagError::CapacityOverflow)?;
+        let data_bytes = vector_bytes
+            .checked_mul(capacity)
+            .ok_or(RagError::CapacityOverflow)?;
+        let total_bytes = HEADER_SIZE
+            .checked_add(data_bytes)
+            .ok_or(RagError::CapacityOverflow)?;
+
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(path)?;
+        file.set_len(total_bytes as u64)?;
+
+        let mut mmap = unsafe { MmapOptions::new().map_mut(&file)? };
+        let header = StorageHeader {
+            magic: MAGIC.to_le(),
+            version: FORMAT_VERSION.to_le(),
+            dimension: (dimension as u32).to_le(),
+            count: 0u32.to_le(),
+        };
+        mmap[..HEADER_SIZE].copy_from_slice(bytemuck::bytes_of(&header));
+        mmap.flush()?;
+
+        Ok(Self {
+            dimension,
+            capacity,
+            count: 0,
+            backing: Backing::Mmap { mmap, _file: file },
+        })
+    }
+
+    /// Open an existing memory-mapped store, validating the header.
+    pub fn open(path: &Path) -> Result<Self, RagError> {
+        let file = OpenOptions::new().read(true).write(true).open(path)?;
+        let mut mmap = unsafe { MmapOptions::new().map_mut(&file)? };
+
+        if mmap.len() < HEADER_SIZE {
+            return Err(RagError::InvalidHeader(
+                "file is smaller than the storage header".to_string(),
+            ));
+        }
+        let raw_header: StorageHeader = *bytemuck::from_bytes(&mmap[..HEADER_SIZE]);
+        let header = StorageHeader {
+            magic: u32::from_le(raw_header.magic),
+            version: u32::from_le(raw_header.version),
+            dimension: u32::from_le(raw_header.dimension),
+            count: u32::from_le(raw_header.count),
+        };
+        if header.magic != MAGIC {
+            return Err(RagError::InvalidHeader(format!(
+                "bad magic {:#010x} (expected {:#010x})",
+                header.magic, MAGIC
+            )));
+        }
+        if header.version != FORMAT_VERSION {
+            return Err(RagError::InvalidHeader(format!(
+                "unsupported format version {} (expected {})",
+                header.version, FORMAT_VERSION
+            )));
+        }
+
+        let dimension = header.dimension as usize;
+        let vector_bytes = dimension * std::mem::size_of::<f32>();
+        let capacity = (mmap.len() - HEADER_SIZE)
+            .checked_div(vector_bytes)
+            .unwrap_or(0);
+        let count = header.count as usize;
+        if count > capacity {
+            return Err(RagError::InvalidHeader(format!(
+                "header count {} exceeds file capacity {}",
+                count, capacity
+            )));
+        }
+
+        swap_stored_vectors_endian(&mut mmap, count, dimension);
+
+        Ok(Self {
+            dimension,
+            capacity,
+            count,
+            backing: Backing::Mmap { mmap, _file: file },
+        })
+    }
+
+    pub fn dimension(&self) -> usize {
+        self.dimension
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn len(&self) -> usize {
+        self.count
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+
+    /// Append `vector` to the store and return the index it was written at.
+    /// Indices are stable and positional — the Nth push always lands at N.
+    ///
+    /// Does NOT fsync the mmap-backed header/data — a `push` per bulk-load
+    /// row used to `mmap.flush()` unconditionally, which turned a large
+    /// insert into one fsync per vector. Call [`VectorStorage::flush`]
+    /// explicitly at whatever checkpoint durability actually matters (e.g.
+    /// once after a whole batch, or before the process exits). Until then,
+    /// pushed vectors are visible to any other view of the same mapping in
+    /// this process, just not guaranteed durable against a crash. `Drop`
+    /// also makes a best-effort flush as a safety net, but its errors are
+    /// unobservable — don't rely on it as your only durability point.
+    pub fn push(&mut self, vector: &[f32]) -> Result<usize, RagError> {
+        if vector.len() != self.dimension {
+            return Err(RagError::DimensionMismatch {
+                expected: self.dimension,
+                got: vector.len(),
+            });
+        }
+        if self.count >= self.capacity {
+            return Err(RagError::CapacityExceeded {
+                capacity: self.capacity,
+            });
+        }
+
+        let index = self.count;
+        match &mut self.backing {
+            Backing::Memory(data) => {
+                let start = index * self.dimension;
+                data[start..start + self.dimension].copy_from_slice(vector);
+            }
+            Backing::Mmap { mmap, .. } => {
+                let vector_bytes = self.dimension * std::mem::size_of::<f32>();
+                let start = HEADER_SIZE + index * vector_bytes;
+                mmap[start..start + vector_bytes].copy_from_slice(bytemuck::cast_slice(vector));
+            }
+        }
+        self.count += 1;
+
+        if let Backing::Mmap { mmap, .. } = &mut self.backing {
+            let header: &mut StorageHeader = bytemuck::from_bytes_mut(&mut mmap[..HEADER_SIZE]);
+            header.count = (self.count as u32).to_le();
+        }
+
+        Ok(index)
+    }
+
+    pub fn get(&self, index: usize) -> Option<&[f32]> {
+        if index >= self.count {
+            return None;
+        }
+        match &self.backing {
+            Backing::Memory(data) => {
+                let start = index * self.dimension;
+                Some(&data[start..start + self.dimension])
+            }
+            Backing::Mmap { mmap, .. } => {
+                let vector_bytes = self.dimension * std::mem::size_of::<f32>();
+                let start = HEADER_SIZE + index * vector_bytes;
+                Some(bytemuck::cast_slice(&mmap[start..start + vector_bytes]))
+            }
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &[f32]> {
+        (0..self.count).map(move |i| self.get(i).expect("index within count"))
+    }
+
+    /// Flush pending writes to disk (fsync the mmap). Since `push` no longer
+    /// flushes on every call, this is now the caller's only durability
+    /// checkpoint for the mmap-backed store — call it after a bulk load, or
+    /// wherever "this must survive a crash now" actually matters. A no-op
+    /// for the in-memory backing.
+    ///
+    /// Takes `&mut self` (not `&self`) because on a big-endian host this
+    /// briefly byte-swaps the resident vector data to disk-little-endian
+    /// around the fsync, then swaps it back — `push`/`get` never see
+    /// anything but native order. A no-op swap (and thus effectively `&self`
+    /// semantics) on the little-endian hosts this actually ships on.
+    pub fn flush(&mut self) -> Result<(), RagError> {
+        if let Backing::Mmap { mmap, .. } = &mut self.backing {
+            swap_stored_vectors_endian(mmap, self.count, self.dimension);
+            let result = mmap.flush();
+            swap_stored_vectors_endian(mmap, self.count, self.dimension);
+            result?;
+        }
+        Ok(())
+    }
+
+    /// Validate that this store's on-disk representation is internally
+    /// consistent, pinpointing the first problem found. A no-op that always
+    /// succeeds for the in-memory backing, which has no on-disk header to
+    /// corrupt.
+    ///
+    /// Checks, in order: header magic/version, the header's dimension
+    /// matches this instance's, `count * vector_size + HEADER_SIZE` fits
+    /// within the mapped length, and (if `check_finite`) that no stored
+    /// vector contains a NaN or infinite value.
+    pub fn verify(&self, check_finite: bool) -> Result<(), RagError> {
+        let mmap = match &self.backing {
+            Backing::Memory(_) => return Ok(()),
+            Backing::Mmap { mmap, .. } => mmap,
+        };
+
+        if mmap.len() < HEADER_SIZE {
+            return Err(RagError::Corrupt(
+                "file is smaller than the storage header".to_string(),
+            ));
+        }
+        let raw_header: StorageHeader = *bytemuck::from_bytes(&mmap[..HEADER_SIZE]);
+        let header = StorageHeader {
+            magic: u32::from_le(raw_header.magic),
+            version: u32::from_le(raw_header.version),
+            dimension: u32::from_le(raw_header.dimension),
+            count: u32::from_le(raw_header.count),
+        };
+        if header.magic != MAGIC {
+            return Err(RagError::Corrupt(format!(
+                "bad magic {:#010x} (expected {:#010x})",
+                header.magic, MAGIC
+            )));
+        }
+        if header.version != FORMAT_VERSION {
+            return Err(RagError::Corrupt(format!(
+                "unsupported format version {} (expected {})",
+                header.version, FORMAT_VERSION
+            )));
+        }
+        if header.dimension as usize != self.dimension {
+            return Err(RagError::Corrupt(format!(
+                "header dimension {} does not match store dimension {}",
+                header.dimension, self.dimension
+            )));
+        }
+
+        let vector_bytes = self.dimension * std::mem::size_of::<f32>();
+        let required_bytes = HEADER_SIZE
+            .checked_add(
+                (header.count as usize)
+                    .checked_mul(vector_bytes)
+                    .ok_or(RagError::CapacityOverflow)?,
+            )
+            .ok_or(RagError::CapacityOverflow)?;
+        if required_bytes > mmap.len() {
+            return Err(RagError::Corrupt(format!(
+                "header count {} implies {} bytes, but the file is only {} bytes",
+                header.count,
+                required_bytes,
+                mmap.len()
+            )));
+        }
+
+        if check_finite {
+            for i in 0..self.count {
+                let start = HEADER_SIZE + i * vector_bytes;
+                let vector: &[f32] = bytemuck::cast_slice(&mmap[start..start + vector_bytes]);
+                if let Some(bad) = vector.iter().position(|value| !value.is_finite()) {
+                    return Err(RagError::Corrupt(format!(
+                        "vector at index {i} contains a non-finite value at component {bad}"
+                    )));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Fault in every page of the mapping so the first real read doesn't pay
+    /// for lazy page-ins from disk. A no-op for the in-memory backing, which
+    /// has no pages to fault in.
+    ///
+    /// Issues a `MADV_WILLNEED` hint first (best-effort — some platforms
+    /// ignore it) and then sequentially touches every page itself, since the
+    /// hint alone doesn't guarantee the pages are resident by the time it
+    /// returns.
+    pub fn warm(&self) -> Result<(), RagError> {
+        if let Backing::Mmap { mmap, .. } = &self.backing {
+            let _ = mmap.advise(memmap2::Advice::WillNeed);
+
+            let mut checksum: u64 = 0;
+            for chunk in mmap.chunks(4096) {
+                checksum = checksum.wrapping_add(u64::from(chunk[0]));
+            }
+            std::hint::black_box(checksum);
+        }
+        Ok(())
+    }
+}
+
+/// Best-effort durability safety net: since `push` no longer flushes on
+/// every insert, a caller that forgets an explicit `flush()` before the
+/// store goes out of scope would otherwise risk losing writes made since the
+/// last flush if the process crashes right after. `Drop` can't return a
+/// `Result`, so a failed flush here is swallowed rather than panicking — the
+/// caller's own `flush()` call is still the only way to observe and handle a
+/// sync failure.
+impl Drop for VectorStorage {
+    fn drop(&mut self) {
+        let _ = self.flush();
+    }
+}
+
+/// Migrate a file-backed store at `src_path` to a new file at `dst_path` with
+/// `new_dimension`, one vector at a time.
+///
+/// With `projection: Some(matrix)`, `matrix` must be a flat `new_dimension x
+/// old_dimension` row-major matrix; each source vector is projected via
+/// matrix-vector multiplication. With `projection: None`, vectors are
+/// zero-padded (`new_dimension > old_dimension`) or truncated (`new_dimension
+/// < old_dimension`) instead.
+pub fn migrate_dimension(
+    src_path: &Path,
+    dst_path: &Path,
+    new_dimension: usize,
+    projection: Option<&[f32]>,
+) -> Result<VectorStorage, RagError> {
+    let src = VectorStorage::open(src_path)?;
+
+    if let Some(matrix) = projection {
+        let expected_len = new_dimension
+            .checked_mul(src.dimension)
+            .ok_or(RagError::CapacityOverflow)?;
+        if matrix.len() != expected_len {
+            return Err(RagError::DimensionMismatch {
+                expected: expected_len,
+                got: matrix.len(),
+            });
+        }
+    }
+
+    let mut dst = VectorStorage::create(dst_path, new_dimension, src.capacity())?;
+    for vector in src.iter() {
+        let projected;
+        let out: &[f32] = match projection {
+            Some(matrix) => {
+                projected = project_vector(matrix, vector, new_dimension, src.dimension);
+                &projected
+            }
+            None => {
+                projected = resize_vector(vector, new_dimension);
+                &projected
+            }
+        };
+        dst.push(out)?;
+    }
+
+    Ok(dst)
+}
+
+/// `matrix` is `out_dim x in_dim` row-major; computes `matrix * vector`.
+fn project_vector(matrix: &[f32], vector: &[f32], out_dim: usize, in_dim: usize) -> Vec<f32> {
+    let mut out = Vec::with_capacity(out_dim);
+    for row in matrix.chunks_exact(in_dim) {
+        let dot: f32 = row.iter().zip(vector).map(|(m, v)| m * v).sum();
+        out.push(dot);
+    }
+    out
+}
+
+/// Zero-pads or truncates `vector` to `new_dimension`.
+fn resize_vector(vector: &[f32], new_dimension: usize) -> Vec<f32> {
+    let mut out = vec![0.0; new_dimension];
+    let copy_len = vector.len().min(new_dimension);
+    out[..copy_len].copy_from_slice(&vector[..copy_len]);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn v(vals: &[f32]) -> Vec<f32> {
+        vals.to_vec()
+    }
+
+    /// A throwaway directory under the OS temp dir, unique per test process +
+    /// timestamp so parallel `cargo test` runs never collide. Caller is
+    /// responsible for cleanup; each test below removes it at the end.
+    fn unique_tempdir(tag: &str) -> std::path::PathBuf {
+        let nanos = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_nanos())
+            .unwrap_or(0);
+        let dir = std::env::temp_dir().join(format!(
+            "rag_engine_storage_test_{}_{}_{}",
+            tag,
+            std::process::id(),
+            nanos
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn in_memory_push_get_iter_len_round_trip() {
+        let mut store = VectorStorage::new(3, 4);
+        assert!(store.is_empty());
+        assert_eq!(store.push(&v(&[1.0, 2.0, 3.0])).unwrap(), 0);
+        assert_eq!(store.push(&v(&[4.0, 5.0, 6.0])).unwrap(), 1);
+        assert_eq!(store.len(), 2);
+        assert_eq!(store.get(0), Some([1.0, 2.0, 3.0].as_slice()));
+        assert_eq!(store.get(1), Some([4.0, 5.0, 6.0].as_slice()));
+        assert_eq!(store.get(2), None);
+
+        let all: Vec<_> = store.iter().collect();
+        assert_eq!(
+            all,
+            vec![[1.0, 2.0, 3.0].as_slice(), [4.0, 5.0, 6.0].as_slice()]
+        );
+    }
+
+    #[test]
+    fn in_memory_rejects_dimension_mismatch() {
+        let mut store = VectorStorage::new(3, 4);
+        let err = store.push(&v(&[1.0, 2.0])).unwrap_err();
+        assert!(matches!(
+            err,
+            RagError::DimensionMismatch {
+                expected: 3,
+                got: 2
+            }
+        ));
+    }
+
+    #[test]
+    fn in_memory_rejects_push_past_capacity() {
+        let mut store = VectorStorage::new(2, 1);
+        store.push(&v(&[1.0, 2.0])).unwrap();
+        let err = store.push(&v(&[3.0, 4.0])).unwrap_err();
+        assert!(matches!(err, RagError::CapacityExceeded { capacity: 1 }));
+    }
+
+    #[test]
+    fn file_backed_create_push_get_persist_across_open() {
+        let dir = unique_tempdir("roundtrip");
+        let path = dir.join("store.bin");
+
+        {
+            let mut store = VectorStorage::create(&path, 3, 4).unwrap();
+            assert_eq!(store.push(&v(&[1.0, 2.0, 3.0])).unwrap(), 0);
+            assert_eq!(store.push(&v(&[4.0, 5.0, 6.0])).unwrap(), 1);
+        }
+
+        let reopened = VectorStorage::open(&path).unwrap();
+        assert_eq!(reopened.len(), 2);
+        assert_eq!(reopened.dimension(), 3);
+        assert_eq!(reopened.get(0), Some([1.0, 2.0, 3.0].as_slice()));
+        assert_eq!(reopened.get(1), Some([4.0, 5.0, 6.0].as_slice()));
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn push_no_longer_flushes_but_explicit_flush_still_succeeds() {
+        let dir = unique_tempdir("explicit_flush");
+        let path = dir.join("store.bin");
+
+        let mut store = VectorStorage::create(&path, 3, 4).unwrap();
+        // A bulk load of several pushes with no flush() in between must not
+        // error and must still be immediately readable back from the same
+        // store — push() no longer fsyncs per call, but it still writes
+        // through the mapping itself.
+        for i in 0..3 {
+            store.push(&v(&[i as f32, i as f32, i as f32])).unwrap();
+        }
+        assert_eq!(store.len(), 3);
+        assert_eq!(store.get(1), Some([1.0, 1.0, 1.0].as_slice()));
+
+        // The caller's explicit checkpoint.
+        store.flush().unwrap();
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn dropping_a_store_flushes_as_a_best_effort_safety_net() {
+        let dir = unique_tempdir("drop_flush");
+        let path = dir.join("store.bin");
+
+        {
+            let mut store = VectorStorage::create(&path, 3, 4).unwrap();
+            store.push(&v(&[7.0, 8.0, 9.0])).unwrap();
+            // No explicit flush() call — Drop must still make a best-effort
+            // attempt so a caller that forgets it isn't left worse off than
+            // before this change.
+        }
+
+        let reopened = VectorStorage::open(&path).unwrap();
+        assert_eq!(reopened.len(), 1);
+        assert_eq!(reopened.get(0), Some([7.0, 8.0, 9.0].as_slice()));
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn open_rejects_bad_magic() {
+        let dir = unique_tempdir("badmagic");
+        let path = dir.join("garbage.bin");
+        std::fs::write(&path, vec![0u8; HEADER_SIZE + 16]).unwrap();
+        assert!(
+            matches!(VectorStorage::open(&path), Err(RagError::InvalidHeader(_))),
+            "an all-zero header must be rejected for its magic, not silently accepted"
+        );
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn open_rejects_truncated_file() {
+        let dir = unique_tempdir("truncated");
+        let path = dir.join("tiny.bin");
+        std::fs::write(&path, vec![0u8; 2]).unwrap();
+        assert!(matches!(
+            VectorStorage::open(&path),
+            Err(RagError::InvalidHeader(_))
+        ));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn indices_are_stable_and_positional() {
+        let mut store = VectorStorage::new(1, 8);
+        for i in 0..5 {
+            let idx = store.push(&v(&[i as f32])).unwrap();
+            assert_eq!(idx, i);
+        }
+    }
+
+    #[test]
+    fn migrate_dimension_zero_pads_when_growing() {
+        let dir = unique_tempdir("migrate_pad");
+        let src_path = dir.join("src.bin");
+        let dst_path = dir.join("dst.bin");
+
+        {
+            let mut store = VectorStorage::create(&src_path, 2, 4).unwrap();
+            store.push(&v(&[1.0, 2.0])).unwrap();
+            store.push(&v(&[3.0, 4.0])).unwrap();
+        }
+
+        let migrated = migrate_dimension(&src_path, &dst_path, 4, None).unwrap();
+        assert_eq!(migrated.dimension(), 4);
+        assert_eq!(migrated.len(), 2);
+        assert_eq!(migrated.get(0), Some([1.0, 2.0, 0.0, 0.0].as_slice()));
+        assert_eq!(migrated.get(1), Some([3.0, 4.0, 0.0, 0.0].as_slice()));
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn migrate_dimension_truncates_when_shrinking() {
+        let dir = unique_tempdir("migrate_truncate");
+        let src_path = dir.join("src.bin");
+        let dst_path = dir.join("dst.bin");
+
+        {
+            let mut store = VectorStorage::create(&src_path, 4, 2).unwrap();
+            store.push(&v(&[1.0, 2.0, 3.0, 4.0])).unwrap();
+        }
+
+        let migrated = migrate_dimension(&src_path, &dst_path, 2, None).unwrap();
+        assert_eq!(migrated.get(0), Some([1.0, 2.0].as_slice()));
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn migrate_dimension_applies_projection_matrix() {
+        let dir = unique_tempdir("migrate_project");
+        let src_path = dir.join("src.bin");
+        let dst_path = dir.join("dst.bin");
+
+        {
+            let mut store = VectorStorage::create(&src_path, 2, 1).unwrap();
+            store.push(&v(&[1.0, 2.0])).unwrap();
+        }
+
+        // 3x2 matrix: row-sums map [1, 2] -> [1*1+0*2, 0*1+1*2, 1*1+1*2] = [1, 2, 3]
+        let matrix = v(&[1.0, 0.0, 0.0, 1.0, 1.0, 1.0]);
+        let migrated = migrate_dimension(&src_path, &dst_path, 3, Some(&matrix)).unwrap();
+        assert_eq!(migrated.dimension(), 3);
+        assert_eq!(migrated.get(0), Some([1.0, 2.0, 3.0].as_slice()));
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn verify_is_a_noop_on_in_memory_backing() {
+        let store = VectorStorage::new(3, 4);
+        store.verify(true).unwrap();
+    }
+
+    #[test]
+    fn verify_passes_for_a_freshly_written_file_backed_store() {
+        let dir = unique_tempdir("verify_ok");
+        let path = dir.join("store.bin");
+
+        let mut store = VectorStorage::create(&path, 3, 4).unwrap();
+        store.push(&v(&[1.0, 2.0, 3.0])).unwrap();
+        store.verify(true).unwrap();
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn open_rejects_a_store_whose_magic_was_corrupted_after_create() {
+        let dir = unique_tempdir("verify_magic");
+        let path = dir.join("store.bin");
+
+        let store = VectorStorage::create(&path, 3, 4).unwrap();
+        drop(store);
+
+        let mut bytes = std::fs::read(&path).unwrap();
+        bytes[0] = bytes[0].wrapping_add(1);
+        std::fs::write(&path, &bytes).unwrap();
+
+        let reopened = VectorStorage::open(&path);
+        assert!(matches!(reopened, Err(RagError::InvalidHeader(_))));
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn verify_detects_count_past_file_end() {
+        let dir = unique_tempdir("verify_overrun");
+        let path = dir.join("store.bin");
+
+        let mut store = VectorStorage::create(&path, 3, 4).unwrap();
+        store.push(&v(&[1.0, 2.0, 3.0])).unwrap();
+
+        // Simulate a torn write: the header claims more entries than the
+        // file actually has room for.
+        if let Backing::Mmap { mmap, .. } = &mut store.backing {
+            let header: &mut StorageHeader = bytemuck::from_bytes_mut(&mut mmap[..HEADER_SIZE]);
+            header.count = 999;
+            mmap.flush().unwrap();
+        }
+
+        assert!(matches!(store.verify(false), Err(RagError::Corrupt(_))));
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn verify_detects_non_finite_values_when_requested() {
+        let dir = unique_tempdir("verify_nonfinite");
+        let path = dir.join("store.bin");
+
+        let mut store = VectorStorage::create(&path, 2, 4).unwrap();
+        store.push(&v(&[1.0, f32::NAN])).unwrap();
+
+        assert!(matches!(store.verify(true), Err(RagError::Corrupt(_))));
+        // Without the finite check, the same store passes.
+        store.verify(false).unwrap();
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn warm_is_a_noop_on_in_memory_backing() {
+        let store = VectorStorage::new(3, 4);
+        store.warm().unwrap();
+    }
+
+    #[test]
+    fn warm_succeeds_on_file_backed_store() {
+        let dir = unique_tempdir("warm");
+        let path = dir.join("store.bin");
+
+        let mut store = VectorStorage::create(&path, 8, 64).unwrap();
+        for i in 0..64 {
+            store.push(&v(&[i as f32; 8])).unwrap();
+        }
+        store.warm().unwrap();
+
+        assert_eq!(store.get(63), Some([63.0; 8].as_slice()));
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn migrate_dimension_rejects_wrong_shaped_projection_matrix() {
+        let dir = unique_tempdir("migrate_bad_matrix");
+        let src_path = dir.join("src.bin");
+        let dst_path = dir.join("dst.bin");
+
+        {
+            let mut store = VectorStorage::create(&src_path, 2, 1).unwrap();
+            store.push(&v(&[1.0, 2.0])).unwrap();
+        }
+
+        let wrong_shape = v(&[1.0, 0.0]);
+        let result = migrate_dimension(&src_path, &dst_path, 3, Some(&wrong_shape));
+        assert!(matches!(result, Err(RagError::DimensionMismatch { .. })));
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn create_writes_a_little_endian_header_regardless_of_host_order() {
+        let dir = unique_tempdir("header_le");
+        let path = dir.join("store.bin");
+
+        {
+            let _store = VectorStorage::create(&path, 3, 4).unwrap();
+        }
+
+        let bytes = std::fs::read(&path).unwrap();
+        assert_eq!(&bytes[0..4], MAGIC.to_le_bytes().as_slice());
+        assert_eq!(&bytes[4..8], FORMAT_VERSION.to_le_bytes().as_slice());
+        assert_eq!(&bytes[8..12], 3u32.to_le_bytes().as_slice());
+        assert_eq!(&bytes[12..16], 0u32.to_le_bytes().as_slice());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn open_decodes_a_hand_crafted_little_endian_file() {
+        let dir = unique_tempdir("header_decode");
+        let path = dir.join("store.bin");
+
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(&MAGIC.to_le_bytes());
+        bytes.extend_from_slice(&FORMAT_VERSION.to_le_bytes());
+        bytes.extend_from_slice(&2u32.to_le_bytes()); // dimension
+        bytes.extend_from_slice(&1u32.to_le_bytes()); // count
+        for value in [1.0f32, 2.0f32] {
+            bytes.extend_from_slice(&value.to_le_bytes());
+        }
+        // Trailing capacity for one more (unused) vector slot.
+        bytes.extend_from_slice(&0.0f32.to_le_bytes());
+        bytes.extend_from_slice(&0.0f32.to_le_bytes());
+        std::fs::write(&path, &bytes).unwrap();
+
+        let store = VectorStorage::open(&path).unwrap();
+        assert_eq!(store.dimension(), 2);
+        assert_eq!(store.len(), 1);
+        assert_eq!(store.get(0), Some([1.0, 2.0].as_slice()));
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn push_then_flush_keeps_the_store_readable_and_writes_le_bytes_to_disk() {
+        let dir = unique_tempdir("flush_le");
+        let path = dir.join("store.bin");
+
+        let mut store = VectorStorage::create(&path, 2, 4).unwrap();
+        store.push(&v(&[1.0, 2.0])).unwrap();
+        store.flush().unwrap();
+
+        // The store itself must still read back correct native values after
+        // flush() swaps to disk order and back -- the swap-back-on-completion
+        // half of the fix, not just the swap-to-LE half.
+        assert_eq!(store.get(0), Some([1.0, 2.0].as_slice()));
+
+        let bytes = std::fs::read(&path).unwrap();
+        let vector_start = HEADER_SIZE;
+        assert_eq!(&bytes[vector_start..vector_start + 4], 1.0f32.to_le_bytes().as_slice());
+        assert_eq!(
+            &bytes[vector_start + 4..vector_start + 8],
+            2.0f32.to_le_bytes().as_slice()
+        );
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}